@@ -1,3 +1,9 @@
+#![allow(
+    clippy::legacy_numeric_constants,
+    clippy::redundant_closure_call,
+    clippy::useless_vec
+)]
+
 use rust_efsm::bound::Bound;
 use rust_efsm::gviz::GvGraph;
 use rust_efsm::machine::{MachineBuilder, Transition, Update};
@@ -101,9 +107,9 @@ fn main() {
             info!("start monitoring");
             for input in vec![b'c', b'b', b'c'] {
                 if let Ok(verdict) = monitor.next(&input) {
-                    info!("input: {}, verdict: {:?}", input as char, verdict);
+                    info!("input: {}, verdict: {}", input as char, verdict);
 
-                    if let Some(_) = verdict {
+                    if verdict.is_conclusive() {
                         break;
                     }
                 } else {
@@ -0,0 +1,219 @@
+//! # Alphabet Module
+//!
+//! This module provides [InputClassifier], which groups the inputs of a machine into a few
+//! classes that every transition treats alike, such as `'b'` and every other byte. Algorithms
+//! that try every input, such as [Machine::words_up_to], [Machine::to_dfa], and the
+//! [test generators](crate::testgen), then only need one input of each class, which
+//! [InputClassifier::alphabet] returns. Graphs drawn by
+//! [GvGraph::with_input_classes](crate::gviz::GvGraph::with_input_classes) label each edge with
+//! the classes enabling it, and a machine built
+//! [with a dispatch table](crate::machine::MachineBuilder::with_dispatch) only tries the
+//! transitions the class of each input may enable.
+//!
+//! A classifier is only faithful to a machine when every guard gives the same answer for all
+//! the inputs of a class. Otherwise, the results over its alphabet miss the inputs that are not
+//! representatives.
+//!
+//! ```
+//! use rust_efsm::alphabet::{InputClassifier, Partition};
+//! use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+//!
+//! // Accept the words containing a 'b'.
+//! let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+//!     .with_transition("s0", Transition {
+//!         to_location: "s0".into(),
+//!         enable: |_, i| *i != b'b',
+//!         ..Default::default()
+//!     })
+//!     .with_transition("s0", Transition {
+//!         to_location: "s1".into(),
+//!         enable: |_, i| *i == b'b',
+//!         ..Default::default()
+//!     })
+//!     .with_transition("s1", Transition { to_location: "s1".into(), ..Default::default() })
+//!     .with_accepting("s1")
+//!     .build();
+//!
+//! let classes = Partition::new("other", b'a').with_class("b", b'b', |i| *i == b'b');
+//! assert_eq!(classes.alphabet(), [b'b', b'a']);
+//!
+//! // Two classes instead of 256 bytes.
+//! let words = machine.words_up_to("s0", 0, 2, &classes.alphabet());
+//! assert_eq!(words, [vec![b'b'], vec![b'b', b'b'], vec![b'b', b'a'], vec![b'a', b'b']]);
+//! ```
+
+use crate::machine::{Machine, Transition};
+use std::fmt::Debug;
+
+/// Maps the inputs of a machine to a small, finite set of classes.
+pub trait InputClassifier<I> {
+    /// The type of the classes.
+    type Class: Clone + Ord + Debug;
+
+    /// Returns every class, in order.
+    fn classes(&self) -> Vec<Self::Class>;
+
+    /// Returns the class of `input`.
+    fn classify(&self, input: &I) -> Self::Class;
+
+    /// Returns an input of `class`, which stands for every input of the class.
+    fn representative(&self, class: &Self::Class) -> I;
+
+    /// Returns the representative of every class, in the order of [classes](Self::classes), to be
+    /// used as the alphabet of algorithms that try every input.
+    fn alphabet(&self) -> Vec<I> {
+        self.classes()
+            .iter()
+            .map(|class| self.representative(class))
+            .collect()
+    }
+}
+
+/// An [InputClassifier] deciding the class of an input with a list of predicates.
+///
+/// An input belongs to the first class whose predicate holds for it, or to the fallback class
+/// when none does. Classes are named, so they can label the edges of a graph.
+///
+/// ```
+/// use rust_efsm::alphabet::{InputClassifier, Partition};
+///
+/// let classes = Partition::new("other", b'x')
+///     .with_class("digit", b'0', |i| i.is_ascii_digit())
+///     .with_class("space", b' ', |i| i.is_ascii_whitespace());
+///
+/// assert_eq!(classes.classes(), ["digit", "space", "other"]);
+/// assert_eq!(classes.classify(&b'7'), "digit");
+/// assert_eq!(classes.classify(&b'\n'), "space");
+/// assert_eq!(classes.classify(&b'q'), "other");
+/// assert_eq!(classes.representative(&"space".to_string()), b' ');
+/// ```
+#[derive(Clone, Debug)]
+pub struct Partition<I> {
+    classes: Vec<Class<I>>,
+    fallback: (String, I),
+}
+
+// A class of a partition, other than its fallback class.
+#[derive(Clone, Debug)]
+struct Class<I> {
+    name: String,
+    representative: I,
+    contains: fn(&I) -> bool,
+}
+
+impl<I> Partition<I> {
+    /// Creates a partition with a single class named `fallback`, represented by
+    /// `representative`.
+    pub fn new(fallback: &str, representative: I) -> Self {
+        Partition {
+            classes: Vec::new(),
+            fallback: (fallback.into(), representative),
+        }
+    }
+
+    /// Adds a class named `name` holding the inputs for which `contains` holds, but that are not
+    /// in a class added before.
+    pub fn with_class(mut self, name: &str, representative: I, contains: fn(&I) -> bool) -> Self {
+        self.classes.push(Class {
+            name: name.into(),
+            representative,
+            contains,
+        });
+        self
+    }
+}
+
+impl<I: Clone> InputClassifier<I> for Partition<I> {
+    type Class = String;
+
+    fn classes(&self) -> Vec<String> {
+        self.classes
+            .iter()
+            .map(|class| &class.name)
+            .chain([&self.fallback.0])
+            .cloned()
+            .collect()
+    }
+
+    fn classify(&self, input: &I) -> String {
+        self.classes
+            .iter()
+            .find(|class| (class.contains)(input))
+            .map_or(&self.fallback.0, |class| &class.name)
+            .clone()
+    }
+
+    fn representative(&self, class: &String) -> I {
+        self.classes
+            .iter()
+            .find(|candidate| candidate.name == *class)
+            .map_or(&self.fallback.1, |class| &class.representative)
+            .clone()
+    }
+}
+
+impl<D, I, U> Transition<D, I, U> {
+    /// Returns the classes of `classifier` whose representative enables this transition with
+    /// `data`.
+    ///
+    /// ```
+    /// use rust_efsm::alphabet::Partition;
+    /// use rust_efsm::machine::{IdentityUpdate, Transition};
+    ///
+    /// let transition = Transition::<u8, u8, IdentityUpdate<u8>> {
+    ///     enable: |data, i| *data > 0 && *i != b'b',
+    ///     ..Default::default()
+    /// };
+    /// let classes = Partition::new("other", b'a').with_class("b", b'b', |i| *i == b'b');
+    ///
+    /// assert_eq!(transition.enabled_classes(&1, &classes), ["other"]);
+    /// assert!(transition.enabled_classes(&0, &classes).is_empty());
+    /// ```
+    pub fn enabled_classes<C>(&self, data: &D, classifier: &C) -> Vec<C::Class>
+    where
+        C: InputClassifier<I>,
+    {
+        classifier
+            .classes()
+            .into_iter()
+            .filter(|class| self.is_enabled(data, &classifier.representative(class)))
+            .collect()
+    }
+}
+
+impl<D, I, U> Machine<D, I, U> {
+    /// Checks that `classifier` is faithful to this machine on `inputs` with `data`: every
+    /// transition is enabled for an input exactly when it is enabled for the representative of
+    /// the input's class.
+    ///
+    /// ```
+    /// use rust_efsm::alphabet::Partition;
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s1".into(),
+    ///         enable: |_, i| i.is_ascii_digit(),
+    ///         ..Default::default()
+    ///     })
+    ///     .build();
+    ///
+    /// let digits = Partition::new("other", b'x').with_class("digit", b'0', |i| i.is_ascii_digit());
+    /// assert!(machine.respects_classes(&digits, &0, 0..=u8::MAX));
+    ///
+    /// let zero = Partition::new("other", b'x').with_class("zero", b'0', |i| *i == b'0');
+    /// assert!(!machine.respects_classes(&zero, &0, 0..=u8::MAX));
+    /// ```
+    pub fn respects_classes<C, T>(&self, classifier: &C, data: &D, inputs: T) -> bool
+    where
+        C: InputClassifier<I>,
+        T: IntoIterator<Item = I>,
+    {
+        inputs.into_iter().all(|input| {
+            let representative = classifier.representative(&classifier.classify(&input));
+            self.get_locations().values().flatten().all(|transition| {
+                transition.is_enabled(data, &input) == transition.is_enabled(data, &representative)
+            })
+        })
+    }
+}
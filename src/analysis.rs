@@ -0,0 +1,156 @@
+//! # Analysis Module
+//!
+//! This module holds the types shared by the analyses of a [Machine](crate::machine::Machine),
+//! such as [Machine::find_non_empty_with](crate::machine::Machine::find_non_empty_with). Long
+//! running analyses report their [Progress] to a callback, and can be stopped early with a
+//! [CancellationToken]. Their results are tagged with an [Approximation], telling whether they
+//! may miss or include values.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// A snapshot of the work done by an analysis.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Progress {
+    /// The number of nodes visited so far.
+    pub visited: usize,
+    /// The number of nodes waiting to be visited.
+    pub frontier: usize,
+}
+
+/// A flag shared between an analysis and the code that may want to stop it.
+///
+/// Clones share the same flag, so a clone can be moved to another thread and cancelled there.
+///
+/// ```
+/// use rust_efsm::analysis::CancellationToken;
+///
+/// let token = CancellationToken::new();
+/// let handle = token.clone();
+///
+/// assert!(!token.is_cancelled());
+/// handle.cancel();
+/// assert!(token.is_cancelled());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Creates a token that is not cancelled.
+    pub fn new() -> Self {
+        CancellationToken::default()
+    }
+
+    /// Asks every analysis holding this token to stop.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Checks if [cancel](CancellationToken::cancel) was called on this token or a clone.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// How the result of an analysis relates to the exact answer.
+///
+/// ```
+/// use rust_efsm::analysis::Approximation;
+///
+/// let approximation = Approximation::Exact.and(Approximation::Over);
+/// assert_eq!(approximation, Approximation::Over);
+/// assert!(approximation.includes_all());
+///
+/// assert_eq!(approximation.and(Approximation::Under), Approximation::Unknown);
+/// assert!(!Approximation::Unknown.includes_all());
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Approximation {
+    /// The result is the exact answer.
+    #[default]
+    Exact,
+    /// The result contains the exact answer, but may include more.
+    Over,
+    /// The result is contained in the exact answer, but may miss some of it.
+    Under,
+    /// The result may both miss some of the exact answer and include more.
+    Unknown,
+}
+
+impl Approximation {
+    /// Returns the approximation of a result combining results approximated by `self` and
+    /// `other`.
+    pub fn and(self, other: Approximation) -> Approximation {
+        match (self, other) {
+            (Approximation::Exact, other) => other,
+            (approximation, Approximation::Exact) => approximation,
+            (approximation, other) if approximation == other => approximation,
+            _ => Approximation::Unknown,
+        }
+    }
+
+    /// Checks if the result cannot miss any of the exact answer.
+    pub fn includes_all(&self) -> bool {
+        matches!(self, Approximation::Exact | Approximation::Over)
+    }
+
+    /// Checks if the result cannot include anything beyond the exact answer.
+    pub fn includes_only(&self) -> bool {
+        matches!(self, Approximation::Exact | Approximation::Under)
+    }
+}
+
+impl std::fmt::Display for Approximation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Approximation::Exact => write!(f, "exact"),
+            Approximation::Over => write!(f, "over-approximate"),
+            Approximation::Under => write!(f, "under-approximate"),
+            Approximation::Unknown => write!(f, "approximate"),
+        }
+    }
+}
+
+/// The result of an analysis that may have stopped before it finished.
+///
+/// With the `serde` feature, an outcome can be stored and read back, so that an analysis is not
+/// run again for a machine that did not change.
+///
+/// ```
+/// # #[cfg(feature = "serde")]
+/// # {
+/// use rust_efsm::analysis::{AnalysisOutcome, CancellationToken};
+/// use rust_efsm::bound::Bound;
+/// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+/// use std::collections::HashMap;
+///
+/// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+///     .with_transition("s0", Transition {
+///         to_location: "s1".into(),
+///         bound: Bound { lower: None, upper: Some(3) },
+///         ..Default::default()
+///     })
+///     .with_accepting("s1")
+///     .build();
+///
+/// let outcome = machine
+///     .find_non_empty_with("s0", |_| {}, &CancellationToken::new())
+///     .unwrap();
+/// let json = serde_json::to_string(&outcome).unwrap();
+///
+/// let stored: AnalysisOutcome<HashMap<String, Bound<u8>>> = serde_json::from_str(&json).unwrap();
+/// assert_eq!(stored, outcome);
+/// assert_eq!(serde_json::to_string(&stored.result["s0"]).unwrap(), r#"{"lower":null,"upper":3}"#);
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AnalysisOutcome<T> {
+    /// The result computed so far.
+    pub result: T,
+    /// How `result` relates to the exact answer. A result is
+    /// [under-approximate](Approximation::Under) when the analysis stopped early, so that it may
+    /// be missing some of what a complete run would find.
+    pub approximation: Approximation,
+}
@@ -0,0 +1,453 @@
+//! # Analyzed Module
+//!
+//! This module provides [AnalyzedMachine], a [Machine] bundled with the results of the analyses
+//! that [monitors](crate::monitor::Monitor) and [test generators](crate::testgen) rely on. The
+//! results are computed once by [Machine::analyze], and kept as plain data in a
+//! [MachineAnalysis], which can be stored and paired with the machine again later.
+//!
+//! ```
+//! use rust_efsm::analyzed::AnalysisConfig;
+//! use rust_efsm::machine::{Classification, IdentityUpdate, MachineBuilder, Transition};
+//! use rust_efsm::monitor::{Monitor, Verdict};
+//!
+//! let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+//!     .with_transition("safe", Transition {
+//!         to_location: "safe".into(),
+//!         enable: |_, i| *i != 0,
+//!         ..Default::default()
+//!     })
+//!     .with_transition("safe", Transition {
+//!         to_location: "unsafe".into(),
+//!         enable: |_, i| *i == 0,
+//!         ..Default::default()
+//!     })
+//!     .with_transition("unsafe", Transition { to_location: "unsafe".into(), ..Default::default() })
+//!     .with_accepting("safe")
+//!     .build();
+//!
+//! let analyzed = machine.analyze(&AnalysisConfig::new("safe").with_alphabet(vec![0, 1])).unwrap();
+//! let analysis = analyzed.analysis();
+//! assert_eq!(analysis.deterministic, Some(true));
+//! assert_eq!(analysis.total, Some(true));
+//! assert_eq!(analysis.classification, Classification::Safety);
+//! assert!(analysis.sinks.contains("unsafe"));
+//!
+//! let mut monitor = Monitor::from_analyzed(analyzed, 0).unwrap();
+//! assert_eq!(monitor.next(&0).unwrap(), Verdict::Violated);
+//! ```
+
+use crate::analysis::{AnalysisOutcome, Approximation, CancellationToken};
+use crate::bound::Bound;
+use crate::machine::{Classification, Machine, MachineError, NoTransitionPolicy, Update};
+use crate::testgen::{self, BoundaryValues, PairCoverage};
+use num::{Bounded, CheckedAdd, CheckedSub, Integer, One};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::hash::Hash;
+
+/// Chooses what [Machine::analyze] computes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AnalysisConfig<I> {
+    /// The location runs start from.
+    pub initial: String,
+    /// The inputs tried by the determinism and totality checks, which are skipped when it is
+    /// empty.
+    pub alphabet: Vec<I>,
+}
+
+impl<I> AnalysisConfig<I> {
+    /// Creates a configuration for runs starting from `initial`, without an alphabet.
+    pub fn new(initial: &str) -> Self {
+        AnalysisConfig {
+            initial: initial.into(),
+            alphabet: Vec::new(),
+        }
+    }
+
+    /// Checks determinism and totality over `alphabet`.
+    pub fn with_alphabet(mut self, alphabet: Vec<I>) -> Self {
+        self.alphabet = alphabet;
+        self
+    }
+}
+
+/// The results of the analyses of a machine, computed by [Machine::analyze].
+///
+/// With the `serde` feature, the results can be stored and paired with the machine again with
+/// [AnalyzedMachine::from_parts], without running the analyses.
+///
+/// ```
+/// # #[cfg(feature = "serde")]
+/// # {
+/// use rust_efsm::analyzed::{AnalysisConfig, AnalyzedMachine, MachineAnalysis};
+/// use rust_efsm::machine::{Classification, IdentityUpdate, MachineBuilder, Transition};
+///
+/// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+///     .with_transition("s0", Transition { to_location: "s0".into(), ..Default::default() })
+///     .with_accepting("s0")
+///     .build();
+///
+/// let (machine, analysis) = machine.analyze(&AnalysisConfig::new("s0")).unwrap().into_parts();
+/// let json = serde_json::to_string(&analysis).unwrap();
+///
+/// let stored: MachineAnalysis<u8> = serde_json::from_str(&json).unwrap();
+/// assert_eq!(stored, analysis);
+///
+/// let analyzed = AnalyzedMachine::from_parts(machine, stored).unwrap();
+/// assert_eq!(analyzed.analysis().classification, Classification::Safety);
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MachineAnalysis<D> {
+    /// The [fingerprint](Machine::fingerprint) of the analyzed machine.
+    pub fingerprint: u64,
+    /// The location runs start from.
+    pub initial: String,
+    /// Whether no two transitions out of a location can be enabled at once, as checked by
+    /// [Machine::check_overlaps], or `None` without an alphabet. It is also `true` when the
+    /// overlaps only occur at data values that were not tried.
+    pub deterministic: Option<bool>,
+    /// Whether every reachable state has a successor on every input of the alphabet, or `None`
+    /// without an alphabet. It is checked with the data values at the endpoints of the
+    /// transition bounds.
+    pub total: Option<bool>,
+    /// The data of each location that can still lead to acceptance, as found by
+    /// [Machine::find_non_empty].
+    pub safe: BTreeMap<String, Bound<D>>,
+    /// How `safe` approximates the exact intervals.
+    pub approximation: Approximation,
+    /// The strongly connected components of the location graph, each sorted, and listed in
+    /// the order of their first location.
+    pub sccs: Vec<BTreeSet<String>>,
+    /// The locations that cannot reach an accepting location, whatever the data.
+    pub sinks: BTreeSet<String>,
+    /// The class of the property, as computed by [Machine::classify].
+    pub classification: Classification,
+}
+
+/// A machine along with the results of its analyses.
+///
+/// The machine cannot be modified through this type, so the results stay valid.
+pub struct AnalyzedMachine<D, I, U> {
+    machine: Machine<D, I, U>,
+    analysis: MachineAnalysis<D>,
+}
+
+impl<D, I, U> Machine<D, I, U> {
+    /// Analyzes this machine as chosen by `config`.
+    ///
+    /// Fails when the non-empty intervals cannot be found, as with [Machine::find_non_empty].
+    pub fn analyze(
+        self,
+        config: &AnalysisConfig<I>,
+    ) -> Result<AnalyzedMachine<D, I, U>, MachineError>
+    where
+        D: Eq + Hash + Integer + Copy + Bounded + fmt::Debug + fmt::Display,
+        I: Clone,
+        U: Update<D = D> + Hash,
+    {
+        let outcome =
+            self.find_non_empty_with(&config.initial, |_| {}, &CancellationToken::new())?;
+
+        let checked = !config.alphabet.is_empty();
+        let graph = location_graph(&self);
+
+        let analysis = MachineAnalysis {
+            fingerprint: self.fingerprint(),
+            initial: config.initial.clone(),
+            deterministic: checked.then(|| self.check_overlaps(&config.alphabet).result.is_empty()),
+            total: checked.then(|| is_total(&self, &graph, config)),
+            safe: outcome.result.into_iter().collect(),
+            approximation: outcome.approximation,
+            sccs: strongly_connected(&graph),
+            sinks: sinks(&self, &graph),
+            classification: self.classify(&config.initial),
+        };
+
+        Ok(AnalyzedMachine {
+            machine: self,
+            analysis,
+        })
+    }
+}
+
+impl<D, I, U> AnalyzedMachine<D, I, U> {
+    /// Pairs `machine` with `analysis`, such as one stored after an earlier
+    /// [analyze](Machine::analyze) of the same machine.
+    ///
+    /// Fails with [MachineError::StaleAnalysis] when the [fingerprint](Machine::fingerprint) of
+    /// `machine` differs from the one recorded in `analysis`. Otherwise the analysis is trusted
+    /// as is, and only memoized for `machine`, not for the clones it was made from or with.
+    ///
+    /// ```
+    /// use rust_efsm::analyzed::{AnalysisConfig, AnalyzedMachine};
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, MachineError, Transition};
+    /// use std::collections::BTreeSet;
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition { to_location: "s1".into(), ..Default::default() })
+    ///     .with_transition("s1", Transition { to_location: "s0".into(), ..Default::default() })
+    ///     .with_transition("s1", Transition { to_location: "s2".into(), ..Default::default() })
+    ///     .with_accepting("s1")
+    ///     .build();
+    ///
+    /// let (machine, analysis) = machine.analyze(&AnalysisConfig::new("s0")).unwrap().into_parts();
+    /// assert_eq!(analysis.sccs, [
+    ///     BTreeSet::from(["s0".to_string(), "s1".to_string()]),
+    ///     BTreeSet::from(["s2".to_string()]),
+    /// ]);
+    /// assert_eq!(analysis.sinks, BTreeSet::from(["s2".to_string()]));
+    ///
+    /// let analyzed = AnalyzedMachine::from_parts(machine.clone(), analysis.clone()).unwrap();
+    /// assert_eq!(analyzed.analysis(), &analysis);
+    ///
+    /// let other = machine.to_builder().with_accepting("s2").build();
+    /// let result = AnalyzedMachine::from_parts(other, analysis);
+    /// assert!(matches!(result, Err(MachineError::StaleAnalysis { .. })));
+    /// ```
+    pub fn from_parts(
+        mut machine: Machine<D, I, U>,
+        analysis: MachineAnalysis<D>,
+    ) -> Result<Self, MachineError>
+    where
+        D: Eq + Hash + Clone,
+        U: Hash,
+    {
+        let fingerprint = machine.fingerprint();
+        if fingerprint != analysis.fingerprint {
+            return Err(MachineError::StaleAnalysis {
+                analyzed: analysis.fingerprint,
+                machine: fingerprint,
+            });
+        }
+
+        machine.memoize_non_empty(
+            &analysis.initial,
+            AnalysisOutcome {
+                result: analysis.safe.clone().into_iter().collect(),
+                approximation: analysis.approximation,
+            },
+        );
+        Ok(AnalyzedMachine { machine, analysis })
+    }
+
+    /// Returns the machine and its analysis.
+    pub fn into_parts(self) -> (Machine<D, I, U>, MachineAnalysis<D>) {
+        (self.machine, self.analysis)
+    }
+
+    /// Returns the analyzed machine.
+    pub fn machine(&self) -> &Machine<D, I, U> {
+        &self.machine
+    }
+
+    /// Returns the results of the analyses.
+    pub fn analysis(&self) -> &MachineAnalysis<D> {
+        &self.analysis
+    }
+
+    /// Generates a suite for transition pair coverage from the initial location, as
+    /// [testgen::pair_coverage] does.
+    pub fn pair_coverage(
+        &self,
+        data: D,
+        alphabet: &[I],
+        max_len: usize,
+    ) -> Result<PairCoverage<I>, MachineError>
+    where
+        D: Ord + Copy + Bounded + Hash,
+        I: Clone + PartialEq,
+        U: Update<D = D>,
+    {
+        testgen::pair_coverage(
+            &self.machine,
+            &self.analysis.initial,
+            data,
+            alphabet,
+            max_len,
+        )
+    }
+
+    /// Generates boundary value cases from the initial location, as [testgen::boundary_values]
+    /// does.
+    pub fn boundary_values(
+        &self,
+        data: D,
+        alphabet: &[I],
+        max_len: usize,
+    ) -> Result<BoundaryValues<D, I>, MachineError>
+    where
+        D: Ord + Copy + Bounded + Hash + CheckedAdd + CheckedSub + One,
+        I: Clone,
+        U: Update<D = D>,
+    {
+        testgen::boundary_values(
+            &self.machine,
+            &self.analysis.initial,
+            data,
+            alphabet,
+            max_len,
+        )
+    }
+}
+
+// The locations each location has an edge to, ignoring guards and bounds, including the implicit
+// moves of the no transition policy.
+type Graph = BTreeMap<String, BTreeSet<String>>;
+
+fn location_graph<D, I, U>(machine: &Machine<D, I, U>) -> Graph {
+    let mut graph = Graph::new();
+    for location in machine.all_locations() {
+        let mut edges: BTreeSet<String> = machine
+            .get_transitions_from(location)
+            .into_iter()
+            .flatten()
+            .map(|transition| transition.to_location.clone())
+            .collect();
+
+        match machine.get_no_transition_policy() {
+            NoTransitionPolicy::Stutter => {
+                edges.insert(location.into());
+            }
+            NoTransitionPolicy::SinkTo(sink) => {
+                edges.insert(sink.clone());
+            }
+            NoTransitionPolicy::Reject | NoTransitionPolicy::Error => {}
+        }
+
+        graph.insert(location.into(), edges);
+    }
+    graph
+}
+
+// Returns the strongly connected components of `graph` with the algorithm of Kosaraju.
+fn strongly_connected(graph: &Graph) -> Vec<BTreeSet<String>> {
+    // Order the locations by the time their depth first search finishes.
+    let mut finished: Vec<&str> = Vec::new();
+    let mut visited: BTreeSet<&str> = BTreeSet::new();
+    for root in graph.keys() {
+        if !visited.insert(root) {
+            continue;
+        }
+
+        let mut stack = vec![(root.as_str(), graph[root].iter())];
+        while let Some((location, edges)) = stack.last_mut() {
+            let location = *location;
+            match edges.find(|to| !visited.contains(to.as_str())) {
+                Some(to) => {
+                    visited.insert(to);
+                    stack.push((to, graph[to].iter()));
+                }
+                None => {
+                    finished.push(location);
+                    stack.pop();
+                }
+            }
+        }
+    }
+
+    let mut reversed: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (location, edges) in graph {
+        for to in edges {
+            reversed.entry(to).or_default().push(location);
+        }
+    }
+
+    // Each search of the reversed graph, in reverse finishing order, finds one component.
+    let mut assigned: BTreeSet<&str> = BTreeSet::new();
+    let mut components = Vec::new();
+    for root in finished.into_iter().rev() {
+        if !assigned.insert(root) {
+            continue;
+        }
+
+        let mut component = BTreeSet::from([root.to_string()]);
+        let mut stack = vec![root];
+        while let Some(location) = stack.pop() {
+            for from in reversed.get(location).into_iter().flatten() {
+                if assigned.insert(from) {
+                    component.insert(from.to_string());
+                    stack.push(from);
+                }
+            }
+        }
+        components.push(component);
+    }
+
+    components.sort();
+    components
+}
+
+// Returns the locations of `graph` that cannot reach an accepting location.
+fn sinks<D, I, U>(machine: &Machine<D, I, U>, graph: &Graph) -> BTreeSet<String> {
+    let mut live: BTreeSet<&str> = machine.get_accepting().iter().map(String::as_str).collect();
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (location, edges) in graph {
+            if !live.contains(location.as_str())
+                && edges.iter().any(|to| live.contains(to.as_str()))
+            {
+                live.insert(location);
+                changed = true;
+            }
+        }
+    }
+
+    graph
+        .keys()
+        .filter(|location| !live.contains(location.as_str()))
+        .cloned()
+        .collect()
+}
+
+// Checks that every location reachable from the initial one, other than rejecting locations,
+// has an enabled transition for every input of the alphabet, at the endpoints of its bounds.
+fn is_total<D, I, U>(machine: &Machine<D, I, U>, graph: &Graph, config: &AnalysisConfig<I>) -> bool
+where
+    D: Ord + Copy + Bounded,
+{
+    // Every state has an implicit move when no transition is enabled.
+    if matches!(
+        machine.get_no_transition_policy(),
+        NoTransitionPolicy::Stutter | NoTransitionPolicy::SinkTo(_)
+    ) {
+        return true;
+    }
+
+    let mut reachable = BTreeSet::from([config.initial.as_str()]);
+    let mut stack = vec![config.initial.as_str()];
+    while let Some(location) = stack.pop() {
+        for to in graph.get(location).into_iter().flatten() {
+            if reachable.insert(to) {
+                stack.push(to);
+            }
+        }
+    }
+
+    reachable
+        .into_iter()
+        .filter(|location| !machine.get_rejecting().contains(*location))
+        .all(|location| {
+            let transitions = machine.get_transitions_from(location);
+            let transitions = transitions.map(Vec::as_slice).unwrap_or_default();
+
+            let mut candidates: Vec<D> = vec![D::min_value(), D::max_value()];
+            for transition in transitions {
+                let (lower, upper) = transition.bound.as_explicit();
+                candidates.extend([lower, upper]);
+            }
+            candidates.sort();
+            candidates.dedup();
+
+            candidates.iter().all(|data| {
+                config.alphabet.iter().all(|input| {
+                    transitions
+                        .iter()
+                        .any(|transition| transition.is_enabled(data, input))
+                })
+            })
+        })
+}
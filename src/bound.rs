@@ -11,6 +11,7 @@
 //! - Test for interval intersection and containment
 //! - Expand bounds to include other intervals
 //! - Type-safe handling of minimum and maximum values for the bounded type
+//! - Collect disjoint intervals into a [BoundSet] with logarithmic membership tests
 //!
 //! The `Bound<D>` struct is generic over the type `D`, which must implement appropriate
 //! traits according to the operations being performed.
@@ -31,13 +32,14 @@
 //! assert_eq!(intersection, Some(Bound { lower: Some(10_u32), upper: Some(20_u32) }));
 //! ```
 
-use num::Bounded;
+use num::{Bounded, CheckedAdd, CheckedSub, FromPrimitive, Integer, ToPrimitive};
 use std::cmp::{max, min};
 use std::fmt;
 use std::fmt::Debug;
 use std::hash::Hash;
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A generic structure representing a bounded interval with optional lower and upper bounds.
 ///
 /// `Bound<D>` can represent various interval types:
@@ -73,17 +75,41 @@ use std::hash::Hash;
 /// ```
 pub struct Bound<D> {
     // TODO: This really needs to be an enum...
+    /// The inclusive lower bound, or `None` if unbounded below.
     pub lower: Option<D>,
+    /// The inclusive upper bound, or `None` if unbounded above.
     pub upper: Option<D>,
 }
 
+/// Writes a missing lower or upper bound as `-∞` or `∞`. The alternate form, `{:#}`, writes
+/// the minimum or maximum value of the type instead.
+///
+/// ```
+/// use rust_efsm::bound::Bound;
+///
+/// let bound = Bound { lower: Some(5_u8), upper: None };
+/// assert_eq!(bound.to_string(), "[5, ∞)");
+/// assert_eq!(format!("{:#}", bound), "[5, 255]");
+/// assert_eq!(Bound::<i8>::unbounded().to_string(), "(-∞, ∞)");
+/// ```
 impl<D> fmt::Display for Bound<D>
 where
     D: fmt::Display + Bounded + Copy,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let (lower, upper) = self.as_explicit();
-        write!(f, "[{}, {}]", lower, upper)
+        if f.alternate() {
+            let (lower, upper) = self.as_explicit();
+            return write!(f, "[{}, {}]", lower, upper);
+        }
+
+        match self.lower {
+            Some(lower) => write!(f, "[{}, ", lower),
+            None => write!(f, "(-∞, "),
+        }?;
+        match self.upper {
+            Some(upper) => write!(f, "{}]", upper),
+            None => write!(f, "∞)"),
+        }
     }
 }
 
@@ -104,6 +130,27 @@ impl<D> Bound<D> {
         }
     }
 
+    /// Checks if the interval has neither a lower nor an upper bound.
+    pub fn is_unbounded(&self) -> bool {
+        self.lower.is_none() && self.upper.is_none()
+    }
+
+    /// Checks if the interval holds a single value.
+    ///
+    /// ```
+    /// use rust_efsm::bound::Bound;
+    ///
+    /// assert!(Bound { lower: Some(3), upper: Some(3) }.is_point());
+    /// assert!(!Bound { lower: Some(3), upper: None }.is_point());
+    /// assert!(Bound::<u8>::unbounded().is_unbounded());
+    /// ```
+    pub fn is_point(&self) -> bool
+    where
+        D: PartialEq,
+    {
+        self.lower.is_some() && self.lower == self.upper
+    }
+
     /// Converts a bound with possible `None` values to explicit values by replacing
     /// `None` with the respective minimum or maximum value for the type.
     ///
@@ -117,7 +164,7 @@ impl<D> Bound<D> {
     ///     lower: Some(10_u32),
     ///     upper: None,
     /// };
-
+    ///
     /// let b = Bound {
     ///     lower: None,
     ///     upper: Some(15_u32),
@@ -283,4 +330,264 @@ impl<D> Bound<D> {
         let (rl, ru) = rhs.as_explicit();
         ll <= rl && lu >= ru
     }
+
+    /// Returns the interval extended by `delta` on either side, as an update that may add or
+    /// subtract up to `delta` would produce.
+    ///
+    /// An endpoint that would overflow becomes unbounded. `delta` is expected not to be negative.
+    ///
+    /// ```
+    /// use rust_efsm::bound::Bound;
+    ///
+    /// let bound = Bound { lower: Some(3_u8), upper: Some(250) };
+    /// assert_eq!(bound.widen_by(6), Bound { lower: None, upper: None });
+    /// assert_eq!(bound.widen_by(2), Bound { lower: Some(1), upper: Some(252) });
+    /// ```
+    pub fn widen_by(&self, delta: D) -> Bound<D>
+    where
+        D: CheckedAdd + CheckedSub + Copy,
+    {
+        Bound {
+            lower: self.lower.and_then(|lower| lower.checked_sub(&delta)),
+            upper: self.upper.and_then(|upper| upper.checked_add(&delta)),
+        }
+    }
+
+    /// Returns the interval with each endpoint moved into `other`, as [Ord::clamp] does for a
+    /// single value.
+    ///
+    /// Unlike [intersect](Bound::intersect), the result is never empty: an interval entirely
+    /// below or above `other` is clamped to its nearest endpoint.
+    ///
+    /// ```
+    /// use rust_efsm::bound::Bound;
+    ///
+    /// let limits = Bound { lower: Some(0_i32), upper: Some(100) };
+    /// let bound = Bound { lower: Some(-20), upper: Some(50) };
+    /// assert_eq!(bound.clamp_to(&limits), Bound { lower: Some(0), upper: Some(50) });
+    ///
+    /// let above = Bound { lower: Some(120), upper: None };
+    /// assert_eq!(above.clamp_to(&limits), Bound { lower: Some(100), upper: Some(100) });
+    /// ```
+    pub fn clamp_to(&self, other: &Bound<D>) -> Bound<D>
+    where
+        D: Ord + Bounded + Copy,
+    {
+        let (lower, upper) = self.as_explicit();
+        let (min, max) = other.as_explicit();
+        if min > max {
+            return other.clone();
+        }
+
+        Bound::from_explicit((lower.clamp(min, max), upper.clamp(min, max)))
+    }
+
+    /// Splits the interval into its values up to `value`, and its values above `value`.
+    ///
+    /// Either part is `None` when it holds no value, so that no endpoint is ever moved past the
+    /// bounds of the type.
+    ///
+    /// ```
+    /// use rust_efsm::bound::Bound;
+    ///
+    /// let bound = Bound { lower: Some(10_u8), upper: None };
+    /// assert_eq!(
+    ///     bound.split_at(20),
+    ///     (Some(Bound { lower: Some(10), upper: Some(20) }), Some(Bound { lower: Some(21), upper: None })),
+    /// );
+    /// assert_eq!(bound.split_at(255), (Some(bound.clone()), None));
+    /// assert_eq!(bound.split_at(5), (None, Some(bound.clone())));
+    /// ```
+    pub fn split_at(&self, value: D) -> (Option<Bound<D>>, Option<Bound<D>>)
+    where
+        D: Integer + Bounded + Copy,
+    {
+        let (lower, upper) = self.as_explicit();
+        if lower > upper {
+            return (None, None);
+        }
+
+        let below = (lower <= value).then(|| Bound::from_explicit((lower, value.min(upper))));
+        let above = (value < upper).then(|| {
+            // The value is below the upper endpoint, so it has a successor.
+            Bound::from_explicit((lower.max(value + D::one()), upper))
+        });
+        (below, above)
+    }
+
+    /// Returns a random value of the interval, drawn with the random numbers returned by `rng`.
+    ///
+    /// Every value is equally likely, up to a bias below `2^-64` for intervals of fewer than
+    /// `2^64` values. Returns `None` when the interval is empty, or when its endpoints do not fit
+    /// in an `i128`.
+    ///
+    /// ```
+    /// use rust_efsm::bound::Bound;
+    ///
+    /// // Any source of random numbers, such as `rand::random`.
+    /// let mut state = 7_u64;
+    /// let mut rng = || {
+    ///     state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+    ///     state
+    /// };
+    ///
+    /// let bound = Bound { lower: Some(-3_i8), upper: Some(3) };
+    /// for _ in 0..100 {
+    ///     assert!(bound.contains(&bound.sample(&mut rng).unwrap()));
+    /// }
+    /// assert_eq!(Bound { lower: Some(5_u8), upper: Some(4) }.sample(&mut rng), None);
+    /// ```
+    pub fn sample<R>(&self, rng: &mut R) -> Option<D>
+    where
+        D: Bounded + Copy + ToPrimitive + FromPrimitive,
+        R: FnMut() -> u64,
+    {
+        let (lower, upper) = self.as_explicit();
+        let (lower, upper) = (lower.to_i128()?, upper.to_i128()?);
+        if lower > upper {
+            return None;
+        }
+
+        // A 128-bit draw keeps the bias of the modulo negligible. The number of values only
+        // overflows for the whole range of i128, where every draw is already uniform.
+        let draw = (u128::from(rng()) << 64) | u128::from(rng());
+        let offset = match (upper.abs_diff(lower)).checked_add(1) {
+            Some(values) => draw % values,
+            None => draw,
+        };
+        D::from_i128(lower.wrapping_add(offset as i128))
+    }
+
+    /// Returns an iterator over the values of the interval, in increasing order.
+    ///
+    /// The iterator is meant for small intervals, since an unbounded one holds every value of
+    /// the type.
+    ///
+    /// ```
+    /// use rust_efsm::bound::Bound;
+    ///
+    /// let bound = Bound { lower: Some(253_u8), upper: None };
+    /// assert_eq!(bound.iter().collect::<Vec<_>>(), [253, 254, 255]);
+    /// assert_eq!(Bound { lower: Some(2), upper: Some(1) }.iter().count(), 0);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = D>
+    where
+        D: Integer + Bounded + Copy,
+    {
+        let (lower, upper) = self.as_explicit();
+        // The successor of the upper endpoint is never computed, so the maximum does not
+        // overflow.
+        std::iter::successors(Some(lower).filter(|lower| *lower <= upper), move |value| {
+            (*value < upper).then(|| *value + D::one())
+        })
+    }
+}
+
+/// A union of disjoint intervals.
+///
+/// The intervals are kept sorted, so checking whether a value belongs to the set is a binary
+/// search over them.
+///
+/// ```
+/// use rust_efsm::bound::{Bound, BoundSet};
+///
+/// let mut set = BoundSet::new();
+/// set.insert(Bound { lower: Some(0_u32), upper: Some(10) });
+/// set.insert(Bound { lower: Some(20), upper: Some(30) });
+/// set.insert(Bound { lower: Some(5), upper: Some(12) });
+///
+/// assert!(set.contains(&12));
+/// assert!(!set.contains(&15));
+/// assert!(set.contains(&25));
+/// assert_eq!(set.iter().count(), 2);
+/// ```
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct BoundSet<D> {
+    // Sorted, disjoint, explicit intervals.
+    bounds: Vec<(D, D)>,
+}
+
+impl<D> BoundSet<D> {
+    /// Creates an empty set.
+    pub fn new() -> Self {
+        BoundSet { bounds: Vec::new() }
+    }
+
+    /// Checks if the set contains no values.
+    pub fn is_empty(&self) -> bool {
+        self.bounds.is_empty()
+    }
+
+    /// Adds every value of `bound` to the set, merging it with the intervals it overlaps.
+    pub fn insert(&mut self, bound: Bound<D>)
+    where
+        D: Ord + Copy + Bounded,
+    {
+        let (mut lower, mut upper) = bound.as_explicit();
+        if lower > upper {
+            return;
+        }
+
+        // The overlapping intervals form a contiguous range of the sorted intervals.
+        let start = self.bounds.partition_point(|(_, u)| *u < lower);
+        let end = self.bounds.partition_point(|(l, _)| *l <= upper);
+
+        if start < end {
+            lower = min(lower, self.bounds[start].0);
+            upper = max(upper, self.bounds[end - 1].1);
+        }
+
+        self.bounds.splice(start..end, [(lower, upper)]);
+    }
+
+    /// Checks if some interval of the set contains `data`.
+    pub fn contains(&self, data: &D) -> bool
+    where
+        D: Ord,
+    {
+        let idx = self.bounds.partition_point(|(lower, _)| lower <= data);
+        idx > 0 && self.bounds[idx - 1].1 >= *data
+    }
+
+    /// Checks if some interval of the set shares a value with `bound`.
+    ///
+    /// ```
+    /// use rust_efsm::bound::{Bound, BoundSet};
+    ///
+    /// let set = BoundSet::from(Bound { lower: Some(10_u32), upper: Some(20) });
+    ///
+    /// assert!(set.intersects(&Bound { lower: Some(15), upper: Some(30) }));
+    /// assert!(!set.intersects(&Bound { lower: Some(21), upper: None }));
+    /// ```
+    pub fn intersects(&self, bound: &Bound<D>) -> bool
+    where
+        D: Ord + Copy + Bounded,
+    {
+        let (lower, upper) = bound.as_explicit();
+        if lower > upper {
+            return false;
+        }
+
+        let idx = self.bounds.partition_point(|(_, u)| *u < lower);
+        idx < self.bounds.len() && self.bounds[idx].0 <= upper
+    }
+
+    /// Iterates over the intervals of the set in increasing order.
+    pub fn iter(&self) -> impl Iterator<Item = Bound<D>> + '_
+    where
+        D: Bounded + Copy + Eq,
+    {
+        self.bounds.iter().map(|bound| Bound::from_explicit(*bound))
+    }
+}
+
+impl<D> From<Bound<D>> for BoundSet<D>
+where
+    D: Ord + Copy + Bounded,
+{
+    fn from(bound: Bound<D>) -> Self {
+        let mut set = BoundSet::new();
+        set.insert(bound);
+        set
+    }
 }
@@ -0,0 +1,155 @@
+//! # Codegen Module
+//!
+//! This module turns a [specification](crate::spec) into Rust source code building the same
+//! machine with a [MachineBuilder](crate::machine::MachineBuilder). Where [spec::parse] interprets
+//! the guards of a specification at runtime, the generated code compiles them into plain
+//! functions, as a hand-written machine would, while the property stays in a specification file.
+//!
+//! It is meant to be called from a build script, with this crate as a build dependency.
+//!
+//! ```text
+//! // build.rs
+//! fn main() {
+//!     let out = std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("counter.rs");
+//!     rust_efsm::codegen::generate::<u32, u8>("counter.spec", out).unwrap();
+//!     println!("cargo:rerun-if-changed=counter.spec");
+//! }
+//!
+//! // src/lib.rs
+//! mod counter {
+//!     include!(concat!(env!("OUT_DIR"), "/counter.rs"));
+//! }
+//!
+//! let machine = counter::machine();
+//! ```
+//!
+//! The data and input types must be primitive integers, or characters for the input, since the
+//! guards compare them as `i128`.
+
+use crate::expr::{Expr, Operand};
+use crate::spec::{self, Item, SpecError};
+use num::{Bounded, FromPrimitive, ToPrimitive, Zero};
+use std::fmt::Debug;
+use std::path::Path;
+
+/// Generates the code of the machine described by `source`, as a function `machine`.
+///
+/// As with [spec::parse], a guard that no data value can satisfy is rejected.
+///
+/// ```
+/// use rust_efsm::codegen;
+///
+/// let code = codegen::to_rust::<u32, u8>(
+///     "accepting s1
+///      rejecting error
+///      s0 -> s1 when data <= 3 && input == 'b' do data += 1
+///      s0 -> error when input == '!'",
+/// )
+/// .unwrap();
+///
+/// assert!(code.contains("pub fn machine()"));
+/// assert!(code.contains("((*_data as i128) <= 3i128) && ((*_input as i128) == 98i128)"));
+/// assert!(code.contains("upper: Some(3)"));
+/// assert!(code.contains(".with_accepting(\"s1\")"));
+/// assert!(code.contains(".with_rejecting(\"error\")"));
+/// ```
+pub fn to_rust<D, I>(source: &str) -> Result<String, SpecError>
+where
+    D: Bounded + Copy + Debug + Eq + FromPrimitive + ToPrimitive + Zero,
+{
+    let data = std::any::type_name::<D>();
+    let input = std::any::type_name::<I>();
+
+    let mut code = format!(
+        "// Generated by rust_efsm::codegen from a specification. Do not edit.\n\
+         \n\
+         #[allow(clippy::all, unused_parens)]\n\
+         pub fn machine() -> ::rust_efsm::machine::Machine<{data}, {input}, \
+         ::rust_efsm::machine::AddUpdate<{data}>> {{\n    \
+         ::rust_efsm::machine::MachineBuilder::new()\n",
+    );
+
+    for item in spec::items(source, Expr::parse, spec::amount)? {
+        match item {
+            Item::Accepting(locations) => {
+                for location in locations {
+                    code.push_str(&format!("        .with_accepting({:?})\n", location));
+                }
+            }
+            Item::Rejecting(locations) => {
+                for location in locations {
+                    code.push_str(&format!("        .with_rejecting({:?})\n", location));
+                }
+            }
+            Item::Transition {
+                line,
+                from_location,
+                to_location,
+                guard,
+                update: amount,
+            } => {
+                let guard = guard.unwrap_or(Expr::Bool(true));
+                let amount = match amount {
+                    Some(amount) => D::from_i128(amount).ok_or_else(|| spec::out_of_range(line))?,
+                    None => D::zero(),
+                };
+                let bound = spec::bound::<D>(line, &guard)?;
+                let bound = format!(
+                    "::rust_efsm::bound::Bound {{ lower: {:?}, upper: {:?} }}",
+                    bound.lower, bound.upper
+                );
+
+                let lines = [
+                    format!(
+                        ".with_transition({:?}, ::rust_efsm::machine::Transition {{",
+                        from_location
+                    ),
+                    format!("    to_location: {:?}.into(),", to_location),
+                    format!("    enable: |_data, _input| {},", rust(&guard)),
+                    format!("    bound: {},", bound),
+                    format!(
+                        "    update: ::rust_efsm::machine::AddUpdate {{ amount: {:?} }},",
+                        amount
+                    ),
+                    "    ..Default::default()".into(),
+                    "})".into(),
+                ];
+                for line in lines {
+                    code.push_str(&format!("        {}\n", line));
+                }
+            }
+        }
+    }
+
+    code.push_str("        .build()\n}\n");
+    Ok(code)
+}
+
+/// Reads the specification file at `spec`, and writes the code of its machine to `out`.
+///
+/// See [to_rust] for the generated code.
+pub fn generate<D, I>(spec: impl AsRef<Path>, out: impl AsRef<Path>) -> Result<(), SpecError>
+where
+    D: Bounded + Copy + Debug + Eq + FromPrimitive + ToPrimitive + Zero,
+{
+    let source = std::fs::read_to_string(spec).map_err(SpecError::Io)?;
+    let code = to_rust::<D, I>(&source)?;
+    std::fs::write(out, code).map_err(SpecError::Io)
+}
+
+// Writes `expr` as a Rust expression over the references `_data` and `_input`.
+fn rust(expr: &Expr) -> String {
+    let operand = |operand: &Operand| match operand {
+        Operand::Data => "(*_data as i128)".to_string(),
+        Operand::Input => "(*_input as i128)".to_string(),
+        Operand::Const(value) => format!("{}i128", value),
+    };
+
+    match expr {
+        Expr::Bool(value) => value.to_string(),
+        Expr::Cmp(lhs, op, rhs) => format!("({} {} {})", operand(lhs), op, operand(rhs)),
+        Expr::Not(expr) => format!("!{}", rust(expr)),
+        Expr::And(lhs, rhs) => format!("({} && {})", rust(lhs), rust(rhs)),
+        Expr::Or(lhs, rhs) => format!("({} || {})", rust(lhs), rust(rhs)),
+    }
+}
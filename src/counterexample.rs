@@ -0,0 +1,288 @@
+//! # Counterexample Module
+//!
+//! This module reads the counterexample traces printed by model checkers, so they can be
+//! replayed on a [Machine] to confirm that the model and the machine agree. A trace is parsed
+//! into a sequence of [TraceSteps](TraceStep), which a user mapping turns into inputs.
+//!
+//! Two formats are supported:
+//!
+//! - [TraceFormat::NuSmv] reads the `-> State: 1.2 <-` and `-> Input: 1.2 <-` blocks printed by
+//!   NuSMV and nuXmv. Each block only lists the variables that changed, so every step holds the
+//!   values of all variables known so far.
+//! - [TraceFormat::Uppaal] reads the `State:` and `Transition:` blocks printed by `verifyta`.
+//!   A state step maps each process to its location and each variable to its value, and a
+//!   transition step holds the `guard`, `sync`, and `assign` labels of its first edge.
+
+use crate::machine::{Machine, MachineError, Update};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::Debug;
+
+/// The formats of counterexample traces that can be parsed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TraceFormat {
+    /// The trace format of NuSMV and nuXmv.
+    NuSmv,
+    /// The textual trace format of UPPAAL's `verifyta`.
+    Uppaal,
+}
+
+/// One step of a counterexample trace.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TraceStep {
+    /// The kind of the step, such as `State`, `Input`, or `Transition`.
+    pub kind: String,
+    /// The values in the step, by name.
+    pub values: BTreeMap<String, String>,
+    /// Whether the loop of a lasso-shaped counterexample starts at this step.
+    pub loop_start: bool,
+}
+
+/// An error found while parsing a counterexample trace.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceError {
+    /// The one-based line number.
+    pub line: usize,
+    /// A description of the problem.
+    pub message: String,
+}
+
+impl fmt::Display for TraceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for TraceError {}
+
+/// Parses a counterexample trace printed in `format`.
+///
+/// ```
+/// use rust_efsm::counterexample::{parse, TraceFormat};
+///
+/// let steps = parse(
+///     "Trace Type: Counterexample
+///        -> State: 1.1 <-
+///          count = 0
+///          ok = TRUE
+///        -> Input: 1.2 <-
+///          event = req
+///        -> State: 1.2 <-
+///          count = 1",
+///     TraceFormat::NuSmv,
+/// )
+/// .unwrap();
+///
+/// assert_eq!(steps.len(), 3);
+/// assert_eq!(steps[1].kind, "Input");
+/// assert_eq!(steps[2].values["count"], "1");
+/// assert_eq!(steps[2].values["ok"], "TRUE");
+/// ```
+pub fn parse(source: &str, format: TraceFormat) -> Result<Vec<TraceStep>, TraceError> {
+    match format {
+        TraceFormat::NuSmv => parse_nusmv(source),
+        TraceFormat::Uppaal => parse_uppaal(source),
+    }
+}
+
+/// Maps each step of a trace to an input with `map`, skipping the steps for which it returns
+/// `None`.
+pub fn to_inputs<I, F>(steps: &[TraceStep], map: F) -> Vec<I>
+where
+    F: FnMut(&TraceStep) -> Option<I>,
+{
+    steps.iter().filter_map(map).collect()
+}
+
+impl<D, I, U> Machine<D, I, U> {
+    /// Runs the inputs mapped from the steps of a counterexample trace, starting from
+    /// `location` with `data`, and returns whether the machine accepts them.
+    ///
+    /// ```
+    /// use rust_efsm::counterexample::{parse, TraceFormat};
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    ///
+    /// // A request must never follow a request.
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("idle", Transition {
+    ///         to_location: "busy".into(),
+    ///         enable: |_, i| *i == b'q',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("busy", Transition {
+    ///         to_location: "idle".into(),
+    ///         enable: |_, i| *i == b'a',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting_all(["idle", "busy"])
+    ///     .build();
+    ///
+    /// let steps = parse(
+    ///     "State:
+    ///      ( P.idle ) x=0
+    ///
+    ///      Transition:
+    ///        P.idle -> P.busy { 1, req!, 1 }
+    ///
+    ///      State:
+    ///      ( P.busy ) x=0
+    ///
+    ///      Transition:
+    ///        P.busy -> P.busy { 1, req!, 1 }",
+    ///     TraceFormat::Uppaal,
+    /// )
+    /// .unwrap();
+    ///
+    /// // The model checker found two requests in a row, which the machine rejects too.
+    /// let accepted = machine
+    ///     .replay("idle", 0, &steps, |step| match step.values.get("sync")?.as_str() {
+    ///         "req!" => Some(b'q'),
+    ///         "ack!" => Some(b'a'),
+    ///         _ => None,
+    ///     })
+    ///     .unwrap();
+    /// assert!(!accepted);
+    /// ```
+    pub fn replay<F>(
+        &self,
+        location: &str,
+        data: D,
+        steps: &[TraceStep],
+        map: F,
+    ) -> Result<bool, MachineError>
+    where
+        D: Clone + Debug,
+        I: Debug,
+        U: Update<D = D>,
+        F: FnMut(&TraceStep) -> Option<I>,
+    {
+        self.try_exec(location, data, to_inputs(steps, map))
+    }
+}
+
+fn parse_nusmv(source: &str) -> Result<Vec<TraceStep>, TraceError> {
+    let mut steps: Vec<TraceStep> = Vec::new();
+    // The values of every variable seen so far, since each block only lists changes.
+    let mut known: BTreeMap<String, String> = BTreeMap::new();
+    let mut loop_start = false;
+
+    for (idx, line) in source.lines().enumerate() {
+        let line = line.trim();
+        let error = |message: &str| TraceError {
+            line: idx + 1,
+            message: message.into(),
+        };
+
+        if line.starts_with("-- Loop starts here") {
+            loop_start = true;
+        } else if let Some(header) = line.strip_prefix("->") {
+            let header = header
+                .strip_suffix("<-")
+                .ok_or_else(|| error("expected a header of the form '-> State: 1.1 <-'"))?;
+            let (kind, _) = header
+                .split_once(':')
+                .ok_or_else(|| error("expected a header of the form '-> State: 1.1 <-'"))?;
+
+            steps.push(TraceStep {
+                kind: kind.trim().into(),
+                values: known.clone(),
+                loop_start,
+            });
+            loop_start = false;
+        } else if let Some((name, value)) = line.split_once('=') {
+            let step = steps
+                .last_mut()
+                .ok_or_else(|| error("expected a state or input header before any value"))?;
+
+            let (name, value) = (name.trim().to_string(), value.trim().to_string());
+            step.values.insert(name.clone(), value.clone());
+            known.insert(name, value);
+        }
+        // Other lines, such as the trace description, carry no values.
+    }
+
+    Ok(steps)
+}
+
+fn parse_uppaal(source: &str) -> Result<Vec<TraceStep>, TraceError> {
+    let mut steps: Vec<TraceStep> = Vec::new();
+
+    for (idx, line) in source.lines().enumerate() {
+        let line = line.trim();
+        let error = |message: &str| TraceError {
+            line: idx + 1,
+            message: message.into(),
+        };
+
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(kind) = line.strip_suffix(':') {
+            steps.push(TraceStep {
+                kind: kind.into(),
+                ..Default::default()
+            });
+            continue;
+        }
+
+        let step = steps
+            .last_mut()
+            .ok_or_else(|| error("expected a 'State:' or 'Transition:' header"))?;
+
+        match step.kind.as_str() {
+            "State" => {
+                let mut rest = line;
+
+                // The locations of the processes come first, between parentheses.
+                if let Some(inner) = rest.strip_prefix('(') {
+                    let (locations, after) = inner
+                        .split_once(')')
+                        .ok_or_else(|| error("expected ')' after the process locations"))?;
+                    for location in locations.split_whitespace() {
+                        let (process, location) = location
+                            .split_once('.')
+                            .ok_or_else(|| error("expected a location of the form 'P.loc'"))?;
+                        step.values.insert(process.into(), location.into());
+                    }
+                    rest = after;
+                }
+
+                for assignment in rest.split_whitespace() {
+                    let (name, value) = assignment
+                        .split_once('=')
+                        .ok_or_else(|| error("expected a value of the form 'x=1'"))?;
+                    step.values.insert(name.into(), value.into());
+                }
+            }
+            "Transition" => {
+                // Only the first edge of a synchronisation is kept.
+                if step.values.contains_key("sync") {
+                    continue;
+                }
+
+                let (_, labels) = line
+                    .split_once('{')
+                    .ok_or_else(|| error("expected the labels of the edge between braces"))?;
+                let labels = labels
+                    .trim_end()
+                    .strip_suffix('}')
+                    .ok_or_else(|| error("expected '}' after the labels of the edge"))?;
+
+                // Assignments are separated by commas too, so they make up the rest.
+                let labels: Vec<&str> = labels.splitn(3, ',').map(str::trim).collect();
+                let [guard, sync, assign] = labels[..] else {
+                    return Err(error("expected a guard, a sync, and an assignment"));
+                };
+
+                step.values.insert("guard".into(), guard.into());
+                step.values.insert("sync".into(), sync.into());
+                step.values.insert("assign".into(), assign.into());
+            }
+            // Other blocks, such as delays, carry no values.
+            _ => {}
+        }
+    }
+
+    Ok(steps)
+}
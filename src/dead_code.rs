@@ -0,0 +1,204 @@
+//! # Dead Code Module
+//!
+//! This module finds the parts of a [Machine] that never affect which inputs it accepts, so an
+//! over-engineered specification can be simplified. It computes an interval containing the data
+//! that can reach each location, and then reports the transitions whose
+//! [bound](crate::machine::Transition::bound) can never be met or never restricts the data, and
+//! the transitions whose update changes data that is never read again.
+//!
+//! The `enable` functions are opaque, so they are assumed to only read the input. Conditions on
+//! the data should be stated in bounds, runtime guards, or assertions for this analysis to see
+//! them. The intervals over-approximate the reachable data, so a reported bound or update is
+//! dead on every run, but some dead code may be missed.
+
+use crate::bound::Bound;
+use crate::machine::{Machine, TransitionId, Update};
+use num::Bounded;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A part of a machine that never affects which inputs it accepts.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum DeadCode {
+    /// The transition leaves a location that cannot be reached.
+    Unreachable(TransitionId),
+    /// The bound of the transition excludes all of the data that reaches its location, so the
+    /// transition is never taken.
+    UnsatisfiableBound(TransitionId),
+    /// The bound of the transition contains all of the data that reaches its location, so it
+    /// can be removed.
+    RedundantBound(TransitionId),
+    /// The update of the transition changes the data, but no bound, runtime guard, or assertion
+    /// reachable after it reads the data.
+    UnreadUpdate(TransitionId),
+}
+
+// The number of times the interval of a location may grow before it is widened.
+const WIDEN_AFTER: usize = 3;
+
+impl<D, I, U> Machine<D, I, U> {
+    /// Returns an interval containing the data of every state that can be reached from
+    /// `location` with data in `interval`, for each reachable location.
+    ///
+    /// An interval that keeps growing, such as that of a counter, is widened to the end of the
+    /// data type, so the search always terminates.
+    ///
+    /// ```
+    /// use rust_efsm::bound::Bound;
+    /// use rust_efsm::machine::{AddUpdate, MachineBuilder, Transition};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, AddUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s1".into(),
+    ///         bound: Bound { lower: None, upper: Some(9) },
+    ///         update: AddUpdate { amount: 10 },
+    ///         ..Default::default()
+    ///     })
+    ///     .build();
+    ///
+    /// let reachable = machine.reachable_intervals("s0", Bound::unbounded());
+    /// assert_eq!(reachable["s1"], Bound { lower: Some(10), upper: Some(19) });
+    /// ```
+    pub fn reachable_intervals(
+        &self,
+        location: &str,
+        interval: Bound<D>,
+    ) -> BTreeMap<String, Bound<D>>
+    where
+        D: Ord + Copy + Bounded,
+        U: Update<D = D>,
+    {
+        let mut reachable: BTreeMap<String, Bound<D>> = BTreeMap::new();
+        let mut growth: BTreeMap<String, usize> = BTreeMap::new();
+        let mut to_visit = vec![(location.to_string(), interval)];
+
+        while let Some((location, interval)) = to_visit.pop() {
+            let joined = match reachable.get(&location) {
+                Some(current) if current.contains_interval(&interval) => continue,
+                Some(current) => {
+                    let mut joined = current.clone();
+                    joined.make_contain(&interval);
+
+                    let count = growth.entry(location.clone()).or_default();
+                    *count += 1;
+                    if *count > WIDEN_AFTER {
+                        // Widen each side that grew, so that loops reach a fixed point.
+                        if joined.as_explicit().0 < current.as_explicit().0 {
+                            joined.lower = None;
+                        }
+                        if joined.as_explicit().1 > current.as_explicit().1 {
+                            joined.upper = None;
+                        }
+                    }
+                    joined
+                }
+                None => interval,
+            };
+
+            to_visit.extend(self.interval_successors(&location, &joined));
+            reachable.insert(location, joined);
+        }
+
+        reachable
+    }
+
+    /// Returns the dead code of this machine when it starts from `location` with data in
+    /// `interval`, in order.
+    ///
+    /// ```
+    /// use rust_efsm::bound::Bound;
+    /// use rust_efsm::dead_code::DeadCode;
+    /// use rust_efsm::machine::{AddUpdate, MachineBuilder, Transition, TransitionId};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, AddUpdate<u8>>::new()
+    ///     // The data starts at 0, so this bound always holds.
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s1".into(),
+    ///         bound: Bound { lower: None, upper: Some(5) },
+    ///         update: AddUpdate { amount: 1 },
+    ///         ..Default::default()
+    ///     })
+    ///     // Nothing from "s1" on reads the data, so neither count matters.
+    ///     .with_transition("s1", Transition {
+    ///         to_location: "s2".into(),
+    ///         update: AddUpdate { amount: 1 },
+    ///         ..Default::default()
+    ///     })
+    ///     // The data entering "s0" is 0, so this transition is never taken.
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s2".into(),
+    ///         bound: Bound { lower: Some(10), upper: None },
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("s3", Transition { to_location: "s2".into(), ..Default::default() })
+    ///     .with_accepting("s2")
+    ///     .build();
+    ///
+    /// let dead = machine.find_dead_code("s0", Bound { lower: Some(0), upper: Some(0) });
+    /// assert_eq!(dead, vec![
+    ///     DeadCode::Unreachable(TransitionId::new("s3", 0)),
+    ///     DeadCode::UnsatisfiableBound(TransitionId::new("s0", 1)),
+    ///     DeadCode::RedundantBound(TransitionId::new("s0", 0)),
+    ///     DeadCode::UnreadUpdate(TransitionId::new("s0", 0)),
+    ///     DeadCode::UnreadUpdate(TransitionId::new("s1", 0)),
+    /// ]);
+    /// ```
+    pub fn find_dead_code(&self, location: &str, interval: Bound<D>) -> Vec<DeadCode>
+    where
+        D: Ord + Copy + Bounded,
+        U: Update<D = D>,
+    {
+        let reachable = self.reachable_intervals(location, interval);
+        let reads = self.locations_reading_data();
+        let nfa = self.project_locations();
+
+        let mut dead = BTreeSet::new();
+        for id in self.transition_ids() {
+            let Some(transition) = self.get_transition(&id) else {
+                continue;
+            };
+
+            let Some(interval) = reachable.get(&id.from_location) else {
+                dead.insert(DeadCode::Unreachable(id));
+                continue;
+            };
+
+            let Some(enabled) = interval.intersect(&transition.bound) else {
+                dead.insert(DeadCode::UnsatisfiableBound(id));
+                continue;
+            };
+
+            if transition.bound != Bound::unbounded()
+                && transition.bound.contains_interval(interval)
+            {
+                dead.insert(DeadCode::RedundantBound(id.clone()));
+            }
+
+            let changes =
+                transition.update_interval(enabled.clone()).as_explicit() != enabled.as_explicit();
+            if changes && nfa.reachable(&transition.to_location).is_disjoint(&reads) {
+                dead.insert(DeadCode::UnreadUpdate(id));
+            }
+        }
+
+        dead.into_iter().collect()
+    }
+
+    // Returns the locations whose transitions or assertions read the data.
+    fn locations_reading_data(&self) -> BTreeSet<String> {
+        let mut reads: BTreeSet<String> = self
+            .get_locations()
+            .iter()
+            .filter(|(_, transitions)| {
+                transitions.iter().any(|transition| {
+                    transition.guard.is_some()
+                        || transition.bound.lower.is_some()
+                        || transition.bound.upper.is_some()
+                })
+            })
+            .map(|(location, _)| location.clone())
+            .collect();
+
+        reads.extend(self.asserted_locations().cloned());
+        reads
+    }
+}
@@ -0,0 +1,254 @@
+//! # Deadline Module
+//!
+//! This module adds deadlines to a [MachineBuilder]: after entering a location, an input must
+//! arrive within a number of inputs, or of time units, or the run moves to a rejecting sink.
+//! The data counts the inputs, or adds up the time, since the location was entered, with a
+//! [CountSinceUpdate] or a [TimeSinceUpdate]. The helpers generate the transitions, bounds, and
+//! updates that would otherwise be written by hand for each deadline.
+//!
+//! The transitions into a location with a deadline should reset the data, so that the count
+//! starts when the location is entered.
+//!
+//! ```
+//! use rust_efsm::machine::{MachineBuilder, Transition};
+//! use rust_efsm::monitor::{Monitor, Verdict};
+//! use rust_efsm::past::CountSinceUpdate;
+//!
+//! // Every request 'q' must be answered by an 'a' within three inputs.
+//! let machine = MachineBuilder::<u8, u8, CountSinceUpdate<u8>>::new()
+//!     .with_transition("idle", Transition {
+//!         to_location: "waiting".into(),
+//!         enable: |_, i| *i == b'q',
+//!         update: CountSinceUpdate::reset(),
+//!         ..Default::default()
+//!     })
+//!     .with_transition("idle", Transition {
+//!         to_location: "idle".into(),
+//!         enable: |_, i| *i != b'q',
+//!         ..Default::default()
+//!     })
+//!     .with_deadline("waiting", "idle", |i| *i == b'a', 3, "late")
+//!     .with_accepting_all(["idle", "waiting"])
+//!     .build();
+//!
+//! let monitor = Monitor::new("idle", 0, machine).unwrap();
+//! assert_eq!(monitor.check(b"qxxaqa").unwrap(), Verdict::Inconclusive);
+//! assert_eq!(monitor.check(b"qxxx").unwrap(), Verdict::Violated);
+//! ```
+
+use crate::bound::Bound;
+use crate::machine::{InputAbstraction, MachineBuilder, Transition, Update};
+use crate::past::{CountSinceUpdate, TimeSinceUpdate};
+use num::{Bounded, CheckedAdd, CheckedSub, One, Zero};
+use std::fmt::Debug;
+use std::sync::Arc;
+
+impl<D, I> MachineBuilder<D, I, CountSinceUpdate<D>>
+where
+    D: Default
+        + Debug
+        + Zero
+        + One
+        + Bounded
+        + CheckedAdd
+        + CheckedSub
+        + Ord
+        + Copy
+        + Send
+        + Sync
+        + 'static,
+    I: Debug + 'static,
+{
+    /// Add transitions out of `location` requiring an input satisfying `arrives` within the
+    /// next `n` inputs.
+    ///
+    /// Such an input moves to `to_location` and resets the count. Once the deadline has passed,
+    /// any input moves to `sink` instead, which is marked as rejecting. Other inputs stay in
+    /// `location`. The location should have no other transitions.
+    ///
+    /// ```
+    /// use rust_efsm::machine::MachineBuilder;
+    /// use rust_efsm::past::CountSinceUpdate;
+    ///
+    /// let machine = MachineBuilder::<u8, u8, CountSinceUpdate<u8>>::new()
+    ///     .with_deadline("open", "closed", |i| *i == b'c', 2, "late")
+    ///     .build();
+    ///
+    /// assert!(machine.exec_report("open", 0, b"xc".to_vec()).died_at.is_none());
+    /// assert!(machine.exec_report("open", 0, b"xxc".to_vec()).died_at.is_some());
+    /// ```
+    pub fn with_deadline(
+        self,
+        location: &str,
+        to_location: &str,
+        arrives: fn(&I) -> bool,
+        n: D,
+        sink: &str,
+    ) -> Self {
+        with_deadline_transitions(
+            self,
+            location,
+            to_location,
+            arrives,
+            (n, D::one()),
+            sink,
+            (CountSinceUpdate::reset(), CountSinceUpdate::count()),
+        )
+    }
+}
+
+impl<D, I> MachineBuilder<D, I, TimeSinceUpdate<D>>
+where
+    D: Default
+        + Debug
+        + Zero
+        + Bounded
+        + CheckedAdd
+        + CheckedSub
+        + One
+        + Ord
+        + Copy
+        + Send
+        + Sync
+        + 'static,
+    I: Debug + 'static,
+{
+    /// Add transitions out of `location` requiring an input satisfying `arrives` within
+    /// `deadline` time units, assuming that `period` elapses between consecutive inputs.
+    ///
+    /// The transitions are those of [with_deadline](MachineBuilder::with_deadline), with the
+    /// data adding up the time instead of counting inputs.
+    ///
+    /// ```
+    /// use rust_efsm::machine::MachineBuilder;
+    /// use rust_efsm::past::TimeSinceUpdate;
+    ///
+    /// // An acknowledgement within 100 milliseconds, with an input every 30.
+    /// let machine = MachineBuilder::<u32, u8, TimeSinceUpdate<u32>>::new()
+    ///     .with_time_deadline("sent", "acked", |i| *i == b'a', 100, 30, "timeout")
+    ///     .build();
+    ///
+    /// assert!(machine.exec_report("sent", 0, b"xxa".to_vec()).died_at.is_none());
+    /// assert!(machine.exec_report("sent", 0, b"xxxa".to_vec()).died_at.is_some());
+    /// ```
+    pub fn with_time_deadline(
+        self,
+        location: &str,
+        to_location: &str,
+        arrives: fn(&I) -> bool,
+        deadline: D,
+        period: D,
+        sink: &str,
+    ) -> Self {
+        with_deadline_transitions(
+            self,
+            location,
+            to_location,
+            arrives,
+            (deadline, period),
+            sink,
+            (TimeSinceUpdate::reset(), TimeSinceUpdate::elapse(period)),
+        )
+    }
+}
+
+// Adds the transitions of a deadline, given as the pair of the deadline and the time elapsed
+// per input, with the pair of updates resetting and advancing the data.
+//
+// The input read with the data at `t` arrives at `t + period`, so an input satisfying `arrives`
+// is in time up to `deadline - period`, and another input leaves time for one more up to
+// `deadline - 2 * period`.
+fn with_deadline_transitions<D, I, U>(
+    builder: MachineBuilder<D, I, U>,
+    location: &str,
+    to_location: &str,
+    arrives: fn(&I) -> bool,
+    (deadline, period): (D, D),
+    sink: &str,
+    (reset, advance): (U, U),
+) -> MachineBuilder<D, I, U>
+where
+    D: Default
+        + Debug
+        + Bounded
+        + CheckedAdd
+        + CheckedSub
+        + One
+        + Ord
+        + Copy
+        + Send
+        + Sync
+        + 'static,
+    I: Debug + 'static,
+    U: Update<D = D> + Clone,
+{
+    let in_time = deadline.checked_sub(&period);
+    let next_in_time = in_time.and_then(|last| last.checked_sub(&period));
+    let after = |last: Option<D>| match last {
+        Some(last) => last.checked_add(&D::one()).map(|first| Bound {
+            lower: Some(first),
+            upper: None,
+        }),
+        None => Some(Bound::unbounded()),
+    };
+
+    let mut builder = builder.with_rejecting(sink);
+    if let Some(last) = in_time {
+        builder = builder.with_transition(
+            location,
+            guarded(to_location, bound_to(last), reset, arrives),
+        );
+    }
+    if let Some(bound) = after(in_time) {
+        builder = builder.with_transition(location, guarded(sink, bound, advance.clone(), arrives));
+    }
+    if let Some(last) = next_in_time {
+        builder = builder.with_transition(
+            location,
+            guarded(location, bound_to(last), advance.clone(), move |i| {
+                !arrives(i)
+            }),
+        );
+    }
+    if let Some(bound) = after(next_in_time) {
+        builder = builder.with_transition(
+            location,
+            guarded(sink, bound, advance, move |i| !arrives(i)),
+        );
+    }
+    builder
+}
+
+// Returns the bound holding the data up to `last`.
+fn bound_to<D>(last: D) -> Bound<D> {
+    Bound {
+        lower: None,
+        upper: Some(last),
+    }
+}
+
+// Creates a transition to `to_location`, enabled when the data lies within `bound` and `when`
+// holds for the input. The bound is also checked at runtime, since the guard alone decides.
+pub(crate) fn guarded<D, I, U>(
+    to_location: &str,
+    bound: Bound<D>,
+    update: U,
+    when: impl Fn(&I) -> bool + Send + Sync + 'static,
+) -> Transition<D, I, U>
+where
+    D: Ord + Copy + Bounded + Send + Sync + 'static,
+{
+    let within = bound.clone();
+    Transition {
+        to_location: to_location.into(),
+        enable: |_, _| true,
+        bound,
+        update,
+        guard: Some(Arc::new(move |data: &D, input: &I| {
+            within.contains(data) && when(input)
+        })),
+        name: None,
+        input: InputAbstraction::Unknown,
+        binding: None,
+    }
+}
@@ -0,0 +1,175 @@
+//! # DFA Module
+//!
+//! This module exports a [Machine] to [Dfa], a plain deterministic automaton stored as a
+//! transition table over the positions of a finite alphabet. A DFA can only be built when the
+//! data reachable over that alphabet is finite, but it then runs without evaluating any guard or
+//! update, and its table can be loaded by other matchers.
+
+use crate::machine::{Machine, MachineError, State, Update};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+/// A deterministic finite automaton over the symbols `0..alphabet_len`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Dfa {
+    /// The number of symbols in the alphabet.
+    pub alphabet_len: usize,
+    /// The state entered from each state on each symbol.
+    pub transitions: Vec<Vec<usize>>,
+    /// Whether each state is accepting.
+    pub accepting: Vec<bool>,
+    /// The initial state.
+    pub start: usize,
+}
+
+impl Dfa {
+    /// Returns the state entered from `state` on `symbol`.
+    pub fn next(&self, state: usize, symbol: usize) -> usize {
+        self.transitions[state][symbol]
+    }
+
+    /// Checks if the sequence of symbols leads from the initial state to an accepting state.
+    pub fn accepts(&self, symbols: impl IntoIterator<Item = usize>) -> bool {
+        let state = symbols
+            .into_iter()
+            .fold(self.start, |state, symbol| self.next(state, symbol));
+        self.accepting[state]
+    }
+
+    /// Writes the automaton as a plain text table.
+    ///
+    /// The first line holds the number of states, the number of symbols, and the initial state.
+    /// Each following line describes one state: whether it is accepting (`1` or `0`) followed by
+    /// the state entered on each symbol.
+    ///
+    /// ```
+    /// use rust_efsm::dfa::Dfa;
+    ///
+    /// let dfa = Dfa {
+    ///     alphabet_len: 2,
+    ///     transitions: vec![vec![1, 0], vec![1, 1]],
+    ///     accepting: vec![false, true],
+    ///     start: 0,
+    /// };
+    ///
+    /// assert_eq!(dfa.to_table(), "2 2 0\n0 1 0\n1 1 1\n");
+    /// ```
+    pub fn to_table(&self) -> String {
+        let mut table = format!(
+            "{} {} {}\n",
+            self.transitions.len(),
+            self.alphabet_len,
+            self.start
+        );
+
+        for (accepting, next) in self.accepting.iter().zip(&self.transitions) {
+            let _ = write!(table, "{}", u8::from(*accepting));
+            for state in next {
+                let _ = write!(table, " {}", state);
+            }
+            table.push('\n');
+        }
+
+        table
+    }
+}
+
+impl<D, I, U> Machine<D, I, U> {
+    /// Builds a DFA accepting the sequences of positions in `alphabet` whose inputs this machine
+    /// accepts from `location` with `data`.
+    ///
+    /// The DFA is built by exploring the sets of states reachable over `alphabet`. Returns
+    /// [MachineError::Undecidable] when more than `max_states` sets are found, which happens when
+    /// the reachable data is not finite.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{AddUpdate, MachineBuilder, Transition};
+    ///
+    /// // Accept sequences with at least two 'a's, counting up to two.
+    /// let machine = MachineBuilder::<u8, u8, AddUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s0".into(),
+    ///         enable: |data, i| *data < 2 && *i == b'a',
+    ///         update: AddUpdate { amount: 1 },
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s0".into(),
+    ///         enable: |data, i| *data == 2 || *i != b'a',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "done".into(),
+    ///         enable: |data, _| *data == 2,
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("done", Transition { to_location: "done".into(), ..Default::default() })
+    ///     .with_accepting("done")
+    ///     .build();
+    ///
+    /// let dfa = machine.to_dfa("s0", 0, &[b'a', b'b'], 64).unwrap();
+    /// assert!(dfa.accepts([0, 1, 0, 1]));
+    /// assert!(!dfa.accepts([0, 1, 1]));
+    /// ```
+    pub fn to_dfa(
+        &self,
+        location: &str,
+        data: D,
+        alphabet: &[I],
+        max_states: usize,
+    ) -> Result<Dfa, MachineError>
+    where
+        D: Clone + Ord,
+        U: Update<D = D>,
+    {
+        let initial = BTreeSet::from([State {
+            location: location.into(),
+            data,
+        }]);
+
+        let mut ids: BTreeMap<BTreeSet<State<D>>, usize> = BTreeMap::from([(initial.clone(), 0)]);
+        let mut sets = vec![initial];
+        let mut transitions = Vec::new();
+
+        // Sets are numbered in the order they are found, so each is explored exactly once.
+        while let Some(set) = sets.get(transitions.len()).cloned() {
+            let mut next = Vec::with_capacity(alphabet.len());
+
+            for i in alphabet {
+                let next_set: BTreeSet<State<D>> = self
+                    .transition(i, set.iter().cloned().collect())?
+                    .into_iter()
+                    .collect();
+
+                let id = match ids.get(&next_set) {
+                    Some(id) => *id,
+                    None => {
+                        if sets.len() >= max_states {
+                            return Err(MachineError::Undecidable);
+                        }
+
+                        ids.insert(next_set.clone(), sets.len());
+                        sets.push(next_set);
+                        sets.len() - 1
+                    }
+                };
+
+                next.push(id);
+            }
+
+            transitions.push(next);
+        }
+
+        let accepting = sets
+            .iter()
+            .map(|set| set.iter().any(|state| self.is_accepting(state)))
+            .collect();
+
+        Ok(Dfa {
+            alphabet_len: alphabet.len(),
+            transitions,
+            accepting,
+            start: 0,
+        })
+    }
+}
@@ -0,0 +1,679 @@
+//! # Dynamic Module
+//!
+//! This module provides [DynMachine], a machine interpreted entirely at runtime. Its data and
+//! inputs are dynamically typed [Values](Value), much like JSON documents, and its guards and
+//! updates are [expressions](DynExpr) evaluated on them. A whole property is loaded from a
+//! specification file, so that it can be changed in production without recompiling the program
+//! that monitors it.
+//!
+//! A dynamic machine trades speed and the interval analyses of a [Machine](crate::machine::Machine)
+//! for this flexibility: every guard is evaluated by walking its syntax tree, and a
+//! [DynMonitor] only reports a violation once no state of the machine can accept anymore.
+//!
+//! ## Format
+//!
+//! The format follows the one of the [spec module](crate::spec), with richer expressions and
+//! updates assigning to fields of the data.
+//!
+//! ```text
+//! # Every request must be answered before more than two are pending.
+//! accepting idle busy
+//! rejecting overloaded
+//! idle -> busy when input.kind == "request" do data.open = 1
+//! busy -> busy when input.kind == "request" && data.open < 2 do data.open = data.open + 1
+//! busy -> overloaded when input.kind == "request" && data.open >= 2
+//! busy -> idle when input.kind == "response" && data.open == 1 do data.open = 0
+//! busy -> busy when input.kind == "response" && data.open > 1 do data.open = data.open - 1
+//! ```
+//!
+//! Several assignments are separated by commas, and all of them read the data from before the
+//! transition.
+//!
+//! Guards and assigned values follow the [grammar of the expr module](crate::expr#grammar) in
+//! full. A field missing from the data or the input reads as `null`, and a character literal
+//! reads as the integer of its code point.
+//!
+//! ## Examples
+//!
+//! ```
+//! use rust_efsm::dynamic::{DynMachine, Value};
+//!
+//! let machine = DynMachine::parse(
+//!     r#"accepting open
+//!        open -> open when input.bytes <= 100 do data.total = data.total + input.bytes
+//!        open -> closed when input.bytes > 100"#,
+//! )
+//! .unwrap();
+//!
+//! let data = Value::map([("total", Value::Int(0))]);
+//! let small = Value::map([("bytes", Value::Int(40))]);
+//! let large = Value::map([("bytes", Value::Int(400))]);
+//!
+//! assert!(machine.exec("open", data.clone(), &[small.clone(), small.clone()]).unwrap());
+//! assert!(!machine.exec("open", data, &[small, large]).unwrap());
+//! ```
+
+use crate::expr::{parse_assignments, parse_syntax, ArithOp, CmpOp, ExprError, Root, Syntax};
+use crate::json::quote;
+use crate::machine::State;
+use crate::monitor::{MonitorError, Verdict, Verify};
+use crate::spec::{self, Item, SpecError};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::Path;
+
+/// A dynamically typed value, read and written by a [DynMachine].
+///
+/// With the `serde` feature, values are read from and written to JSON documents as is.
+///
+/// ```
+/// # #[cfg(feature = "serde")]
+/// # {
+/// use rust_efsm::dynamic::Value;
+///
+/// let value: Value = serde_json::from_str(r#"{"kind": "request", "ids": [7, 8]}"#).unwrap();
+/// assert_eq!(value.get("ids"), Some(&Value::List(vec![Value::Int(7), Value::Int(8)])));
+/// assert_eq!(serde_json::to_string(&value).unwrap(), r#"{"ids":[7,8],"kind":"request"}"#);
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(untagged)
+)]
+pub enum Value {
+    /// The absence of a value.
+    #[default]
+    Null,
+    /// A truth value.
+    Bool(bool),
+    /// An integer.
+    Int(i64),
+    /// A string.
+    Str(String),
+    /// An ordered list of values.
+    List(Vec<Value>),
+    /// A map from field names to values.
+    Map(BTreeMap<String, Value>),
+}
+
+impl Value {
+    /// Creates a map from pairs of field names and values.
+    pub fn map<'a>(fields: impl IntoIterator<Item = (&'a str, Value)>) -> Self {
+        Value::Map(
+            fields
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect(),
+        )
+    }
+
+    /// Returns the field `name` of a map, if any.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        match self {
+            Value::Map(fields) => fields.get(name),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the type of the value, as used in errors.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::Bool(_) => "bool",
+            Value::Int(_) => "int",
+            Value::Str(_) => "string",
+            Value::List(_) => "list",
+            Value::Map(_) => "map",
+        }
+    }
+
+    // Returns the value at the end of `path`, or null when a field is missing.
+    fn lookup(&self, path: &[String]) -> Value {
+        let mut value = self;
+        for name in path {
+            match value.get(name) {
+                Some(field) => value = field,
+                None => return Value::Null,
+            }
+        }
+        value.clone()
+    }
+
+    // Replaces the value at the end of `path` with `new`, creating the missing maps on the way.
+    fn assign(&mut self, path: &[String], new: Value) -> Result<(), DynError> {
+        let Some((name, rest)) = path.split_first() else {
+            *self = new;
+            return Ok(());
+        };
+
+        if *self == Value::Null {
+            *self = Value::Map(BTreeMap::new());
+        }
+        match self {
+            Value::Map(fields) => fields.entry(name.clone()).or_default().assign(rest, new),
+            value => Err(DynError::Eval(format!(
+                "cannot assign field {} of a {}",
+                name,
+                value.type_name()
+            ))),
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Int(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Str(value.into())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Str(value)
+    }
+}
+
+impl fmt::Display for Value {
+    /// Writes the value as JSON.
+    ///
+    /// ```
+    /// use rust_efsm::dynamic::Value;
+    ///
+    /// let value = Value::map([("kind", "request".into()), ("id", Value::Int(7))]);
+    /// assert_eq!(value.to_string(), r#"{"id": 7, "kind": "request"}"#);
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Str(value) => write!(f, "{}", quote(value)),
+            Value::List(values) => {
+                let values: Vec<String> = values.iter().map(Value::to_string).collect();
+                write!(f, "[{}]", values.join(", "))
+            }
+            Value::Map(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(name, value)| format!("{}: {}", quote(name), value))
+                    .collect();
+                write!(f, "{{{}}}", fields.join(", "))
+            }
+        }
+    }
+}
+
+/// Errors that can occur while loading or running a [DynMachine].
+#[derive(Debug)]
+pub enum DynError {
+    /// The specification file could not be read.
+    Io(std::io::Error),
+    /// A line of the specification is malformed.
+    Syntax {
+        /// The one-based line number.
+        line: usize,
+        /// A description of the problem.
+        message: String,
+    },
+    /// An expression could not be evaluated, such as a sum of a string and an integer.
+    Eval(String),
+}
+
+impl fmt::Display for DynError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DynError::Io(error) => write!(f, "failed to read spec: {}", error),
+            DynError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+            DynError::Eval(message) => write!(f, "evaluation failed: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for DynError {}
+
+/// An expression over the data and the input of a [DynMachine].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum DynExpr {
+    /// A constant.
+    Lit(Value),
+    /// A field of the data or of the input, reached through the named fields in order.
+    Path(Root, Vec<String>),
+    /// Logical negation.
+    Not(Box<DynExpr>),
+    /// Arithmetic negation.
+    Neg(Box<DynExpr>),
+    /// An arithmetic operation.
+    Arith(ArithOp, Box<DynExpr>, Box<DynExpr>),
+    /// A comparison.
+    Cmp(CmpOp, Box<DynExpr>, Box<DynExpr>),
+    /// Logical conjunction, evaluating its right side only when needed.
+    And(Box<DynExpr>, Box<DynExpr>),
+    /// Logical disjunction, evaluating its right side only when needed.
+    Or(Box<DynExpr>, Box<DynExpr>),
+}
+
+impl DynExpr {
+    /// Parses an expression from its textual form.
+    ///
+    /// ```
+    /// use rust_efsm::dynamic::{DynExpr, Value};
+    ///
+    /// let expr = DynExpr::parse(r#"data.count * 2 + 1 > 6 && input != "reset""#).unwrap();
+    /// let data = Value::map([("count", Value::Int(3))]);
+    /// assert_eq!(expr.eval(&data, &"tick".into()).unwrap(), Value::Bool(true));
+    ///
+    /// assert!(DynExpr::parse("data.count +").is_err());
+    /// ```
+    pub fn parse(source: &str) -> Result<Self, DynError> {
+        parse_syntax(source)
+            .and_then(DynExpr::from_syntax)
+            .map_err(|error| DynError::Syntax {
+                line: 1,
+                message: error.to_string(),
+            })
+    }
+
+    // Converts a syntax tree, whose integers must fit in an i64.
+    fn from_syntax(syntax: Syntax) -> Result<Self, ExprError> {
+        let convert = |syntax: Box<Syntax>| DynExpr::from_syntax(*syntax).map(Box::new);
+
+        Ok(match syntax {
+            Syntax::Null => DynExpr::Lit(Value::Null),
+            Syntax::Bool(value) => DynExpr::Lit(Value::Bool(value)),
+            Syntax::Int(value) => DynExpr::Lit(Value::Int(
+                i64::try_from(value).map_err(|_| ExprError::InvalidLiteral(value.to_string()))?,
+            )),
+            Syntax::Str(value) => DynExpr::Lit(Value::Str(value)),
+            Syntax::Path(root, path) => DynExpr::Path(root, path),
+            Syntax::Not(syntax) => DynExpr::Not(convert(syntax)?),
+            Syntax::Neg(syntax) => DynExpr::Neg(convert(syntax)?),
+            Syntax::Arith(op, lhs, rhs) => DynExpr::Arith(op, convert(lhs)?, convert(rhs)?),
+            Syntax::Cmp(op, lhs, rhs) => DynExpr::Cmp(op, convert(lhs)?, convert(rhs)?),
+            Syntax::And(lhs, rhs) => DynExpr::And(convert(lhs)?, convert(rhs)?),
+            Syntax::Or(lhs, rhs) => DynExpr::Or(convert(lhs)?, convert(rhs)?),
+        })
+    }
+
+    /// Evaluates the expression for the given data and input.
+    pub fn eval(&self, data: &Value, input: &Value) -> Result<Value, DynError> {
+        let bool = |expr: &DynExpr| match expr.eval(data, input)? {
+            Value::Bool(value) => Ok(value),
+            value => Err(mismatch("a bool", &value)),
+        };
+
+        Ok(match self {
+            DynExpr::Lit(value) => value.clone(),
+            DynExpr::Path(Root::Data, path) => data.lookup(path),
+            DynExpr::Path(Root::Input, path) => input.lookup(path),
+            DynExpr::Not(expr) => Value::Bool(!bool(expr)?),
+            DynExpr::Neg(expr) => match expr.eval(data, input)? {
+                Value::Int(value) => Value::Int(value.checked_neg().ok_or_else(overflow)?),
+                value => return Err(mismatch("an int", &value)),
+            },
+            DynExpr::Arith(op, lhs, rhs) => {
+                arith(*op, lhs.eval(data, input)?, rhs.eval(data, input)?)?
+            }
+            DynExpr::Cmp(op, lhs, rhs) => Value::Bool(compare(
+                *op,
+                &lhs.eval(data, input)?,
+                &rhs.eval(data, input)?,
+            )?),
+            DynExpr::And(lhs, rhs) => Value::Bool(bool(lhs)? && bool(rhs)?),
+            DynExpr::Or(lhs, rhs) => Value::Bool(bool(lhs)? || bool(rhs)?),
+        })
+    }
+}
+
+impl fmt::Display for DynExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DynExpr::Lit(value) => write!(f, "{}", value),
+            DynExpr::Path(root, path) => {
+                write!(f, "{}", root)?;
+                for name in path {
+                    write!(f, ".{}", name)?;
+                }
+                Ok(())
+            }
+            DynExpr::Not(expr) => write!(f, "!({})", expr),
+            DynExpr::Neg(expr) => write!(f, "-({})", expr),
+            DynExpr::Arith(op, lhs, rhs) => write!(f, "({} {} {})", lhs, op, rhs),
+            DynExpr::Cmp(op, lhs, rhs) => write!(f, "({} {} {})", lhs, op, rhs),
+            DynExpr::And(lhs, rhs) => write!(f, "({} && {})", lhs, rhs),
+            DynExpr::Or(lhs, rhs) => write!(f, "({} || {})", lhs, rhs),
+        }
+    }
+}
+
+/// A transition of a [DynMachine].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DynTransition {
+    /// The location entered when this transition is taken.
+    pub to_location: String,
+    /// The expression deciding whether this transition is enabled, which must evaluate to a bool.
+    pub guard: DynExpr,
+    /// The fields of the data assigned when this transition is taken, each with the expression
+    /// giving its new value. An empty path replaces the whole data.
+    pub updates: Vec<(Vec<String>, DynExpr)>,
+}
+
+/// A machine whose data, inputs, guards, and updates are all interpreted at runtime.
+///
+/// Like a [Machine](crate::machine::Machine), it may be nondeterministic: every enabled
+/// transition is taken, and a state without any is dropped.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DynMachine {
+    transitions: BTreeMap<String, Vec<DynTransition>>,
+    accepting: BTreeSet<String>,
+    rejecting: BTreeSet<String>,
+}
+
+impl DynMachine {
+    /// Creates a machine without locations.
+    pub fn new() -> Self {
+        DynMachine::default()
+    }
+
+    /// Adds a transition out of `from_location`.
+    pub fn with_transition(mut self, from_location: &str, transition: DynTransition) -> Self {
+        self.transitions
+            .entry(from_location.into())
+            .or_default()
+            .push(transition);
+        self
+    }
+
+    /// Marks `location` as accepting.
+    pub fn with_accepting(mut self, location: &str) -> Self {
+        self.accepting.insert(location.into());
+        self
+    }
+
+    /// Marks `location` as rejecting: a [DynMonitor] reports a violation once every state is in
+    /// one.
+    pub fn with_rejecting(mut self, location: &str) -> Self {
+        self.rejecting.insert(location.into());
+        self
+    }
+
+    /// Parses a machine from the text of a specification.
+    ///
+    /// ```
+    /// use rust_efsm::dynamic::{DynError, DynMachine, Value};
+    ///
+    /// let error = DynMachine::parse("s0 -> s1 when input ==").unwrap_err();
+    /// assert!(matches!(error, DynError::Syntax { line: 1, .. }));
+    ///
+    /// // Keywords inside of strings do not split the line.
+    /// let machine = DynMachine::parse(
+    ///     r#"accepting s1
+    ///        s0 -> s1 when input == "to do" do data.done = true"#,
+    /// )
+    /// .unwrap();
+    /// assert!(machine.exec("s0", Value::Null, &["to do".into()]).unwrap());
+    /// ```
+    pub fn parse(source: &str) -> Result<Self, DynError> {
+        let guard = |guard: &str| parse_syntax(guard).and_then(DynExpr::from_syntax);
+        let items = spec::items(source, guard, updates).map_err(|error| match error {
+            SpecError::Io(error) => DynError::Io(error),
+            SpecError::Syntax { line, message } => DynError::Syntax { line, message },
+            SpecError::Guard { line, error } => DynError::Syntax {
+                line,
+                message: format!("invalid guard: {}", error),
+            },
+        })?;
+
+        let mut machine = DynMachine::new();
+        for item in items {
+            match item {
+                Item::Accepting(locations) => machine.accepting.extend(locations),
+                Item::Rejecting(locations) => machine.rejecting.extend(locations),
+                Item::Transition {
+                    from_location,
+                    to_location,
+                    guard,
+                    update,
+                    ..
+                } => {
+                    machine = machine.with_transition(
+                        &from_location,
+                        DynTransition {
+                            to_location,
+                            guard: guard.unwrap_or(DynExpr::Lit(Value::Bool(true))),
+                            updates: update.unwrap_or_default(),
+                        },
+                    );
+                }
+            }
+        }
+
+        Ok(machine)
+    }
+
+    /// Reads and parses a machine from the specification file at `path`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, DynError> {
+        let source = std::fs::read_to_string(path).map_err(DynError::Io)?;
+        DynMachine::parse(&source)
+    }
+
+    /// Checks if `location` is accepting.
+    pub fn is_accepting(&self, location: &str) -> bool {
+        self.accepting.contains(location)
+    }
+
+    /// Checks if `location` is rejecting.
+    pub fn is_rejecting(&self, location: &str) -> bool {
+        self.rejecting.contains(location)
+    }
+
+    /// Returns the states reached from `state` by reading `input`, one per enabled transition.
+    pub fn successors(
+        &self,
+        state: &State<Value>,
+        input: &Value,
+    ) -> Result<Vec<State<Value>>, DynError> {
+        let mut successors = Vec::new();
+        for transition in self.transitions.get(&state.location).into_iter().flatten() {
+            match transition.guard.eval(&state.data, input)? {
+                Value::Bool(true) => {}
+                Value::Bool(false) => continue,
+                value => return Err(mismatch("a bool guard", &value)),
+            }
+
+            let mut data = state.data.clone();
+            for (path, expr) in &transition.updates {
+                data.assign(path, expr.eval(&state.data, input)?)?;
+            }
+            successors.push(State {
+                location: transition.to_location.clone(),
+                data,
+            });
+        }
+        Ok(successors)
+    }
+
+    /// Checks if the inputs `inputs` belong to the language of this machine, when started in
+    /// `location` with `data`.
+    pub fn exec(&self, location: &str, data: Value, inputs: &[Value]) -> Result<bool, DynError> {
+        let mut states = vec![State {
+            location: location.into(),
+            data,
+        }];
+        for input in inputs {
+            states = self.step(&states, input)?;
+        }
+        Ok(states
+            .iter()
+            .any(|state| self.is_accepting(&state.location)))
+    }
+
+    /// Creates a monitor starting in `location` with `data`.
+    pub fn monitor(&self, location: &str, data: Value) -> DynMonitor<'_> {
+        DynMonitor {
+            machine: self,
+            states: vec![State {
+                location: location.into(),
+                data,
+            }],
+        }
+    }
+
+    // Returns the distinct successors of every state in `states`.
+    fn step(&self, states: &[State<Value>], input: &Value) -> Result<Vec<State<Value>>, DynError> {
+        let mut next: Vec<State<Value>> = Vec::new();
+        for state in states {
+            for successor in self.successors(state, input)? {
+                if !next.contains(&successor) {
+                    next.push(successor);
+                }
+            }
+        }
+        Ok(next)
+    }
+}
+
+/// A monitor of a [DynMachine], created by [DynMachine::monitor].
+///
+/// Without the analyses of a [Monitor](crate::monitor::Monitor), it reports
+/// [Verdict::Violated] once every state is in a rejecting location or no state is left, and
+/// [Verdict::Inconclusive] before.
+///
+/// ```
+/// use rust_efsm::dynamic::{DynMachine, Value};
+/// use rust_efsm::monitor::{Verdict, Verify};
+///
+/// let machine = DynMachine::parse(
+///     r#"accepting ok
+///        rejecting error
+///        ok -> ok when input.level != "fatal"
+///        ok -> error when input.level == "fatal""#,
+/// )
+/// .unwrap();
+///
+/// let mut monitor = machine.monitor("ok", Value::Null);
+/// let info = Value::map([("level", "info".into())]);
+/// let fatal = Value::map([("level", "fatal".into())]);
+/// assert_eq!(monitor.next(&info).unwrap(), Verdict::Inconclusive);
+/// assert_eq!(monitor.next(&fatal).unwrap(), Verdict::Violated);
+/// ```
+pub struct DynMonitor<'a> {
+    machine: &'a DynMachine,
+    states: Vec<State<Value>>,
+}
+
+impl DynMonitor<'_> {
+    /// Returns the states the monitored system may currently be in.
+    pub fn states(&self) -> &[State<Value>] {
+        &self.states
+    }
+
+    // Returns the verdict on the current states.
+    fn verdict(&self) -> Verdict {
+        match self
+            .states
+            .iter()
+            .all(|state| self.machine.is_rejecting(&state.location))
+        {
+            true => Verdict::Violated,
+            false => Verdict::Inconclusive,
+        }
+    }
+}
+
+impl Verify<Value> for DynMonitor<'_> {
+    /// Fails with [MonitorError::TransitionFailed] when a guard or an update cannot be evaluated.
+    fn next(&mut self, input: &Value) -> Result<Verdict, MonitorError> {
+        self.states = self
+            .machine
+            .step(&self.states, input)
+            .map_err(|error| MonitorError::TransitionFailed(error.to_string()))?;
+        Ok(self.verdict())
+    }
+
+    fn initial_verdict(&self) -> Verdict {
+        self.verdict()
+    }
+}
+
+// Returns the error for a value of another type than `expected`.
+fn mismatch(expected: &str, value: &Value) -> DynError {
+    DynError::Eval(format!("expected {}, got {}", expected, value.type_name()))
+}
+
+fn overflow() -> DynError {
+    DynError::Eval("integer overflow".into())
+}
+
+// Applies `op` to integers, or concatenates strings.
+fn arith(op: ArithOp, lhs: Value, rhs: Value) -> Result<Value, DynError> {
+    match (op, lhs, rhs) {
+        (op, Value::Int(lhs), Value::Int(rhs)) => {
+            let value = match op {
+                ArithOp::Add => lhs.checked_add(rhs),
+                ArithOp::Sub => lhs.checked_sub(rhs),
+                ArithOp::Mul => lhs.checked_mul(rhs),
+            };
+            value.map(Value::Int).ok_or_else(overflow)
+        }
+        (ArithOp::Add, Value::Str(lhs), Value::Str(rhs)) => Ok(Value::Str(lhs + &rhs)),
+        (op, lhs, rhs) => Err(DynError::Eval(format!(
+            "cannot apply {} to {} and {}",
+            op,
+            lhs.type_name(),
+            rhs.type_name()
+        ))),
+    }
+}
+
+// Compares any two values for equality, and integers or strings for order.
+fn compare(op: CmpOp, lhs: &Value, rhs: &Value) -> Result<bool, DynError> {
+    let ordering = match (op, lhs, rhs) {
+        (CmpOp::Eq, lhs, rhs) => return Ok(lhs == rhs),
+        (CmpOp::Ne, lhs, rhs) => return Ok(lhs != rhs),
+        (_, Value::Int(lhs), Value::Int(rhs)) => lhs.cmp(rhs),
+        (_, Value::Str(lhs), Value::Str(rhs)) => lhs.cmp(rhs),
+        (op, lhs, rhs) => {
+            return Err(DynError::Eval(format!(
+                "cannot compare {} {} {}",
+                lhs.type_name(),
+                op,
+                rhs.type_name()
+            )))
+        }
+    };
+
+    Ok(match op {
+        CmpOp::Lt => ordering.is_lt(),
+        CmpOp::Le => ordering.is_le(),
+        CmpOp::Gt => ordering.is_gt(),
+        CmpOp::Ge => ordering.is_ge(),
+        CmpOp::Eq | CmpOp::Ne => unreachable!(),
+    })
+}
+
+// Parses the assignments to fields of the data that update a transition.
+fn updates(source: &str) -> Result<Vec<(Vec<String>, DynExpr)>, String> {
+    let assignments = parse_assignments(source).map_err(|error| error.to_string())?;
+    assignments
+        .into_iter()
+        .map(|(root, path, syntax)| match root {
+            Root::Data => DynExpr::from_syntax(syntax)
+                .map(|expr| (path, expr))
+                .map_err(|error| error.to_string()),
+            Root::Input => Err("expected an assignment to the data".into()),
+        })
+        .collect()
+}
@@ -0,0 +1,745 @@
+//! # Expr Module
+//!
+//! This module provides a small expression language for writing transition guards as text, such
+//! as `data >= 3 && input == 'b'`. A parsed [Expr] can be turned into a runtime
+//! [guard](crate::machine::Guard) and into a [Bound] on the data that is usable by the interval
+//! analyses.
+//!
+//! ## Grammar
+//!
+//! ```text
+//! expr    := and ( "||" and )*
+//! and     := not ( "&&" not )*
+//! not     := "!" not | cmp
+//! cmp     := sum ( ( "==" | "!=" | "<" | "<=" | ">" | ">=" ) sum )?
+//! sum     := product ( ( "+" | "-" ) product )*
+//! product := unary ( "*" unary )*
+//! unary   := "-" unary | "(" expr ")" | literal | path
+//! literal := "null" | "true" | "false" | integer | 'c' | "string"
+//! path    := ( "data" | "input" ) ( "." field )*
+//! ```
+//!
+//! Character literals evaluate to their code point, so `input == 'b'` compares a `u8` input with
+//! `98`. An [Expr] only supports the part of the grammar that compares integers: truth values,
+//! and comparisons between `data`, `input`, and constants, combined with `!`, `&&`, and `||`.
+//! The whole grammar, with fields, arithmetic, and strings, is used by the
+//! [expressions of dynamic machines](crate::dynamic::DynExpr).
+//!
+//! ## Examples
+//!
+//! ```
+//! use rust_efsm::bound::Bound;
+//! use rust_efsm::expr::Expr;
+//!
+//! let expr: Expr = "data >= 3 && input == 'b'".parse().unwrap();
+//!
+//! assert!(expr.eval(3, b'b' as i128));
+//! assert!(!expr.eval(2, b'b' as i128));
+//! assert_eq!(expr.bound::<u32>(), Some(Bound { lower: Some(3), upper: None }));
+//! ```
+
+use crate::bound::Bound;
+use crate::json::quote;
+use crate::machine::Guard;
+use num::{Bounded, FromPrimitive, ToPrimitive};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// A value that can appear on either side of a comparison.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Operand {
+    /// The data value of the machine.
+    Data,
+    /// The input being read.
+    Input,
+    /// An integer or character constant.
+    Const(i128),
+}
+
+/// The value a [path](crate::dynamic::DynExpr::Path) starts from.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Root {
+    /// The data of the machine.
+    Data,
+    /// The input being read.
+    Input,
+}
+
+impl fmt::Display for Root {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Root::Data => write!(f, "data"),
+            Root::Input => write!(f, "input"),
+        }
+    }
+}
+
+/// An arithmetic operator.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ArithOp {
+    /// `+`, which also concatenates strings.
+    Add,
+    /// `-`
+    Sub,
+    /// `*`
+    Mul,
+}
+
+impl fmt::Display for ArithOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            ArithOp::Add => "+",
+            ArithOp::Sub => "-",
+            ArithOp::Mul => "*",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+/// A comparison operator.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum CmpOp {
+    /// `==`
+    Eq,
+    /// `!=`
+    Ne,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+}
+
+impl CmpOp {
+    fn apply(self, lhs: i128, rhs: i128) -> bool {
+        match self {
+            CmpOp::Eq => lhs == rhs,
+            CmpOp::Ne => lhs != rhs,
+            CmpOp::Lt => lhs < rhs,
+            CmpOp::Le => lhs <= rhs,
+            CmpOp::Gt => lhs > rhs,
+            CmpOp::Ge => lhs >= rhs,
+        }
+    }
+
+    // Returns the operator that gives the same result when the operands are swapped.
+    fn flip(self) -> Self {
+        match self {
+            CmpOp::Lt => CmpOp::Gt,
+            CmpOp::Le => CmpOp::Ge,
+            CmpOp::Gt => CmpOp::Lt,
+            CmpOp::Ge => CmpOp::Le,
+            op => op,
+        }
+    }
+}
+
+impl fmt::Display for CmpOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            CmpOp::Eq => "==",
+            CmpOp::Ne => "!=",
+            CmpOp::Lt => "<",
+            CmpOp::Le => "<=",
+            CmpOp::Gt => ">",
+            CmpOp::Ge => ">=",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::Data => write!(f, "data"),
+            Operand::Input => write!(f, "input"),
+            Operand::Const(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// A boolean expression over the data and the input of a transition.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Expr {
+    /// A constant truth value.
+    Bool(bool),
+    /// A comparison between two operands.
+    Cmp(Operand, CmpOp, Operand),
+    /// Logical negation.
+    Not(Box<Expr>),
+    /// Logical conjunction.
+    And(Box<Expr>, Box<Expr>),
+    /// Logical disjunction.
+    Or(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// Parses an expression from its textual form.
+    ///
+    /// ```
+    /// use rust_efsm::expr::{CmpOp, Expr, Operand};
+    ///
+    /// let expr = Expr::parse("input != 'b'").unwrap();
+    /// assert_eq!(expr, Expr::Cmp(Operand::Input, CmpOp::Ne, Operand::Const(98)));
+    ///
+    /// assert!(Expr::parse("data >=").is_err());
+    /// assert!(Expr::parse("data.count > 3").is_err());
+    /// ```
+    pub fn parse(source: &str) -> Result<Self, ExprError> {
+        Expr::narrow(parse_syntax(source)?)
+    }
+
+    // Narrows a syntax tree of the whole grammar to the comparisons of integers.
+    fn narrow(syntax: Syntax) -> Result<Self, ExprError> {
+        let operand = |syntax: Syntax| match syntax {
+            Syntax::Path(Root::Data, path) if path.is_empty() => Ok(Operand::Data),
+            Syntax::Path(Root::Input, path) if path.is_empty() => Ok(Operand::Input),
+            Syntax::Int(value) => Ok(Operand::Const(value)),
+            Syntax::Neg(syntax) => match *syntax {
+                Syntax::Int(value) => Ok(Operand::Const(-value)),
+                syntax => Err(ExprError::Unsupported(
+                    Syntax::Neg(Box::new(syntax)).to_string(),
+                )),
+            },
+            syntax => Err(ExprError::Unsupported(syntax.to_string())),
+        };
+
+        Ok(match syntax {
+            Syntax::Bool(value) => Expr::Bool(value),
+            Syntax::Cmp(op, lhs, rhs) => Expr::Cmp(operand(*lhs)?, op, operand(*rhs)?),
+            Syntax::Not(syntax) => Expr::Not(Box::new(Expr::narrow(*syntax)?)),
+            Syntax::And(lhs, rhs) => {
+                Expr::And(Box::new(Expr::narrow(*lhs)?), Box::new(Expr::narrow(*rhs)?))
+            }
+            Syntax::Or(lhs, rhs) => {
+                Expr::Or(Box::new(Expr::narrow(*lhs)?), Box::new(Expr::narrow(*rhs)?))
+            }
+            syntax => return Err(ExprError::Unsupported(syntax.to_string())),
+        })
+    }
+
+    /// Evaluates the expression for the given data and input values.
+    pub fn eval(&self, data: i128, input: i128) -> bool {
+        let value = |operand: &Operand| match operand {
+            Operand::Data => data,
+            Operand::Input => input,
+            Operand::Const(value) => *value,
+        };
+
+        match self {
+            Expr::Bool(value) => *value,
+            Expr::Cmp(lhs, op, rhs) => op.apply(value(lhs), value(rhs)),
+            Expr::Not(expr) => !expr.eval(data, input),
+            Expr::And(lhs, rhs) => lhs.eval(data, input) && rhs.eval(data, input),
+            Expr::Or(lhs, rhs) => lhs.eval(data, input) || rhs.eval(data, input),
+        }
+    }
+
+    /// Returns a guard that evaluates this expression at runtime.
+    ///
+    /// Data or input values that cannot be represented as an `i128` never enable the guard.
+    ///
+    /// ```
+    /// use rust_efsm::expr::Expr;
+    ///
+    /// let guard = Expr::parse("data < input").unwrap().guard::<u32, u8>();
+    /// assert!(guard(&1, &2));
+    /// assert!(!guard(&2, &2));
+    /// ```
+    pub fn guard<D, I>(&self) -> Guard<D, I>
+    where
+        D: ToPrimitive,
+        I: ToPrimitive,
+    {
+        let expr = self.clone();
+        Arc::new(
+            move |data: &D, input: &I| match (data.to_i128(), input.to_i128()) {
+                (Some(data), Some(input)) => expr.eval(data, input),
+                _ => false,
+            },
+        )
+    }
+
+    /// Returns an interval containing every data value for which the expression can be true.
+    ///
+    /// Returns `None` when no data value can satisfy the expression. Constraints that involve
+    /// the input or that cannot be expressed as an interval are over-approximated.
+    ///
+    /// ```
+    /// use rust_efsm::bound::Bound;
+    /// use rust_efsm::expr::Expr;
+    ///
+    /// let expr = Expr::parse("data > 3 && data <= 10 && input == 'a'").unwrap();
+    /// assert_eq!(expr.bound::<u8>(), Some(Bound { lower: Some(4), upper: Some(10) }));
+    ///
+    /// let expr = Expr::parse("data < 2 || data == 7").unwrap();
+    /// assert_eq!(expr.bound::<u8>(), Some(Bound { lower: None, upper: Some(7) }));
+    ///
+    /// let expr = Expr::parse("data > 300").unwrap();
+    /// assert_eq!(expr.bound::<u8>(), None);
+    /// ```
+    pub fn bound<D>(&self) -> Option<Bound<D>>
+    where
+        D: Bounded + Copy + Eq + ToPrimitive + FromPrimitive,
+    {
+        let min = D::min_value().to_i128()?;
+        let max = D::max_value().to_i128()?;
+
+        let (lower, upper) = self.explicit_bound(min, max)?;
+        let lower = D::from_i128(lower.max(min))?;
+        let upper = D::from_i128(upper.min(max))?;
+
+        Some(Bound::from_explicit((lower, upper)))
+    }
+
+    // Computes the bound as an inclusive pair inside of [min, max], or None when it is empty.
+    fn explicit_bound(&self, min: i128, max: i128) -> Option<(i128, i128)> {
+        let non_empty =
+            |(lower, upper): (i128, i128)| Some((lower, upper)).filter(|_| lower <= upper);
+
+        match self {
+            Expr::Bool(true) => Some((min, max)),
+            Expr::Bool(false) => None,
+            Expr::Cmp(Operand::Data, op, Operand::Const(value)) => {
+                let value = *value;
+                match op {
+                    CmpOp::Eq => non_empty((value.max(min), value.min(max))),
+                    CmpOp::Lt => non_empty((min, value.saturating_sub(1).min(max))),
+                    CmpOp::Le => non_empty((min, value.min(max))),
+                    CmpOp::Gt => non_empty((value.saturating_add(1).max(min), max)),
+                    CmpOp::Ge => non_empty((value.max(min), max)),
+                    CmpOp::Ne => Some((min, max)),
+                }
+            }
+            Expr::Cmp(Operand::Const(value), op, Operand::Data) => {
+                Expr::Cmp(Operand::Data, op.flip(), Operand::Const(*value)).explicit_bound(min, max)
+            }
+            Expr::Cmp(Operand::Const(lhs), op, Operand::Const(rhs)) => {
+                Some((min, max)).filter(|_| op.apply(*lhs, *rhs))
+            }
+            Expr::Cmp(_, _, _) | Expr::Not(_) => Some((min, max)),
+            Expr::And(lhs, rhs) => {
+                let (l_lower, l_upper) = lhs.explicit_bound(min, max)?;
+                let (r_lower, r_upper) = rhs.explicit_bound(min, max)?;
+                non_empty((l_lower.max(r_lower), l_upper.min(r_upper)))
+            }
+            Expr::Or(lhs, rhs) => {
+                match (lhs.explicit_bound(min, max), rhs.explicit_bound(min, max)) {
+                    (Some((l_lower, l_upper)), Some((r_lower, r_upper))) => {
+                        Some((l_lower.min(r_lower), l_upper.max(r_upper)))
+                    }
+                    (Some(bound), None) | (None, Some(bound)) => Some(bound),
+                    (None, None) => None,
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for Expr {
+    type Err = ExprError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        Expr::parse(source)
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Bool(value) => write!(f, "{}", value),
+            Expr::Cmp(lhs, op, rhs) => write!(f, "{} {} {}", lhs, op, rhs),
+            Expr::Not(expr) => write!(f, "!({})", expr),
+            Expr::And(lhs, rhs) => write!(f, "({} && {})", lhs, rhs),
+            Expr::Or(lhs, rhs) => write!(f, "({} || {})", lhs, rhs),
+        }
+    }
+}
+
+/// Errors that can occur while parsing an [Expr].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExprError {
+    /// The source contains a character that does not start any token.
+    UnexpectedChar(char),
+    /// The parser found a token it did not expect.
+    UnexpectedToken(String),
+    /// The source ended in the middle of an expression.
+    UnexpectedEnd,
+    /// A numeric, character, or string literal is malformed.
+    InvalidLiteral(String),
+    /// The expression is valid in the grammar, but not where it is used, such as a field of the
+    /// data in an [Expr].
+    Unsupported(String),
+}
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExprError::UnexpectedChar(c) => write!(f, "unexpected character '{}'", c),
+            ExprError::UnexpectedToken(token) => write!(f, "unexpected token '{}'", token),
+            ExprError::UnexpectedEnd => write!(f, "unexpected end of expression"),
+            ExprError::InvalidLiteral(literal) => write!(f, "invalid literal '{}'", literal),
+            ExprError::Unsupported(expr) => write!(f, "unsupported expression '{}'", expr),
+        }
+    }
+}
+
+// A syntax tree of the whole grammar, before it is narrowed to an Expr or converted to a DynExpr.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Syntax {
+    Null,
+    Bool(bool),
+    Int(i128),
+    Str(String),
+    Path(Root, Vec<String>),
+    Not(Box<Syntax>),
+    Neg(Box<Syntax>),
+    Arith(ArithOp, Box<Syntax>, Box<Syntax>),
+    Cmp(CmpOp, Box<Syntax>, Box<Syntax>),
+    And(Box<Syntax>, Box<Syntax>),
+    Or(Box<Syntax>, Box<Syntax>),
+}
+
+impl fmt::Display for Syntax {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Syntax::Null => write!(f, "null"),
+            Syntax::Bool(value) => write!(f, "{}", value),
+            Syntax::Int(value) => write!(f, "{}", value),
+            Syntax::Str(value) => write!(f, "{}", quote(value)),
+            Syntax::Path(root, path) => {
+                write!(f, "{}", root)?;
+                for name in path {
+                    write!(f, ".{}", name)?;
+                }
+                Ok(())
+            }
+            Syntax::Not(syntax) => write!(f, "!({})", syntax),
+            Syntax::Neg(syntax) => write!(f, "-({})", syntax),
+            Syntax::Arith(op, lhs, rhs) => write!(f, "({} {} {})", lhs, op, rhs),
+            Syntax::Cmp(op, lhs, rhs) => write!(f, "({} {} {})", lhs, op, rhs),
+            Syntax::And(lhs, rhs) => write!(f, "({} && {})", lhs, rhs),
+            Syntax::Or(lhs, rhs) => write!(f, "({} || {})", lhs, rhs),
+        }
+    }
+}
+
+// Parses an expression of the whole grammar.
+pub(crate) fn parse_syntax(source: &str) -> Result<Syntax, ExprError> {
+    let mut parser = Parser::new(source)?;
+    let syntax = parser.parse_or()?;
+    parser.finish()?;
+    Ok(syntax)
+}
+
+// Parses assignments of the form `path = expr`, separated by commas.
+pub(crate) fn parse_assignments(
+    source: &str,
+) -> Result<Vec<(Root, Vec<String>, Syntax)>, ExprError> {
+    let mut parser = Parser::new(source)?;
+    let mut assignments = Vec::new();
+    loop {
+        let (root, path) = match parser.parse_unary()? {
+            Syntax::Path(root, path) => (root, path),
+            syntax => return Err(ExprError::Unsupported(syntax.to_string())),
+        };
+        parser.expect(&Token::Assign)?;
+        assignments.push((root, path, parser.parse_or()?));
+        if parser.peek() != Some(&Token::Comma) {
+            break;
+        }
+        parser.pos += 1;
+    }
+    parser.finish()?;
+    Ok(assignments)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Int(i128),
+    Str(String),
+    Cmp(CmpOp),
+    Arith(ArithOp),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+    Dot,
+    Comma,
+    Assign,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(ident) => write!(f, "{}", ident),
+            Token::Int(value) => write!(f, "{}", value),
+            Token::Str(value) => write!(f, "{}", quote(value)),
+            Token::Cmp(op) => write!(f, "{}", op),
+            Token::Arith(op) => write!(f, "{}", op),
+            Token::And => write!(f, "&&"),
+            Token::Or => write!(f, "||"),
+            Token::Not => write!(f, "!"),
+            Token::Open => write!(f, "("),
+            Token::Close => write!(f, ")"),
+            Token::Dot => write!(f, "."),
+            Token::Comma => write!(f, ","),
+            Token::Assign => write!(f, "="),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, ExprError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    while pos < chars.len() {
+        let c = chars[pos];
+        let next = chars.get(pos + 1).copied();
+
+        let (token, len) = match (c, next) {
+            (c, _) if c.is_whitespace() => {
+                pos += 1;
+                continue;
+            }
+            ('&', Some('&')) => (Token::And, 2),
+            ('|', Some('|')) => (Token::Or, 2),
+            ('=', Some('=')) => (Token::Cmp(CmpOp::Eq), 2),
+            ('!', Some('=')) => (Token::Cmp(CmpOp::Ne), 2),
+            ('<', Some('=')) => (Token::Cmp(CmpOp::Le), 2),
+            ('>', Some('=')) => (Token::Cmp(CmpOp::Ge), 2),
+            ('<', _) => (Token::Cmp(CmpOp::Lt), 1),
+            ('>', _) => (Token::Cmp(CmpOp::Gt), 1),
+            ('=', _) => (Token::Assign, 1),
+            ('!', _) => (Token::Not, 1),
+            ('+', _) => (Token::Arith(ArithOp::Add), 1),
+            ('-', _) => (Token::Arith(ArithOp::Sub), 1),
+            ('*', _) => (Token::Arith(ArithOp::Mul), 1),
+            ('(', _) => (Token::Open, 1),
+            (')', _) => (Token::Close, 1),
+            ('.', _) => (Token::Dot, 1),
+            (',', _) => (Token::Comma, 1),
+            ('\'', _) => {
+                let end = chars[pos + 1..]
+                    .iter()
+                    .position(|c| *c == '\'')
+                    .map(|offset| pos + 1 + offset)
+                    .filter(|end| *end > pos + 1)
+                    .ok_or(ExprError::UnexpectedEnd)?;
+
+                let literal: String = chars[pos + 1..end].iter().collect();
+                let value = match literal.as_str() {
+                    "\\n" => '\n',
+                    "\\t" => '\t',
+                    "\\0" => '\0',
+                    "\\\\" => '\\',
+                    _ if literal.chars().count() == 1 => literal.chars().next().unwrap(),
+                    _ => return Err(ExprError::InvalidLiteral(literal)),
+                };
+
+                (Token::Int(value as i128), end + 1 - pos)
+            }
+            ('"', _) => {
+                let mut value = String::new();
+                let mut end = pos + 1;
+                loop {
+                    match chars.get(end) {
+                        Some('"') => break,
+                        Some('\\') => {
+                            value.push(match chars.get(end + 1) {
+                                Some('n') => '\n',
+                                Some('t') => '\t',
+                                Some(c @ ('"' | '\\')) => *c,
+                                _ => {
+                                    let literal = chars[pos..(end + 2).min(chars.len())].iter();
+                                    return Err(ExprError::InvalidLiteral(literal.collect()));
+                                }
+                            });
+                            end += 2;
+                        }
+                        Some(c) => {
+                            value.push(*c);
+                            end += 1;
+                        }
+                        None => return Err(ExprError::UnexpectedEnd),
+                    }
+                }
+                (Token::Str(value), end + 1 - pos)
+            }
+            (c, _) if c.is_ascii_digit() => {
+                let len = chars[pos..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_digit())
+                    .count();
+
+                let literal: String = chars[pos..pos + len].iter().collect();
+                let value = literal
+                    .parse()
+                    .map_err(|_| ExprError::InvalidLiteral(literal.clone()))?;
+
+                (Token::Int(value), len)
+            }
+            (c, _) if c.is_ascii_alphabetic() || c == '_' => {
+                let len = chars[pos..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_alphanumeric() || **c == '_')
+                    .count();
+
+                (Token::Ident(chars[pos..pos + len].iter().collect()), len)
+            }
+            (c, _) => return Err(ExprError::UnexpectedChar(c)),
+        };
+
+        tokens.push(token);
+        pos += len;
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn new(source: &str) -> Result<Self, ExprError> {
+        Ok(Parser {
+            tokens: tokenize(source)?,
+            pos: 0,
+        })
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<Token, ExprError> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or(ExprError::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: &Token) -> Result<(), ExprError> {
+        match self.next()? {
+            token if token == *expected => Ok(()),
+            token => Err(ExprError::UnexpectedToken(token.to_string())),
+        }
+    }
+
+    fn finish(&self) -> Result<(), ExprError> {
+        match self.peek() {
+            None => Ok(()),
+            Some(token) => Err(ExprError::UnexpectedToken(token.to_string())),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Syntax, ExprError> {
+        let mut syntax = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            syntax = Syntax::Or(Box::new(syntax), Box::new(self.parse_and()?));
+        }
+        Ok(syntax)
+    }
+
+    fn parse_and(&mut self) -> Result<Syntax, ExprError> {
+        let mut syntax = self.parse_not()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            syntax = Syntax::And(Box::new(syntax), Box::new(self.parse_not()?));
+        }
+        Ok(syntax)
+    }
+
+    fn parse_not(&mut self) -> Result<Syntax, ExprError> {
+        match self.peek() {
+            Some(Token::Not) => {
+                self.pos += 1;
+                Ok(Syntax::Not(Box::new(self.parse_not()?)))
+            }
+            _ => self.parse_cmp(),
+        }
+    }
+
+    fn parse_cmp(&mut self) -> Result<Syntax, ExprError> {
+        let syntax = self.parse_sum()?;
+        match self.peek() {
+            Some(Token::Cmp(op)) => {
+                let op = *op;
+                self.pos += 1;
+                Ok(Syntax::Cmp(
+                    op,
+                    Box::new(syntax),
+                    Box::new(self.parse_sum()?),
+                ))
+            }
+            _ => Ok(syntax),
+        }
+    }
+
+    fn parse_sum(&mut self) -> Result<Syntax, ExprError> {
+        let mut syntax = self.parse_product()?;
+        while let Some(Token::Arith(op @ (ArithOp::Add | ArithOp::Sub))) = self.peek() {
+            let op = *op;
+            self.pos += 1;
+            syntax = Syntax::Arith(op, Box::new(syntax), Box::new(self.parse_product()?));
+        }
+        Ok(syntax)
+    }
+
+    fn parse_product(&mut self) -> Result<Syntax, ExprError> {
+        let mut syntax = self.parse_unary()?;
+        while self.peek() == Some(&Token::Arith(ArithOp::Mul)) {
+            self.pos += 1;
+            let rhs = self.parse_unary()?;
+            syntax = Syntax::Arith(ArithOp::Mul, Box::new(syntax), Box::new(rhs));
+        }
+        Ok(syntax)
+    }
+
+    fn parse_unary(&mut self) -> Result<Syntax, ExprError> {
+        match self.next()? {
+            Token::Arith(ArithOp::Sub) => Ok(Syntax::Neg(Box::new(self.parse_unary()?))),
+            Token::Open => {
+                let syntax = self.parse_or()?;
+                self.expect(&Token::Close)?;
+                Ok(syntax)
+            }
+            Token::Int(value) => Ok(Syntax::Int(value)),
+            Token::Str(value) => Ok(Syntax::Str(value)),
+            Token::Ident(ident) => {
+                let root = match ident.as_str() {
+                    "null" => return Ok(Syntax::Null),
+                    "true" => return Ok(Syntax::Bool(true)),
+                    "false" => return Ok(Syntax::Bool(false)),
+                    "data" => Root::Data,
+                    "input" => Root::Input,
+                    _ => return Err(ExprError::UnexpectedToken(ident)),
+                };
+
+                let mut path = Vec::new();
+                while self.peek() == Some(&Token::Dot) {
+                    self.pos += 1;
+                    match self.next()? {
+                        Token::Ident(name) => path.push(name),
+                        token => return Err(ExprError::UnexpectedToken(token.to_string())),
+                    }
+                }
+                Ok(Syntax::Path(root, path))
+            }
+            token => Err(ExprError::UnexpectedToken(token.to_string())),
+        }
+    }
+}
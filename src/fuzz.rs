@@ -0,0 +1,314 @@
+//! # Fuzz Module
+//!
+//! This module checks a [Monitor] against its [Machine] on random input sequences. Every
+//! sequence is run through both the monitor and an offline execution of the machine, and any
+//! disagreement between the two, panic, error, or blowup of the number of tracked states is
+//! reported as a [Finding].
+//!
+//! The sequences are generated from a seed, so a finding can be reproduced by running the fuzzer
+//! again with the same [FuzzConfig].
+
+use crate::machine::{Machine, State, Update};
+use crate::monitor::{Monitor, Verdict};
+use num::{Bounded, CheckedAdd, CheckedSub, One};
+use std::fmt;
+use std::hash::Hash;
+use std::panic::{self, AssertUnwindSafe};
+
+/// Configures [random_traces].
+#[derive(Clone, Debug, PartialEq)]
+pub struct FuzzConfig {
+    /// The number of input sequences to generate.
+    pub traces: usize,
+    /// The maximum length of an input sequence.
+    pub max_len: usize,
+    /// The seed of the random number generator.
+    pub seed: u64,
+    /// The probability of preferring an input that drives the data next to a transition bound.
+    pub boundary_bias: f64,
+    /// The maximum number of states the offline execution may track at once.
+    pub max_states: usize,
+}
+
+impl Default for FuzzConfig {
+    fn default() -> Self {
+        FuzzConfig {
+            traces: 100,
+            max_len: 20,
+            seed: 0,
+            boundary_bias: 0.5,
+            max_states: 1024,
+        }
+    }
+}
+
+/// A problem found while fuzzing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Finding<I> {
+    /// The monitor reached a verdict that the offline execution contradicts.
+    Divergence {
+        /// The input sequence up to the contradicting prefix.
+        trace: Vec<I>,
+        /// The conclusive verdict of the monitor.
+        verdict: Verdict,
+    },
+    /// Constructing or running the monitor, or executing the machine, panicked.
+    Panic {
+        /// The input sequence that caused the panic.
+        trace: Vec<I>,
+        /// The panic message.
+        message: String,
+    },
+    /// Constructing or running the monitor, or executing the machine, returned an error.
+    Error {
+        /// The input sequence that caused the error.
+        trace: Vec<I>,
+        /// The error message.
+        message: String,
+    },
+    /// The offline execution tracked more states than [FuzzConfig::max_states].
+    Budget {
+        /// The input sequence that exceeded the budget.
+        trace: Vec<I>,
+        /// The number of states tracked.
+        states: usize,
+    },
+}
+
+/// The outcome of [random_traces].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FuzzReport<I> {
+    /// The number of input sequences that were run.
+    pub traces: usize,
+    /// The problems found, at most one per input sequence.
+    pub findings: Vec<Finding<I>>,
+}
+
+/// Runs random input sequences over `alphabet` through a [Monitor] of `machine` and an offline
+/// execution of `machine`, both starting from `location` with `data`.
+///
+/// A verdict of the monitor claims that every extension of the inputs seen so far is accepted,
+/// or that none is. Each prefix of the sequence after a verdict is checked against it.
+///
+/// ```
+/// use rust_efsm::fuzz::{self, FuzzConfig};
+/// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+///
+/// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+///     .with_transition("safe", Transition {
+///         to_location: "safe".into(),
+///         enable: |_, i| *i != 0,
+///         ..Default::default()
+///     })
+///     .with_transition("safe", Transition {
+///         to_location: "unsafe".into(),
+///         enable: |_, i| *i == 0,
+///         ..Default::default()
+///     })
+///     .with_transition("unsafe", Transition { to_location: "unsafe".into(), ..Default::default() })
+///     .with_accepting("safe")
+///     .build();
+///
+/// let config = FuzzConfig { traces: 10, max_len: 5, ..Default::default() };
+/// let report = fuzz::random_traces(&machine, "safe", 0, &[0, 1, 2], &config);
+///
+/// assert_eq!(report.traces, 10);
+/// assert!(report.findings.is_empty());
+/// ```
+pub fn random_traces<D, I, U>(
+    machine: &Machine<D, I, U>,
+    location: &str,
+    data: D,
+    alphabet: &[I],
+    config: &FuzzConfig,
+) -> FuzzReport<I>
+where
+    D: Eq + Hash + fmt::Debug + Bounded + Ord + Copy + fmt::Display + CheckedAdd + CheckedSub + One,
+    I: Clone,
+    U: Clone + Update<D = D>,
+{
+    let mut rng = SplitMix64(config.seed);
+    let mut findings = Vec::new();
+
+    for _ in 0..config.traces {
+        if alphabet.is_empty() {
+            break;
+        }
+
+        let mut trace = Vec::new();
+        let outcome = panic::catch_unwind(AssertUnwindSafe(|| {
+            run_trace(
+                machine, location, data, alphabet, config, &mut rng, &mut trace,
+            )
+        }));
+
+        let finding = match outcome {
+            Ok(finding) => finding,
+            Err(payload) => {
+                let message = payload
+                    .downcast_ref::<&str>()
+                    .map(|message| message.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_default();
+
+                Some(Finding::Panic { trace, message })
+            }
+        };
+
+        findings.extend(finding);
+    }
+
+    FuzzReport {
+        traces: config.traces,
+        findings,
+    }
+}
+
+// Generates and runs a single trace, recording the inputs in `trace` as they are generated.
+fn run_trace<D, I, U>(
+    machine: &Machine<D, I, U>,
+    location: &str,
+    data: D,
+    alphabet: &[I],
+    config: &FuzzConfig,
+    rng: &mut SplitMix64,
+    trace: &mut Vec<I>,
+) -> Option<Finding<I>>
+where
+    D: Eq + Hash + fmt::Debug + Bounded + Ord + Copy + fmt::Display + CheckedAdd + CheckedSub + One,
+    I: Clone,
+    U: Clone + Update<D = D>,
+{
+    let error = |trace: &Vec<I>, message: String| Finding::Error {
+        trace: trace.clone(),
+        message,
+    };
+
+    let mut monitor = match Monitor::new(location, data, machine.clone()) {
+        Ok(monitor) => monitor,
+        Err(e) => return Some(error(trace, e.to_string())),
+    };
+
+    let mut states = vec![State {
+        location: location.into(),
+        data,
+    }];
+
+    for step in 0..=config.max_len {
+        // Check the prefix read so far against the verdict of the monitor.
+        let verdict = monitor.verdict();
+        if verdict.is_conclusive() {
+            let accepted = states.iter().any(|state| machine.is_accepting(state));
+
+            if accepted != (verdict == Verdict::Satisfied) {
+                return Some(Finding::Divergence {
+                    trace: trace.clone(),
+                    verdict,
+                });
+            }
+        }
+
+        if step == config.max_len {
+            break;
+        }
+
+        let input = choose_input(machine, &states, alphabet, config, rng);
+        trace.push(input.clone());
+
+        states = match machine.transition(&input, states) {
+            Ok(states) => states,
+            Err(e) => return Some(error(trace, e.to_string())),
+        };
+
+        if states.len() > config.max_states {
+            return Some(Finding::Budget {
+                trace: trace.clone(),
+                states: states.len(),
+            });
+        }
+
+        if let Err(e) = monitor.next(&input) {
+            return Some(error(trace, e.to_string()));
+        }
+    }
+
+    None
+}
+
+// Picks the next input, preferring with probability `config.boundary_bias` an input that leads
+// to a state next to the bound of one of its transitions.
+fn choose_input<D, I, U>(
+    machine: &Machine<D, I, U>,
+    states: &[State<D>],
+    alphabet: &[I],
+    config: &FuzzConfig,
+    rng: &mut SplitMix64,
+) -> I
+where
+    D: Copy + Ord + CheckedAdd + CheckedSub + One,
+    I: Clone,
+    U: Update<D = D>,
+{
+    if rng.next_f64() < config.boundary_bias {
+        let boundary: Vec<&I> = alphabet
+            .iter()
+            .filter(|i| {
+                machine
+                    .transition(i, states.to_vec())
+                    .unwrap_or_default()
+                    .iter()
+                    .any(|state| on_boundary(machine, state))
+            })
+            .collect();
+
+        if !boundary.is_empty() {
+            return boundary[rng.next_index(boundary.len())].clone();
+        }
+    }
+
+    alphabet[rng.next_index(alphabet.len())].clone()
+}
+
+// Checks if the data of `state` is at, or one step outside, an explicit endpoint of the bound of
+// a transition out of its location.
+fn on_boundary<D, I, U>(machine: &Machine<D, I, U>, state: &State<D>) -> bool
+where
+    D: Copy + Ord + CheckedAdd + CheckedSub + One,
+{
+    let near = |endpoint: Option<D>| {
+        endpoint.is_some_and(|endpoint| {
+            state.data == endpoint
+                || endpoint.checked_sub(&D::one()) == Some(state.data)
+                || endpoint.checked_add(&D::one()) == Some(state.data)
+        })
+    };
+
+    machine
+        .get_transitions_from(&state.location)
+        .into_iter()
+        .flatten()
+        .any(|transition| near(transition.bound.lower) || near(transition.bound.upper))
+}
+
+// The SplitMix64 generator, which is small and good enough to pick inputs.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // Returns a value in [0, 1).
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    // Returns a value in [0, len).
+    fn next_index(&mut self, len: usize) -> usize {
+        (self.next_u64() % len as u64) as usize
+    }
+}
@@ -1,10 +1,38 @@
-use crate::machine::Machine;
+//! # Gviz Module
+//!
+//! This module converts a [Machine] into a graph in the DOT language, which can be rendered by
+//! Graphviz.
+//!
+//! Guards are code, so a plain conversion only labels each edge with its update and bound. When
+//! the inputs are known, through an [alphabet](GvGraph::with_alphabet) or an
+//! [input classifier](GvGraph::with_input_classes), each edge is labelled with the inputs
+//! enabling it instead, and parallel edges between two locations are merged into one edge with a
+//! line per transition. Bounds are left out of the labels of transitions enabled for any data.
+//!
+//! Locations with the same [tag](crate::machine::MachineBuilder::with_tag) are drawn together in
+//! a cluster, labelled with the tag unless [another label](GvGraph::with_cluster_label) is given.
+//!
+//! Edges can be drawn as thick as the traffic through them, measured by
+//! [fit_probabilities](Machine::fit_probabilities), with [GvGraph::with_firing_frequencies].
+//!
+//! With the `render` feature, a graph can also be laid out and drawn without Graphviz, as
+//! [SVG](GvGraph::render_svg) or [PNG](GvGraph::render_png). The embedded layout engine does not
+//! support clusters, so tagged locations are only outlined in the color of their cluster.
+
+use crate::alphabet::InputClassifier;
+use crate::machine::{Machine, Transition, TransitionId};
+use crate::stats::FiringFrequencies;
 use num::Bounded;
+use std::collections::BTreeMap;
 use std::fmt;
+#[cfg(feature = "render")]
+use std::path::Path;
 
+/// A graph description that can be written out in the DOT language.
 pub struct GvGraph {
     nodes: Vec<GvNode>,
     edges: Vec<GvEdge>,
+    clusters: BTreeMap<String, GvCluster>,
 }
 
 impl GvGraph {
@@ -12,21 +40,478 @@ impl GvGraph {
         GvGraph {
             nodes: Vec::new(),
             edges: Vec::new(),
+            clusters: BTreeMap::new(),
+        }
+    }
+
+    /// Labels the cluster of the locations tagged with `tag` with `label` instead of the tag.
+    ///
+    /// ```
+    /// use rust_efsm::gviz::GvGraph;
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("c0", Transition { to_location: "c1".into(), ..Default::default() })
+    ///     .with_transition("c1", Transition { to_location: "s0".into(), ..Default::default() })
+    ///     .with_tag("c0", "client")
+    ///     .with_tag("c1", "client")
+    ///     .build();
+    ///
+    /// let graph = GvGraph::from(machine)
+    ///     .with_cluster_label("client", "Client")
+    ///     .with_cluster_color("client", "blue");
+    /// let dot = String::from(graph);
+    /// assert!(dot.contains(
+    ///     "subgraph cluster_0 {\nlabel=\"Client\";\ncolor=\"blue\";\n\
+    ///      c0[shape=circle,peripheries=1];\nc1[shape=circle,peripheries=1];\n}\n"
+    /// ));
+    /// // The untagged location is drawn outside of any cluster.
+    /// assert!(dot.contains("}\ns0[shape=circle,peripheries=1];\n"));
+    /// ```
+    pub fn with_cluster_label(mut self, tag: &str, label: &str) -> Self {
+        if let Some(cluster) = self.clusters.get_mut(tag) {
+            cluster.label = label.into();
+        }
+        self
+    }
+
+    /// Draws the border of the cluster of the locations tagged with `tag` in `color`, which is
+    /// any color name or value understood by Graphviz.
+    pub fn with_cluster_color(mut self, tag: &str, color: &str) -> Self {
+        if let Some(cluster) = self.clusters.get_mut(tag) {
+            cluster.color = Some(color.into());
+        }
+        self
+    }
+
+    /// Draws each edge with a thickness proportional to how often its transitions fired in
+    /// `frequencies`, from 1 for the least to 5 for the most. Edges that never fired are dashed,
+    /// showing the parts of the machine that the traces did not exercise.
+    ///
+    /// Only the DOT output shows the thickness.
+    ///
+    /// ```
+    /// use rust_efsm::gviz::GvGraph;
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s0".into(),
+    ///         enable: |_, i| *i == b'a',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s1".into(),
+    ///         enable: |_, i| *i == b'b',
+    ///         ..Default::default()
+    ///     })
+    ///     .build();
+    ///
+    /// let frequencies = machine.fit_probabilities("s0", 0, vec![b"aaa".to_vec()]).unwrap();
+    /// let dot = String::from(GvGraph::from(machine).with_firing_frequencies(&frequencies));
+    /// assert!(dot.contains("s0 -> s0 [label=<data>,penwidth=5.0];"));
+    /// assert!(dot.contains("s0 -> s1 [label=<data>,penwidth=1.0,style=dashed];"));
+    /// ```
+    pub fn with_firing_frequencies(mut self, frequencies: &FiringFrequencies) -> Self {
+        let fired = |edge: &GvEdge| -> u64 {
+            edge.transitions
+                .iter()
+                .filter_map(|id| frequencies.get(id))
+                .map(|frequency| frequency.fired)
+                .sum()
+        };
+
+        let most = self.edges.iter().map(fired).max().unwrap_or_default();
+        for edge in &mut self.edges {
+            let fired = fired(edge);
+            let width = match most {
+                0 => 1.0,
+                most => 1.0 + 4.0 * fired as f64 / most as f64,
+            };
+            edge.penwidth = Some((width, fired == 0));
+        }
+        self
+    }
+
+    /// Converts `machine` into a graph whose edges are labelled with the input classes of
+    /// `classifier` enabling them, with a line for each pair of a transition and a class.
+    ///
+    /// Guards also depend on the data, so a class is listed when its representative enables the
+    /// transition at either end of the transition's bound. Parallel edges are merged.
+    ///
+    /// ```
+    /// use rust_efsm::alphabet::Partition;
+    /// use rust_efsm::gviz::GvGraph;
+    /// use rust_efsm::machine::{AddUpdate, MachineBuilder, Transition};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, AddUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s1".into(),
+    ///         enable: |_, i| *i == b'b',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s1".into(),
+    ///         enable: |_, i| i.is_ascii_digit(),
+    ///         update: AddUpdate { amount: 1 },
+    ///         ..Default::default()
+    ///     })
+    ///     .build();
+    ///
+    /// let classes = Partition::new("other", b'a')
+    ///     .with_class("b", b'b', |i| *i == b'b')
+    ///     .with_class("digit", b'0', |i| i.is_ascii_digit());
+    /// let dot = String::from(GvGraph::with_input_classes(&machine, &classes));
+    /// assert!(dot.contains(
+    ///     "s0 -> s1 [label=<b / data += 0<br/>digit / data += 1>];"
+    /// ));
+    /// ```
+    pub fn with_input_classes<D, I, U, C>(machine: &Machine<D, I, U>, classifier: &C) -> Self
+    where
+        D: fmt::Display + Bounded + Copy,
+        U: fmt::Display,
+        C: InputClassifier<I>,
+        C::Class: fmt::Display,
+    {
+        from_machine(machine, true, |_, t| {
+            let (lower, upper) = t.bound.as_explicit();
+            let mut classes = t.enabled_classes(&lower, classifier);
+            classes.extend(t.enabled_classes(&upper, classifier));
+            classes.sort();
+            classes.dedup();
+
+            classes
+                .iter()
+                .map(|class| action(t, &class.to_string()))
+                .collect()
+        })
+    }
+
+    /// Converts `machine` into a graph whose edges are labelled with the inputs of `alphabet`
+    /// enabling them, with a line for each transition.
+    ///
+    /// Inputs next to each other in `alphabet` are written as ranges, and an edge enabled for
+    /// most of the alphabet is written as the whole alphabet minus the inputs it excludes. As
+    /// with [with_input_classes](GvGraph::with_input_classes), guards are checked at either end
+    /// of the transition's bound, and parallel edges are merged.
+    ///
+    /// ```
+    /// use rust_efsm::gviz::GvGraph;
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    ///
+    /// let machine = MachineBuilder::<u8, char, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s0".into(),
+    ///         enable: |_, i| *i != 'b',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s1".into(),
+    ///         enable: |_, i| *i == 'b' || *i == 'x' || *i == 'y',
+    ///         ..Default::default()
+    ///     })
+    ///     .build();
+    ///
+    /// let alphabet: Vec<char> = ('a'..='z').collect();
+    /// let dot = String::from(GvGraph::with_alphabet(&machine, &alphabet));
+    /// assert!(dot.contains(r"s0 -> s0 [label=<a..z \ {b} / data>];"));
+    /// assert!(dot.contains("s0 -> s1 [label=<b, x, y / data>];"));
+    /// ```
+    pub fn with_alphabet<D, I, U>(machine: &Machine<D, I, U>, alphabet: &[I]) -> Self
+    where
+        D: fmt::Display + Bounded + Copy,
+        I: fmt::Display,
+        U: fmt::Display,
+    {
+        from_machine(machine, true, |_, t| {
+            let (lower, upper) = t.bound.as_explicit();
+            let enabled: Vec<bool> = alphabet
+                .iter()
+                .map(|i| t.is_enabled(&lower, i) || t.is_enabled(&upper, i))
+                .collect();
+
+            match enabled.contains(&true) {
+                true => vec![action(t, &describe_inputs(alphabet, &enabled))],
+                false => Vec::new(),
+            }
+        })
+    }
+
+    /// Converts `machine` into a graph whose edges are labelled with the
+    /// [label](Machine::transition_label) and bound of their transition, leaving out the update.
+    ///
+    /// Unlike the other conversions, this does not require the update to implement
+    /// [Display](fmt::Display), so every machine can be drawn.
+    ///
+    /// ```
+    /// use rust_efsm::bound::Bound;
+    /// use rust_efsm::gviz::GvGraph;
+    /// use rust_efsm::machine::{MachineBuilder, Transition, Update};
+    ///
+    /// // An update without a Display implementation.
+    /// #[derive(Clone, Default)]
+    /// struct Double;
+    ///
+    /// impl Update for Double {
+    ///     type D = u8;
+    ///     fn update<I>(&self, data: u8, _: &I) -> u8 {
+    ///         data.saturating_mul(2)
+    ///     }
+    ///     fn update_interval(&self, interval: Bound<u8>) -> Bound<u8> {
+    ///         Bound::unbounded()
+    ///     }
+    /// }
+    ///
+    /// let machine = MachineBuilder::<u8, u8, Double>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s1".into(),
+    ///         name: Some("double".into()),
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("s1", Transition { to_location: "s0".into(), ..Default::default() })
+    ///     .build();
+    ///
+    /// let dot = String::from(GvGraph::with_transition_labels(&machine));
+    /// assert!(dot.contains("s0 -> s1 [label=<double>];"));
+    /// assert!(dot.contains("s1 -> s0 [label=<s1#0>];"));
+    /// ```
+    pub fn with_transition_labels<D, I, U>(machine: &Machine<D, I, U>) -> Self
+    where
+        D: fmt::Display + Bounded + Copy,
+    {
+        from_machine(machine, false, |id, t| {
+            vec![match t.bound.is_unbounded() {
+                true => machine.transition_label(id),
+                false => format!("{}<br/>{}", machine.transition_label(id), t.bound),
+            }]
+        })
+    }
+}
+
+// Returns a single line label of `t`, taken on `inputs`.
+fn action<D, I, U>(t: &Transition<D, I, U>, inputs: &str) -> String
+where
+    D: fmt::Display + Bounded + Copy,
+    U: fmt::Display,
+{
+    // A transition enabled for any data needs no bound.
+    let bound = match t.bound.is_unbounded() {
+        true => String::new(),
+        false => format!(" in {}", t.bound),
+    };
+    match &t.name {
+        Some(name) => format!("{}: {} / {}{}", name, inputs, t.update, bound),
+        None => format!("{} / {}{}", inputs, t.update, bound),
+    }
+}
+
+// Describes the inputs of `alphabet` marked in `enabled`, writing runs of at least three inputs
+// as ranges. When few inputs are excluded, they are subtracted from the whole alphabet instead.
+fn describe_inputs<I: fmt::Display>(alphabet: &[I], enabled: &[bool]) -> String {
+    // The runs of enabled or excluded inputs, as ranges of positions.
+    let runs = |value: bool| {
+        let mut runs: Vec<(usize, usize)> = Vec::new();
+        for (idx, _) in enabled.iter().enumerate().filter(|(_, e)| **e == value) {
+            match runs.last_mut() {
+                Some((_, end)) if *end + 1 == idx => *end = idx,
+                _ => runs.push((idx, idx)),
+            }
         }
+        runs
+    };
+    let list = |runs: Vec<(usize, usize)>| {
+        let parts: Vec<String> = runs
+            .into_iter()
+            .flat_map(|(start, end)| match end - start {
+                0 => vec![alphabet[start].to_string()],
+                1 => vec![alphabet[start].to_string(), alphabet[end].to_string()],
+                _ => vec![format!("{}..{}", alphabet[start], alphabet[end])],
+            })
+            .collect();
+        parts.join(", ")
+    };
+
+    let included = runs(true);
+    let excluded = runs(false);
+    let all = list(vec![(0, alphabet.len() - 1)]);
+    if excluded.is_empty() {
+        all
+    } else if excluded.len() < included.len() {
+        format!("{} \\ {{{}}}", all, list(excluded))
+    } else {
+        list(included)
     }
 }
 
 struct GvNode {
     label: String,
     peripheries: u8,
+    xlabel: Option<String>,
+    tag: Option<String>,
+}
+
+struct GvCluster {
+    label: String,
+    color: Option<String>,
+}
+
+impl GvNode {
+    fn to_dot(&self) -> String {
+        let xlabel = match &self.xlabel {
+            Some(xlabel) => format!(",xlabel=<{}>", xlabel),
+            None => String::new(),
+        };
+        format!(
+            "{}[shape=circle,peripheries={}{}];\n",
+            self.label, self.peripheries, xlabel
+        )
+    }
 }
 
 struct GvEdge {
     label: String,
     head: String,
     tail: String,
+    // The transitions drawn by this edge.
+    transitions: Vec<TransitionId>,
+    // The width of the line and whether it is dashed, when firing frequencies are drawn.
+    penwidth: Option<(f64, bool)>,
 }
 
+#[cfg(feature = "render")]
+impl GvGraph {
+    /// Lays out this graph and draws it as an SVG document.
+    ///
+    /// ```
+    /// use rust_efsm::gviz::GvGraph;
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition { to_location: "s1".into(), ..Default::default() })
+    ///     .with_accepting("s1")
+    ///     .build();
+    ///
+    /// let svg = GvGraph::from(machine).render_svg();
+    /// assert!(svg.contains("<svg"));
+    /// assert!(svg.contains("s1"));
+    /// ```
+    pub fn render_svg(&self) -> String {
+        use layout::backends::svg::SVGWriter;
+        use layout::core::base::Orientation;
+        use layout::core::color::Color;
+        use layout::core::style::StyleAttr;
+        use layout::std_shapes::render::get_shape_size;
+        use layout::std_shapes::shapes::{Arrow, Element, ShapeKind};
+        use layout::topo::layout::VisualGraph;
+
+        let orientation = Orientation::LeftToRight;
+        let mut vg = VisualGraph::new(orientation);
+
+        let mut handles = BTreeMap::new();
+        for node in &self.nodes {
+            let label = match &node.xlabel {
+                Some(xlabel) => format!("{}\n{}", node.label, xlabel),
+                None => node.label.clone(),
+            };
+            let shape = match node.peripheries {
+                1 => ShapeKind::new_circle(&label),
+                _ => ShapeKind::new_double_circle(&label),
+            };
+
+            let mut look = StyleAttr::simple();
+            let color = node
+                .tag
+                .as_ref()
+                .and_then(|tag| self.clusters.get(tag))
+                .and_then(|cluster| cluster.color.as_deref())
+                .and_then(Color::from_name);
+            if let Some(color) = color {
+                look.line_color = color;
+            }
+
+            let size = get_shape_size(orientation, &shape, look.font_size, true);
+            let element = Element::create(shape, look, orientation, size);
+            handles.insert(node.label.as_str(), vg.add_node(element));
+        }
+
+        for edge in &self.edges {
+            let (Some(head), Some(tail)) = (
+                handles.get(edge.head.as_str()),
+                handles.get(edge.tail.as_str()),
+            ) else {
+                continue;
+            };
+            vg.add_edge(
+                Arrow::simple(&edge.label.replace("<br/>", "\n")),
+                *head,
+                *tail,
+            );
+        }
+
+        let mut svg = SVGWriter::new();
+        if vg.num_nodes() > 0 {
+            vg.do_it(false, false, false, &mut svg);
+        }
+        svg.finalize()
+    }
+
+    /// Lays out this graph and writes it to `path` as a PNG image.
+    ///
+    /// Text is drawn with the fonts installed on the system.
+    ///
+    /// ```no_run
+    /// use rust_efsm::gviz::GvGraph;
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_accepting("s0")
+    ///     .build();
+    ///
+    /// GvGraph::from(machine).render_png("machine.png").unwrap();
+    /// ```
+    pub fn render_png(&self, path: impl AsRef<Path>) -> Result<(), RenderError> {
+        use resvg::{tiny_skia, usvg};
+
+        let mut options = usvg::Options::default();
+        options.fontdb_mut().load_system_fonts();
+
+        let tree = usvg::Tree::from_str(&self.render_svg(), &options)
+            .map_err(|e| RenderError::Svg(e.to_string()))?;
+        let size = tree.size().to_int_size();
+        let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height())
+            .ok_or_else(|| RenderError::Png("the image is empty".into()))?;
+        pixmap.fill(tiny_skia::Color::WHITE);
+        resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+
+        pixmap
+            .save_png(path)
+            .map_err(|e| RenderError::Png(e.to_string()))
+    }
+}
+
+/// Errors that can occur while rendering a [GvGraph] to an image.
+#[cfg(feature = "render")]
+#[derive(Debug)]
+pub enum RenderError {
+    /// The drawn SVG document could not be read back.
+    Svg(String),
+    /// The PNG image could not be drawn or written.
+    Png(String),
+}
+
+#[cfg(feature = "render")]
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::Svg(reason) => write!(f, "invalid svg: {}", reason),
+            RenderError::Png(reason) => write!(f, "cannot write png: {}", reason),
+        }
+    }
+}
+
+#[cfg(feature = "render")]
+impl std::error::Error for RenderError {}
+
 impl From<GvGraph> for String {
     fn from(graph: GvGraph) -> Self {
         let mut spec = String::new();
@@ -36,17 +521,36 @@ impl From<GvGraph> for String {
         spec.push_str("graph [center=true pad=.5];\n");
         spec.push_str("rankdir=LR;\n");
 
-        for node in graph.nodes {
-            spec.push_str(&format!(
-                "{}[shape=circle,peripheries={}];\n",
-                node.label, node.peripheries
-            ));
+        // Clusters are numbered in the order of their tags, since a tag may not be a valid ID.
+        for (idx, (tag, cluster)) in graph.clusters.iter().enumerate() {
+            spec.push_str(&format!("subgraph cluster_{} {{\n", idx));
+            spec.push_str(&format!("label=\"{}\";\n", cluster.label));
+            if let Some(color) = &cluster.color {
+                spec.push_str(&format!("color=\"{}\";\n", color));
+            }
+            for node in graph
+                .nodes
+                .iter()
+                .filter(|node| node.tag.as_ref() == Some(tag))
+            {
+                spec.push_str(&node.to_dot());
+            }
+            spec.push_str("}\n");
+        }
+
+        for node in graph.nodes.iter().filter(|node| node.tag.is_none()) {
+            spec.push_str(&node.to_dot());
         }
 
         for edge in graph.edges {
+            let style = match edge.penwidth {
+                Some((width, true)) => format!(",penwidth={:.1},style=dashed", width),
+                Some((width, false)) => format!(",penwidth={:.1}", width),
+                None => String::new(),
+            };
             spec.push_str(&format!(
-                "{} -> {} [label=<{}>];\n",
-                edge.head, edge.tail, edge.label
+                "{} -> {} [label=<{}>{}];\n",
+                edge.head, edge.tail, edge.label, style
             ));
         }
 
@@ -63,36 +567,94 @@ where
     U: fmt::Display,
 {
     fn from(machine: Machine<D, I, U>) -> Self {
-        let mut gv = GvGraph::new();
+        from_machine(&machine, false, |_, t| {
+            let mut lines: Vec<String> = t.name.iter().cloned().collect();
+            lines.push(t.update.to_string());
+            if !t.bound.is_unbounded() {
+                lines.push(t.bound.to_string());
+            }
+            vec![lines.join("<br/>")]
+        })
+    }
+}
 
-        for (location, transitions) in machine.get_locations() {
-            // Double line for accepting states.
-            let peripheries = match machine.get_accepting().contains(location) {
-                true => 2,
-                false => 1,
-            };
+// Converts `machine` into a graph with an edge for each line returned by `labels` for a
+// transition. When `merge` is set, the edges between the same pair of locations are merged into
+// one, with each distinct line of their labels.
+fn from_machine<D, I, U, F>(machine: &Machine<D, I, U>, merge: bool, labels: F) -> GvGraph
+where
+    D: fmt::Display + Bounded + Copy,
+    F: Fn(&TransitionId, &Transition<D, I, U>) -> Vec<String>,
+{
+    let mut gv = GvGraph::new();
 
-            // Each state gets a GvNode.
-            gv.nodes.push(GvNode {
-                label: location.clone(),
-                peripheries,
+    for location in machine.all_locations() {
+        // Double line for accepting states.
+        let peripheries = match machine.get_accepting().contains(location) {
+            true => 2,
+            false => 1,
+        };
+
+        // Accepting states with a condition on the data show it beside them.
+        let xlabel = machine
+            .get_accepting_conditions()
+            .get(location)
+            .map(|condition| condition.to_string());
+
+        // Tagged states are grouped in a cluster per tag.
+        let tag = machine.get_tags().get(location).cloned();
+        if let Some(tag) = &tag {
+            gv.clusters.entry(tag.clone()).or_insert_with(|| GvCluster {
+                label: tag.clone(),
+                color: None,
             });
+        }
 
-            // Each transition gets a GvEdge.
-            for t in transitions {
-                gv.edges.push(GvEdge {
-                    label: format!("{}<br/>{}", t.update, t.bound),
-
-                    // TODO: We can avoid clone by referencing the machine's original copy.
-                    // TODO: This requires that the machine outlives the graph.
-                    // TODO: That requirement seems logical, and may be the best option.
-                    // TODO: Further thought is required.
-                    head: location.clone(),
-                    tail: t.to_location.clone(),
-                });
+        // Each state gets a GvNode.
+        gv.nodes.push(GvNode {
+            label: location.into(),
+            peripheries,
+            xlabel,
+            tag,
+        });
+
+        // Each transition gets a GvEdge.
+        let transitions = machine.get_transitions_from(location).into_iter().flatten();
+        for (index, t) in transitions.enumerate() {
+            let id = TransitionId::new(location, index);
+            for label in labels(&id, t) {
+                let parallel = gv
+                    .edges
+                    .iter_mut()
+                    .filter(|_| merge)
+                    .find(|edge| edge.head == location && edge.tail == t.to_location);
+
+                match parallel {
+                    Some(edge) => {
+                        if !edge.transitions.contains(&id) {
+                            edge.transitions.push(id.clone());
+                        }
+                        if !edge.label.split("<br/>").any(|line| line == label) {
+                            edge.label.push_str("<br/>");
+                            edge.label.push_str(&label);
+                        }
+                    }
+                    None => gv.edges.push(GvEdge {
+                        label,
+                        transitions: vec![id.clone()],
+                        penwidth: None,
+
+                        // TODO: We can avoid clone by referencing the machine's original copy.
+                        // TODO: This requires that the machine outlives the graph.
+                        // TODO: That requirement seems logical, and may be the best option.
+                        // TODO: Further thought is required.
+                        head: location.into(),
+                        tail: t.to_location.clone(),
+                    }),
+                }
             }
         }
-
-        gv
     }
+
+    gv
 }
@@ -0,0 +1,226 @@
+//! # Kafka Integration
+//!
+//! This module drives a monitor from a message stream, such as a Kafka topic partition or a NATS
+//! JetStream consumer. The client of the broker is wrapped in a [Consumer], and a
+//! [StreamMonitor] polls it, turns each payload into an input with a deserializer, and commits
+//! the offset of a message once it reaches a verdict.
+//!
+//! Offsets are committed only on verdicts, so a consumer restarted after a crash reads again
+//! every message since the last verdict, and no message is lost. This is at-least-once handling:
+//! a fresh monitor replaying those messages reaches the same verdicts, as long as the monitor
+//! restarts on each verdict, as in [MonitorMode::ResetOnVerdict](crate::monitor::MonitorMode).
+//!
+//! ## Examples
+//!
+//! ```
+//! use rust_efsm::integrations::kafka::{Consumer, Message, StreamMonitor};
+//! use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+//! use rust_efsm::monitor::{Monitor, MonitorMode, Verdict};
+//! use std::collections::VecDeque;
+//!
+//! // A partition held in memory, in place of a broker.
+//! struct Partition {
+//!     messages: VecDeque<Message>,
+//!     committed: u64,
+//! }
+//!
+//! impl Consumer for Partition {
+//!     type Error = std::convert::Infallible;
+//!
+//!     fn poll(&mut self) -> Result<Option<Message>, Self::Error> {
+//!         Ok(self.messages.pop_front())
+//!     }
+//!
+//!     fn commit(&mut self, offset: u64) -> Result<(), Self::Error> {
+//!         self.committed = offset;
+//!         Ok(())
+//!     }
+//! }
+//!
+//! // Every payment must stay below 100.
+//! let machine = MachineBuilder::<u8, u32, IdentityUpdate<u8>>::new()
+//!     .with_transition("ok", Transition {
+//!         to_location: "ok".into(),
+//!         enable: |_, amount| *amount < 100,
+//!         ..Default::default()
+//!     })
+//!     .with_transition("ok", Transition {
+//!         to_location: "fraud".into(),
+//!         enable: |_, amount| *amount >= 100,
+//!         ..Default::default()
+//!     })
+//!     .with_accepting("ok")
+//!     .with_rejecting("fraud")
+//!     .build();
+//!
+//! let payments = ["20", "oops", "500", "30"];
+//! let partition = Partition {
+//!     messages: payments
+//!         .iter()
+//!         .enumerate()
+//!         .map(|(offset, payload)| Message::new(offset as u64, payload.as_bytes().to_vec()))
+//!         .collect(),
+//!     committed: 0,
+//! };
+//!
+//! let monitor = Monitor::new("ok", 0, machine)
+//!     .unwrap()
+//!     .with_mode(MonitorMode::ResetOnVerdict);
+//! let mut stream = StreamMonitor::new(partition, monitor, |payload| {
+//!     std::str::from_utf8(payload).ok()?.parse().ok()
+//! });
+//!
+//! assert_eq!(stream.run().unwrap(), Verdict::Violated);
+//! assert_eq!(stream.consumer().committed, 3);
+//! assert_eq!(stream.skipped(), 1);
+//!
+//! // The stream runs dry without another verdict.
+//! assert_eq!(stream.run().unwrap(), Verdict::Inconclusive);
+//! assert_eq!(stream.consumer().committed, 3);
+//! ```
+
+use crate::log::{debug, span};
+use crate::monitor::{MonitorError, Verdict, Verify};
+use std::fmt;
+
+/// A message read from a stream.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Message {
+    /// The offset of the message in its partition.
+    pub offset: u64,
+    /// The key of the message, if any.
+    pub key: Option<Vec<u8>>,
+    /// The payload of the message.
+    pub payload: Vec<u8>,
+}
+
+impl Message {
+    /// Creates a message without a key.
+    pub fn new(offset: u64, payload: Vec<u8>) -> Self {
+        Message {
+            offset,
+            key: None,
+            payload,
+        }
+    }
+
+    /// Sets the key of the message.
+    pub fn with_key(mut self, key: Vec<u8>) -> Self {
+        self.key = Some(key);
+        self
+    }
+}
+
+/// A client reading the messages of one partition of a stream, in order.
+pub trait Consumer {
+    /// The error returned by the client.
+    type Error;
+
+    /// Returns the next message, or `None` when no message is available yet.
+    fn poll(&mut self) -> Result<Option<Message>, Self::Error>;
+
+    /// Records that every message before `offset` was processed, so that a restarted consumer
+    /// reads from `offset` on.
+    fn commit(&mut self, offset: u64) -> Result<(), Self::Error>;
+}
+
+/// Errors that can occur while monitoring a stream.
+#[derive(Debug)]
+pub enum StreamError<E> {
+    /// The consumer failed to poll or commit.
+    Consumer(E),
+    /// The monitor failed to process an input.
+    Monitor(MonitorError),
+}
+
+impl<E: fmt::Display> fmt::Display for StreamError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamError::Consumer(err) => write!(f, "consumer failed: {}", err),
+            StreamError::Monitor(err) => write!(f, "monitor failed: {}", err),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for StreamError<E> {}
+
+impl<E> From<MonitorError> for StreamError<E> {
+    fn from(err: MonitorError) -> Self {
+        StreamError::Monitor(err)
+    }
+}
+
+/// Turns the payload of a message into an input, or `None` to skip the message.
+pub type Deserializer<I> = fn(&[u8]) -> Option<I>;
+
+/// A monitor reading its inputs from a [Consumer].
+pub struct StreamMonitor<C, M, I> {
+    consumer: C,
+    monitor: M,
+    deserialize: Deserializer<I>,
+    skipped: usize,
+}
+
+impl<C, M, I> StreamMonitor<C, M, I>
+where
+    C: Consumer,
+    M: Verify<I>,
+{
+    /// Creates a monitor reading the messages of `consumer`, deserialized by `deserialize`.
+    pub fn new(consumer: C, monitor: M, deserialize: Deserializer<I>) -> Self {
+        StreamMonitor {
+            consumer,
+            monitor,
+            deserialize,
+            skipped: 0,
+        }
+    }
+
+    /// Processes messages until one reaches a verdict, whose offset is then committed, or
+    /// until no message is available.
+    ///
+    /// Returns the verdict, or [Verdict::Inconclusive] when no message is available, in which
+    /// case it is called again to wait for more. Messages that fail to deserialize are skipped.
+    pub fn run(&mut self) -> Result<Verdict, StreamError<C::Error>> {
+        span!(DEBUG, "stream_monitor_run");
+
+        while let Some(message) = self.consumer.poll().map_err(StreamError::Consumer)? {
+            let Some(input) = (self.deserialize)(&message.payload) else {
+                debug!(offset = message.offset, "skipped message");
+                self.skipped += 1;
+                continue;
+            };
+
+            let verdict = self.monitor.next(&input)?;
+            if verdict.is_conclusive() {
+                debug!(offset = message.offset, %verdict, "committing offset");
+                self.consumer
+                    .commit(message.offset + 1)
+                    .map_err(StreamError::Consumer)?;
+                return Ok(verdict);
+            }
+        }
+
+        Ok(Verdict::Inconclusive)
+    }
+
+    /// Returns the number of messages that failed to deserialize.
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// Returns the consumer.
+    pub fn consumer(&self) -> &C {
+        &self.consumer
+    }
+
+    /// Returns the monitor.
+    pub fn monitor(&self) -> &M {
+        &self.monitor
+    }
+
+    /// Returns the consumer and the monitor.
+    pub fn into_parts(self) -> (C, M) {
+        (self.consumer, self.monitor)
+    }
+}
@@ -0,0 +1,10 @@
+//! # Integrations Module
+//!
+//! This module connects [monitors](crate::monitor::Monitor) to external sources of events.
+
+#[cfg(feature = "kafka")]
+#[warn(missing_docs)]
+pub mod kafka;
+
+#[warn(missing_docs)]
+pub mod tracing;
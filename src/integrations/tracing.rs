@@ -0,0 +1,211 @@
+//! # Tracing Integration
+//!
+//! This module verifies an application's own structured logs. A [MonitorLayer] is a
+//! [tracing_subscriber] layer that records every matching [tracing] event, converts it into an
+//! input with a user closure, and sends it to a monitor running on a background thread.
+//!
+//! ## Examples
+//!
+//! ```
+//! use rust_efsm::integrations::tracing::{monitor_events, EventFilter};
+//! use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+//! use rust_efsm::monitor::{Monitor, Verdict};
+//! use tracing_subscriber::layer::SubscriberExt;
+//!
+//! // Every reported latency must stay below 100.
+//! let machine = MachineBuilder::<u8, u64, IdentityUpdate<u8>>::new()
+//!     .with_transition("ok", Transition {
+//!         to_location: "ok".into(),
+//!         enable: |_, latency| *latency < 100,
+//!         ..Default::default()
+//!     })
+//!     .with_transition("ok", Transition {
+//!         to_location: "bad".into(),
+//!         enable: |_, latency| *latency >= 100,
+//!         ..Default::default()
+//!     })
+//!     .with_transition("bad", Transition { to_location: "bad".into(), ..Default::default() })
+//!     .with_accepting("ok")
+//!     .build();
+//!
+//! let monitor = Monitor::new("ok", 0, machine).unwrap();
+//! let (layer, verdict) = monitor_events(
+//!     monitor,
+//!     EventFilter::target("server").with_field("latency"),
+//!     |event| event.fields.get("latency")?.parse().ok(),
+//! );
+//!
+//! let subscriber = tracing_subscriber::registry().with(layer);
+//! tracing::subscriber::with_default(subscriber, || {
+//!     tracing::info!(target: "server", latency = 20);
+//!     tracing::info!(target: "client", latency = 500);
+//!     tracing::info!(target: "server", latency = 250);
+//! });
+//!
+//! assert_eq!(verdict.join().unwrap().unwrap(), Verdict::Violated);
+//! ```
+
+use crate::monitor::{MonitorError, Verdict, Verify};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+use tracing_subscriber::layer::{Context, Layer};
+
+/// A [tracing] event recorded by a [MonitorLayer].
+#[derive(Clone, Debug)]
+pub struct TracingEvent {
+    /// The target of the event.
+    pub target: String,
+    /// The level of the event.
+    pub level: ::tracing::Level,
+    /// The fields of the event, including `message`, formatted as strings.
+    pub fields: HashMap<String, String>,
+}
+
+/// Selects the [tracing] events that are converted into inputs.
+#[derive(Clone, Debug, Default)]
+pub struct EventFilter {
+    target: Option<String>,
+    fields: Vec<String>,
+}
+
+impl EventFilter {
+    /// Creates a filter that accepts every event.
+    pub fn any() -> Self {
+        EventFilter::default()
+    }
+
+    /// Creates a filter that accepts events whose target starts with `target`.
+    pub fn target(target: &str) -> Self {
+        EventFilter {
+            target: Some(target.into()),
+            fields: Vec::new(),
+        }
+    }
+
+    /// Also require the event to have a field called `field`.
+    pub fn with_field(mut self, field: &str) -> Self {
+        self.fields.push(field.into());
+        self
+    }
+
+    fn matches(&self, event: &::tracing::Event<'_>) -> bool {
+        let metadata = event.metadata();
+        let target_matches = self
+            .target
+            .as_ref()
+            .is_none_or(|target| metadata.target().starts_with(target.as_str()));
+
+        target_matches
+            && self
+                .fields
+                .iter()
+                .all(|field| metadata.fields().field(field).is_some())
+    }
+}
+
+// Converts a recorded event into an input.
+type EventMap<I> = Box<dyn Fn(&TracingEvent) -> Option<I> + Send + Sync>;
+
+/// A layer that sends matching [tracing] events to a monitor.
+///
+/// Created by [monitor_events].
+pub struct MonitorLayer<I> {
+    filter: EventFilter,
+    map: EventMap<I>,
+    sender: Sender<I>,
+}
+
+impl<S, I> Layer<S> for MonitorLayer<I>
+where
+    S: ::tracing::Subscriber,
+    I: Send + 'static,
+{
+    fn on_event(&self, event: &::tracing::Event<'_>, _ctx: Context<'_, S>) {
+        // Events emitted by this crate while monitoring must not feed back into the monitor.
+        if event
+            .metadata()
+            .target()
+            .starts_with(env!("CARGO_CRATE_NAME"))
+        {
+            return;
+        }
+
+        if !self.filter.matches(event) {
+            return;
+        }
+
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+
+        let event = TracingEvent {
+            target: event.metadata().target().into(),
+            level: *event.metadata().level(),
+            fields: visitor.fields,
+        };
+
+        if let Some(input) = (self.map)(&event) {
+            // The monitor stops listening once it reaches a verdict.
+            let _ = self.sender.send(input);
+        }
+    }
+}
+
+/// Feeds the events selected by `filter` to `monitor` on a background thread.
+///
+/// Each event is converted into an input with `map`, and events for which it returns `None`
+/// are skipped. The returned handle resolves to the first conclusive verdict, or to
+/// [Verdict::Inconclusive] once the layer is dropped without one.
+pub fn monitor_events<M, I, F>(
+    mut monitor: M,
+    filter: EventFilter,
+    map: F,
+) -> (MonitorLayer<I>, JoinHandle<Result<Verdict, MonitorError>>)
+where
+    M: Verify<I> + Send + 'static,
+    I: Send + 'static,
+    F: Fn(&TracingEvent) -> Option<I> + Send + Sync + 'static,
+{
+    let (sender, receiver) = mpsc::channel();
+
+    let handle = thread::spawn(move || {
+        let verdict = monitor.initial_verdict();
+        if verdict.is_conclusive() {
+            return Ok(verdict);
+        }
+
+        for input in receiver {
+            let verdict = monitor.next(&input)?;
+            if verdict.is_conclusive() {
+                return Ok(verdict);
+            }
+        }
+
+        Ok(Verdict::Inconclusive)
+    });
+
+    let layer = MonitorLayer {
+        filter,
+        map: Box::new(map),
+        sender,
+    };
+
+    (layer, handle)
+}
+
+#[derive(Default)]
+struct FieldVisitor {
+    fields: HashMap<String, String>,
+}
+
+impl ::tracing::field::Visit for FieldVisitor {
+    fn record_str(&mut self, field: &::tracing::field::Field, value: &str) {
+        self.fields.insert(field.name().into(), value.into());
+    }
+
+    fn record_debug(&mut self, field: &::tracing::field::Field, value: &dyn fmt::Debug) {
+        self.fields
+            .insert(field.name().into(), format!("{:?}", value));
+    }
+}
@@ -0,0 +1,98 @@
+//! # JSON Module
+//!
+//! This module converts a [Machine] into a JSON graph for interactive visualizations in the
+//! browser. The output uses the `nodes` and `edges` element format of Cytoscape.js, where the
+//! properties of each element are stored under `data`. For a D3 force layout, the `data` objects
+//! of the nodes and edges can be used as its nodes and links directly.
+
+use crate::machine::{Machine, TransitionId};
+use num::Bounded;
+use std::fmt;
+
+impl<D, I, U> Machine<D, I, U>
+where
+    D: fmt::Display + Bounded + Copy,
+    U: fmt::Display,
+{
+    /// Returns the machine as a JSON graph of locations and transitions.
+    ///
+    /// Each node has an `id` and an `accepting` flag. Each edge has the `id` of its transition,
+    /// a `source`, a `target`, and the `label`, `update`, and `bound` of its transition. Nodes
+    /// and edges are sorted by location so the output is stable.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{AddUpdate, MachineBuilder, Transition};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, AddUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition { to_location: "s1".into(), ..Default::default() })
+    ///     .with_accepting("s1")
+    ///     .build();
+    ///
+    /// assert_eq!(
+    ///     machine.to_json_graph(),
+    ///     concat!(
+    ///         r#"{"nodes":[{"data":{"id":"s0","accepting":false}},"#,
+    ///         r#"{"data":{"id":"s1","accepting":true}}],"#,
+    ///         r#""edges":[{"data":{"id":"s0#0","source":"s0","target":"s1","#,
+    ///         r#""label":"s0#0","update":"data += 0","bound":"[0, 255]"}}]}"#,
+    ///     ),
+    /// );
+    /// ```
+    pub fn to_json_graph(&self) -> String {
+        let locations = self.all_locations();
+
+        let nodes: Vec<String> = locations
+            .iter()
+            .map(|location| {
+                format!(
+                    r#"{{"data":{{"id":{},"accepting":{}}}}}"#,
+                    quote(location),
+                    self.get_accepting().contains(*location)
+                )
+            })
+            .collect();
+
+        let mut edges = Vec::new();
+        for location in &locations {
+            let transitions = self.get_transitions_from(location).into_iter().flatten();
+            for (index, transition) in transitions.enumerate() {
+                let id = TransitionId::new(location, index);
+                edges.push(format!(
+                    r#"{{"data":{{"id":{},"source":{},"target":{},"label":{},"update":{},"bound":{}}}}}"#,
+                    quote(&id.to_string()),
+                    quote(location),
+                    quote(&transition.to_location),
+                    quote(&self.transition_label(&id)),
+                    quote(&transition.update.to_string()),
+                    quote(&format!("{:#}", transition.bound)),
+                ));
+            }
+        }
+
+        format!(
+            r#"{{"nodes":[{}],"edges":[{}]}}"#,
+            nodes.join(","),
+            edges.join(",")
+        )
+    }
+}
+
+// Formats `value` as a JSON string literal.
+pub(crate) fn quote(value: &str) -> String {
+    let mut quoted = String::from("\"");
+
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if u32::from(c) < 0x20 => quoted.push_str(&format!("\\u{:04x}", u32::from(c))),
+            c => quoted.push(c),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
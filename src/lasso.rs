@@ -0,0 +1,188 @@
+//! # Lasso Module
+//!
+//! This module finds the cycles of a [Machine] that can still lead to acceptance. A lasso is a
+//! path from the initial location to a cycle, followed by the cycle itself. A machine with a
+//! reachable lasso accepts infinitely many inputs, and only a lasso through an accepting location
+//! can accept an infinite input.
+//!
+//! The search follows the location graph from [Machine::project_locations], and discards a lasso
+//! when the data [bounds](crate::bound::Bound) along it cannot be met. The bounds are checked
+//! once around the cycle, so a cycle that only becomes infeasible after several iterations is
+//! still reported.
+
+use crate::bound::Bound;
+use crate::machine::{Machine, TransitionId, Update};
+use crate::nfa::Nfa;
+use num::Bounded;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A path from an initial location into a cycle that can lead to acceptance.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lasso {
+    /// The edges from the initial location to [Lasso::entry], labelled as in [Nfa].
+    pub stem: Vec<Option<TransitionId>>,
+    /// The location where the cycle starts and ends.
+    pub entry: String,
+    /// The edges of the cycle, labelled as in [Nfa].
+    pub cycle: Vec<Option<TransitionId>>,
+}
+
+// Identifies a cycle by its edges and their source locations, starting from the smallest.
+type CycleKey = Vec<(String, Option<TransitionId>)>;
+
+// The search state shared by every step of the depth first search.
+struct Search {
+    nfa: Nfa,
+    can_accept: BTreeSet<String>,
+    locations: Vec<String>,
+    edges: Vec<Option<TransitionId>>,
+    found: BTreeMap<CycleKey, Lasso>,
+}
+
+impl<D, I, U> Machine<D, I, U> {
+    /// Checks if this machine accepts infinitely many inputs from `initial`.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    ///
+    /// // Accepts any number of 'a's from "s0", and nothing but the empty input from "done".
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s0".into(),
+    ///         enable: |_, i| *i == b'a',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "done".into(),
+    ///         enable: |_, i| *i == b'.',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("s0")
+    ///     .with_accepting("done")
+    ///     .build();
+    ///
+    /// assert!(machine.has_infinite_language("s0"));
+    /// assert!(!machine.has_infinite_language("done"));
+    /// ```
+    pub fn has_infinite_language(&self, initial: &str) -> bool
+    where
+        D: Ord + Copy + Bounded,
+        U: Update<D = D>,
+    {
+        !self.accepting_lassos(initial).is_empty()
+    }
+
+    /// Returns one lasso for each cycle reachable from `initial` that can still lead to
+    /// acceptance and whose bounds can be met.
+    ///
+    /// Every simple path is explored, so this is meant for machines with few locations.
+    ///
+    /// ```
+    /// use rust_efsm::bound::Bound;
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition, TransitionId};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition { to_location: "s1".into(), ..Default::default() })
+    ///     .with_transition("s1", Transition { to_location: "s1".into(), ..Default::default() })
+    ///     .with_transition("s1", Transition {
+    ///         to_location: "s2".into(),
+    ///         bound: Bound { lower: None, upper: Some(5) },
+    ///         ..Default::default()
+    ///     })
+    ///     // The data entering "s2" is at most 5, so this cycle is discarded.
+    ///     .with_transition("s2", Transition {
+    ///         to_location: "s2".into(),
+    ///         bound: Bound { lower: Some(10), upper: None },
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("s2")
+    ///     .build();
+    ///
+    /// let lassos = machine.accepting_lassos("s0");
+    /// assert_eq!(lassos.len(), 1);
+    /// assert_eq!(lassos[0].stem, vec![Some(TransitionId::new("s0", 0))]);
+    /// assert_eq!(lassos[0].entry, "s1");
+    /// assert_eq!(lassos[0].cycle, vec![Some(TransitionId::new("s1", 0))]);
+    /// ```
+    pub fn accepting_lassos(&self, initial: &str) -> Vec<Lasso>
+    where
+        D: Ord + Copy + Bounded,
+        U: Update<D = D>,
+    {
+        let nfa = self.project_locations();
+        let can_accept = nfa
+            .locations
+            .iter()
+            .filter(|location| !nfa.reachable(location).is_disjoint(&nfa.accepting))
+            .cloned()
+            .collect();
+
+        let mut search = Search {
+            nfa,
+            can_accept,
+            locations: Vec::new(),
+            edges: Vec::new(),
+            found: BTreeMap::new(),
+        };
+
+        self.visit_lassos(&mut search, initial, Bound::unbounded());
+        search.found.into_values().collect()
+    }
+
+    // Extends the current path of `search` from `location`, recording a lasso whenever an edge
+    // closes a cycle. `interval` holds the data that can reach `location` along the path.
+    fn visit_lassos(&self, search: &mut Search, location: &str, interval: Bound<D>)
+    where
+        D: Ord + Copy + Bounded,
+        U: Update<D = D>,
+    {
+        search.locations.push(location.into());
+
+        let out = search.nfa.edges.get(location).cloned().unwrap_or_default();
+        for (label, to_location) in out {
+            let next = match &label {
+                Some(id) => self.get_transition(id).and_then(|transition| {
+                    interval
+                        .intersect(&transition.bound)
+                        .map(|bound| transition.update_interval(bound))
+                }),
+                // Implicit moves leave the data unchanged.
+                None => Some(interval.clone()),
+            };
+
+            let Some(next) = next else {
+                continue;
+            };
+
+            search.edges.push(label);
+
+            match search.locations.iter().position(|l| *l == to_location) {
+                Some(start) if search.can_accept.contains(&to_location) => {
+                    let mut key: CycleKey = search.locations[start..]
+                        .iter()
+                        .cloned()
+                        .zip(search.edges[start..].iter().cloned())
+                        .collect();
+                    let first = key
+                        .iter()
+                        .enumerate()
+                        .min_by(|(_, a), (_, b)| a.cmp(b))
+                        .map_or(0, |(idx, _)| idx);
+                    key.rotate_left(first);
+
+                    search.found.entry(key).or_insert_with(|| Lasso {
+                        stem: search.edges[..start].to_vec(),
+                        entry: to_location.clone(),
+                        cycle: search.edges[start..].to_vec(),
+                    });
+                }
+                Some(_) => {}
+                None => self.visit_lassos(search, &to_location, next),
+            }
+
+            search.edges.pop();
+        }
+
+        search.locations.pop();
+    }
+}
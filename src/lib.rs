@@ -5,28 +5,136 @@
 //! and __rejecting__ different input sequences called words. Machines should be specified using the
 //! [builder](MachineBuilder).
 //!
+//! # Features
+//!
+//! * `kafka` - Drives monitors from [message streams](integrations::kafka), such as Kafka
+//!   topics, through a consumer trait implemented over the client of the broker.
+//! * `log` (default) - Logs executions, analyses, and verdicts through [tracing]. Without it, the
+//!   logging of the crate compiles out entirely.
+//! * `parallel` - Checks many traces at once with [rayon](https://docs.rs/rayon).
+//! * `render` - Draws [graphs](gviz::GvGraph) as SVG or PNG images without Graphviz, with
+//!   [layout-rs](https://docs.rs/layout-rs) and [resvg](https://docs.rs/resvg).
+//! * `serde` - Serializes [bounds](bound::Bound), [state intervals](machine::StateInterval),
+//!   [analysis outcomes](analysis::AnalysisOutcome), [machine analyses](analyzed::MachineAnalysis),
+//!   and [dynamic values](dynamic::Value) with [serde](https://docs.rs/serde), so that the
+//!   results of [find_non_empty](machine::Machine::find_non_empty) and
+//!   [analyze](machine::Machine::analyze) can be stored.
+//!
 //! # References
 //!
 //! \[1\] Cheng, K.-T. & Krishnakumar, A. Automatic Functional Test Generation Using The Extended Finite State Machine Model.
 
+#[warn(missing_docs)]
+pub mod alphabet;
+
+#[warn(missing_docs)]
+pub mod analysis;
+
+#[warn(missing_docs)]
+pub mod analyzed;
+
 #[warn(missing_docs)]
 pub mod bound;
 
+#[warn(missing_docs)]
+pub mod codegen;
+
+#[warn(missing_docs)]
+pub mod counterexample;
+
+#[warn(missing_docs)]
+pub mod dead_code;
+
+#[warn(missing_docs)]
+pub mod deadline;
+
+#[warn(missing_docs)]
+pub mod dfa;
+
+#[warn(missing_docs)]
+pub mod dynamic;
+
+#[warn(missing_docs)]
+pub mod expr;
+
+#[warn(missing_docs)]
+pub mod fuzz;
+
 #[warn(missing_docs)]
 pub mod gviz;
 
+#[warn(missing_docs)]
+pub mod integrations;
+
+#[warn(missing_docs)]
+pub mod json;
+
+#[warn(missing_docs)]
+pub mod lasso;
+
+mod log;
+
 #[warn(missing_docs)]
 pub mod machine;
 
 #[warn(missing_docs)]
 pub mod monitor;
 
+#[warn(missing_docs)]
+pub mod mutation;
+
+#[warn(missing_docs)]
+pub mod nfa;
+
+#[warn(missing_docs)]
+pub mod past;
+
+#[warn(missing_docs)]
+pub mod patterns;
+
+#[warn(missing_docs)]
+pub mod persist;
+
+#[warn(missing_docs)]
+pub mod plantuml;
+
+#[warn(missing_docs)]
+pub mod predicate;
+
+#[warn(missing_docs)]
+pub mod refine;
+
+#[warn(missing_docs)]
+pub mod regression;
+
+#[warn(missing_docs)]
+pub mod simulation;
+
+#[warn(missing_docs)]
+pub mod spec;
+
+#[warn(missing_docs)]
+pub mod stats;
+
+#[warn(missing_docs)]
+pub mod template;
+
+#[warn(missing_docs)]
+pub mod testgen;
+
+#[warn(missing_docs)]
+pub mod traverse;
+
+#[warn(missing_docs)]
+pub mod uppaal;
+
 #[cfg(test)]
 mod tests {
     use crate::machine::{IdentityUpdate, Machine, MachineBuilder, Transition};
-    use crate::monitor::Monitor;
+    use crate::monitor::{Monitor, Verdict};
 
     #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn monitor_not() {
         let machine = make_machine();
         let input = vec![1, 2, 3, 4, 0, 4, 3, 2, 1];
@@ -34,9 +142,9 @@ mod tests {
         if let Ok(mut monitor) = Monitor::new("safe", input[0], machine) {
             for verdict in input.into_iter().map(|input| monitor.next(&input)) {
                 if let Ok(verdict) = verdict {
-                    if let Some(verdict) = verdict {
+                    if verdict.is_conclusive() {
                         // We expect the verdict to be false.
-                        assert!(!verdict);
+                        assert_eq!(verdict, Verdict::Violated);
 
                         return;
                     }
@@ -0,0 +1,58 @@
+//! # Log Module
+//!
+//! This module wraps the [tracing] macros used by the crate, so that its own logging compiles
+//! out entirely without the `log` feature. The [tracing integration](crate::integrations) does
+//! not depend on it.
+//!
+//! Levels are chosen so that a production subscriber at `info` only sees the key events:
+//!
+//! * `warn` - the work of a machine or a monitor is growing out of hand, such as a
+//!   [state set explosion](LARGE_STATE_SET), or a property is not monitorable.
+//! * `info` - a monitor reached its first conclusive verdict.
+//! * `debug` - spans around executions and analyses, and their outcomes.
+//! * `trace` - every input, state set, and change to a machine.
+
+/// The number of states a machine or a monitor may track at once before a warning is logged.
+pub(crate) const LARGE_STATE_SET: usize = 1024;
+
+// Logs an event at the trace level.
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "log")]
+        ::tracing::trace!($($arg)*);
+    };
+}
+
+// Logs an event at the debug level.
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "log")]
+        ::tracing::debug!($($arg)*);
+    };
+}
+
+// Logs an event at the info level.
+macro_rules! info {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "log")]
+        ::tracing::info!($($arg)*);
+    };
+}
+
+// Logs an event at the warn level. Named apart from the builtin `warn` attribute.
+macro_rules! warning {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "log")]
+        ::tracing::warn!($($arg)*);
+    };
+}
+
+// Enters a span at `level` until the end of the enclosing block.
+macro_rules! span {
+    ($level:ident, $($arg:tt)*) => {
+        #[cfg(feature = "log")]
+        let _span = ::tracing::span!(::tracing::Level::$level, $($arg)*).entered();
+    };
+}
+
+pub(crate) use {debug, info, span, trace, warning};
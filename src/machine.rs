@@ -1,261 +1,3427 @@
+//! # Machine Module
+//!
+//! This module defines the [Machine] type, its [transitions](Transition), and the
+//! [builder](MachineBuilder) used to specify them.
+
+use crate::alphabet::InputClassifier;
+use crate::analysis::{AnalysisOutcome, Approximation, CancellationToken, Progress};
 use crate::bound::Bound;
-use num::{Bounded, CheckedAdd};
-use std::collections::{HashMap, HashSet};
+use crate::log::{debug, span, trace, warning, LARGE_STATE_SET};
+use num::{Bounded, CheckedAdd, Integer};
+use smallvec::SmallVec;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt;
 use std::fmt::Debug;
-use std::hash::Hash;
-use std::ops::Add;
-use tracing::{debug, info};
+use std::hash::{Hash, Hasher};
+use std::ops::{Add, Neg};
+use std::sync::{Arc, Mutex, OnceLock};
 
 /// Describes an EFSM.
 /// In most cases, use the [builder](MachineBuilder) to specify a machine.
 ///
+/// # Thread Safety
+///
+/// A machine is `Send` and `Sync` whenever its data and update types are, whatever its input
+/// type: guards and predicates that capture state are required to be `Send + Sync`, and the
+/// caches of a machine are behind locks. Executing a machine only needs a shared reference, so
+/// one machine can be [shared](Machine::shared) by many threads without cloning it.
+///
+/// ```
+/// use rust_efsm::machine::{AddUpdate, Machine};
+/// use std::rc::Rc;
+///
+/// fn assert_send_sync<T: Send + Sync>() {}
+/// assert_send_sync::<Machine<u32, u8, AddUpdate<u32>>>();
+/// assert_send_sync::<Machine<u32, Rc<str>, AddUpdate<u32>>>();
+/// ```
+///
 /// # See also
 ///
 /// * [MachineBuilder]
-#[derive(Clone)]
 pub struct Machine<D, I, U> {
     // Represents the directed graph of locations and transitions.
     locations: HashMap<String, Vec<Transition<D, I, U>>>,
 
     // Represents accepting locations.
     accepting: HashSet<String>,
+
+    // Represents rejecting locations, which end every run that enters them.
+    rejecting: HashSet<String>,
+
+    // Restricts the data accepted in some of the accepting locations.
+    accepting_conditions: HashMap<String, AcceptingCondition<D>>,
+
+    // Decides what happens to a state without an enabled transition.
+    no_transition_policy: NoTransitionPolicy,
+
+    // Counts guard evaluations and firings per transition, when enabled.
+    metrics: Option<MetricsTable>,
+
+    // Memoizes find_non_empty by start location until the machine is modified.
+    non_empty: NonEmptyCache<D>,
+
+    // Represents the data assertions checked on entering each location.
+    assertions: HashMap<String, Vec<Assertion<D>>>,
+
+    // Groups locations for presentation, such as the clusters of a graph.
+    tags: HashMap<String, String>,
+
+    // Narrows the transitions tried for an input down to those its class may enable.
+    dispatch: Option<Dispatch<D, I>>,
+
+    // Memoizes the results of guards, when enabled.
+    guard_cache: Option<Arc<dyn GuardMemo<D, I>>>,
+
+    // Returns every input of the machine, when declared.
+    alphabet: Option<Symbols<I>>,
+}
+
+// Implemented by hand, because the input type does not need to be cloned.
+impl<D: Clone, I, U: Clone> Clone for Machine<D, I, U> {
+    fn clone(&self) -> Self {
+        Machine {
+            locations: self.locations.clone(),
+            accepting: self.accepting.clone(),
+            rejecting: self.rejecting.clone(),
+            accepting_conditions: self.accepting_conditions.clone(),
+            no_transition_policy: self.no_transition_policy.clone(),
+            metrics: self.metrics.clone(),
+            non_empty: self.non_empty.clone(),
+            assertions: self.assertions.clone(),
+            tags: self.tags.clone(),
+            dispatch: self.dispatch.clone(),
+            guard_cache: self.guard_cache.clone(),
+            alphabet: self.alphabet.clone(),
+        }
+    }
+}
+
+impl<D: Debug, I, U: Debug> Debug for Machine<D, I, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Sort locations so the output does not depend on hashing.
+        let locations: BTreeMap<_, _> = self.locations.iter().collect();
+        let accepting: BTreeSet<_> = self.accepting.iter().collect();
+        let rejecting: BTreeSet<_> = self.rejecting.iter().collect();
+        let accepting_conditions: BTreeMap<_, _> = self.accepting_conditions.iter().collect();
+        let tags: BTreeMap<_, _> = self.tags.iter().collect();
+
+        f.debug_struct("Machine")
+            .field("locations", &locations)
+            .field("accepting", &accepting)
+            .field("accepting_conditions", &accepting_conditions)
+            .field("rejecting", &rejecting)
+            .field("no_transition_policy", &self.no_transition_policy)
+            .field("tags", &tags)
+            .finish()
+    }
+}
+
+/// Machines are equal when they have the same locations, transitions, accepting and rejecting
+/// locations, accepting conditions, and no transition policy. See [Transition] for how transitions are compared.
+///
+/// ```
+/// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+///
+/// let build = |name: &str| {
+///     MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+///         .with_transition("s0", Transition {
+///             to_location: "s1".into(),
+///             enable: |_, i| *i == b'a',
+///             name: Some(name.into()),
+///             ..Default::default()
+///         })
+///         .with_accepting("s1")
+///         .build()
+/// };
+///
+/// assert_eq!(build("read a"), build("read a"));
+/// assert_ne!(build("read a"), build("read b"));
+/// ```
+impl<D: PartialEq, I, U: PartialEq> PartialEq for Machine<D, I, U> {
+    fn eq(&self, other: &Self) -> bool {
+        self.locations == other.locations
+            && self.accepting == other.accepting
+            && self.rejecting == other.rejecting
+            && self.accepting_conditions == other.accepting_conditions
+            && self.no_transition_policy == other.no_transition_policy
+    }
 }
 
 impl<D, I, U> Machine<D, I, U> {
     fn new(
         locations: HashMap<String, Vec<Transition<D, I, U>>>,
         accepting: HashSet<String>,
+        no_transition_policy: NoTransitionPolicy,
     ) -> Self {
         Machine {
             locations,
             accepting,
+            rejecting: HashSet::new(),
+            accepting_conditions: HashMap::new(),
+            no_transition_policy,
+            metrics: None,
+            non_empty: NonEmptyCache::default(),
+            assertions: HashMap::new(),
+            tags: HashMap::new(),
+            dispatch: None,
+            guard_cache: None,
+            alphabet: None,
         }
     }
 
+    // Discards the analysis results memoized for this machine. Clones made before the
+    // modification keep the results, which are still valid for them.
+    fn invalidate(&mut self) {
+        self.non_empty = NonEmptyCache::default();
+        if let Some(dispatch) = &mut self.dispatch {
+            dispatch.table = OnceLock::new();
+        }
+        // Clones share the cache, so it is replaced rather than cleared.
+        if let Some(cache) = &mut self.guard_cache {
+            *cache = cache.empty();
+        }
+    }
+
+    /// Returns the transitions out of every location that has at least one transition.
     pub fn get_locations(&self) -> &HashMap<String, Vec<Transition<D, I, U>>> {
         &self.locations
     }
 
+    /// Returns the set of accepting locations.
     pub fn get_accepting(&self) -> &HashSet<String> {
         &self.accepting
     }
 
-    pub fn get_transitions_from(&self, location: &str) -> Option<&Vec<Transition<D, I, U>>> {
-        self.locations.get(location)
+    /// Returns the set of rejecting locations.
+    pub fn get_rejecting(&self) -> &HashSet<String> {
+        &self.rejecting
+    }
+
+    /// Returns the conditions on the data of the accepting locations that have one.
+    pub fn get_accepting_conditions(&self) -> &HashMap<String, AcceptingCondition<D>> {
+        &self.accepting_conditions
     }
 
-    pub fn transition(&self, i: &I, states: Vec<State<D>>) -> Vec<State<D>>
+    /// Checks if a run ending in `state` is accepted. The location must be accepting and not
+    /// rejecting, and the data must meet the [AcceptingCondition] of the location, if it has one.
+    pub fn is_accepting(&self, state: &State<D>) -> bool {
+        self.accepting.contains(&state.location)
+            && !self.rejecting.contains(&state.location)
+            && self
+                .accepting_conditions
+                .get(&state.location)
+                .is_none_or(|condition| condition.holds(&state.data))
+    }
+
+    // Checks if a run ending in `location` may be accepted for some data in `interval`.
+    fn may_accept(&self, location: &str, interval: &Bound<D>) -> bool
     where
-        D: Clone,
-        U: Update<D = D>,
+        D: Ord + Copy + Bounded,
     {
-        let mut next_states: Vec<State<D>> = Vec::new();
+        self.accepting.contains(location)
+            && !self.rejecting.contains(location)
+            && self
+                .accepting_conditions
+                .get(location)
+                .is_none_or(|condition| condition.may_hold(interval))
+    }
 
-        // Iterate over the current states.
-        for (location, data) in states.into_iter().map(|state| state.into()) {
-            // Get the list of transitions out of this location.
-            if let Some(transitions) = self.locations.get(&location) {
-                for transition in transitions {
-                    // Check if the transition is enabled.
-                    if (transition.enable)(&data, &i) {
-                        // Take the transition, which means we apply the update function.
-                        let data = transition.update.update(data.clone(), i);
-                        next_states.push(State {
-                            location: transition.to_location.clone(),
-                            data,
-                        });
-                    }
-                }
-            }
-        }
+    /// Returns the tag of each tagged location.
+    pub fn get_tags(&self) -> &HashMap<String, String> {
+        &self.tags
+    }
 
-        next_states
+    /// Returns the policy applied to states without an enabled transition.
+    pub fn get_no_transition_policy(&self) -> &NoTransitionPolicy {
+        &self.no_transition_policy
     }
 
-    /// Checks if the input sequence `input` belongs to the language defined by this machine.
-    pub fn exec(&self, location: &str, data: D, input: Vec<I>) -> bool
+    /// Returns the transition identified by `id`, if it exists.
+    pub fn get_transition(&self, id: &TransitionId) -> Option<&Transition<D, I, U>> {
+        self.locations.get(&id.from_location)?.get(id.index)
+    }
+
+    // Returns the transition identified by `id` for modification, if it exists.
+    pub(crate) fn get_transition_mut(
+        &mut self,
+        id: &TransitionId,
+    ) -> Option<&mut Transition<D, I, U>> {
+        self.invalidate();
+        self.locations.get_mut(&id.from_location)?.get_mut(id.index)
+    }
+
+    /// Moves this machine behind an [Arc], so that threads can execute it at once.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    /// use std::thread;
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s0".into(),
+    ///         enable: |_, i| *i != b'!',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("s0")
+    ///     .build()
+    ///     .shared();
+    ///
+    /// let workers: Vec<_> = [b"abc".to_vec(), b"a!c".to_vec()]
+    ///     .into_iter()
+    ///     .map(|input| {
+    ///         let machine = machine.clone();
+    ///         thread::spawn(move || machine.exec("s0", 0, input))
+    ///     })
+    ///     .collect();
+    ///
+    /// let accepted: Vec<bool> = workers.into_iter().map(|w| w.join().unwrap()).collect();
+    /// assert_eq!(accepted, [true, false]);
+    /// ```
+    pub fn shared(self) -> Arc<Self> {
+        Arc::new(self)
+    }
+
+    /// Returns a builder holding a copy of this machine's specification.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_accepting("s0")
+    ///     .build();
+    ///
+    /// let extended = machine.to_builder().with_accepting("s1").build();
+    /// assert!(extended.exec("s1", 0, vec![]));
+    /// assert!(!machine.exec("s1", 0, vec![]));
+    /// ```
+    pub fn to_builder(&self) -> MachineBuilder<D, I, U>
     where
-        D: Clone + Debug,
-        I: Debug,
-        U: Update<D = D>,
+        D: Clone,
+        U: Clone,
     {
-        info!("executing input sequence");
+        let machine = self.clone();
+        MachineBuilder {
+            locations: machine.locations,
+            accepting: machine.accepting,
+            rejecting: machine.rejecting,
+            accepting_conditions: machine.accepting_conditions,
+            no_transition_policy: machine.no_transition_policy,
+            metrics: machine.metrics.is_some(),
+            assertions: machine.assertions,
+            tags: machine.tags,
+            dispatch: machine.dispatch.map(Dispatch::reset),
+            guard_cache: machine.guard_cache.map(|cache| cache.empty()),
+            alphabet: machine.alphabet,
+        }
+    }
 
-        let mut states = vec![State {
-            location: location.into(),
-            data,
-        }];
+    /// Adds a transition from `from_location` and returns its identifier.
+    ///
+    /// The analyses are computed on demand, so they reflect the change on their next use.
+    /// Monitors already built from this machine hold their own copy and are not affected.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition, TransitionId};
+    ///
+    /// let mut machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_accepting("s1")
+    ///     .build();
+    /// assert!(!machine.exec("s0", 0, vec![b'a']));
+    ///
+    /// let id = machine.add_transition("s0", Transition {
+    ///     to_location: "s1".into(),
+    ///     ..Default::default()
+    /// });
+    /// assert_eq!(id, TransitionId::new("s0", 0));
+    /// assert!(machine.exec("s0", 0, vec![b'a']));
+    /// ```
+    pub fn add_transition(
+        &mut self,
+        from_location: &str,
+        transition: Transition<D, I, U>,
+    ) -> TransitionId {
+        trace!(
+            "add transition {} to {}",
+            from_location,
+            transition.to_location
+        );
+        self.invalidate();
+        let transitions = self.locations.entry(from_location.into()).or_default();
+        transitions.push(transition);
+        TransitionId::new(from_location, transitions.len() - 1)
+    }
 
-        for i in input {
-            info!("received input {:?}", i);
-            info!("in states {:?}", states);
+    /// Removes the transition identified by `id` and returns it, if it exists.
+    ///
+    /// The transitions added after it out of the same location move down by one index, so
+    /// identifiers of those transitions obtained before the removal no longer refer to them.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition, TransitionId};
+    ///
+    /// let mut machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition { to_location: "s1".into(), ..Default::default() })
+    ///     .with_accepting("s1")
+    ///     .build();
+    ///
+    /// assert!(machine.remove_transition(&TransitionId::new("s0", 0)).is_some());
+    /// assert!(machine.remove_transition(&TransitionId::new("s0", 0)).is_none());
+    /// assert!(!machine.exec("s0", 0, vec![b'a']));
+    /// ```
+    pub fn remove_transition(&mut self, id: &TransitionId) -> Option<Transition<D, I, U>> {
+        let transitions = self.locations.get_mut(&id.from_location)?;
+        if id.index >= transitions.len() {
+            return None;
+        }
 
-            states = self.transition(&i, states);
+        trace!("remove transition {}", id);
+        let transition = transitions.remove(id.index);
+
+        // Keep the counts of the remaining transitions aligned with their new indices.
+        if let Some(Ok(mut table)) = self.metrics.as_ref().map(|metrics| metrics.lock()) {
+            if let Some(counts) = table.get_mut(&id.from_location) {
+                if id.index < counts.len() {
+                    counts.remove(id.index);
+                }
+            }
+        }
 
-            info!("transitioned to states {:?}", states);
+        // Only locations with at least one transition are kept.
+        if transitions.is_empty() {
+            self.locations.remove(&id.from_location);
         }
 
-        states
-            .iter()
-            .map(|state| self.accepting.contains(&state.location))
-            .fold(false, |acc, accept| acc || accept)
+        self.invalidate();
+        Some(transition)
     }
 
-    pub fn complement(mut self) -> Result<Machine<D, I, U>, MachineError> {
-        // Preconditions:
-        // (1) Machine is deterministic.
-        // (2) Machine is total i.e. its state is defined for all inputs.
-        //
-        // TODO: I need some infrastructure for checking these and returing errors.
+    /// Marks `location` as accepting or rejecting. Any [AcceptingCondition] of the location is
+    /// removed.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder};
+    ///
+    /// let mut machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new().build();
+    ///
+    /// machine.set_accepting("s0", true);
+    /// assert!(machine.exec("s0", 0, vec![]));
+    ///
+    /// machine.set_accepting("s0", false);
+    /// assert!(!machine.exec("s0", 0, vec![]));
+    /// ```
+    pub fn set_accepting(&mut self, location: &str, accepting: bool) {
+        trace!("mark location {} as accepting: {}", location, accepting);
+        self.invalidate();
+        self.accepting_conditions.remove(location);
+        match accepting {
+            true => self.accepting.insert(location.into()),
+            false => self.accepting.remove(location),
+        };
+    }
+
+    /// Returns how often the guard of each transition was evaluated and how often it fired, or
+    /// `None` unless the machine was built [with metrics](MachineBuilder::with_metrics).
+    ///
+    /// Every transition is listed, including those that were never evaluated. Clones of a machine
+    /// share its counters, so the counts include the work of [monitors](crate::monitor::Monitor)
+    /// built from it. A monitor evaluates each input in both its prover and its falsifier.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition, TransitionId};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s0".into(),
+    ///         enable: |_, i| *i == b'a',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s1".into(),
+    ///         enable: |_, i| *i == b'b',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("s0")
+    ///     .with_metrics()
+    ///     .build();
+    ///
+    /// machine.exec("s0", 0, vec![b'a', b'a']);
+    ///
+    /// let metrics = machine.metrics().unwrap();
+    /// assert_eq!(metrics[&TransitionId::new("s0", 0)].fired, 2);
+    /// assert_eq!(metrics[&TransitionId::new("s0", 1)].evaluated, 2);
+    /// assert_eq!(metrics[&TransitionId::new("s0", 1)].fired, 0);
+    /// ```
+    pub fn metrics(&self) -> Option<BTreeMap<TransitionId, TransitionMetrics>> {
+        let table = self.metrics.as_ref()?.lock().ok()?;
 
-        let mut rejecting: HashSet<String> = HashSet::new();
-        for loc in self.locations.keys() {
-            if !self.accepting.contains(loc) {
-                rejecting.insert(loc.clone());
+        let mut metrics: BTreeMap<_, _> = self
+            .transition_ids()
+            .map(|id| (id, TransitionMetrics::default()))
+            .collect();
+
+        for (location, counts) in table.iter() {
+            for (index, count) in counts.iter().enumerate() {
+                metrics.insert(TransitionId::new(location, index), *count);
             }
         }
 
-        self.accepting = rejecting;
-        Ok(self)
+        Some(metrics)
     }
 
-    /// Find all StateIntervals that lead to acceptance.
+    /// Sets the counts returned by [metrics](Machine::metrics) back to zero.
+    pub fn reset_metrics(&self) {
+        if let Some(Ok(mut table)) = self.metrics.as_ref().map(|metrics| metrics.lock()) {
+            table.clear();
+        }
+    }
+
+    /// Returns the identifiers of every transition in this machine.
+    pub fn transition_ids(&self) -> impl Iterator<Item = TransitionId> + '_ {
+        self.locations.iter().flat_map(|(location, transitions)| {
+            (0..transitions.len()).map(move |index| TransitionId::new(location, index))
+        })
+    }
+
+    /// Returns the name of the transition identified by `id`, or the identifier itself when the
+    /// transition is unnamed.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition, TransitionId};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s1".into(),
+    ///         name: Some("start".into()),
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("s0", Transition { to_location: "s0".into(), ..Default::default() })
+    ///     .build();
+    ///
+    /// assert_eq!(machine.transition_label(&TransitionId::new("s0", 0)), "start");
+    /// assert_eq!(machine.transition_label(&TransitionId::new("s0", 1)), "s0#1");
+    /// ```
+    pub fn transition_label(&self, id: &TransitionId) -> String {
+        self.get_transition(id)
+            .and_then(|transition| transition.name.clone())
+            .unwrap_or_else(|| id.to_string())
+    }
+
+    /// Returns a hash of the structure of this machine.
+    ///
+    /// The hash covers the locations, the transitions with their targets, names, bounds, and
+    /// updates, the accepting and rejecting locations, the accepting conditions, and the no
+    /// transition policy. Guards, bindings, predicate conditions, and assertions are only covered
+    /// by their presence, the kind of binding, and the messages of the assertions, along with the
+    /// transition names. The hash is stable across runs and builds on platforms with the same
+    /// endianness and pointer width, so it can detect when a persisted specification has changed.
     ///
     /// ```
-    /// use rust_efsm::machine::{Machine, MachineBuilder, AddUpdate, Transition, Update};
     /// use rust_efsm::bound::Bound;
-    /// let machine = MachineBuilder::<u8, u8, AddUpdate<u8>>::new().build();
+    /// use rust_efsm::machine::{AddUpdate, MachineBuilder, Transition};
+    ///
+    /// let build = |amount: u32| {
+    ///     MachineBuilder::<u32, u8, AddUpdate<u32>>::new()
+    ///         .with_transition("s0", Transition {
+    ///             to_location: "s0".into(),
+    ///             update: AddUpdate { amount },
+    ///             ..Default::default()
+    ///         })
+    ///         .with_accepting("s0")
+    ///         .build()
+    /// };
+    ///
+    /// assert_eq!(build(1).fingerprint(), build(1).fingerprint());
+    /// assert_ne!(build(1).fingerprint(), build(2).fingerprint());
     ///
+    /// let rejecting = build(1).to_builder().with_rejecting("s1").build();
+    /// assert_ne!(build(1).fingerprint(), rejecting.fingerprint());
     ///
+    /// let conditional = build(1)
+    ///     .to_builder()
+    ///     .with_accepting_when("s0", Bound { lower: None, upper: Some(3) })
+    ///     .build();
+    /// assert_ne!(build(1).fingerprint(), conditional.fingerprint());
     /// ```
-    pub fn find_non_empty(&self, location: &str) -> Result<HashMap<String, Bound<D>>, MachineError>
+    pub fn fingerprint(&self) -> u64
     where
-        D: Eq + Hash + Clone + Ord + Copy + Bounded + Debug + fmt::Display,
-        U: Update<D = D>,
+        D: Hash,
+        U: Hash,
     {
-        // Prerequisites
-        // Deterministic?
-        // FIXME: Cycles can cause unbounded execution... I think?
-        // All transitions must be bounded.
+        let mut hasher = Fnv1a::default();
 
-        // A path is a vector of state intervals.
-        // A path is completed when it reaches an accepting state.
-        // A path is completed when it reaches a previously validated state interval.
-        // All state intervals in a completed path are not sink state intervals.
+        // Sort locations so the hash does not depend on the order of the hash map.
+        let locations: BTreeMap<_, _> = self.locations.iter().collect();
+        for (location, transitions) in locations {
+            location.hash(&mut hasher);
+            transitions.len().hash(&mut hasher);
 
-        let mut safe: HashMap<String, Bound<D>> = HashMap::new();
-        for location in &self.accepting {
-            safe.insert(location.clone(), Bound::unbounded());
+            for transition in transitions {
+                transition.to_location.hash(&mut hasher);
+                transition.name.hash(&mut hasher);
+                transition.bound.hash(&mut hasher);
+                transition.update.hash(&mut hasher);
+                transition.guard.is_some().hash(&mut hasher);
+                transition
+                    .binding
+                    .as_ref()
+                    .map(|binding| matches!(binding, Binding::Store(_)))
+                    .hash(&mut hasher);
+            }
         }
 
-        let mut nodes: Vec<PathNode<D>> = Vec::new();
+        let accepting: BTreeSet<_> = self.accepting.iter().collect();
+        accepting.hash(&mut hasher);
+        let rejecting: BTreeSet<_> = self.rejecting.iter().collect();
+        rejecting.hash(&mut hasher);
 
-        let location = String::from(location);
-        let path_root = PathNode {
-            idx: nodes.len(),
-            parent: None,
-            interval: Bound::unbounded(),
-            location,
-        };
+        let conditions: BTreeMap<_, _> = self.accepting_conditions.iter().collect();
+        for (location, condition) in conditions {
+            location.hash(&mut hasher);
+            condition.bound.hash(&mut hasher);
+            condition.negated.hash(&mut hasher);
+        }
 
-        nodes.push(path_root);
+        let assertions: BTreeMap<_, _> = self.assertions.iter().collect();
+        for (location, assertions) in assertions {
+            location.hash(&mut hasher);
+            assertions.len().hash(&mut hasher);
+            for assertion in assertions {
+                assertion.message.hash(&mut hasher);
+            }
+        }
+        self.no_transition_policy.hash(&mut hasher);
 
-        // Depth first search for accepting paths.
-        let mut nodes_to_visit: Vec<usize> = vec![0];
+        hasher.finish()
+    }
 
-        const MAX_NODES: usize = 100;
-        while nodes.len() <= MAX_NODES {
-            // Check if current node is accepting
-            // Check if current node is in safe.
-            // If either of these, then add the full path to safe.
-            // We do not care if the intervals we push to safe are unique because the hash set will
-            // handle that.
+    /// Returns the transitions out of `location`, if it has any.
+    pub fn get_transitions_from(&self, location: &str) -> Option<&Vec<Transition<D, I, U>>> {
+        self.locations.get(location)
+    }
 
-            if let Some(idx) = nodes_to_visit.pop() {
-                let current = &nodes[idx];
+    /// Computes the set of states reached from `states` after reading input `i`.
+    ///
+    /// States without an enabled transition are handled according to the machine's
+    /// [NoTransitionPolicy]. States entering a [rejecting](MachineBuilder::with_rejecting)
+    /// location are dropped.
+    pub fn transition(&self, i: &I, states: Vec<State<D>>) -> Result<Vec<State<D>>, MachineError>
+    where
+        D: Clone,
+        U: Update<D = D>,
+    {
+        let mut next_states: Vec<State<D>> = Vec::with_capacity(states.len());
 
-                debug!(
-                    "visit {} with interval {}",
-                    current.location, current.interval
-                );
+        // Iterate over the current states.
+        for state in states {
+            next_states.extend(
+                self.successors(state, i)?
+                    .into_iter()
+                    .map(|(_, next)| next)
+                    .filter(|next| !self.rejecting.contains(&next.location)),
+            );
+        }
 
-                // Check if the interval is completely inside of already safe bounds.
-                let is_bound = match safe.get(&current.location) {
-                    Some(bound) => bound.contains_interval(&current.interval),
-                    None => false,
-                };
+        Ok(next_states)
+    }
 
-                if is_bound || self.accepting.contains(&current.location) {
-                    // Add path to safe.
-                    // Traverse up the parents to get the path.
+    // Computes the states reached from `states` after reading input `i`, like transition, and
+    // pairs each of them with the index of the state it was reached from and the index of the
+    // transition taken.
+    fn traced_transition(
+        &self,
+        i: &I,
+        states: &[State<D>],
+    ) -> Result<(Vec<State<D>>, Sources), MachineError>
+    where
+        D: Clone,
+        U: Update<D = D>,
+    {
+        let mut next_states = Vec::with_capacity(states.len());
+        let mut sources = Vec::with_capacity(states.len());
 
-                    debug!("safe:");
+        for (source, state) in states.iter().enumerate() {
+            for (transition, next) in self.successors(state.clone(), i)? {
+                if !self.rejecting.contains(&next.location) {
+                    next_states.push(next);
+                    sources.push((source, transition));
+                }
+            }
+        }
 
-                    let path_iter = nodes[idx].path_to(&nodes[..]);
-                    for (location, safe_interval) in path_iter
-                        .filter_map(|idx| nodes[idx].parent.clone())
-                        .map(|(idx, bound)| (nodes[idx].location.clone(), bound))
-                    {
-                        debug!("    (loc:{}, cond: {})", location, safe_interval);
-                        safe.entry(location.clone())
-                            .and_modify(|bound| bound.make_contain(&safe_interval))
-                            .or_insert(safe_interval.clone());
-                    }
+        Ok((next_states, sources))
+    }
 
-                    debug!("after adding we have the following safe states:");
-                    for (location, interval) in &safe {
-                        debug!("    loc: {} is safe over interval: {}", location, interval);
-                    }
-                }
+    // Returns the states reached from `state` on input `i`, each paired with the index of the
+    // transition taken, or None for an implicit move under the no transition policy.
+    pub(crate) fn successors(&self, state: State<D>, i: &I) -> Result<Successors<D>, MachineError>
+    where
+        D: Clone,
+        U: Update<D = D>,
+    {
+        let transitions = self
+            .locations
+            .get(&state.location)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
 
-                // Iterate over transitions out of current node.
-                if let Some(transitions) = self.locations.get(&nodes[idx].location) {
-                    debug!("exploring transitions");
-                    for trans in transitions {
-                        // Compute intersection of the current state interval with the transition bounds.
-                        // If the resulting state interval is invalid, then continue.
-                        // This result indicates that this transition is not enabled from this state interval.
+        // Only the transitions the class of the input may enable need to be tried.
+        let candidates = self.dispatch.as_ref().and_then(|dispatch| {
+            dispatch.candidates(&self.locations, &state.location, &state.data, i)
+        });
+        let count = candidates.map_or(transitions.len(), <[usize]>::len);
 
-                        let child_idx = nodes.len();
-                        let node = &mut nodes[idx];
-                        if let Some(postcondition) = node.interval.clone().intersect(&trans.bound) {
-                            // Apply the update function to the state interval.
-                            // The resulting state interval represents a new node in the path.
+        // Evaluate every guard before applying updates, so the data is only cloned when more
+        // than one transition is enabled.
+        let mut enabled: SmallVec<[usize; 2]> = SmallVec::new();
+        for position in 0..count {
+            let idx = candidates.map_or(position, |candidates| candidates[position]);
+            let transition = &transitions[idx];
+            let is_enabled = match &self.guard_cache {
+                Some(cache) => cache.check(&state.location, idx, &state.data, i, &|| {
+                    transition.is_enabled(&state.data, i)
+                }),
+                None => transition.is_enabled(&state.data, i),
+            };
+            if let Some(metrics) = &self.metrics {
+                record(metrics, &state.location, idx, transitions.len(), is_enabled);
+            }
 
-                            let location = trans.to_location.clone();
-                            let next_interval = trans.update.update_interval(postcondition.clone());
+            if is_enabled {
+                enabled.push(idx);
+            }
+        }
 
-                            debug!("    found: ({}: {})", location, next_interval);
-                            let path_node = PathNode {
-                                idx: child_idx,
-                                parent: Some((idx, postcondition)),
-                                interval: next_interval,
-                                location,
-                            };
+        let mut next_states = Successors::new();
 
-                            nodes_to_visit.push(child_idx);
-                            nodes.push(path_node);
-                        }
-                    }
+        if let Some((&last, rest)) = enabled.split_last() {
+            // Take the transitions, which means we apply the update function.
+            for &idx in rest {
+                let transition = &transitions[idx];
+                next_states.push((
+                    Some(idx),
+                    State {
+                        location: transition.to_location.clone(),
+                        data: transition.take(state.data.clone(), i),
+                    },
+                ));
+            }
+
+            let transition = &transitions[last];
+            next_states.push((
+                Some(last),
+                State {
+                    location: transition.to_location.clone(),
+                    data: transition.take(state.data, i),
+                },
+            ));
+        } else {
+            match &self.no_transition_policy {
+                NoTransitionPolicy::Reject => {}
+                NoTransitionPolicy::Stutter => next_states.push((None, state)),
+                NoTransitionPolicy::SinkTo(location) => next_states.push((
+                    None,
+                    State {
+                        location: location.clone(),
+                        data: state.data,
+                    },
+                )),
+                NoTransitionPolicy::Error => {
+                    return Err(MachineError::NoTransition(state.location));
+                }
+            }
+        }
+
+        // Check the assertions of the locations entered.
+        for (_, next) in &next_states {
+            for assertion in self.assertions.get(&next.location).into_iter().flatten() {
+                if !(assertion.predicate)(&next.data) {
+                    return Err(MachineError::AssertionViolated(AssertionViolation {
+                        location: next.location.clone(),
+                        data: (assertion.describe)(&next.data),
+                        input_index: None,
+                        message: assertion.message.clone(),
+                    }));
                 }
-            } else {
-                break;
             }
         }
 
-        Ok(safe)
+        Ok(next_states)
     }
-}
 
-/// Describes a single transition relation.
-#[derive(Clone)]
-pub struct Transition<D, I, U> {
-    pub to_location: String,
-    pub enable: Enable<D, I>,
-    pub bound: Bound<D>,
-    pub update: U,
-}
+    // Returns the locations and intervals that states of `location` with data in `interval` may
+    // move to on some input. Guards cannot be evaluated over an interval, so every transition
+    // whose bound meets the interval is assumed to be enabled for some of its data.
+    pub(crate) fn interval_successors(
+        &self,
+        location: &str,
+        interval: &Bound<D>,
+    ) -> Vec<(String, Bound<D>)>
+    where
+        D: Ord + Copy + Bounded,
+        U: Update<D = D>,
+    {
+        let mut next: Vec<(String, Bound<D>)> = self
+            .locations
+            .get(location)
+            .into_iter()
+            .flatten()
+            .filter_map(|transition| {
+                interval.intersect(&transition.bound).map(|bound| {
+                    (
+                        transition.to_location.clone(),
+                        transition.update_interval(bound),
+                    )
+                })
+            })
+            .collect();
+
+        // Some of the data may not enable any transition.
+        match &self.no_transition_policy {
+            NoTransitionPolicy::Stutter => next.push((location.into(), interval.clone())),
+            NoTransitionPolicy::SinkTo(sink) => next.push((sink.clone(), interval.clone())),
+            NoTransitionPolicy::Reject | NoTransitionPolicy::Error => {}
+        }
+
+        // Runs end on entering a rejecting location.
+        next.retain(|(location, _)| !self.rejecting.contains(location));
+        next
+    }
+
+    /// Checks if the input sequence `input` belongs to the language defined by this machine.
+    ///
+    /// The empty sequence is accepted exactly when `location` is accepting.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_accepting("s0")
+    ///     .build();
+    ///
+    /// assert!(machine.exec("s0", 0, vec![]));
+    /// assert!(!machine.exec("s1", 0, vec![]));
+    /// assert!(!machine.exec("s0", 0, vec![b'a']));
+    /// ```
+    ///
+    /// Under [NoTransitionPolicy::Error], an input without an enabled transition rejects the
+    /// sequence. Use [try_exec](Machine::try_exec) to observe the error instead.
+    pub fn exec(&self, location: &str, data: D, input: Vec<I>) -> bool
+    where
+        D: Clone + Debug,
+        I: Debug,
+        U: Update<D = D>,
+    {
+        self.try_exec(location, data, input).unwrap_or(false)
+    }
+
+    /// Checks if the input sequence `input` belongs to the language defined by this machine.
+    ///
+    /// Returns an error when the machine's [NoTransitionPolicy] is
+    /// [Error](NoTransitionPolicy::Error) and some input has no enabled transition.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, NoTransitionPolicy, Transition};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s0".into(),
+    ///         enable: |_, i| *i == b'a',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("s0")
+    ///     .with_no_transition_policy(NoTransitionPolicy::Error)
+    ///     .build();
+    ///
+    /// assert!(machine.try_exec("s0", 0, vec![b'a']).unwrap());
+    /// assert!(machine.try_exec("s0", 0, vec![b'a', b'b']).is_err());
+    /// ```
+    pub fn try_exec(&self, location: &str, data: D, input: Vec<I>) -> Result<bool, MachineError>
+    where
+        D: Clone + Debug,
+        I: Debug,
+        U: Update<D = D>,
+    {
+        self.exec_iter(location, data, input, &ExecConfig::default())
+    }
+
+    /// Checks if the input sequence `input` belongs to the language defined by this machine,
+    /// without reading more inputs or tracking more states than `config` allows.
+    ///
+    /// A non-deterministic machine may track a number of states that grows with every input, so
+    /// the limits bound the memory used on adversarial or unexpectedly long traces. What happens
+    /// when a limit is exceeded is decided by [ExecConfig::on_exceed].
+    ///
+    /// ```
+    /// use rust_efsm::machine::{
+    ///     Budget, ExceedPolicy, ExecConfig, IdentityUpdate, MachineBuilder, MachineError,
+    ///     Transition,
+    /// };
+    ///
+    /// // Every input either stays or moves on, so the number of states grows with the input.
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition { to_location: "s0".into(), ..Default::default() })
+    ///     .with_transition("s0", Transition { to_location: "s1".into(), ..Default::default() })
+    ///     .with_transition("s1", Transition { to_location: "s2".into(), ..Default::default() })
+    ///     .with_transition("s2", Transition { to_location: "s3".into(), ..Default::default() })
+    ///     .with_accepting("s3")
+    ///     .build();
+    ///
+    /// let config = ExecConfig { max_states: Some(2), ..Default::default() };
+    /// let Err(MachineError::BudgetExceeded { budget, input_index }) =
+    ///     machine.exec_iter("s0", 0, [b'a'; 3], &config)
+    /// else {
+    ///     panic!("expected the budget to be exceeded");
+    /// };
+    /// assert_eq!(budget, Budget::States(2));
+    /// assert_eq!(input_index, 1);
+    ///
+    /// let config = ExecConfig {
+    ///     max_steps: Some(2),
+    ///     on_exceed: ExceedPolicy::Reject,
+    ///     ..Default::default()
+    /// };
+    /// assert!(!machine.exec_iter("s0", 0, [b'a'; 3], &config).unwrap());
+    /// assert!(machine.exec_iter("s0", 0, [b'a'; 3], &ExecConfig::default()).unwrap());
+    /// ```
+    pub fn exec_iter<T>(
+        &self,
+        location: &str,
+        data: D,
+        input: T,
+        config: &ExecConfig,
+    ) -> Result<bool, MachineError>
+    where
+        D: Clone + Debug,
+        I: Debug,
+        U: Update<D = D>,
+        T: IntoIterator<Item = I>,
+    {
+        span!(DEBUG, "exec", location);
+
+        let states = vec![State {
+            location: location.into(),
+            data,
+        }];
+        self.exec_states(states, input, config)
+    }
+
+    /// Checks if the input sequence `input` belongs to the language defined by this machine,
+    /// when started from any of `states`.
+    ///
+    /// This is for machines whose start configuration is uncertain, or which have several entry
+    /// points, such as machines [prefixed](Machine::prefixed) and merged into one. The sequence
+    /// is accepted when a run from one of the states accepts it, and no state at all accepts
+    /// nothing.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("a", Transition {
+    ///         to_location: "done".into(),
+    ///         enable: |_, i| *i == b'a',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("b", Transition {
+    ///         to_location: "done".into(),
+    ///         enable: |_, i| *i == b'b',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("done")
+    ///     .build();
+    ///
+    /// let starts = vec![("a".to_string(), 0), ("b".to_string(), 0)];
+    /// assert!(machine.exec_from(starts.clone(), vec![b'a']));
+    /// assert!(machine.exec_from(starts.clone(), vec![b'b']));
+    /// assert!(!machine.exec_from(starts, vec![b'c']));
+    /// assert!(!machine.exec_from(vec![], vec![]));
+    /// ```
+    pub fn exec_from(&self, states: Vec<(String, D)>, input: Vec<I>) -> bool
+    where
+        D: Clone + Debug,
+        I: Debug,
+        U: Update<D = D>,
+    {
+        span!(DEBUG, "exec_from", starts = states.len());
+
+        let states = states
+            .into_iter()
+            .map(|(location, data)| State { location, data })
+            .collect();
+        self.exec_states(states, input, &ExecConfig::default())
+            .unwrap_or(false)
+    }
+
+    /// Runs the machine on `input` like [try_exec](Machine::try_exec), and reports how far the
+    /// runs got, to explain why an input was rejected.
+    ///
+    /// This is what [assert_accepts](crate::assert_accepts) and
+    /// [assert_rejects](crate::assert_rejects) print when they fail.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, State, Transition};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s0".into(),
+    ///         enable: |_, i| *i == b'a',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("s0")
+    ///     .build();
+    ///
+    /// let report = machine.exec_report("s0", 0, b"aaba".to_vec());
+    /// assert!(!report.accepted);
+    /// assert_eq!(report.read, 2);
+    /// assert_eq!(report.died_at, Some(2));
+    /// assert_eq!(report.states, [State { location: "s0".into(), data: 0 }]);
+    /// ```
+    pub fn exec_report(&self, location: &str, data: D, input: Vec<I>) -> ExecReport<D>
+    where
+        D: Clone + Debug,
+        I: Debug,
+        U: Update<D = D>,
+    {
+        span!(DEBUG, "exec_report", location);
+
+        let mut report = ExecReport {
+            accepted: false,
+            read: 0,
+            died_at: None,
+            states: vec![State {
+                location: location.into(),
+                data,
+            }],
+            error: None,
+        };
+
+        for (idx, i) in input.iter().enumerate() {
+            match self.transition(i, report.states.clone()) {
+                Ok(states) if states.is_empty() => {
+                    report.died_at = Some(idx);
+                    return report;
+                }
+                Ok(states) => {
+                    report.states = states;
+                    report.read = idx + 1;
+                }
+                Err(error) => {
+                    report.died_at = Some(idx);
+                    report.error = Some(error);
+                    return report;
+                }
+            }
+        }
+
+        report.accepted = report.states.iter().any(|state| self.is_accepting(state));
+        report
+    }
+
+    // Runs the machine from every state of `states` on `input`, within the limits of `config`.
+    fn exec_states<T>(
+        &self,
+        mut states: Vec<State<D>>,
+        input: T,
+        config: &ExecConfig,
+    ) -> Result<bool, MachineError>
+    where
+        D: Clone + Debug,
+        I: Debug,
+        U: Update<D = D>,
+        T: IntoIterator<Item = I>,
+    {
+        for (idx, i) in input.into_iter().enumerate() {
+            if let Some(max_steps) = config.max_steps.filter(|max_steps| idx >= *max_steps) {
+                match config.exceed(Budget::Steps(max_steps), idx)? {
+                    true => break,
+                    false => return Ok(false),
+                }
+            }
+
+            trace!(input = ?i, states = ?states, "read input");
+            let before = states.len();
+
+            states = self.transition(&i, states).map_err(|error| match error {
+                MachineError::AssertionViolated(violation) => {
+                    MachineError::AssertionViolated(AssertionViolation {
+                        input_index: Some(idx),
+                        ..violation
+                    })
+                }
+                error => error,
+            })?;
+
+            trace!(states = ?states, "transitioned");
+            if before <= LARGE_STATE_SET && states.len() > LARGE_STATE_SET {
+                warning!(
+                    states = states.len(),
+                    input_index = idx,
+                    "state set explosion"
+                );
+            }
+
+            if let Some(max_states) = config.max_states.filter(|max| states.len() > *max) {
+                match config.exceed(Budget::States(max_states), idx)? {
+                    true => states.truncate(max_states),
+                    false => return Ok(false),
+                }
+            }
+
+            // Every run entered a rejecting location or had no transition, so the rest of the
+            // input cannot be accepted.
+            if states.is_empty() {
+                return Ok(false);
+            }
+        }
+
+        Ok(states.iter().any(|state| self.is_accepting(state)))
+    }
+
+    /// Reads the input sequence `inputs` and yields, after every input, whether the prefix read
+    /// so far belongs to the language defined by this machine.
+    ///
+    /// This gives the same answers as calling [try_exec](Machine::try_exec) on every prefix, in
+    /// a single pass and without building a [Monitor](crate::monitor::Monitor). The empty prefix
+    /// is not yielded; it is accepted exactly when [is_accepting](Machine::is_accepting) holds for
+    /// the initial state. After an error is yielded the iterator ends.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    ///
+    /// // Accept the words ending in an 'a'.
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition { to_location: "s0".into(), ..Default::default() })
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s1".into(),
+    ///         enable: |_, i| *i == b'a',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("s1")
+    ///     .build();
+    ///
+    /// let accepted: Result<Vec<bool>, _> = machine.exec_prefixes("s0", 0, *b"abba").collect();
+    /// assert_eq!(accepted.unwrap(), [true, false, false, true]);
+    /// ```
+    pub fn exec_prefixes<T>(
+        &self,
+        location: &str,
+        data: D,
+        inputs: T,
+    ) -> Prefixes<'_, D, I, U, T::IntoIter>
+    where
+        T: IntoIterator<Item = I>,
+    {
+        Prefixes {
+            machine: self,
+            states: Some(vec![State {
+                location: location.into(),
+                data,
+            }]),
+            inputs: inputs.into_iter().enumerate(),
+            last: None,
+            breakpoints: Vec::new(),
+        }
+    }
+
+    /// Returns every run on the input sequence `input` that ends in an accepting location.
+    ///
+    /// Runs that reach an input without an enabled transition under
+    /// [NoTransitionPolicy::Error] are discarded.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    ///
+    /// // Non-deterministically guess the last input.
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition { to_location: "s0".into(), ..Default::default() })
+    ///     .with_transition("s0", Transition { to_location: "s1".into(), ..Default::default() })
+    ///     .with_accepting("s1")
+    ///     .build();
+    ///
+    /// let runs = machine.accepting_runs("s0", 0, vec![b'a', b'b']);
+    /// assert_eq!(runs.len(), 1);
+    ///
+    /// let locations: Vec<&str> = runs[0].states.iter().map(|s| s.location.as_str()).collect();
+    /// assert_eq!(locations, ["s0", "s0", "s1"]);
+    ///
+    /// let taken: Vec<String> = runs[0].transitions.iter().flatten().map(|id| id.to_string()).collect();
+    /// assert_eq!(taken, ["s0#0", "s0#1"]);
+    /// ```
+    pub fn accepting_runs(&self, location: &str, data: D, input: Vec<I>) -> Vec<Run<D>>
+    where
+        D: Clone,
+        U: Update<D = D>,
+    {
+        let mut runs = vec![Run {
+            states: vec![State {
+                location: location.into(),
+                data,
+            }],
+            transitions: Vec::new(),
+        }];
+
+        for i in input {
+            let mut next_runs = Vec::new();
+
+            for run in runs {
+                let last = run.states.last().expect("runs are never empty").clone();
+                let from_location = last.location.clone();
+
+                // A run that hits an error cannot be extended any further.
+                let Ok(successors) = self.successors(last, &i) else {
+                    continue;
+                };
+
+                for (transition, state) in successors {
+                    if self.rejecting.contains(&state.location) {
+                        continue;
+                    }
+
+                    let mut next_run = run.clone();
+                    next_run.states.push(state);
+                    next_run
+                        .transitions
+                        .push(transition.map(|index| TransitionId::new(&from_location, index)));
+                    next_runs.push(next_run);
+                }
+            }
+
+            runs = next_runs;
+        }
+
+        runs.retain(|run| {
+            let last = run.states.last().expect("runs are never empty");
+            self.is_accepting(last)
+        });
+
+        runs
+    }
+
+    /// Returns every input sequence over `alphabet` of length at most `n` that is accepted from
+    /// `location` and `data`.
+    ///
+    /// Words are returned shortest first, and in `alphabet` order among words of equal length.
+    /// The search stops extending a prefix once none of its states can still lead to acceptance:
+    /// a state that is not accepting is dropped when its location cannot reach an accepting
+    /// location, or when its data lies outside the [bound](Transition::bound) of every transition
+    /// towards such a location and the [NoTransitionPolicy] does not move it to one.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    ///
+    /// // Accept words that contain at least one 'b'.
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s0".into(),
+    ///         enable: |_, i| *i != b'b',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s1".into(),
+    ///         enable: |_, i| *i == b'b',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("s1", Transition { to_location: "s1".into(), ..Default::default() })
+    ///     .with_accepting("s1")
+    ///     .build();
+    ///
+    /// let words = machine.words_up_to("s0", 0, 2, &[b'a', b'b']);
+    /// assert_eq!(words, [vec![b'b'], vec![b'a', b'b'], vec![b'b', b'a'], vec![b'b', b'b']]);
+    /// ```
+    pub fn words_up_to(&self, location: &str, data: D, n: usize, alphabet: &[I]) -> Vec<Vec<I>>
+    where
+        D: Ord + Copy + Bounded,
+        I: Clone,
+        U: Update<D = D>,
+    {
+        let live = self.co_reachable(self.accepting.clone());
+        let mut words = Vec::new();
+
+        if !live.contains(location) {
+            return words;
+        }
+
+        let mut frontier = vec![(
+            Vec::new(),
+            vec![State {
+                location: location.into(),
+                data,
+            }],
+        )];
+
+        for len in 0..=n {
+            let mut next_frontier = Vec::new();
+
+            for (word, states) in frontier {
+                if states.iter().any(|state| self.is_accepting(state)) {
+                    words.push(word.clone());
+                }
+
+                if len == n {
+                    continue;
+                }
+
+                for i in alphabet {
+                    let Ok(mut next_states) = self.transition(i, states.clone()) else {
+                        continue;
+                    };
+
+                    // Prune states that can never lead to acceptance.
+                    next_states.retain(|state| self.may_lead_to_acceptance(state, &live));
+
+                    if !next_states.is_empty() {
+                        let mut next_word = word.clone();
+                        next_word.push(i.clone());
+                        next_frontier.push((next_word, next_states));
+                    }
+                }
+            }
+
+            frontier = next_frontier;
+        }
+
+        words
+    }
+
+    // Returns the locations from which some location in `targets` is reachable, ignoring guards
+    // and bounds.
+    fn co_reachable(&self, targets: HashSet<String>) -> HashSet<String> {
+        let mut live: HashSet<String> = targets;
+        let mut changed = true;
+
+        while changed {
+            changed = false;
+
+            for (location, transitions) in &self.locations {
+                if live.contains(location) {
+                    continue;
+                }
+
+                let sink_is_live = match &self.no_transition_policy {
+                    NoTransitionPolicy::SinkTo(sink) => live.contains(sink),
+                    _ => false,
+                };
+
+                if sink_is_live
+                    || transitions
+                        .iter()
+                        .any(|transition| live.contains(&transition.to_location))
+                {
+                    live.insert(location.clone());
+                    changed = true;
+                }
+            }
+        }
+
+        live
+    }
+
+    /// Returns every location of this machine in order, including those that are only the
+    /// target of a transition, only accepting, or only the sink of the [NoTransitionPolicy].
+    ///
+    /// Unlike [get_locations](Machine::get_locations), which only holds locations with outgoing
+    /// transitions, this includes every location a state can be in.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, NoTransitionPolicy, Transition};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition { to_location: "s1".into(), ..Default::default() })
+    ///     .with_accepting("s2")
+    ///     .with_no_transition_policy(NoTransitionPolicy::SinkTo("sink".into()))
+    ///     .build();
+    ///
+    /// assert_eq!(machine.get_locations().len(), 1);
+    /// assert_eq!(
+    ///     machine.all_locations().into_iter().collect::<Vec<_>>(),
+    ///     vec!["s0", "s1", "s2", "sink"],
+    /// );
+    /// ```
+    pub fn all_locations(&self) -> BTreeSet<&str> {
+        let mut locations: BTreeSet<&str> = self.all_location_names().map(String::as_str).collect();
+        if let NoTransitionPolicy::SinkTo(sink) = &self.no_transition_policy {
+            locations.insert(sink);
+        }
+
+        locations
+    }
+
+    // Iterates over every source, target, and accepting location, possibly more than once.
+    pub(crate) fn all_location_names(&self) -> impl Iterator<Item = &String> {
+        self.locations
+            .keys()
+            .chain(self.accepting.iter())
+            .chain(self.rejecting.iter())
+            .chain(
+                self.locations
+                    .values()
+                    .flatten()
+                    .map(|transition| &transition.to_location),
+            )
+    }
+
+    // Iterates over the locations that have data assertions.
+    pub(crate) fn asserted_locations(&self) -> impl Iterator<Item = &String> {
+        self.assertions.keys()
+    }
+
+    // Checks if `location` is a source, target, or accepting location of this machine.
+    fn has_location(&self, location: &str) -> bool {
+        self.all_location_names().any(|name| name == location)
+    }
+
+    // Returns the locations reachable from `location`, ignoring guards and bounds.
+    pub(crate) fn reachable(&self, location: &str) -> HashSet<String> {
+        let mut reachable = HashSet::from([String::from(location)]);
+        let mut to_visit = vec![String::from(location)];
+
+        while let Some(location) = to_visit.pop() {
+            let mut targets: Vec<&String> = self
+                .locations
+                .get(&location)
+                .into_iter()
+                .flatten()
+                .map(|transition| &transition.to_location)
+                .collect();
+
+            if let NoTransitionPolicy::SinkTo(sink) = &self.no_transition_policy {
+                targets.push(sink);
+            }
+
+            for target in targets {
+                if reachable.insert(target.clone()) {
+                    to_visit.push(target.clone());
+                }
+            }
+        }
+
+        reachable
+    }
+
+    /// Classifies the property defined by this machine, starting from `location`.
+    ///
+    /// The classification only considers the location graph, so guards and bounds are assumed
+    /// to always allow a transition.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{Classification, IdentityUpdate, MachineBuilder, Transition};
+    ///
+    /// // Once the machine leaves "safe" it can never return.
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("safe", Transition { to_location: "safe".into(), ..Default::default() })
+    ///     .with_transition("safe", Transition { to_location: "unsafe".into(), ..Default::default() })
+    ///     .with_transition("unsafe", Transition { to_location: "unsafe".into(), ..Default::default() })
+    ///     .with_accepting("safe")
+    ///     .build();
+    ///
+    /// assert_eq!(machine.classify("safe"), Classification::Safety);
+    /// ```
+    pub fn classify(&self, location: &str) -> Classification {
+        let reachable = self.reachable(location);
+        let rejecting: HashSet<String> = reachable
+            .iter()
+            .filter(|location| !self.accepting.contains(*location))
+            .cloned()
+            .collect();
+
+        let can_accept = self.co_reachable(self.accepting.clone());
+        let can_reject = self.co_reachable(rejecting);
+
+        // Once rejected, the machine can never accept again.
+        if reachable
+            .iter()
+            .all(|location| self.accepting.contains(location) || !can_accept.contains(location))
+        {
+            return Classification::Safety;
+        }
+
+        // Once accepted, the machine can never reject again.
+        if reachable
+            .iter()
+            .all(|location| !self.accepting.contains(location) || !can_reject.contains(location))
+        {
+            return Classification::CoSafety;
+        }
+
+        // Every reachable location can still reach a location where the verdict is decided.
+        let decided: HashSet<String> = reachable
+            .iter()
+            .filter(|location| !can_accept.contains(*location) || !can_reject.contains(*location))
+            .cloned()
+            .collect();
+        let can_decide = self.co_reachable(decided);
+
+        if reachable
+            .iter()
+            .all(|location| can_decide.contains(location))
+        {
+            Classification::Monitorable
+        } else {
+            Classification::NonMonitorable
+        }
+    }
+
+    /// Returns a machine accepting the reverse of every word accepted from `initial`.
+    ///
+    /// Every transition is reversed and its update [inverted](Invert). The reversed machine
+    /// starts in the new location `start`, which takes the reversed transitions into the
+    /// accepting locations of this machine, and it accepts in `initial`. A reversed run must be
+    /// started with the data that ended the forward run. The reversed machine always uses
+    /// [NoTransitionPolicy::Reject].
+    ///
+    /// Returns [MachineError::LocationExists] when `start` is already a location of this machine,
+    /// and [MachineError::Undecidable] when a transition [stores](Transition::with_store) a
+    /// field of the input, since the data it overwrites cannot be recovered.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{AddUpdate, MachineBuilder, Transition};
+    /// use rust_efsm::bound::Bound;
+    ///
+    /// // Accept "ab" while counting the inputs.
+    /// let machine = MachineBuilder::<i32, u8, AddUpdate<i32>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s1".into(),
+    ///         enable: |d, i| *d == 0 && *i == b'a',
+    ///         bound: Bound::unbounded(),
+    ///         update: AddUpdate { amount: 1 },
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("s1", Transition {
+    ///         to_location: "s2".into(),
+    ///         enable: |d, i| *d == 1 && *i == b'b',
+    ///         bound: Bound::unbounded(),
+    ///         update: AddUpdate { amount: 1 },
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("s2")
+    ///     .build();
+    ///
+    /// let reversed = machine.reverse("s0", "start").unwrap();
+    /// assert!(reversed.exec("start", 2, vec![b'b', b'a']));
+    /// assert!(!reversed.exec("start", 2, vec![b'a', b'b']));
+    /// ```
+    pub fn reverse(&self, initial: &str, start: &str) -> Result<Machine<D, I, U>, MachineError>
+    where
+        D: Clone + 'static,
+        I: 'static,
+        U: Invert + Update<D = D> + Clone + Send + Sync + 'static,
+    {
+        if self.has_location(start) {
+            return Err(MachineError::LocationExists(start.into()));
+        }
+
+        let stores = self
+            .locations
+            .values()
+            .flatten()
+            .any(|transition| matches!(transition.binding, Some(Binding::Store(_))));
+        if stores {
+            return Err(MachineError::Undecidable);
+        }
+
+        let mut locations: HashMap<String, Vec<Transition<D, I, U>>> = HashMap::new();
+
+        for (from_location, transitions) in &self.locations {
+            for transition in transitions {
+                let forward = transition.to_guard();
+                let inverse = transition.update.invert();
+
+                // The reversed guard checks the original guard against the data before the
+                // original update.
+                let reversed_inverse = inverse.clone();
+                let reversed = Transition {
+                    to_location: from_location.clone(),
+                    enable: |_, _| true,
+                    bound: transition.update_interval(transition.bound.clone()),
+                    update: inverse,
+                    guard: Some(Arc::new(move |data: &D, input: &I| {
+                        let before = reversed_inverse.update(data.clone(), input);
+                        forward(&before, input)
+                    })),
+                    name: transition.name.clone(),
+                    input: transition.input.clone(),
+                    binding: None,
+                };
+
+                // The data `start` begins with is the data a run ends with, so it must meet the
+                // condition of the accepting location.
+                if self.accepting.contains(&transition.to_location) {
+                    let from_start = match self.accepting_conditions.get(&transition.to_location) {
+                        Some(condition) => reversed.clone().restrict(condition),
+                        None => reversed.clone(),
+                    };
+                    locations.entry(start.into()).or_default().push(from_start);
+                }
+
+                locations
+                    .entry(transition.to_location.clone())
+                    .or_default()
+                    .push(reversed);
+            }
+        }
+
+        let mut accepting = HashSet::from([String::from(initial)]);
+        let mut conditions = HashMap::new();
+        if self.accepting.contains(initial) {
+            accepting.insert(start.into());
+            if let Some(condition) = self.accepting_conditions.get(initial) {
+                conditions.insert(start.into(), condition.clone());
+            }
+        }
+
+        let mut machine = Machine::new(locations, accepting, NoTransitionPolicy::Reject);
+        machine.accepting_conditions = conditions;
+        machine.tags = self.tags.clone();
+        Ok(machine)
+    }
+
+    /// Returns a machine accepting a word accepted by this machine followed by a word accepted by
+    /// `other` from `other_initial`.
+    ///
+    /// Every accepting location of this machine takes copies of the transitions out of
+    /// `other_initial`, and the data carries over unchanged between the two machines. The result
+    /// keeps the assertions of both.
+    ///
+    /// Returns [MachineError::LocationExists] when the machines share a location, and
+    /// [MachineError::PolicyMismatch] when their [no transition policies](NoTransitionPolicy)
+    /// differ, since the result can only follow one.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{
+    ///     IdentityUpdate, MachineBuilder, MachineError, NoTransitionPolicy, Transition,
+    /// };
+    ///
+    /// let a = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("a0", Transition {
+    ///         to_location: "a1".into(),
+    ///         enable: |_, i| *i == b'a',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("a1")
+    ///     .build();
+    ///
+    /// let b = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("b0", Transition {
+    ///         to_location: "b1".into(),
+    ///         enable: |_, i| *i == b'b',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("b1")
+    ///     .build();
+    ///
+    /// let ab = a.clone().concat(b.clone(), "b0").unwrap();
+    /// assert!(ab.exec("a0", 0, vec![b'a', b'b']));
+    /// assert!(!ab.exec("a0", 0, vec![b'a']));
+    ///
+    /// let stutter = b.to_builder().with_no_transition_policy(NoTransitionPolicy::Stutter).build();
+    /// assert!(matches!(a.concat(stutter, "b0"), Err(MachineError::PolicyMismatch(..))));
+    /// ```
+    pub fn concat(
+        self,
+        other: Machine<D, I, U>,
+        other_initial: &str,
+    ) -> Result<Machine<D, I, U>, MachineError>
+    where
+        D: Clone + 'static,
+        I: 'static,
+        U: Clone,
+    {
+        if let Some(location) = other.all_location_names().find(|l| self.has_location(l)) {
+            return Err(MachineError::LocationExists(location.clone()));
+        }
+        if self.no_transition_policy != other.no_transition_policy {
+            return Err(MachineError::PolicyMismatch(
+                self.no_transition_policy,
+                other.no_transition_policy,
+            ));
+        }
+
+        let Machine {
+            mut locations,
+            accepting,
+            rejecting,
+            accepting_conditions,
+            no_transition_policy,
+            mut tags,
+            mut assertions,
+            dispatch,
+            guard_cache,
+            ..
+        } = self;
+
+        let bridges = other
+            .locations
+            .get(other_initial)
+            .cloned()
+            .unwrap_or_default();
+
+        // A word of this machine only ends where it is accepted, so the bridges out of a location
+        // with a condition are restricted to it.
+        for location in &accepting {
+            let condition = accepting_conditions.get(location);
+            locations
+                .entry(location.clone())
+                .or_default()
+                .extend(bridges.iter().cloned().map(|bridge| match condition {
+                    Some(condition) => bridge.restrict(condition),
+                    None => bridge,
+                }));
+        }
+
+        // Words of this machine are still accepted when `other` accepts the empty word, with the
+        // data meeting the conditions of both machines.
+        let mut next_accepting = other.accepting;
+        let mut next_conditions = other.accepting_conditions;
+        if next_accepting.contains(other_initial) {
+            let initial_condition = next_conditions.get(other_initial).cloned();
+            for location in &accepting {
+                let condition = match (accepting_conditions.get(location), &initial_condition) {
+                    (Some(lhs), Some(rhs)) => Some(lhs.and(rhs)),
+                    (lhs, rhs) => lhs.or(rhs.as_ref()).cloned(),
+                };
+                if let Some(condition) = condition {
+                    next_conditions.insert(location.clone(), condition);
+                }
+            }
+            next_accepting.extend(accepting);
+        }
+
+        locations.extend(other.locations);
+
+        let mut machine = Machine::new(locations, next_accepting, no_transition_policy);
+        machine.rejecting = rejecting.into_iter().chain(other.rejecting).collect();
+        machine.accepting_conditions = next_conditions;
+        tags.extend(other.tags);
+        machine.tags = tags;
+        assertions.extend(other.assertions);
+        machine.assertions = assertions;
+        machine.dispatch = dispatch.map(Dispatch::reset);
+        machine.guard_cache = guard_cache.map(|cache| cache.empty());
+        Ok(machine)
+    }
+
+    /// Returns a machine accepting any number of consecutive words accepted from `initial`.
+    ///
+    /// The new accepting location `start` takes copies of the transitions out of `initial` and
+    /// accepts the empty word. Every accepting location also takes these copies, so a run can
+    /// begin another word with the data it ended the previous one with.
+    ///
+    /// Returns [MachineError::LocationExists] when `start` is already a location of this machine.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    ///
+    /// let ab = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s1".into(),
+    ///         enable: |_, i| *i == b'a',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("s1", Transition {
+    ///         to_location: "s2".into(),
+    ///         enable: |_, i| *i == b'b',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("s2")
+    ///     .build();
+    ///
+    /// let star = ab.star("s0", "start").unwrap();
+    /// assert!(star.exec("start", 0, vec![]));
+    /// assert!(star.exec("start", 0, vec![b'a', b'b', b'a', b'b']));
+    /// assert!(!star.exec("start", 0, vec![b'a', b'b', b'a']));
+    /// ```
+    pub fn star(self, initial: &str, start: &str) -> Result<Machine<D, I, U>, MachineError>
+    where
+        D: Clone + 'static,
+        I: 'static,
+        U: Clone,
+    {
+        if self.has_location(start) {
+            return Err(MachineError::LocationExists(start.into()));
+        }
+
+        let Machine {
+            mut locations,
+            mut accepting,
+            rejecting,
+            accepting_conditions,
+            no_transition_policy,
+            tags,
+            assertions,
+            dispatch,
+            guard_cache,
+            ..
+        } = self;
+
+        let restarts = locations.get(initial).cloned().unwrap_or_default();
+
+        // A word only ends where it is accepted, so the restarts out of a location with a
+        // condition are restricted to it.
+        for location in accepting
+            .iter()
+            .chain(std::iter::once(&String::from(start)))
+        {
+            let condition = accepting_conditions.get(location);
+            locations
+                .entry(location.clone())
+                .or_default()
+                .extend(restarts.iter().cloned().map(|restart| match condition {
+                    Some(condition) => restart.restrict(condition),
+                    None => restart,
+                }));
+        }
+
+        accepting.insert(start.into());
+
+        let mut machine = Machine::new(locations, accepting, no_transition_policy);
+        machine.rejecting = rejecting;
+        machine.accepting_conditions = accepting_conditions;
+        machine.tags = tags;
+        machine.assertions = assertions;
+        machine.dispatch = dispatch.map(Dispatch::reset);
+        machine.guard_cache = guard_cache.map(|cache| cache.empty());
+        Ok(machine)
+    }
+
+    /// Returns a machine accepting every prefix of the words accepted from `initial`.
+    ///
+    /// Every location from which acceptance is still reachable becomes accepting, for the data
+    /// found by [find_non_empty](Machine::find_non_empty). The result is a safety property: a run
+    /// is only rejected once no extension of its input can be accepted. When the analysis
+    /// over-approximates, so does the result.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    ///
+    /// // Accepts exactly "ab".
+    /// let ab = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s1".into(),
+    ///         enable: |_, i| *i == b'a',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("s1", Transition {
+    ///         to_location: "s2".into(),
+    ///         enable: |_, i| *i == b'b',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("s2")
+    ///     .build();
+    ///
+    /// let prefixes = ab.prefix_closure("s0").unwrap();
+    /// assert!(prefixes.exec("s0", 0, vec![]));
+    /// assert!(prefixes.exec("s0", 0, vec![b'a']));
+    /// assert!(prefixes.exec("s0", 0, vec![b'a', b'b']));
+    /// assert!(!prefixes.exec("s0", 0, vec![b'b']));
+    /// ```
+    pub fn prefix_closure(mut self, initial: &str) -> Result<Machine<D, I, U>, MachineError>
+    where
+        D: Eq + Hash + Ord + Copy + Bounded + Debug + fmt::Display + Send + Sync + 'static,
+        U: Update<D = D>,
+    {
+        span!(DEBUG, "prefix_closure", initial);
+
+        for (location, bound) in self.find_non_empty(initial)? {
+            if self.rejecting.contains(&location) {
+                continue;
+            }
+
+            // A location accepting every value already accepts every prefix ending in it.
+            let accepts_all = self.accepting.contains(&location)
+                && !self.accepting_conditions.contains_key(&location);
+            if !accepts_all {
+                self.accepting_conditions
+                    .insert(location.clone(), AcceptingCondition::new(bound));
+                self.accepting.insert(location);
+            }
+        }
+
+        self.invalidate();
+        Ok(self)
+    }
+
+    /// Returns a machine accepting every word that ends with a word accepted from `initial`.
+    ///
+    /// The new location `start` reads any input on a self-loop taking the update `stay`, which
+    /// should leave the data unchanged. It also takes copies of the transitions out of `initial`,
+    /// and accepts like `initial` does, so a run may begin the accepted word after any input.
+    /// The result is non-deterministic.
+    ///
+    /// Returns [MachineError::LocationExists] when `start` is already a location of this machine.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    ///
+    /// // Accepts exactly "ab".
+    /// let ab = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s1".into(),
+    ///         enable: |_, i| *i == b'a',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("s1", Transition {
+    ///         to_location: "s2".into(),
+    ///         enable: |_, i| *i == b'b',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("s2")
+    ///     .build();
+    ///
+    /// let suffixes = ab.suffix_extension("s0", "start", IdentityUpdate::default()).unwrap();
+    /// assert!(suffixes.exec("start", 0, b"ab".to_vec()));
+    /// assert!(suffixes.exec("start", 0, b"bbab".to_vec()));
+    /// assert!(!suffixes.exec("start", 0, b"aba".to_vec()));
+    /// ```
+    pub fn suffix_extension(
+        mut self,
+        initial: &str,
+        start: &str,
+        stay: U,
+    ) -> Result<Machine<D, I, U>, MachineError>
+    where
+        D: Clone,
+        U: Clone,
+    {
+        if self.has_location(start) {
+            return Err(MachineError::LocationExists(start.into()));
+        }
+
+        let mut transitions = vec![Transition {
+            to_location: start.into(),
+            enable: |_, _| true,
+            bound: Bound::unbounded(),
+            update: stay,
+            guard: None,
+            name: None,
+            input: Default::default(),
+            binding: None,
+        }];
+        transitions.extend(self.locations.get(initial).cloned().unwrap_or_default());
+        self.locations.insert(start.into(), transitions);
+
+        if self.accepting.contains(initial) {
+            self.accepting.insert(start.into());
+        }
+        if let Some(condition) = self.accepting_conditions.get(initial).cloned() {
+            self.accepting_conditions.insert(start.into(), condition);
+        }
+
+        self.invalidate();
+        Ok(self)
+    }
+
+    /// Returns this machine with every location renamed by `mapper`.
+    ///
+    /// Every reference to a location is rewritten, including transition targets, accepting and
+    /// rejecting locations, accepting conditions, assertions, tags, the sink of the
+    /// [no transition policy](NoTransitionPolicy::SinkTo), and the counts of
+    /// [metrics](Machine::metrics).
+    ///
+    /// Returns [MachineError::LocationExists] when `mapper` gives two locations the same name.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, MachineError, Transition};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition { to_location: "s1".into(), ..Default::default() })
+    ///     .with_accepting("s1")
+    ///     .build();
+    ///
+    /// let renamed = machine.clone().rename_locations(|l| l.replace('s', "q")).unwrap();
+    /// assert!(renamed.exec("q0", 0, vec![b'a']));
+    /// assert!(renamed.get_accepting().contains("q1"));
+    ///
+    /// let merged = machine.rename_locations(|_| "s".into());
+    /// assert!(matches!(merged, Err(MachineError::LocationExists(name)) if name == "s"));
+    /// ```
+    pub fn rename_locations<F>(self, mapper: F) -> Result<Machine<D, I, U>, MachineError>
+    where
+        F: Fn(&str) -> String,
+    {
+        let mut names = HashMap::new();
+        for location in self.all_locations() {
+            let name = mapper(location);
+            if names.values().any(|other| *other == name) {
+                return Err(MachineError::LocationExists(name));
+            }
+            names.insert(location.to_string(), name);
+        }
+        trace!("rename {} locations", names.len());
+
+        // Every location referenced by the machine is in `names`.
+        let rename = |location: String| names.get(&location).cloned().unwrap_or(location);
+        fn rename_keys<V>(
+            map: HashMap<String, V>,
+            rename: impl Fn(String) -> String,
+        ) -> HashMap<String, V> {
+            map.into_iter()
+                .map(|(location, value)| (rename(location), value))
+                .collect()
+        }
+
+        let locations = self
+            .locations
+            .into_iter()
+            .map(|(location, transitions)| {
+                let transitions = transitions
+                    .into_iter()
+                    .map(|transition| Transition {
+                        to_location: rename(transition.to_location),
+                        ..transition
+                    })
+                    .collect();
+                (rename(location), transitions)
+            })
+            .collect();
+
+        let no_transition_policy = match self.no_transition_policy {
+            NoTransitionPolicy::SinkTo(sink) => NoTransitionPolicy::SinkTo(rename(sink)),
+            policy => policy,
+        };
+
+        let accepting = self.accepting.into_iter().map(rename).collect();
+        let mut machine = Machine::new(locations, accepting, no_transition_policy);
+        machine.rejecting = self.rejecting.into_iter().map(rename).collect();
+        machine.accepting_conditions = rename_keys(self.accepting_conditions, rename);
+        machine.assertions = rename_keys(self.assertions, rename);
+        machine.tags = rename_keys(self.tags, rename);
+        machine.dispatch = self.dispatch.map(Dispatch::reset);
+        machine.guard_cache = self.guard_cache.map(|cache| cache.empty());
+        machine.metrics = self.metrics.map(|metrics| {
+            let table = match metrics.lock() {
+                Ok(table) => rename_keys(table.clone(), rename),
+                Err(_) => HashMap::new(),
+            };
+            Arc::new(Mutex::new(table))
+        });
+        Ok(machine)
+    }
+
+    /// Returns this machine with `prefix` prepended to every location, so that it can be
+    /// [concatenated](Machine::concat) or combined with another machine using the same names.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    ///
+    /// let read = |enable| {
+    ///     MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///         .with_transition("s0", Transition {
+    ///             to_location: "s1".into(),
+    ///             enable,
+    ///             ..Default::default()
+    ///         })
+    ///         .with_accepting("s1")
+    ///         .build()
+    /// };
+    /// let a = read(|_, i| *i == b'a').prefixed("a.");
+    /// let b = read(|_, i| *i == b'b').prefixed("b.");
+    ///
+    /// let ab = a.concat(b, "b.s0").unwrap();
+    /// assert!(ab.exec("a.s0", 0, vec![b'a', b'b']));
+    /// assert!(ab.all_locations().contains("b.s1"));
+    /// ```
+    pub fn prefixed(self, prefix: &str) -> Machine<D, I, U> {
+        match self.rename_locations(|location| format!("{}{}", prefix, location)) {
+            Ok(machine) => machine,
+            // Distinct locations keep distinct names under a common prefix.
+            Err(_) => unreachable!("prefixing merged two locations"),
+        }
+    }
+
+    /// Returns a machine that reads inputs of type `J` by converting them with `map`.
+    ///
+    /// The transition table is kept as is, and every guard is evaluated on the converted input.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    ///
+    /// #[derive(Debug)]
+    /// struct Record {
+    ///     level: u8,
+    /// }
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("ok", Transition {
+    ///         to_location: "ok".into(),
+    ///         enable: |_, level| *level < 3,
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("ok")
+    ///     .build()
+    ///     .map_input(|record: &Record| record.level);
+    ///
+    /// assert!(machine.exec("ok", 0, vec![Record { level: 1 }, Record { level: 2 }]));
+    /// assert!(!machine.exec("ok", 0, vec![Record { level: 4 }]));
+    /// ```
+    pub fn map_input<J>(self, map: fn(&J) -> I) -> Machine<D, J, U>
+    where
+        D: 'static,
+        I: 'static,
+        J: 'static,
+    {
+        let locations = self
+            .locations
+            .into_iter()
+            .map(|(location, transitions)| {
+                let transitions = transitions
+                    .into_iter()
+                    .map(|transition| {
+                        let guard = transition.to_guard();
+                        Transition {
+                            to_location: transition.to_location,
+                            enable: |_, _| true,
+                            bound: transition.bound,
+                            update: transition.update,
+                            guard: Some(Arc::new(move |data: &D, input: &J| {
+                                guard(data, &map(input))
+                            })),
+                            name: transition.name,
+                            input: transition.input,
+                            binding: transition.binding.map(|binding| {
+                                binding.map_field(move |field| {
+                                    Arc::new(move |input: &J| field(&map(input)))
+                                })
+                            }),
+                        }
+                    })
+                    .collect();
+                (location, transitions)
+            })
+            .collect();
+
+        let mut machine = Machine::new(locations, self.accepting, self.no_transition_policy);
+        machine.rejecting = self.rejecting;
+        machine.accepting_conditions = self.accepting_conditions;
+        machine.tags = self.tags;
+        machine.assertions = self.assertions;
+        machine
+    }
+
+    /// Returns a machine over data of type `E`, converting to this machine's data with `into`
+    /// and back with `from`.
+    ///
+    /// Guards and [assertions](MachineBuilder::with_assertion) are evaluated on the converted
+    /// data and updates are wrapped in [MapData]. Since the conversions need not preserve order,
+    /// every bound of the new machine is unbounded.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{AddUpdate, MachineBuilder, Transition};
+    /// use rust_efsm::bound::Bound;
+    ///
+    /// let machine = MachineBuilder::<u32, u8, AddUpdate<u32>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s0".into(),
+    ///         enable: |count, _| *count < 2,
+    ///         bound: Bound::unbounded(),
+    ///         update: AddUpdate { amount: 1 },
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("s0")
+    ///     .build()
+    ///     .map_data(|count: &u64| *count as u32, |count| count as u64);
+    ///
+    /// assert!(machine.exec("s0", 0_u64, vec![b'a', b'a']));
+    /// assert!(!machine.exec("s0", 0_u64, vec![b'a', b'a', b'a']));
+    /// ```
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, MachineError, Transition};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition { to_location: "s0".into(), ..Default::default() })
+    ///     .with_accepting("s0")
+    ///     .with_assertion("s0", |data| *data < 10, "the data must stay below 10")
+    ///     .build()
+    ///     .map_data(|data: &u32| *data as u8, |data| data as u32);
+    ///
+    /// assert!(machine.try_exec("s0", 3, vec![b'a']).unwrap());
+    /// assert!(matches!(
+    ///     machine.try_exec("s0", 12, vec![b'a']),
+    ///     Err(MachineError::AssertionViolated(_)),
+    /// ));
+    /// ```
+    pub fn map_data<E>(self, into: fn(&E) -> D, from: fn(D) -> E) -> Machine<E, I, MapData<U, D, E>>
+    where
+        D: 'static,
+        I: 'static,
+        E: 'static,
+    {
+        let locations = self
+            .locations
+            .into_iter()
+            .map(|(location, transitions)| {
+                let transitions = transitions
+                    .into_iter()
+                    .map(|transition| {
+                        let guard = transition.to_guard();
+                        Transition {
+                            to_location: transition.to_location,
+                            enable: |_, _| true,
+                            bound: Bound::unbounded(),
+                            update: MapData {
+                                update: transition.update,
+                                into,
+                                from,
+                            },
+                            guard: Some(Arc::new(move |data: &E, input: &I| {
+                                guard(&into(data), input)
+                            })),
+                            name: transition.name,
+                            input: InputAbstraction::Unknown,
+                            binding: transition.binding.map(|binding| {
+                                binding.map_field(move |field| {
+                                    Arc::new(move |input: &I| from(field(input)))
+                                })
+                            }),
+                        }
+                    })
+                    .collect();
+                (location, transitions)
+            })
+            .collect();
+
+        let accepting_conditions = self
+            .accepting_conditions
+            .into_iter()
+            .map(|(location, condition)| {
+                let holds = condition.to_predicate();
+                let condition = AcceptingCondition::from_predicate(move |data| holds(&into(data)));
+                (location, condition)
+            })
+            .collect();
+
+        let assertions = self
+            .assertions
+            .into_iter()
+            .map(|(location, assertions)| {
+                let assertions = assertions
+                    .into_iter()
+                    .map(|assertion| assertion.map(into))
+                    .collect();
+                (location, assertions)
+            })
+            .collect();
+
+        let mut machine = Machine::new(locations, self.accepting, self.no_transition_policy);
+        machine.rejecting = self.rejecting;
+        machine.accepting_conditions = accepting_conditions;
+        machine.tags = self.tags;
+        machine.assertions = assertions;
+        machine
+    }
+
+    /// Returns a machine accepting exactly the inputs this machine rejects.
+    ///
+    /// The machine is first completed, so that every state has a successor on every input. Under
+    /// [NoTransitionPolicy::Reject] and [NoTransitionPolicy::Error], a state without an enabled
+    /// transition moves to a new sink location instead, which the complement accepts. The
+    /// analyses only take that move where an input of the
+    /// [declared alphabet](MachineBuilder::with_alphabet) may enable no transition, or from
+    /// every state when there is none. Then the accepting and non-accepting locations are
+    /// swapped, and the
+    /// [rejecting](MachineBuilder::with_rejecting) locations become accepting locations without
+    /// transitions. A location with an [AcceptingCondition] stays accepting under the negated
+    /// condition. Under [NoTransitionPolicy::SinkTo], the sink must therefore not be accepting
+    /// in this machine when it has rejecting locations.
+    ///
+    /// The result only accepts the complement language when the machine is deterministic.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    ///
+    /// // Accepts exactly "a".
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s1".into(),
+    ///         enable: |_, i| *i == b'a',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("s1")
+    ///     .build();
+    ///
+    /// let complement = machine.complement().unwrap();
+    /// assert!(!complement.exec("s0", 0, vec![b'a']));
+    /// assert!(complement.exec("s0", 0, vec![]));
+    /// assert!(complement.exec("s0", 0, vec![b'b']));
+    /// assert!(complement.exec("s0", 0, vec![b'a', b'a']));
+    /// ```
+    pub fn complement(mut self) -> Result<Machine<D, I, U>, MachineError> {
+        // Preconditions:
+        // (1) Machine is deterministic.
+        //
+        // TODO: I need some infrastructure for checking these and returing errors.
+
+        let mut locations: HashSet<String> =
+            self.all_locations().into_iter().map(String::from).collect();
+
+        // Complete the machine with a sink for the inputs it has no transition for.
+        if let NoTransitionPolicy::Reject | NoTransitionPolicy::Error = self.no_transition_policy {
+            let sink = (0..)
+                .map(|n| match n {
+                    0 => String::from(COMPLEMENT_SINK),
+                    n => format!("{}_{}", COMPLEMENT_SINK, n),
+                })
+                .find(|name| !locations.contains(name))
+                .unwrap_or_default();
+
+            locations.insert(sink.clone());
+            self.no_transition_policy = NoTransitionPolicy::SinkTo(sink);
+        }
+
+        // A run entering a rejecting location is rejected whatever follows, so the complement
+        // accepts it whatever follows. Without transitions, the run stays in the location or
+        // moves to the sink.
+        for location in &self.rejecting {
+            self.locations.remove(location);
+        }
+
+        // A location accepting only some of the data accepts the rest of it in the complement.
+        let conditions = std::mem::take(&mut self.accepting_conditions);
+        self.accepting = locations
+            .into_iter()
+            .filter(|location| {
+                !self.accepting.contains(location)
+                    || self.rejecting.contains(location)
+                    || conditions.contains_key(location)
+            })
+            .collect();
+        self.accepting_conditions = conditions
+            .into_iter()
+            .filter(|(location, _)| !self.rejecting.contains(location))
+            .map(|(location, condition)| (location, condition.negate()))
+            .collect();
+        self.rejecting.clear();
+        self.invalidate();
+        Ok(self)
+    }
+
+    /// Finds pairs of transitions out of the same location that are enabled at the same time.
+    ///
+    /// Every input in `alphabet` is tried against data values within the intersection of the
+    /// bounds of both transitions. An intersection of at most 256 values is tried in full.
+    /// Otherwise, the values tried are its endpoints, its midpoint, and the endpoints of the
+    /// bounds out of the location that fall within it, along with their neighbours, since guards
+    /// tend to change there. Each returned [Overlap] carries a concrete witness, so a reported
+    /// overlap is a real source of non-determinism. The result is
+    /// [under-approximate](Approximation::Under) when some intersection was not tried in full,
+    /// since overlaps that only occur at other data values are not found.
+    ///
+    /// ```
+    /// use rust_efsm::analysis::Approximation;
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    /// use rust_efsm::bound::Bound;
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s1".into(),
+    ///         enable: |_, i| *i == b'a',
+    ///         bound: Bound { lower: None, upper: Some(5) },
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s2".into(),
+    ///         enable: |_, i| *i != b'b',
+    ///         bound: Bound { lower: Some(5), upper: None },
+    ///         ..Default::default()
+    ///     })
+    ///     .build();
+    ///
+    /// let overlaps = machine.check_overlaps(&[b'a', b'b']);
+    /// assert_eq!(overlaps.result.len(), 1);
+    /// assert_eq!(overlaps.result[0].data, 5);
+    /// assert_eq!(overlaps.result[0].input, b'a');
+    /// assert_eq!(overlaps.approximation, Approximation::Exact);
+    /// ```
+    ///
+    /// An overlap inside a wide intersection, away from every endpoint, may be missed.
+    ///
+    /// ```
+    /// use rust_efsm::analysis::Approximation;
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    ///
+    /// let machine = MachineBuilder::<u32, u8, IdentityUpdate<u32>>::new()
+    ///     .with_transition("s0", Transition { to_location: "s1".into(), ..Default::default() })
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s2".into(),
+    ///         enable: |data, _| *data == 1000,
+    ///         ..Default::default()
+    ///     })
+    ///     .build();
+    ///
+    /// let overlaps = machine.check_overlaps(&[b'a']);
+    /// assert!(overlaps.result.is_empty());
+    /// assert_eq!(overlaps.approximation, Approximation::Under);
+    /// ```
+    pub fn check_overlaps(&self, alphabet: &[I]) -> AnalysisOutcome<Vec<Overlap<D, I>>>
+    where
+        D: Integer + Copy + Bounded,
+        I: Clone,
+    {
+        let mut overlaps = Vec::new();
+        let mut approximation = Approximation::Exact;
+
+        for (location, transitions) in &self.locations {
+            let endpoints: Vec<D> = transitions
+                .iter()
+                .flat_map(|transition| {
+                    let (lower, upper) = transition.bound.as_explicit();
+                    [lower, upper]
+                })
+                .collect();
+
+            for (first, a) in transitions.iter().enumerate() {
+                for (second, b) in transitions.iter().enumerate().skip(first + 1) {
+                    // Only data inside of both bounds can enable both transitions.
+                    let Some(shared) = a.bound.intersect(&b.bound) else {
+                        continue;
+                    };
+
+                    let (candidates, exhaustive) = overlap_candidates(&shared, &endpoints);
+                    if !exhaustive {
+                        approximation = Approximation::Under;
+                    }
+
+                    let witness = candidates
+                        .iter()
+                        .flat_map(|data| alphabet.iter().map(move |input| (data, input)))
+                        .find(|(data, input)| {
+                            a.is_enabled(data, input) && b.is_enabled(data, input)
+                        });
+
+                    if let Some((data, input)) = witness {
+                        overlaps.push(Overlap {
+                            location: location.clone(),
+                            transitions: (
+                                TransitionId::new(location, first),
+                                TransitionId::new(location, second),
+                            ),
+                            data: *data,
+                            input: input.clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        AnalysisOutcome {
+            result: overlaps,
+            approximation,
+        }
+    }
+
+    /// Find all StateIntervals that lead to acceptance.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{Machine, MachineBuilder, AddUpdate, Transition, Update};
+    /// use rust_efsm::bound::Bound;
+    /// let machine = MachineBuilder::<u8, u8, AddUpdate<u8>>::new().build();
+    ///
+    ///
+    /// ```
+    pub fn find_non_empty(&self, location: &str) -> Result<HashMap<String, Bound<D>>, MachineError>
+    where
+        D: Eq + Hash + Clone + Ord + Copy + Bounded + Debug + fmt::Display,
+        U: Update<D = D>,
+    {
+        self.find_non_empty_with(location, |_| {}, &CancellationToken::new())
+            .map(|outcome| outcome.result)
+    }
+
+    /// Find all StateIntervals that lead to acceptance, reporting progress to `progress` after
+    /// each visited node and stopping early once `cancel` is cancelled.
+    ///
+    /// The result of a search that was not cancelled is memoized by start location, and shared
+    /// with clones of this machine, such as those held by [monitors](crate::monitor::Monitor). It
+    /// is discarded when the machine is modified.
+    ///
+    /// The result is tagged with its [Approximation]. It may miss intervals when the search
+    /// stopped before visiting every node, either because it was cancelled or because it
+    /// reached its node limit, or when an interval is only partly known to lead to acceptance.
+    /// It may include extra intervals when a transition has a runtime [guard](Transition::guard),
+    /// when an accepting condition is not a plain bound, when implicit moves to a sink are taken,
+    /// or when disjoint intervals of a location are merged. The `enable` function of a
+    /// transition cannot be inspected, so its [bound](Transition::bound) and its
+    /// [update](Update::update_interval_with) are assumed to be exact.
+    ///
+    /// ```
+    /// use rust_efsm::analysis::{Approximation, CancellationToken};
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition { to_location: "s1".into(), ..Default::default() })
+    ///     .with_accepting("s1")
+    ///     .build();
+    ///
+    /// let cancel = CancellationToken::new();
+    /// cancel.cancel();
+    /// let outcome = machine.find_non_empty_with("s0", |_| {}, &cancel).unwrap();
+    /// assert_eq!(outcome.approximation, Approximation::Under);
+    ///
+    /// let mut visited = 0;
+    /// let outcome = machine
+    ///     .find_non_empty_with("s0", |progress| visited = progress.visited, &CancellationToken::new())
+    ///     .unwrap();
+    /// assert_eq!(outcome.approximation, Approximation::Exact);
+    /// assert!(outcome.result.contains_key("s0"));
+    /// assert_eq!(visited, 2);
+    ///
+    /// // The complete result is memoized, so it is returned without another search.
+    /// visited = 0;
+    /// machine.find_non_empty_with("s0", |progress| visited = progress.visited, &cancel).unwrap();
+    /// assert_eq!(visited, 0);
+    /// ```
+    pub fn find_non_empty_with<F>(
+        &self,
+        location: &str,
+        mut progress: F,
+        cancel: &CancellationToken,
+    ) -> Result<AnalysisOutcome<HashMap<String, Bound<D>>>, MachineError>
+    where
+        D: Eq + Hash + Clone + Ord + Copy + Bounded + Debug + fmt::Display,
+        U: Update<D = D>,
+        F: FnMut(Progress),
+    {
+        span!(DEBUG, "find_non_empty", location);
+
+        let cached = self
+            .non_empty
+            .lock()
+            .ok()
+            .and_then(|cache| cache.get(location).cloned());
+        if let Some(outcome) = cached {
+            debug!("reuse cached non-empty intervals");
+            return Ok(outcome);
+        }
+
+        // Prerequisites
+        // Deterministic?
+        // FIXME: Cycles can cause unbounded execution... I think?
+        // All transitions must be bounded.
+
+        // A path is a vector of state intervals.
+        // A path is completed when it reaches an accepting state.
+        // A path is completed when it reaches a previously validated state interval.
+        // All state intervals in a completed path are not sink state intervals.
+
+        // Whether the result may include intervals that do not lead to acceptance, or miss
+        // some that do.
+        let mut over = false;
+        let mut under = false;
+
+        let mut safe: HashMap<String, Bound<D>> = HashMap::new();
+        for location in self.accepting.difference(&self.rejecting) {
+            let interval = match self.accepting_conditions.get(location) {
+                Some(condition) => {
+                    over |= condition.is_negated() || condition.get_bound().is_none();
+                    condition.hull()
+                }
+                None => Bound::unbounded(),
+            };
+            safe.insert(location.clone(), interval);
+        }
+
+        let mut nodes: Vec<PathNode<D>> = Vec::new();
+
+        // The intervals of the nodes expanded so far, by location.
+        let mut expanded: HashMap<String, Vec<Bound<D>>> = HashMap::new();
+
+        let path_root = PathNode {
+            idx: nodes.len(),
+            parent: None,
+            interval: Bound::unbounded(),
+            location: location.into(),
+        };
+
+        nodes.push(path_root);
+
+        // Depth first search for accepting paths. Runs end on entering a rejecting location, so
+        // no node is created for one.
+        let mut nodes_to_visit: Vec<usize> = match self.rejecting.contains(location) {
+            true => Vec::new(),
+            false => vec![0],
+        };
+
+        const MAX_NODES: usize = 100;
+        let mut visited = 0;
+        while nodes.len() <= MAX_NODES {
+            if cancel.is_cancelled() {
+                debug!(visited, "find_non_empty cancelled");
+                break;
+            }
+
+            // Check if current node is accepting
+            // Check if current node is in safe.
+            // If either of these, then add the full path to safe.
+            // We do not care if the intervals we push to safe are unique because the hash set will
+            // handle that.
+
+            if let Some(idx) = nodes_to_visit.pop() {
+                let current = &nodes[idx];
+
+                trace!(location = %current.location, interval = %current.interval, "visit");
+
+                // Check if the interval is completely inside of already safe bounds.
+                let is_bound = match safe.get(&current.location) {
+                    Some(bound) => bound.contains_interval(&current.interval),
+                    None => false,
+                };
+
+                if is_bound || self.may_accept(&current.location, &current.interval) {
+                    // A condition holding for only part of the interval still marks all of it.
+                    over |= !is_bound
+                        && self
+                            .accepting_conditions
+                            .get(&current.location)
+                            .is_some_and(|condition| !condition.holds_within(&current.interval));
+
+                    // Add path to safe.
+                    // Traverse up the parents to get the path.
+
+                    let path_iter = nodes[idx].path_to(&nodes[..]);
+                    for (location, safe_interval) in path_iter
+                        .filter_map(|idx| nodes[idx].parent.clone())
+                        .map(|(idx, bound)| (nodes[idx].location.clone(), bound))
+                    {
+                        trace!(%location, interval = %safe_interval, "mark safe");
+                        over |= mark_safe(&mut safe, location, &safe_interval);
+                    }
+                }
+
+                // A node covered by an expanded node of the same location has no new successors,
+                // so it is left as a leaf. Its safety is settled after the search.
+                let current = &nodes[idx];
+                let covered = expanded.get(&current.location).is_some_and(|intervals| {
+                    intervals
+                        .iter()
+                        .any(|interval| interval.contains_interval(&current.interval))
+                });
+                if !covered {
+                    expanded
+                        .entry(current.location.clone())
+                        .or_default()
+                        .push(current.interval.clone());
+                }
+
+                // Iterate over transitions out of current node.
+                if let Some(transitions) = self
+                    .locations
+                    .get(&nodes[idx].location)
+                    .filter(|_| !covered)
+                {
+                    for trans in transitions {
+                        // Compute intersection of the current state interval with the transition bounds.
+                        // If the resulting state interval is invalid, then continue.
+                        // This result indicates that this transition is not enabled from this state interval.
+
+                        if self.rejecting.contains(&trans.to_location) {
+                            continue;
+                        }
+
+                        // Runtime guards are ignored, as if they always held.
+                        over |= trans.guard.is_some();
+
+                        let child_idx = nodes.len();
+                        let node = &mut nodes[idx];
+                        if let Some(postcondition) = trans
+                            .analysis_bound()
+                            .and_then(|bound| node.interval.clone().intersect(&bound))
+                        {
+                            // Apply the update function to the state interval.
+                            // The resulting state interval represents a new node in the path.
+
+                            let location = trans.to_location.clone();
+                            let next_interval = trans.update_interval(postcondition.clone());
+
+                            trace!(%location, interval = %next_interval, "found");
+                            let path_node = PathNode {
+                                idx: child_idx,
+                                parent: Some((idx, postcondition)),
+                                interval: next_interval,
+                                location,
+                            };
+
+                            nodes_to_visit.push(child_idx);
+                            nodes.push(path_node);
+                        }
+                    }
+                }
+
+                // A sink policy adds an implicit transition that leaves the data unchanged. The
+                // move from the sink to itself would only repeat the current node.
+                match &self.no_transition_policy {
+                    NoTransitionPolicy::SinkTo(location)
+                        if !covered
+                            && *location != nodes[idx].location
+                            && !self.rejecting.contains(location)
+                            && self.may_block(&nodes[idx].location, &nodes[idx].interval) =>
+                    {
+                        // The move is taken as if no transition were ever enabled.
+                        over = true;
+
+                        let child_idx = nodes.len();
+                        let interval = nodes[idx].interval.clone();
+                        nodes_to_visit.push(child_idx);
+                        nodes.push(PathNode {
+                            idx: child_idx,
+                            parent: Some((idx, interval.clone())),
+                            interval,
+                            location: location.clone(),
+                        });
+                    }
+                    _ => {}
+                }
+
+                visited += 1;
+                progress(Progress {
+                    visited,
+                    frontier: nodes_to_visit.len(),
+                });
+            } else {
+                break;
+            }
+        }
+
+        // A path is only added to safe when its last node is visited, so a leaf left unexpanded
+        // or visited before its location became safe is settled here, by adding the
+        // precondition of every safe node to its parent until nothing changes.
+        loop {
+            let mut changed = false;
+
+            for node in &nodes {
+                let Some((parent, precondition)) = &node.parent else {
+                    continue;
+                };
+                let is_safe = self.may_accept(&node.location, &node.interval)
+                    || safe
+                        .get(&node.location)
+                        .is_some_and(|bound| bound.contains_interval(&node.interval));
+                if !is_safe {
+                    continue;
+                }
+
+                let location = &nodes[*parent].location;
+                if !safe
+                    .get(location)
+                    .is_some_and(|bound| bound.contains_interval(precondition))
+                {
+                    over |= mark_safe(&mut safe, location.clone(), precondition);
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        // A node only partly inside the safe interval of its location is not marked, even though
+        // some of its data leads to acceptance.
+        under |= !nodes_to_visit.is_empty()
+            || nodes.iter().any(|node| {
+                safe.get(&node.location).is_some_and(|bound| {
+                    bound.intersect(&node.interval).is_some()
+                        && !bound.contains_interval(&node.interval)
+                })
+            });
+
+        let approximation = match (over, under) {
+            (false, false) => Approximation::Exact,
+            (true, false) => Approximation::Over,
+            (false, true) => Approximation::Under,
+            (true, true) => Approximation::Unknown,
+        };
+        let outcome = AnalysisOutcome {
+            result: safe,
+            approximation,
+        };
+        debug!(nodes = nodes.len(), %approximation, "found non-empty intervals");
+
+        // A cancelled search is not memoized, so that it can be run again to completion.
+        if !cancel.is_cancelled() {
+            if let Ok(mut cache) = self.non_empty.lock() {
+                cache.insert(location.into(), outcome.clone());
+            }
+        }
+
+        Ok(outcome)
+    }
+
+    // Memoizes `outcome` as the non-empty intervals found from `location`, such as those of an
+    // analysis computed before. Clones share the cache, so this machine gets a cache of its own
+    // first, and the outcome never reaches the others.
+    pub(crate) fn memoize_non_empty(
+        &mut self,
+        location: &str,
+        outcome: AnalysisOutcome<HashMap<String, Bound<D>>>,
+    ) {
+        let cache = HashMap::from([(location.to_string(), outcome)]);
+        self.non_empty = Arc::new(Mutex::new(cache));
+    }
+}
+
+// Widens the safe interval of `location` to contain `interval`. Returns whether this includes
+// data from neither, when the two are disjoint.
+fn mark_safe<D>(safe: &mut HashMap<String, Bound<D>>, location: String, interval: &Bound<D>) -> bool
+where
+    D: Ord + Copy + Bounded,
+{
+    match safe.get_mut(&location) {
+        Some(bound) => {
+            let disjoint = bound.intersect(interval).is_none();
+            bound.make_contain(interval);
+            disjoint
+        }
+        None => {
+            safe.insert(location, interval.clone());
+            false
+        }
+    }
+}
+
+impl<D, I, U> Machine<D, I, U>
+where
+    D: Ord + Copy + Bounded,
+{
+    // Checks if `state` may still lead to acceptance, given the `live` locations that can reach an
+    // accepting location. Its transitions are only trusted to fire within their bounds.
+    fn may_lead_to_acceptance(&self, state: &State<D>, live: &HashSet<String>) -> bool {
+        if !live.contains(&state.location) {
+            return false;
+        }
+        if self.is_accepting(state) {
+            return true;
+        }
+        if let NoTransitionPolicy::SinkTo(sink) = &self.no_transition_policy {
+            if live.contains(sink) {
+                return true;
+            }
+        }
+
+        self.locations
+            .get(&state.location)
+            .into_iter()
+            .flatten()
+            .any(|trans| {
+                live.contains(&trans.to_location)
+                    && trans
+                        .analysis_bound()
+                        .is_some_and(|bound| bound.contains(&state.data))
+            })
+    }
+
+    // Checks if a state of `location` with data in `interval` may have no enabled transition for
+    // some input. Guards are evaluated at either end of the interval, as elsewhere in the
+    // analyses. Without a declared alphabet, any state may.
+    fn may_block(&self, location: &str, interval: &Bound<D>) -> bool {
+        let Some(alphabet) = &self.alphabet else {
+            return true;
+        };
+        let transitions = self.locations.get(location).map_or(&[][..], Vec::as_slice);
+        let (lower, upper) = interval.as_explicit();
+        alphabet().iter().any(|input| {
+            !transitions.iter().any(|trans| {
+                trans
+                    .analysis_bound()
+                    .is_some_and(|bound| bound.contains_interval(interval))
+                    && trans.is_enabled(&lower, input)
+                    && trans.is_enabled(&upper, input)
+            })
+        })
+    }
+}
+
+// The largest intersection of bounds that Machine::check_overlaps tries in full.
+const OVERLAP_VALUES: usize = 256;
+
+// Returns the data values within `shared` that Machine::check_overlaps tries, and whether they
+// are all of its values.
+fn overlap_candidates<D>(shared: &Bound<D>, endpoints: &[D]) -> (Vec<D>, bool)
+where
+    D: Integer + Copy + Bounded,
+{
+    if shared.iter().nth(OVERLAP_VALUES).is_none() {
+        return (shared.iter().collect(), true);
+    }
+
+    let (lower, upper) = shared.as_explicit();
+    let two = D::one() + D::one();
+    let mut candidates = vec![lower / two + upper / two];
+    for value in endpoints.iter().copied().chain([lower, upper]) {
+        if !shared.contains(&value) {
+            continue;
+        }
+        candidates.push(value);
+        if value > lower {
+            candidates.push(value - D::one());
+        }
+        if value < upper {
+            candidates.push(value + D::one());
+        }
+    }
+    candidates.sort();
+    candidates.dedup();
+    (candidates, false)
+}
+
+// The name of the sink added by Machine::complement, suffixed when it is already taken.
+const COMPLEMENT_SINK: &str = "complement_sink";
+
+/// Identifies a transition by its source location and its position among the transitions out
+/// of that location, in the order they were added to the [MachineBuilder].
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TransitionId {
+    /// The location the transition leaves from.
+    pub from_location: String,
+    /// The position of the transition in [Machine::get_transitions_from].
+    pub index: usize,
+}
+
+impl TransitionId {
+    /// Creates the identifier of the transition at `index` out of `from_location`.
+    pub fn new(from_location: &str, index: usize) -> Self {
+        TransitionId {
+            from_location: from_location.into(),
+            index,
+        }
+    }
+}
+
+impl fmt::Display for TransitionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}#{}", self.from_location, self.index)
+    }
+}
+
+/// Witness that two transitions out of the same location can be enabled at the same time.
+///
+/// See [Machine::check_overlaps].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Overlap<D, I> {
+    /// The location both transitions leave from.
+    pub location: String,
+    /// The overlapping transitions.
+    pub transitions: (TransitionId, TransitionId),
+    /// A data value enabling both transitions.
+    pub data: D,
+    /// An input enabling both transitions.
+    pub input: I,
+}
+
+/// How often the guard of a transition was evaluated and how often it fired.
+///
+/// See [Machine::metrics].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TransitionMetrics {
+    /// The number of times the guard was evaluated.
+    pub evaluated: u64,
+    /// The number of times the guard held and the transition was taken.
+    pub fired: u64,
+}
+
+// The non-empty intervals of each location, keyed by the start location of the search.
+type NonEmptyCache<D> = Arc<Mutex<HashMap<String, AnalysisOutcome<HashMap<String, Bound<D>>>>>>;
+
+// Returns the position of the class of an input, if it has a known class.
+type Classify<I> = Arc<dyn Fn(&I) -> Option<usize> + Send + Sync>;
+
+// Returns the inputs declared with MachineBuilder::with_alphabet.
+type Symbols<I> = Arc<dyn Fn() -> Vec<I> + Send + Sync>;
+
+// Maps each input to the transitions out of a location that its class may enable.
+struct Dispatch<D, I> {
+    classify: Classify<I>,
+    // Returns the representative of the class at a position.
+    representative: Arc<dyn Fn(usize) -> I + Send + Sync>,
+    // The number of classes.
+    classes: usize,
+    // The data values each class is tried with.
+    data: Arc<Vec<D>>,
+    // Checks if a value is one of the data values, the only ones the table is exact for.
+    sampled: Arc<dyn Fn(&D) -> bool + Send + Sync>,
+    // For each location, the candidate transitions of each class, computed on first use.
+    table: OnceLock<HashMap<String, Vec<Vec<usize>>>>,
+}
+
+// Implemented by hand, because neither the data nor the input type need to be cloned.
+impl<D, I> Clone for Dispatch<D, I> {
+    fn clone(&self) -> Self {
+        Dispatch {
+            classify: self.classify.clone(),
+            representative: self.representative.clone(),
+            classes: self.classes,
+            data: self.data.clone(),
+            sampled: self.sampled.clone(),
+            table: self.table.clone(),
+        }
+    }
+}
+
+impl<D, I> Dispatch<D, I> {
+    fn new<C>(classifier: C, data: Vec<D>) -> Self
+    where
+        D: PartialEq + Send + Sync + 'static,
+        C: InputClassifier<I> + Send + Sync + 'static,
+        C::Class: Send + Sync + 'static,
+    {
+        let classes = classifier.classes();
+        let positions: BTreeMap<C::Class, usize> = classes
+            .iter()
+            .cloned()
+            .enumerate()
+            .map(|(position, class)| (class, position))
+            .collect();
+
+        // Both closures share the classifier, so the inputs are never stored.
+        let classifier = Arc::new(classifier);
+        let represented = classifier.clone();
+        let data = Arc::new(data);
+        let sample = data.clone();
+        Dispatch {
+            classes: classes.len(),
+            representative: Arc::new(move |position| {
+                represented.representative(&classes[position])
+            }),
+            classify: Arc::new(move |input| positions.get(&classifier.classify(input)).copied()),
+            data,
+            sampled: Arc::new(move |value| sample.contains(value)),
+            table: OnceLock::new(),
+        }
+    }
+
+    // Returns this dispatch without its table, to be computed again for other transitions.
+    fn reset(self) -> Self {
+        Dispatch {
+            table: OnceLock::new(),
+            ..self
+        }
+    }
+
+    // Computes the candidate transitions of each class out of every location.
+    fn index<U>(
+        &self,
+        locations: &HashMap<String, Vec<Transition<D, I, U>>>,
+    ) -> HashMap<String, Vec<Vec<usize>>> {
+        locations
+            .iter()
+            .map(|(location, transitions)| {
+                let classes = (0..self.classes)
+                    .map(|class| (self.representative)(class))
+                    .map(|representative| {
+                        (0..transitions.len())
+                            .filter(|idx| {
+                                self.data
+                                    .iter()
+                                    .any(|data| transitions[*idx].is_enabled(data, &representative))
+                            })
+                            .collect()
+                    })
+                    .collect();
+                (location.clone(), classes)
+            })
+            .collect()
+    }
+
+    // Returns the transitions out of `location` that the class of `input` may enable with
+    // `data`, or `None` when the input has no known class or the data was not sampled, so that
+    // every transition must be tried.
+    fn candidates<U>(
+        &self,
+        locations: &HashMap<String, Vec<Transition<D, I, U>>>,
+        location: &str,
+        data: &D,
+        input: &I,
+    ) -> Option<&[usize]> {
+        if !(self.sampled)(data) {
+            return None;
+        }
+        let class = (self.classify)(input)?;
+        let table = self.table.get_or_init(|| self.index(locations));
+        Some(table.get(location)?.get(class)?.as_slice())
+    }
+}
+
+// Memoizes the results of guards. The cache is used through this trait so that a machine does
+// not need its data and input types to be hashable, or to be sendable when it is shared.
+trait GuardMemo<D, I>: Send + Sync {
+    // Returns the result of the guard of the transition at `index` out of `location` with
+    // `data` and `input`, calling `evaluate` when it is not known yet.
+    fn check(
+        &self,
+        location: &str,
+        index: usize,
+        data: &D,
+        input: &I,
+        evaluate: &dyn Fn() -> bool,
+    ) -> bool;
+
+    // Returns an empty cache with the same capacity.
+    fn empty(&self) -> Arc<dyn GuardMemo<D, I>>;
+}
+
+// The results of the guards out of each location, keyed by transition index, data, and input.
+type GuardResults<D, I> = HashMap<String, HashMap<(usize, D, I), bool>>;
+
+// A guard cache holding at most `capacity` results, along with how many it holds.
+struct GuardCache<D, I> {
+    capacity: usize,
+    table: Mutex<(usize, GuardResults<D, I>)>,
+}
+
+impl<D, I> GuardCache<D, I> {
+    fn new(capacity: usize) -> Self {
+        GuardCache {
+            capacity,
+            table: Mutex::new((0, HashMap::new())),
+        }
+    }
+}
+
+impl<D, I> GuardMemo<D, I> for GuardCache<D, I>
+where
+    D: Hash + Eq + Clone + Send + 'static,
+    I: Hash + Eq + Clone + Send + 'static,
+{
+    fn check(
+        &self,
+        location: &str,
+        index: usize,
+        data: &D,
+        input: &I,
+        evaluate: &dyn Fn() -> bool,
+    ) -> bool {
+        let key = (index, data.clone(), input.clone());
+        if let Ok((_, table)) = self.table.lock().as_deref() {
+            if let Some(&result) = table.get(location).and_then(|results| results.get(&key)) {
+                return result;
+            }
+        }
+
+        // The lock is not held while the guard runs, since it may take long.
+        let result = evaluate();
+        if let Ok((len, table)) = self.table.lock().as_deref_mut() {
+            if *len >= self.capacity {
+                *len = 0;
+                table.clear();
+            }
+            if table
+                .entry(location.into())
+                .or_default()
+                .insert(key, result)
+                .is_none()
+            {
+                *len += 1;
+            }
+        }
+        result
+    }
+
+    fn empty(&self) -> Arc<dyn GuardMemo<D, I>> {
+        Arc::new(GuardCache::<D, I>::new(self.capacity))
+    }
+}
+
+// The counts of each transition, keyed by source location and then by index.
+type MetricsTable = Arc<Mutex<HashMap<String, Vec<TransitionMetrics>>>>;
+
+// Records one evaluation of the transition at `index` out of `location`.
+fn record(metrics: &MetricsTable, location: &str, index: usize, count: usize, fired: bool) {
+    let Ok(mut table) = metrics.lock() else {
+        return;
+    };
+
+    if !table.contains_key(location) {
+        table.insert(location.into(), Vec::new());
+    }
+
+    // Transitions may have been added since the location was first recorded.
+    let counts = table
+        .get_mut(location)
+        .expect("the location was just inserted");
+    if counts.len() < count {
+        counts.resize(count, TransitionMetrics::default());
+    }
+
+    counts[index].evaluated += 1;
+    if fired {
+        counts[index].fired += 1;
+    }
+}
+
+/// Describes a single transition relation.
+pub struct Transition<D, I, U> {
+    /// The location entered when this transition is taken.
+    pub to_location: String,
+    /// Guard deciding whether this transition is enabled for some data and input.
+    pub enable: Enable<D, I>,
+    /// Interval of data values over which this transition may be enabled.
+    pub bound: Bound<D>,
+    /// Update applied to the data when this transition is taken.
+    pub update: U,
+    /// Optional runtime guard which must hold in addition to `enable`.
+    ///
+    /// Unlike `enable`, this guard may capture state, such as a parsed
+    /// [expression](crate::expr::Expr).
+    pub guard: Option<Guard<D, I>>,
+    /// Optional name used in place of the [TransitionId] in reports and graphs.
+    pub name: Option<String>,
+    /// What the analyses may assume about the inputs enabling this transition, for updates
+    /// that depend on the input.
+    pub input: InputAbstraction<D>,
+    /// Optional binding between the data and a field of the input, set by
+    /// [with_store](Transition::with_store) or [with_match](Transition::with_match).
+    pub binding: Option<Binding<D, I>>,
+}
+
+// Implemented by hand, because the input type does not need to be cloned.
+impl<D: Clone, I, U: Clone> Clone for Transition<D, I, U> {
+    fn clone(&self) -> Self {
+        Transition {
+            to_location: self.to_location.clone(),
+            enable: self.enable,
+            bound: self.bound.clone(),
+            update: self.update.clone(),
+            guard: self.guard.clone(),
+            name: self.name.clone(),
+            input: self.input.clone(),
+            binding: self.binding.clone(),
+        }
+    }
+}
+
+impl<D: Debug, I, U: Debug> Debug for Transition<D, I, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Transition")
+            .field("name", &self.name)
+            .field("to_location", &self.to_location)
+            .field("bound", &self.bound)
+            .field("update", &self.update)
+            .field("guard", &self.guard.as_ref().map(|_| "<guard>"))
+            .field("input", &self.input)
+            .field("binding", &self.binding)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Guards cannot be inspected, so two named transitions are assumed to have the same guards
+/// when their names are equal. Unnamed transitions only have the same guards when they share
+/// the same `enable` function and the same `guard`.
+impl<D: PartialEq, I, U: PartialEq> PartialEq for Transition<D, I, U> {
+    fn eq(&self, other: &Self) -> bool {
+        let same_guards = match (&self.name, &other.name) {
+            (Some(name), Some(other_name)) => name == other_name,
+            (None, None) => {
+                std::ptr::fn_addr_eq(self.enable, other.enable)
+                    && match (&self.guard, &other.guard) {
+                        (Some(guard), Some(other_guard)) => Arc::ptr_eq(guard, other_guard),
+                        (None, None) => true,
+                        _ => false,
+                    }
+                    && match (&self.binding, &other.binding) {
+                        (Some(binding), Some(other_binding)) => binding.same(other_binding),
+                        (None, None) => true,
+                        _ => false,
+                    }
+            }
+            _ => false,
+        };
+
+        same_guards
+            && self.to_location == other.to_location
+            && self.bound == other.bound
+            && self.update == other.update
+            && self.input == other.input
+    }
+}
+
+impl<D, I, U> Transition<D, I, U> {
+    /// Checks if this transition is enabled for `data` and `input`.
+    pub fn is_enabled(&self, data: &D, input: &I) -> bool {
+        (self.enable)(data, input) && self.guard.as_ref().is_none_or(|guard| guard(data, input))
+    }
+
+    /// Returns this transition, additionally enabled only while the data lies within a bound
+    /// computed from the input.
+    ///
+    /// The input bound is checked at runtime as part of the [guard](Transition::guard). The
+    /// analyses cannot evaluate it, so they keep using the static [bound](Transition::bound),
+    /// which must contain every bound that `input_bound` can return.
+    ///
+    /// ```
+    /// use rust_efsm::bound::Bound;
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    ///
+    /// // The data must be within 5 of the input.
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition(
+    ///         "s0",
+    ///         Transition { to_location: "s0".into(), ..Default::default() }
+    ///             .with_input_bound(|i| Bound {
+    ///                 lower: Some(i.saturating_sub(5)),
+    ///                 upper: Some(i.saturating_add(5)),
+    ///             }),
+    ///     )
+    ///     .with_accepting("s0")
+    ///     .build();
+    ///
+    /// assert!(machine.exec("s0", 10, vec![12, 8, 15]));
+    /// assert!(!machine.exec("s0", 10, vec![12, 16]));
+    /// ```
+    pub fn with_input_bound(mut self, input_bound: fn(&I) -> Bound<D>) -> Self
+    where
+        D: Ord + Copy + Bounded + 'static,
+        I: 'static,
+    {
+        let guard = self.guard.take();
+        self.guard = Some(Arc::new(move |data: &D, input: &I| {
+            input_bound(input).contains(data)
+                && guard.as_ref().is_none_or(|guard| guard(data, input))
+        }));
+        self
+    }
+
+    /// Returns this transition, storing `field` of the input into the data when it is taken,
+    /// in place of its update.
+    ///
+    /// The analyses assume that the stored data lies within the
+    /// [input abstraction](Transition::input) of this transition.
+    ///
+    /// ```
+    /// use rust_efsm::bound::Bound;
+    /// use rust_efsm::machine::{IdentityUpdate, InputAbstraction, MachineBuilder, Transition};
+    ///
+    /// // Every request must be answered with its own identifier, below 100.
+    /// let machine = MachineBuilder::<u32, (u8, u32), IdentityUpdate<u32>>::new()
+    ///     .with_transition(
+    ///         "idle",
+    ///         Transition {
+    ///             to_location: "waiting".into(),
+    ///             enable: |_, (kind, _)| *kind == b'q',
+    ///             input: InputAbstraction::Within(Bound { lower: None, upper: Some(99) }),
+    ///             ..Default::default()
+    ///         }
+    ///         .with_store(|(_, id)| *id),
+    ///     )
+    ///     .with_transition(
+    ///         "waiting",
+    ///         Transition {
+    ///             to_location: "idle".into(),
+    ///             enable: |_, (kind, _)| *kind == b'a',
+    ///             input: InputAbstraction::Within(Bound { lower: None, upper: Some(99) }),
+    ///             ..Default::default()
+    ///         }
+    ///         .with_match(|(_, id)| *id),
+    ///     )
+    ///     .with_accepting("idle")
+    ///     .build();
+    ///
+    /// assert!(machine.exec("idle", 0, vec![(b'q', 7), (b'a', 7), (b'q', 3), (b'a', 3)]));
+    /// assert!(!machine.exec("idle", 0, vec![(b'q', 7), (b'a', 8)]));
+    ///
+    /// // The identifier stored while waiting is below 100.
+    /// let safe = machine.find_non_empty("idle").unwrap();
+    /// assert_eq!(safe["waiting"], Bound { lower: None, upper: Some(99) });
+    /// ```
+    pub fn with_store(mut self, field: fn(&I) -> D) -> Self
+    where
+        D: 'static,
+        I: 'static,
+    {
+        self.binding = Some(Binding::Store(Arc::new(field)));
+        self
+    }
+
+    /// Returns this transition, additionally enabled only while the data equals `field` of the
+    /// input, such as an identifier stored [earlier](Transition::with_store).
+    ///
+    /// The equality is checked at runtime as part of the [guard](Transition::guard). The
+    /// analyses assume that the matched data lies within the
+    /// [input abstraction](Transition::input) of this transition.
+    pub fn with_match(mut self, field: fn(&I) -> D) -> Self
+    where
+        D: PartialEq + 'static,
+        I: 'static,
+    {
+        let guard = self.guard.take();
+        self.guard = Some(Arc::new(move |data: &D, input: &I| {
+            field(input) == *data && guard.as_ref().is_none_or(|guard| guard(data, input))
+        }));
+        self.binding = Some(Binding::Match(Arc::new(field)));
+        self
+    }
+
+    /// Returns an interval containing the data after taking this transition from any data in
+    /// `interval`, using [update_interval_with](Update::update_interval_with) with this
+    /// transition's [input abstraction](Transition::input).
+    ///
+    /// The data stored by a [binding](Transition::with_store) lies within the input
+    /// abstraction, whatever `interval` is.
+    pub fn update_interval(&self, interval: Bound<D>) -> Bound<D>
+    where
+        D: Clone,
+        U: Update<D = D>,
+    {
+        match (&self.binding, &self.input) {
+            (Some(Binding::Store(_)), InputAbstraction::Within(bound)) => bound.clone(),
+            (Some(Binding::Store(_)), InputAbstraction::Unknown) => Bound::unbounded(),
+            _ => self.update.update_interval_with(interval, &self.input),
+        }
+    }
+
+    // Returns the data after taking this transition on `input`.
+    fn take(&self, data: D, input: &I) -> D
+    where
+        U: Update<D = D>,
+    {
+        match &self.binding {
+            Some(Binding::Store(field)) => field(input),
+            _ => self.update.update(data, input),
+        }
+    }
+
+    // Returns the data the analyses may assume this transition to be enabled for. Data matched
+    // against the input lies within the input abstraction, so it narrows the bound.
+    fn analysis_bound(&self) -> Option<Bound<D>>
+    where
+        D: Ord + Copy + Bounded,
+    {
+        match (&self.binding, &self.input) {
+            (Some(Binding::Match(_)), InputAbstraction::Within(bound)) => {
+                self.bound.intersect(bound)
+            }
+            _ => Some(self.bound.clone()),
+        }
+    }
+
+    // Returns this transition, only enabled when `condition` also holds for the data.
+    fn restrict(self, condition: &AcceptingCondition<D>) -> Self
+    where
+        D: 'static,
+        I: 'static,
+    {
+        let guard = self.to_guard();
+        let holds = condition.to_predicate();
+        Transition {
+            enable: |_, _| true,
+            guard: Some(Arc::new(move |data: &D, input: &I| {
+                holds(data) && guard(data, input)
+            })),
+            ..self
+        }
+    }
+
+    // Returns a single guard equivalent to both `enable` and `guard`.
+    fn to_guard(&self) -> Guard<D, I>
+    where
+        D: 'static,
+        I: 'static,
+    {
+        let enable = self.enable;
+        let guard = self.guard.clone();
+        Arc::new(move |data: &D, input: &I| {
+            enable(data, input) && guard.as_ref().is_none_or(|guard| guard(data, input))
+        })
+    }
+}
 
 impl<D, I, U: Default> Default for Transition<D, I, U> {
     fn default() -> Self {
@@ -264,13 +3430,323 @@ impl<D, I, U: Default> Default for Transition<D, I, U> {
             enable: |_, _| true,
             bound: Bound::unbounded(),
             update: Default::default(),
+            guard: None,
+            name: None,
+            input: InputAbstraction::Unknown,
+            binding: None,
         }
     }
 }
 
+/// A sequence of states visited by a machine while reading an input sequence.
+///
+/// See [Machine::accepting_runs].
 #[derive(Debug, Clone)]
+pub struct Run<D> {
+    /// The visited states, starting with the initial state.
+    pub states: Vec<State<D>>,
+    /// For each input, the transition taken out of the preceding state's location.
+    ///
+    /// `None` marks an implicit move made under the machine's [NoTransitionPolicy].
+    pub transitions: Vec<Option<TransitionId>>,
+}
+
+/// An iterator over the acceptance of every prefix of an input sequence.
+///
+/// Created by [Machine::exec_prefixes]. Besides iterating, the execution can be stepped through
+/// like a debugger: [resume](Prefixes::resume) reads inputs until a
+/// [breakpoint](Prefixes::break_on) is hit, and the [states](Prefixes::states) and their
+/// [last changes](Prefixes::diff_last) can then be inspected.
+pub struct Prefixes<'a, D, I, U, T> {
+    machine: &'a Machine<D, I, U>,
+    // The states reached by the prefix read so far, or `None` after an error.
+    states: Option<Vec<State<D>>>,
+    inputs: std::iter::Enumerate<T>,
+    // The states before the last input, and where each of the current states came from.
+    last: Option<LastStep<D>>,
+    breakpoints: Vec<Breakpoint<'a, D>>,
+}
+
+// A predicate on the location and data of a state, pausing an execution when a state meeting it
+// is reached.
+type Breakpoint<'a, D> = Box<dyn Fn(&str, &D) -> bool + 'a>;
+
+// The states before the last input was read. Each current state is paired with the index of
+// the previous state it was reached from, and the index of the transition taken.
+struct LastStep<D> {
+    input_index: usize,
+    previous: Vec<State<D>>,
+    sources: Sources,
+}
+
+impl<'a, D, I, U, T> Prefixes<'a, D, I, U, T> {
+    /// Pauses [resume](Prefixes::resume) once a state meeting `predicate` is reached.
+    ///
+    /// A breakpoint is only hit by the states that were not already there before the last
+    /// input, so a state that is kept as it is does not hit it again.
+    pub fn break_on(mut self, predicate: impl Fn(&str, &D) -> bool + 'a) -> Self {
+        self.breakpoints.push(Box::new(predicate));
+        self
+    }
+
+    /// Pauses [resume](Prefixes::resume) once `location` is entered.
+    pub fn break_at(self, location: &str) -> Self {
+        let location = location.to_string();
+        self.break_on(move |entered, _| entered == location)
+    }
+
+    /// Returns the states reached by the prefix read so far, which are none after an error.
+    pub fn states(&self) -> &[State<D>] {
+        self.states.as_deref().unwrap_or_default()
+    }
+
+    /// Reads inputs until a [breakpoint](Prefixes::break_on) is hit, and returns the index of
+    /// the input hitting it, or `None` when the inputs run out first.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{AddUpdate, MachineBuilder, Transition, TransitionId};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, AddUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s0".into(),
+    ///         update: AddUpdate { amount: 1 },
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s1".into(),
+    ///         enable: |_, i| *i == b'!',
+    ///         ..Default::default()
+    ///     })
+    ///     .build();
+    ///
+    /// let mut run = machine
+    ///     .exec_prefixes("s0", 0, *b"ab!c!")
+    ///     .break_on(|_, count| *count == 2)
+    ///     .break_at("s1");
+    ///
+    /// assert_eq!(run.resume().unwrap(), Some(1));
+    /// assert_eq!(run.states()[0].data, 2);
+    ///
+    /// assert_eq!(run.resume().unwrap(), Some(2));
+    /// let diff = run.diff_last().unwrap();
+    /// assert_eq!(diff.added[0].transition, Some(TransitionId::new("s0", 1)));
+    ///
+    /// // The state in "s1" has no transition, so "c" drops it and the next "!" enters again.
+    /// assert_eq!(run.resume().unwrap(), Some(4));
+    /// assert_eq!(run.resume().unwrap(), None);
+    /// ```
+    pub fn resume(&mut self) -> Result<Option<usize>, MachineError>
+    where
+        D: Clone + PartialEq,
+        U: Update<D = D>,
+        T: Iterator<Item = I>,
+    {
+        while let Some(result) = self.next() {
+            result?;
+            if self.hit_breakpoint() {
+                return Ok(self.last.as_ref().map(|last| last.input_index));
+            }
+        }
+        Ok(None)
+    }
+
+    // Checks if a state that was not there before the last input meets a breakpoint.
+    fn hit_breakpoint(&self) -> bool
+    where
+        D: PartialEq,
+    {
+        let Some(last) = &self.last else {
+            return false;
+        };
+
+        self.states()
+            .iter()
+            .filter(|state| !last.previous.contains(state))
+            .any(|state| {
+                self.breakpoints
+                    .iter()
+                    .any(|breakpoint| breakpoint(&state.location, &state.data))
+            })
+    }
+
+    /// Returns how the states changed on reading the last input, or `None` before the first
+    /// input and after an error.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{AddUpdate, MachineBuilder, State, Transition, TransitionId};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, AddUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s0".into(),
+    ///         update: AddUpdate { amount: 1 },
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s1".into(),
+    ///         enable: |count, _| *count > 0,
+    ///         ..Default::default()
+    ///     })
+    ///     .build();
+    ///
+    /// let mut prefixes = machine.exec_prefixes("s0", 0, *b"ab");
+    /// assert!(prefixes.diff_last().is_none());
+    ///
+    /// prefixes.next();
+    /// let diff = prefixes.diff_last().unwrap();
+    /// assert!(diff.added.is_empty());
+    /// assert_eq!(diff.updated[0].to, State { location: "s0".into(), data: 1 });
+    ///
+    /// // The guard of "s0#1" is now enabled.
+    /// prefixes.next();
+    /// let diff = prefixes.diff_last().unwrap();
+    /// assert_eq!(diff.added[0].transition, Some(TransitionId::new("s0", 1)));
+    /// assert_eq!(diff.to_string(), "~ s0: 1 -> 2 via s0#0\n+ s1: 1 via s0#1 from s0: 1\n");
+    /// ```
+    pub fn diff_last(&self) -> Option<StepDiff<D>>
+    where
+        D: Clone + PartialEq,
+    {
+        let states = self.states.as_ref()?;
+        let last = self.last.as_ref()?;
+
+        let mut diff = StepDiff {
+            input_index: last.input_index,
+            added: Vec::new(),
+            updated: Vec::new(),
+            removed: Vec::new(),
+        };
+
+        for (state, (source, transition)) in states.iter().zip(&last.sources) {
+            if last.previous.contains(state) {
+                continue;
+            }
+
+            let from = &last.previous[*source];
+            let change = StateChange {
+                from: from.clone(),
+                to: state.clone(),
+                transition: transition.map(|index| TransitionId::new(&from.location, index)),
+            };
+            match from.location == state.location {
+                true => diff.updated.push(change),
+                false if !diff.added.contains(&change) => diff.added.push(change),
+                false => {}
+            }
+        }
+
+        // A state updated in place is shown as updated rather than removed.
+        for state in &last.previous {
+            if !states.contains(state) && !diff.updated.iter().any(|change| change.from == *state) {
+                diff.removed.push(state.clone());
+            }
+        }
+
+        Some(diff)
+    }
+}
+
+impl<D, I, U, T> Iterator for Prefixes<'_, D, I, U, T>
+where
+    D: Clone,
+    U: Update<D = D>,
+    T: Iterator<Item = I>,
+{
+    type Item = Result<bool, MachineError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let previous = self.states.take()?;
+        let (idx, i) = self.inputs.next()?;
+        self.last = None;
+
+        match self.machine.traced_transition(&i, &previous) {
+            Ok((states, sources)) => {
+                let accepted = states.iter().any(|state| self.machine.is_accepting(state));
+                self.states = Some(states);
+                self.last = Some(LastStep {
+                    input_index: idx,
+                    previous,
+                    sources,
+                });
+                Some(Ok(accepted))
+            }
+            Err(MachineError::AssertionViolated(violation)) => {
+                Some(Err(MachineError::AssertionViolated(AssertionViolation {
+                    input_index: Some(idx),
+                    ..violation
+                })))
+            }
+            Err(error) => Some(Err(error)),
+        }
+    }
+}
+
+/// How the states of a machine changed on reading one input.
+///
+/// States that were kept as they were are left out. See [Prefixes::diff_last].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepDiff<D> {
+    /// The position of the input in the input sequence.
+    pub input_index: usize,
+    /// The states that moved to a new location.
+    pub added: Vec<StateChange<D>>,
+    /// The states that stayed in their location with new data.
+    pub updated: Vec<StateChange<D>>,
+    /// The states that were left without a replacement in their location.
+    pub removed: Vec<State<D>>,
+}
+
+/// A state reached on reading an input, with the state and transition it was reached from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateChange<D> {
+    /// The state before the input.
+    pub from: State<D>,
+    /// The state after the input.
+    pub to: State<D>,
+    /// The transition taken, or `None` for an implicit move under the [NoTransitionPolicy].
+    pub transition: Option<TransitionId>,
+}
+
+impl<D: fmt::Display> fmt::Display for StepDiff<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let via = |transition: &Option<TransitionId>| match transition {
+            Some(id) => id.to_string(),
+            None => "no transition".into(),
+        };
+
+        for change in &self.updated {
+            writeln!(
+                f,
+                "~ {}: {} -> {} via {}",
+                change.to.location,
+                change.from.data,
+                change.to.data,
+                via(&change.transition)
+            )?;
+        }
+        for change in &self.added {
+            writeln!(
+                f,
+                "+ {}: {} via {} from {}: {}",
+                change.to.location,
+                change.to.data,
+                via(&change.transition),
+                change.from.location,
+                change.from.data
+            )?;
+        }
+        for state in &self.removed {
+            writeln!(f, "- {}: {}", state.location, state.data)?;
+        }
+        Ok(())
+    }
+}
+
+/// A configuration of a machine: a location paired with a data value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct State<D> {
+    /// The current location.
     pub location: String,
+    /// The current data value.
     pub data: D,
 }
 
@@ -287,12 +3763,16 @@ impl<D> From<(String, D)> for State<D> {
     }
 }
 
+/// A set of states: a location paired with an interval of data values.
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StateInterval<D>
 where
     D: Eq + Hash,
 {
+    /// The location of every state in the set.
     pub location: String,
+    /// The data values of the states in the set.
     pub interval: Bound<D>,
 }
 
@@ -307,12 +3787,250 @@ where
 
 type Enable<D, I> = fn(&D, &I) -> bool;
 
+// A state paired with the index of the transition that reached it.
+pub(crate) type Successor<D> = (Option<usize>, State<D>);
+
+// The successors of a single state, which are usually one or two.
+pub(crate) type Successors<D> = SmallVec<[Successor<D>; 2]>;
+
+// For each state of a set, the index of the state it was reached from and of the transition
+// taken.
+type Sources = Vec<(usize, Option<usize>)>;
+
+/// A shareable guard that may capture state.
+pub type Guard<D, I> = Arc<dyn Fn(&D, &I) -> bool + Send + Sync>;
+
+// The enable function and guard of a transition, which together decide whether it is enabled.
+type Enabling<D, I> = (fn(&D, &I) -> bool, Option<Guard<D, I>>);
+
+/// A field read from an input, as a data value.
+pub type Field<D, I> = Arc<dyn Fn(&I) -> D + Send + Sync>;
+
+/// Relates the data to a field of the input read by a transition, in the style of quantified
+/// event automata.
+///
+/// Unlike a guard, a binding is declared, so the analyses can follow it: the data stored or
+/// matched lies within the [input abstraction](Transition::input) of the transition.
+pub enum Binding<D, I> {
+    /// The field is stored into the data when the transition is taken, in place of the update.
+    Store(Field<D, I>),
+    /// The transition is only enabled when the field equals the data.
+    Match(Field<D, I>),
+}
+
+// Implemented by hand, because neither the data nor the input type need to be cloned.
+impl<D, I> Clone for Binding<D, I> {
+    fn clone(&self) -> Self {
+        match self {
+            Binding::Store(field) => Binding::Store(field.clone()),
+            Binding::Match(field) => Binding::Match(field.clone()),
+        }
+    }
+}
+
+impl<D, I> Debug for Binding<D, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Binding::Store(_) => write!(f, "Store(<field>)"),
+            Binding::Match(_) => write!(f, "Match(<field>)"),
+        }
+    }
+}
+
+impl<D, I> Binding<D, I> {
+    // Checks if both bindings are of the same kind and share the same field.
+    fn same(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Binding::Store(field), Binding::Store(other)) => Arc::ptr_eq(field, other),
+            (Binding::Match(field), Binding::Match(other)) => Arc::ptr_eq(field, other),
+            _ => false,
+        }
+    }
+
+    // Returns a binding of the same kind, reading the field returned by `map`.
+    fn map_field<E, J>(self, map: impl FnOnce(Field<D, I>) -> Field<E, J>) -> Binding<E, J> {
+        match self {
+            Binding::Store(field) => Binding::Store(map(field)),
+            Binding::Match(field) => Binding::Match(map(field)),
+        }
+    }
+}
+
+/// A condition on the data that a run must meet to be accepted in an accepting location.
+///
+/// A condition built from a [Bound] is visible to the interval analyses, such as
+/// [find_non_empty](Machine::find_non_empty). A condition built from a predicate is opaque, so
+/// these analyses assume it may hold for any data.
+///
+/// ```
+/// use rust_efsm::bound::Bound;
+/// use rust_efsm::machine::AcceptingCondition;
+///
+/// let condition = AcceptingCondition::new(Bound { lower: None, upper: Some(3_u8) });
+/// assert!(condition.holds(&3));
+/// assert!(!condition.holds(&4));
+/// assert!(condition.clone().negate().holds(&4));
+/// ```
+pub struct AcceptingCondition<D> {
+    bound: Option<Bound<D>>,
+    negated: bool,
+    contains: Arc<dyn Fn(&D) -> bool + Send + Sync>,
+}
+
+impl<D> AcceptingCondition<D> {
+    /// Creates a condition that holds for the data in `bound`.
+    pub fn new(bound: Bound<D>) -> Self
+    where
+        D: Ord + Copy + Bounded + Send + Sync + 'static,
+    {
+        let inner = bound.clone();
+        AcceptingCondition {
+            bound: Some(bound),
+            negated: false,
+            contains: Arc::new(move |data| inner.contains(data)),
+        }
+    }
+
+    /// Creates a condition that holds for the data meeting `predicate`.
+    pub fn from_predicate(predicate: impl Fn(&D) -> bool + Send + Sync + 'static) -> Self {
+        AcceptingCondition {
+            bound: None,
+            negated: false,
+            contains: Arc::new(predicate),
+        }
+    }
+
+    /// Checks if the condition holds for `data`.
+    pub fn holds(&self, data: &D) -> bool {
+        (self.contains)(data) != self.negated
+    }
+
+    /// Returns the condition that holds exactly when this one does not.
+    pub fn negate(mut self) -> Self {
+        self.negated = !self.negated;
+        self
+    }
+
+    /// Returns the bound this condition was built from, if any.
+    pub fn get_bound(&self) -> Option<&Bound<D>> {
+        self.bound.as_ref()
+    }
+
+    /// Checks if the condition holds outside of its bound, rather than inside of it.
+    pub fn is_negated(&self) -> bool {
+        self.negated
+    }
+
+    // Returns an interval containing all of the data the condition holds for.
+    pub(crate) fn hull(&self) -> Bound<D>
+    where
+        D: Clone,
+    {
+        match (&self.bound, self.negated) {
+            (Some(bound), false) => bound.clone(),
+            _ => Bound::unbounded(),
+        }
+    }
+
+    // Checks if the condition may hold for some of the data in `interval`.
+    pub(crate) fn may_hold(&self, interval: &Bound<D>) -> bool
+    where
+        D: Ord + Copy + Bounded,
+    {
+        match (&self.bound, self.negated) {
+            (None, _) => true,
+            (Some(bound), false) => bound.intersect(interval).is_some(),
+            (Some(bound), true) => !bound.contains_interval(interval),
+        }
+    }
+
+    // Checks if the condition holds for all of the data in `interval`. A condition built from a
+    // predicate is opaque, so it is never known to hold.
+    pub(crate) fn holds_within(&self, interval: &Bound<D>) -> bool
+    where
+        D: Ord + Copy + Bounded,
+    {
+        match (&self.bound, self.negated) {
+            (None, _) => false,
+            (Some(bound), false) => bound.contains_interval(interval),
+            (Some(bound), true) => bound.intersect(interval).is_none(),
+        }
+    }
+
+    // Returns a condition holding for the data both conditions hold for. It is opaque to the
+    // interval analyses, like a condition built from a predicate.
+    pub(crate) fn and(&self, other: &Self) -> Self
+    where
+        D: 'static,
+    {
+        let (lhs, rhs) = (self.to_predicate(), other.to_predicate());
+        AcceptingCondition::from_predicate(move |data| lhs(data) && rhs(data))
+    }
+
+    // Returns a predicate equivalent to this condition, which does not borrow it.
+    pub(crate) fn to_predicate(&self) -> Arc<dyn Fn(&D) -> bool + Send + Sync>
+    where
+        D: 'static,
+    {
+        if !self.negated {
+            return self.contains.clone();
+        }
+        let contains = self.contains.clone();
+        Arc::new(move |data| !contains(data))
+    }
+}
+
+impl<D: Clone> Clone for AcceptingCondition<D> {
+    fn clone(&self) -> Self {
+        AcceptingCondition {
+            bound: self.bound.clone(),
+            negated: self.negated,
+            contains: self.contains.clone(),
+        }
+    }
+}
+
+impl<D: Debug> Debug for AcceptingCondition<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AcceptingCondition")
+            .field("bound", &self.bound)
+            .field("negated", &self.negated)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Conditions built from bounds are equal when their bounds and negations are. Conditions built
+/// from predicates are only equal to their own clones.
+impl<D: PartialEq> PartialEq for AcceptingCondition<D> {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.bound, &other.bound) {
+            (Some(lhs), Some(rhs)) => lhs == rhs && self.negated == other.negated,
+            _ => Arc::ptr_eq(&self.contains, &other.contains) && self.negated == other.negated,
+        }
+    }
+}
+
+impl<D> fmt::Display for AcceptingCondition<D>
+where
+    D: fmt::Display + Bounded + Copy,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.bound, self.negated) {
+            (Some(bound), false) => write!(f, "{}", bound),
+            (Some(bound), true) => write!(f, "not {}", bound),
+            (None, false) => write!(f, "predicate"),
+            (None, true) => write!(f, "not predicate"),
+        }
+    }
+}
+
 /// Creates a D based on information from an existing D and a new I.
 /// It can also use an immutable reference to self.
 ///
 /// It is similar to Enable, because it is called during a transition.
 /// However, the Update function may store read-only state.
 pub trait Update {
+    /// The data type this update operates on.
     type D;
 
     // NOTE: ATM, there is only one implementation of update function used for every transition.
@@ -320,37 +4038,188 @@ pub trait Update {
     // NOTE: I don't know if this is really desirable yet?
     // NOTE: I think the trade off is between suffering dynamic disbatch to enable different
     // updates or using generics but only get one update struct.
+
+    /// Returns the data after taking a transition on `input`.
     fn update<I>(&self, data: Self::D, input: &I) -> Self::D;
+
+    /// Returns an interval containing the result of [update](Update::update) for every value
+    /// in `interval`.
     fn update_interval(&self, interval: Bound<Self::D>) -> Bound<Self::D>;
+
+    /// Returns an interval containing the result of [update](Update::update) for every value
+    /// in `interval` and every input described by `input`.
+    ///
+    /// The analyses call this method rather than [update_interval](Update::update_interval).
+    /// Updates that depend on the input must override it, and stay sound when the input is
+    /// [Unknown](InputAbstraction::Unknown). By default, the input is ignored.
+    ///
+    /// ```
+    /// use rust_efsm::bound::Bound;
+    /// use rust_efsm::machine::{InputAbstraction, MachineBuilder, Transition, Update};
+    ///
+    /// // Adds an offset between 0 and the input to the data.
+    /// #[derive(Clone, Default)]
+    /// struct AddUpTo;
+    ///
+    /// impl Update for AddUpTo {
+    ///     type D = u8;
+    ///
+    ///     fn update<I>(&self, data: u8, _input: &I) -> u8 {
+    ///         // Adding no offset is always allowed.
+    ///         data
+    ///     }
+    ///
+    ///     fn update_interval(&self, interval: Bound<u8>) -> Bound<u8> {
+    ///         self.update_interval_with(interval, &InputAbstraction::Unknown)
+    ///     }
+    ///
+    ///     fn update_interval_with(
+    ///         &self,
+    ///         interval: Bound<u8>,
+    ///         input: &InputAbstraction<u8>,
+    ///     ) -> Bound<u8> {
+    ///         let (lower, upper) = interval.as_explicit();
+    ///         match input {
+    ///             InputAbstraction::Within(bound) => Bound {
+    ///                 lower: Some(lower),
+    ///                 upper: Some(upper.saturating_add(bound.as_explicit().1)),
+    ///             },
+    ///             InputAbstraction::Unknown => Bound { lower: Some(lower), upper: None },
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let machine = MachineBuilder::<u8, u8, AddUpTo>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s1".into(),
+    ///         bound: Bound { lower: None, upper: Some(10) },
+    ///         input: InputAbstraction::Within(Bound { lower: None, upper: Some(5) }),
+    ///         ..Default::default()
+    ///     })
+    ///     .build();
+    ///
+    /// let transition = &machine.get_transitions_from("s0").unwrap()[0];
+    /// assert_eq!(
+    ///     transition.update_interval(transition.bound.clone()),
+    ///     Bound { lower: Some(0), upper: Some(15) },
+    /// );
+    /// ```
+    fn update_interval_with(
+        &self,
+        interval: Bound<Self::D>,
+        input: &InputAbstraction<Self::D>,
+    ) -> Bound<Self::D> {
+        let _ = input;
+        self.update_interval(interval)
+    }
+}
+
+/// What the analyses know about the inputs read by a transition, as values of the data type.
+///
+/// See [Update::update_interval_with].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum InputAbstraction<D> {
+    /// Nothing is known about the input.
+    #[default]
+    Unknown,
+    /// The input lies within the bound.
+    Within(Bound<D>),
+}
+
+/// An [Update] that can be undone.
+pub trait Invert: Update {
+    /// Returns an update that maps the result of [update](Update::update) back to its argument.
+    fn invert(&self) -> Self;
+}
+
+/// An update that adds a constant amount to the data.
+#[derive(Clone, Debug, Default, PartialEq, Hash)]
+pub struct AddUpdate<D>
+where
+    D: Add,
+{
+    /// The amount added on every update.
+    pub amount: D,
+}
+
+impl<D> Update for AddUpdate<D>
+where
+    D: Add<Output = D> + Bounded + Copy + CheckedAdd,
+{
+    type D = D;
+
+    fn update<I>(&self, data: D, _input: &I) -> D {
+        data + self.amount
+    }
+    fn update_interval(&self, interval: Bound<D>) -> Bound<D> {
+        let (lower, upper) = interval.as_explicit();
+        Bound {
+            lower: Some(lower + self.amount),
+            upper: upper.checked_add(&self.amount),
+        }
+    }
+}
+
+impl<D> Invert for AddUpdate<D>
+where
+    D: Add<Output = D> + Neg<Output = D> + Bounded + Copy + CheckedAdd,
+{
+    fn invert(&self) -> Self {
+        AddUpdate {
+            amount: -self.amount,
+        }
+    }
+}
+
+impl<D> fmt::Display for AddUpdate<D>
+where
+    D: Add + fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "data += {}", self.amount)
+    }
+}
+
+/// An update over data of type `E` that applies an update over data of type `D`.
+///
+/// See [Machine::map_data].
+pub struct MapData<U, D, E> {
+    /// The wrapped update.
+    pub update: U,
+    /// Converts the data to the type of the wrapped update.
+    pub into: fn(&E) -> D,
+    /// Converts the result of the wrapped update back.
+    pub from: fn(D) -> E,
 }
 
-#[derive(Clone)]
-pub struct AddUpdate<D>
-where
-    D: Add,
-{
-    pub amount: D,
+// Implemented by hand, because the data types do not need to be cloned.
+impl<U: Clone, D, E> Clone for MapData<U, D, E> {
+    fn clone(&self) -> Self {
+        MapData {
+            update: self.update.clone(),
+            into: self.into,
+            from: self.from,
+        }
+    }
 }
 
-impl<D> Update for AddUpdate<D>
+impl<U, D, E> Update for MapData<U, D, E>
 where
-    D: Add<Output = D> + Bounded + Copy + CheckedAdd,
+    U: Update<D = D>,
 {
-    type D = D;
+    type D = E;
 
-    fn update<I>(&self, data: D, _input: &I) -> D {
-        data + self.amount
+    fn update<I>(&self, data: E, input: &I) -> E {
+        (self.from)(self.update.update((self.into)(&data), input))
     }
-    fn update_interval(&self, interval: Bound<D>) -> Bound<D> {
-        let (lower, upper) = interval.as_explicit();
-        Bound {
-            lower: Some(lower + self.amount),
-            upper: upper.checked_add(&self.amount),
-        }
+
+    fn update_interval(&self, _interval: Bound<E>) -> Bound<E> {
+        Bound::unbounded()
     }
 }
 
-#[derive(Clone, Default)]
+/// An update that leaves the data unchanged.
+#[derive(Clone, Debug, Default, PartialEq, Hash)]
 pub struct IdentityUpdate<D>(D);
 
 impl<D> Update for IdentityUpdate<D> {
@@ -364,6 +4233,19 @@ impl<D> Update for IdentityUpdate<D> {
     }
 }
 
+impl<D: Clone> Invert for IdentityUpdate<D> {
+    fn invert(&self) -> Self {
+        self.clone()
+    }
+}
+
+impl<D> fmt::Display for IdentityUpdate<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "data")
+    }
+}
+
+/// A node in the search tree explored by [Machine::find_non_empty].
 #[derive(Debug)]
 pub struct PathNode<D>
 where
@@ -379,6 +4261,7 @@ impl<D> PathNode<D>
 where
     D: Eq + Hash + Clone,
 {
+    /// Returns the indices of the nodes on the path from the root of `table` to this node.
     pub fn path_to(&self, table: &[PathNode<D>]) -> impl Iterator<Item = usize> {
         let mut path: Vec<usize> = vec![];
         let mut next = self.idx;
@@ -408,10 +4291,248 @@ where
     }
 }
 
+/// Describes data that violated an assertion added with [MachineBuilder::with_assertion].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AssertionViolation {
+    /// The location whose assertion was violated.
+    pub location: String,
+    /// The offending data, formatted with [Debug].
+    pub data: String,
+    /// The position of the input that led to the violation, when known.
+    pub input_index: Option<usize>,
+    /// The message given with the assertion.
+    pub message: String,
+}
+
+impl fmt::Display for AssertionViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "assertion failed in {} with data {}",
+            self.location, self.data
+        )?;
+        if let Some(idx) = self.input_index {
+            write!(f, " after input {}", idx)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+// A predicate over the data that must hold whenever a location is entered.
+#[derive(Clone)]
+struct Assertion<D> {
+    predicate: Arc<dyn Fn(&D) -> bool + Send + Sync>,
+    message: String,
+    // Formats the data for an AssertionViolation.
+    describe: Arc<dyn Fn(&D) -> String + Send + Sync>,
+}
+
+impl<D: 'static> Assertion<D> {
+    // Returns this assertion over data of type `E`, checked on the data converted with `into`.
+    fn map<E: 'static>(self, into: fn(&E) -> D) -> Assertion<E> {
+        let (predicate, describe) = (self.predicate, self.describe);
+        Assertion {
+            predicate: Arc::new(move |data| predicate(&into(data))),
+            message: self.message,
+            describe: Arc::new(move |data| describe(&into(data))),
+        }
+    }
+}
+
+fn describe<D: Debug>(data: &D) -> String {
+    format!("{:?}", data)
+}
+
+/// Limits the work of [Machine::exec_iter].
+///
+/// The default configuration has no limits.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ExecConfig {
+    /// The maximum number of inputs to read, or `None` for no limit.
+    pub max_steps: Option<usize>,
+    /// The maximum number of states to track at once, or `None` for no limit.
+    pub max_states: Option<usize>,
+    /// Decides what happens when a limit is exceeded.
+    pub on_exceed: ExceedPolicy,
+}
+
+impl ExecConfig {
+    // Applies the policy to `budget` being exceeded at input `input_index`. Returns whether the
+    // execution continues within the limits, or an error.
+    fn exceed(&self, budget: Budget, input_index: usize) -> Result<bool, MachineError> {
+        debug!(%budget, input_index, "budget exceeded");
+        match self.on_exceed {
+            ExceedPolicy::Error => Err(MachineError::BudgetExceeded {
+                budget,
+                input_index,
+            }),
+            ExceedPolicy::Reject => Ok(false),
+            ExceedPolicy::Truncate => Ok(true),
+        }
+    }
+}
+
+/// Decides what happens when an execution exceeds a limit of its [ExecConfig].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ExceedPolicy {
+    /// Report a [MachineError::BudgetExceeded].
+    #[default]
+    Error,
+    /// Reject the input sequence.
+    Reject,
+    /// Stay within the limits, by ignoring the inputs past the maximum number of steps and
+    /// dropping the states past the maximum number of states. The result may then differ from
+    /// that of an unlimited execution.
+    Truncate,
+}
+
+/// A limit of an [ExecConfig].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Budget {
+    /// The maximum number of inputs to read.
+    Steps(usize),
+    /// The maximum number of states to track at once.
+    States(usize),
+}
+
+impl fmt::Display for Budget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Budget::Steps(max) => write!(f, "{} steps", max),
+            Budget::States(max) => write!(f, "{} states", max),
+        }
+    }
+}
+
+/// How far the runs of a machine got on an input, as returned by [Machine::exec_report].
+#[derive(Debug)]
+pub struct ExecReport<D> {
+    /// Whether the input was accepted.
+    pub accepted: bool,
+    /// The length of the longest prefix of the input that some run survived.
+    pub read: usize,
+    /// The index of the input on which every run died, if any.
+    pub died_at: Option<usize>,
+    /// The states reached after the longest surviving prefix.
+    pub states: Vec<State<D>>,
+    /// The error that ended the runs, if any.
+    pub error: Option<MachineError>,
+}
+
+impl<D: Debug> fmt::Display for ExecReport<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.accepted, self.died_at, &self.error) {
+            (true, _, _) => write!(f, "accepted"),
+            (false, Some(idx), Some(error)) => write!(f, "failed on input {}: {}", idx, error),
+            (false, Some(idx), None) => write!(f, "every run died on input {}", idx),
+            (false, None, _) => write!(f, "rejected, no state is accepting"),
+        }?;
+        write!(
+            f,
+            " after reading {} inputs, in states {:?}",
+            self.read, self.states
+        )
+    }
+}
+
+/// Asserts that a machine accepts an input from a location and data, printing how far its runs
+/// got otherwise.
+///
+/// The input is anything a [Vec] can be created from, such as an array or a slice.
+///
+/// ```
+/// use rust_efsm::assert_accepts;
+/// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+///
+/// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+///     .with_transition("s0", Transition {
+///         to_location: "s1".into(),
+///         enable: |_, i| *i == b'a',
+///         ..Default::default()
+///     })
+///     .with_accepting("s1")
+///     .build();
+///
+/// assert_accepts!(machine, "s0", 0, [b'a']);
+/// ```
+///
+/// ```should_panic
+/// # use rust_efsm::assert_accepts;
+/// # use rust_efsm::machine::{IdentityUpdate, MachineBuilder};
+/// # let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new().build();
+/// // Panics with "every run died on input 0 after reading 0 inputs, in states [...]".
+/// assert_accepts!(machine, "s0", 0, b"ab");
+/// ```
+#[macro_export]
+macro_rules! assert_accepts {
+    ($machine:expr, $location:expr, $data:expr, $input:expr $(,)?) => {{
+        let (location, input) = ($location, ::std::vec::Vec::from($input));
+        let report = $machine.exec_report(location, $data, input.clone());
+        if !report.accepted {
+            panic!(
+                "assertion failed: {:?} is accepted from {:?}\n{}",
+                input, location, report
+            );
+        }
+    }};
+}
+
+/// Asserts that a machine rejects an input from a location and data, printing the states it
+/// accepted in otherwise.
+///
+/// ```
+/// use rust_efsm::assert_rejects;
+/// use rust_efsm::machine::{IdentityUpdate, MachineBuilder};
+///
+/// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+///     .with_accepting("s0")
+///     .build();
+///
+/// assert_rejects!(machine, "s0", 0, [b'a']);
+/// ```
+#[macro_export]
+macro_rules! assert_rejects {
+    ($machine:expr, $location:expr, $data:expr, $input:expr $(,)?) => {{
+        let (location, input) = ($location, ::std::vec::Vec::from($input));
+        let report = $machine.exec_report(location, $data, input.clone());
+        if report.accepted {
+            panic!(
+                "assertion failed: {:?} is rejected from {:?}\n{}",
+                input, location, report
+            );
+        }
+    }};
+}
+
+/// Errors that can occur while operating on a machine.
 #[derive(Debug)]
 pub enum MachineError {
+    /// The requested analysis cannot be decided for this machine.
     Undecidable,
+    /// The search for non-empty states failed.
     FindNonEmptyFailed,
+    /// No transition out of the given location was enabled under [NoTransitionPolicy::Error].
+    NoTransition(String),
+    /// A location that had to be created already exists in the machine.
+    LocationExists(String),
+    /// Two machines being combined have different [no transition policies](NoTransitionPolicy).
+    PolicyMismatch(NoTransitionPolicy, NoTransitionPolicy),
+    /// The data entering a location violated one of its assertions.
+    AssertionViolated(AssertionViolation),
+    /// An analysis was paired with a machine other than the one it was computed for.
+    StaleAnalysis {
+        /// The [fingerprint](Machine::fingerprint) of the machine the analysis was computed for.
+        analyzed: u64,
+        /// The fingerprint of the machine it was paired with.
+        machine: u64,
+    },
+    /// An execution exceeded a limit of its [ExecConfig].
+    BudgetExceeded {
+        /// The limit that was exceeded.
+        budget: Budget,
+        /// The position of the input at which it was exceeded.
+        input_index: usize,
+    },
 }
 
 impl fmt::Display for MachineError {
@@ -419,14 +4540,79 @@ impl fmt::Display for MachineError {
         match self {
             MachineError::Undecidable => write!(f, "{:?}", self),
             MachineError::FindNonEmptyFailed => write!(f, "{:?}", self),
+            MachineError::NoTransition(_) => write!(f, "{:?}", self),
+            MachineError::LocationExists(_) => write!(f, "{:?}", self),
+            MachineError::PolicyMismatch(_, _) => write!(f, "{:?}", self),
+            MachineError::AssertionViolated(violation) => write!(f, "{}", violation),
+            MachineError::StaleAnalysis { analyzed, machine } => write!(
+                f,
+                "the analysis of machine {:016x} does not apply to machine {:016x}",
+                analyzed, machine
+            ),
+            MachineError::BudgetExceeded {
+                budget,
+                input_index,
+            } => write!(
+                f,
+                "exceeded the limit of {} at input {}",
+                budget, input_index
+            ),
         }
     }
 }
 
+/// The class of a property, as computed by [Machine::classify].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Classification {
+    /// Every rejected prefix stays rejected, so violations are detected conclusively.
+    Safety,
+    /// Every accepted prefix stays accepted, so satisfaction is detected conclusively.
+    CoSafety,
+    /// Every prefix can be extended to one with a conclusive verdict.
+    Monitorable,
+    /// Some prefix can never be extended to one with a conclusive verdict.
+    NonMonitorable,
+}
+
+/// Decides what happens to a state that has no enabled transition for an input.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum NoTransitionPolicy {
+    /// Drop the state, so it can no longer lead to acceptance.
+    #[default]
+    Reject,
+    /// Keep the state unchanged, as if it took a self-loop.
+    Stutter,
+    /// Move to the given location, keeping the data unchanged.
+    SinkTo(String),
+    /// Report a [MachineError::NoTransition].
+    Error,
+}
+
 /// Helps with specifying [Machines](Machine).
 pub struct MachineBuilder<D, I, U> {
     locations: HashMap<String, Vec<Transition<D, I, U>>>,
     accepting: HashSet<String>,
+    rejecting: HashSet<String>,
+    accepting_conditions: HashMap<String, AcceptingCondition<D>>,
+    no_transition_policy: NoTransitionPolicy,
+    metrics: bool,
+    assertions: HashMap<String, Vec<Assertion<D>>>,
+    tags: HashMap<String, String>,
+    dispatch: Option<Dispatch<D, I>>,
+    guard_cache: Option<Arc<dyn GuardMemo<D, I>>>,
+    alphabet: Option<Symbols<I>>,
+}
+
+impl<D, I, U> Default for MachineBuilder<D, I, U>
+where
+    D: Default + Clone + Debug,
+    I: Debug,
+    U: Update<D = D>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<D, I, U> MachineBuilder<D, I, U>
@@ -440,35 +4626,577 @@ where
         MachineBuilder {
             locations: HashMap::new(),
             accepting: HashSet::new(),
+            rejecting: HashSet::new(),
+            accepting_conditions: HashMap::new(),
+            no_transition_policy: NoTransitionPolicy::default(),
+            metrics: false,
+            assertions: HashMap::new(),
+            tags: HashMap::new(),
+            dispatch: None,
+            guard_cache: None,
+            alphabet: None,
         }
     }
 
     /// Add a transition from state `from_location`.
     pub fn with_transition(mut self, from_location: &str, transition: Transition<D, I, U>) -> Self {
-        info!(
+        trace!(
             "add transition {} to {}",
-            from_location, transition.to_location
+            from_location,
+            transition.to_location
         );
         self.locations
             .entry(from_location.into())
-            .or_insert(Vec::new())
+            .or_default()
             .push(transition);
         self
     }
 
+    /// Create a machine builder with a transition for every `(from_location, transition)` edge.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    ///
+    /// let edges = [("s0", "s1"), ("s1", "s2")].map(|(from, to)| {
+    ///     (from, Transition { to_location: to.into(), ..Default::default() })
+    /// });
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::from_edges(edges)
+    ///     .with_accepting("s2")
+    ///     .build();
+    ///
+    /// assert!(machine.exec("s0", 0, vec![b'a', b'b']));
+    /// ```
+    pub fn from_edges<S>(edges: impl IntoIterator<Item = (S, Transition<D, I, U>)>) -> Self
+    where
+        S: AsRef<str>,
+    {
+        edges
+            .into_iter()
+            .fold(Self::new(), |builder, (from_location, transition)| {
+                builder.with_transition(from_location.as_ref(), transition)
+            })
+    }
+
+    /// Add every transition in `transitions` from state `from_location`.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transitions("s0", ["s1", "s2"].map(|to| Transition {
+    ///         to_location: to.into(),
+    ///         ..Default::default()
+    ///     }))
+    ///     .build();
+    ///
+    /// assert_eq!(machine.get_transitions_from("s0").unwrap().len(), 2);
+    /// ```
+    pub fn with_transitions(
+        self,
+        from_location: &str,
+        transitions: impl IntoIterator<Item = Transition<D, I, U>>,
+    ) -> Self {
+        transitions.into_iter().fold(self, |builder, transition| {
+            builder.with_transition(from_location, transition)
+        })
+    }
+
+    /// Add to every location a self-loop, taken on the inputs that enable none of its other
+    /// transitions, so that no input is left without a transition by accident.
+    ///
+    /// The self-loops take the default update, which leaves the data unchanged for updates such
+    /// as [IdentityUpdate] and [AddUpdate]. Only the transitions added so far are taken into
+    /// account, so this should be called after them. Rejecting locations are left as they are.
+    /// The self-loops have a runtime [guard](Transition::guard), which the analyses assume may
+    /// always hold.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    ///
+    /// // Wait for a 'go', whatever comes before it.
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("waiting", Transition {
+    ///         to_location: "going".into(),
+    ///         enable: |_, i| *i == b'g',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("going")
+    ///     .with_default_self_loops()
+    ///     .build();
+    ///
+    /// assert!(machine.exec("waiting", 0, b"xyzgxy".to_vec()));
+    /// assert!(!machine.exec("waiting", 0, b"xyz".to_vec()));
+    /// ```
+    pub fn with_default_self_loops(mut self) -> Self
+    where
+        D: 'static,
+        I: 'static,
+        U: Default,
+    {
+        let mut locations: BTreeSet<String> = self.locations.keys().cloned().collect();
+        locations.extend(
+            self.locations
+                .values()
+                .flatten()
+                .map(|transition| transition.to_location.clone()),
+        );
+        locations.extend(self.accepting.iter().cloned());
+        locations.extend(self.accepting_conditions.keys().cloned());
+
+        locations.retain(|location| !self.rejecting.contains(location));
+
+        for location in &locations {
+            let others: Vec<Enabling<D, I>> = self
+                .locations
+                .get(location)
+                .into_iter()
+                .flatten()
+                .map(|transition| (transition.enable, transition.guard.clone()))
+                .collect();
+
+            let otherwise = Transition {
+                to_location: location.clone(),
+                guard: Some(Arc::new(move |data: &D, input: &I| {
+                    !others.iter().any(|(enable, guard)| {
+                        enable(data, input) && guard.as_ref().is_none_or(|guard| guard(data, input))
+                    })
+                })),
+                ..Default::default()
+            };
+            self = self.with_transition(location, otherwise);
+        }
+
+        self
+    }
+
     /// Mark state `s` as accepting.
     pub fn with_accepting(mut self, location: &str) -> Self {
-        info!("mark location {} as accepting", location);
+        trace!("mark location {} as accepting", location);
+        self.accepting.insert(location.into());
+        self
+    }
+
+    /// Mark every state in `locations` as accepting.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_accepting_all(["s0", "s1"])
+    ///     .build();
+    ///
+    /// assert_eq!(machine.get_accepting().len(), 2);
+    /// ```
+    pub fn with_accepting_all<S>(self, locations: impl IntoIterator<Item = S>) -> Self
+    where
+        S: AsRef<str>,
+    {
+        locations.into_iter().fold(self, |builder, location| {
+            builder.with_accepting(location.as_ref())
+        })
+    }
+
+    /// Mark `location` as accepting only for the data in `bound`.
+    ///
+    /// ```
+    /// use rust_efsm::bound::Bound;
+    /// use rust_efsm::machine::{AddUpdate, MachineBuilder, Transition};
+    ///
+    /// // Accepts at most three inputs.
+    /// let machine = MachineBuilder::<u8, u8, AddUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s0".into(),
+    ///         update: AddUpdate { amount: 1 },
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting_when("s0", Bound { lower: None, upper: Some(3) })
+    ///     .build();
+    ///
+    /// assert!(machine.exec("s0", 0, vec![b'a'; 3]));
+    /// assert!(!machine.exec("s0", 0, vec![b'a'; 4]));
+    ///
+    /// let complement = machine.clone().complement().unwrap();
+    /// assert!(complement.exec("s0", 0, vec![b'a'; 4]));
+    /// assert!(!complement.exec("s0", 0, vec![b'a'; 3]));
+    ///
+    /// // The data can only reach "s1" when it is too large to be accepted there.
+    /// let machine = MachineBuilder::<u8, u8, AddUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s1".into(),
+    ///         bound: Bound { lower: Some(10), upper: None },
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting_when("s1", Bound { lower: None, upper: Some(3) })
+    ///     .build();
+    ///
+    /// assert!(!machine.find_non_empty("s0").unwrap().contains_key("s0"));
+    /// ```
+    pub fn with_accepting_when(self, location: &str, bound: Bound<D>) -> Self
+    where
+        D: Ord + Copy + Bounded + Send + Sync + 'static,
+    {
+        self.with_accepting_condition(location, AcceptingCondition::new(bound))
+    }
+
+    /// Mark `location` as accepting only for the data meeting `predicate`.
+    ///
+    /// Unlike [with_accepting_when](MachineBuilder::with_accepting_when), the predicate is
+    /// opaque to the interval analyses, which assume it may hold for any data.
+    pub fn with_accepting_if(self, location: &str, predicate: fn(&D) -> bool) -> Self
+    where
+        D: 'static,
+    {
+        self.with_accepting_condition(location, AcceptingCondition::from_predicate(predicate))
+    }
+
+    /// Mark `location` as accepting only for the data meeting `condition`.
+    pub fn with_accepting_condition(
+        mut self,
+        location: &str,
+        condition: AcceptingCondition<D>,
+    ) -> Self {
+        trace!("mark location {} as accepting under a condition", location);
         self.accepting.insert(location.into());
+        self.accepting_conditions.insert(location.into(), condition);
+        self
+    }
+
+    /// Mark `location` as rejecting, so that every run entering it is rejected, whatever input
+    /// follows.
+    ///
+    /// Unlike a location that is merely not accepting, a rejecting location is known to be a
+    /// violation as soon as it is entered, so [exec](Machine::exec) stops early, and monitors
+    /// report a verdict without relying on the transitions out of it. A location that is both
+    /// accepting and rejecting is rejecting.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    /// use rust_efsm::monitor::{Monitor, Verdict};
+    ///
+    /// // A 'q' must never follow a 'q'.
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("idle", Transition {
+    ///         to_location: "waiting".into(),
+    ///         enable: |_, i| *i == b'q',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("idle", Transition {
+    ///         to_location: "idle".into(),
+    ///         enable: |_, i| *i != b'q',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("waiting", Transition {
+    ///         to_location: "error".into(),
+    ///         enable: |_, i| *i == b'q',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("waiting", Transition {
+    ///         to_location: "idle".into(),
+    ///         enable: |_, i| *i != b'q',
+    ///         ..Default::default()
+    ///     })
+    ///     // Even a transition back to an accepting location cannot save the run.
+    ///     .with_transition("error", Transition { to_location: "idle".into(), ..Default::default() })
+    ///     .with_accepting_all(["idle", "waiting"])
+    ///     .with_rejecting("error")
+    ///     .build();
+    ///
+    /// assert!(machine.exec("idle", 0, vec![b'q', b'a', b'q']));
+    /// assert!(!machine.exec("idle", 0, vec![b'q', b'q', b'a']));
+    ///
+    /// let mut monitor = Monitor::new("idle", 0, machine).unwrap();
+    /// assert_eq!(monitor.next(&b'q').unwrap(), Verdict::Inconclusive);
+    /// assert_eq!(monitor.next(&b'q').unwrap(), Verdict::Violated);
+    /// ```
+    pub fn with_rejecting(mut self, location: &str) -> Self {
+        trace!("mark location {} as rejecting", location);
+        self.rejecting.insert(location.into());
+        self
+    }
+
+    /// Tag `location` with `tag`, replacing any previous tag.
+    ///
+    /// Tags do not change the language of the machine. They group related locations, such as
+    /// those of one component of a larger machine, so that [graphs](crate::gviz) can draw each
+    /// group as a cluster.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_tag("idle", "client")
+    ///     .with_tag("busy", "client")
+    ///     .build();
+    ///
+    /// assert_eq!(machine.get_tags().get("idle").map(String::as_str), Some("client"));
+    /// ```
+    pub fn with_tag(mut self, location: &str, tag: &str) -> Self {
+        trace!("tag location {} with {}", location, tag);
+        self.tags.insert(location.into(), tag.into());
+        self
+    }
+
+    /// Set the policy for states without an enabled transition.
+    ///
+    /// Defaults to [NoTransitionPolicy::Reject].
+    pub fn with_no_transition_policy(mut self, policy: NoTransitionPolicy) -> Self {
+        trace!("use no transition policy {:?}", policy);
+        self.no_transition_policy = policy;
+        self
+    }
+
+    /// Require `predicate` to hold for the data whenever state `location` is entered.
+    ///
+    /// A violation stops the execution with [MachineError::AssertionViolated], which reports
+    /// `message` along with the location, the data, and the position of the input. Monitors
+    /// report it as [MonitorError::AssertionViolated](crate::monitor::MonitorError), and
+    /// [exec](Machine::exec) rejects the input sequence.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{AddUpdate, MachineBuilder, MachineError, Transition};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, AddUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s0".into(),
+    ///         update: AddUpdate { amount: 2 },
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("s0")
+    ///     .with_assertion("s0", |data| *data % 2 == 0, "the count must stay even")
+    ///     .build();
+    ///
+    /// assert!(machine.try_exec("s0", 0, vec![1, 2, 3]).unwrap());
+    ///
+    /// let Err(MachineError::AssertionViolated(violation)) = machine.try_exec("s0", 1, vec![1, 2])
+    /// else {
+    ///     panic!("expected an assertion violation");
+    /// };
+    /// assert_eq!(violation.data, "3");
+    /// assert_eq!(violation.input_index, Some(0));
+    /// ```
+    pub fn with_assertion(
+        mut self,
+        location: &str,
+        predicate: fn(&D) -> bool,
+        message: &str,
+    ) -> Self
+    where
+        D: 'static,
+    {
+        trace!("add assertion to location {}", location);
+        self.assertions
+            .entry(location.into())
+            .or_default()
+            .push(Assertion {
+                predicate: Arc::new(predicate),
+                message: message.into(),
+                describe: Arc::new(describe::<D>),
+            });
+        self
+    }
+
+    /// Count how often the guard of each transition is evaluated and how often it fires.
+    ///
+    /// See [Machine::metrics].
+    pub fn with_metrics(mut self) -> Self {
+        trace!("enable transition metrics");
+        self.metrics = true;
+        self
+    }
+
+    /// Only try the transitions that the class of each input, under `classifier`, may enable.
+    ///
+    /// For every location, a table from each class to its candidate transitions is computed
+    /// once: a transition is a candidate when the representative of the class enables it with
+    /// one of `data`. The table is only used while the data is one of `data`, such as the values
+    /// of a small data domain or the few values a machine spends most of its time in. With any
+    /// other data, every transition is tried. `classifier` must be
+    /// [faithful](crate::alphabet) to the machine, otherwise some transitions are missed.
+    /// [Machine::respects_classes] checks it.
+    ///
+    /// The table is computed again after the machine is modified.
+    ///
+    /// ```
+    /// use rust_efsm::alphabet::Partition;
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition, TransitionId};
+    ///
+    /// let mut builder = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new();
+    /// for digit in 0..10 {
+    ///     builder = builder.with_transition("s0", Transition {
+    ///         to_location: format!("digit {}", digit),
+    ///         enable: |_, i| i.is_ascii_digit(),
+    ///         ..Default::default()
+    ///     });
+    /// }
+    /// let classes = Partition::new("other", b'x').with_class("digit", b'0', |i| i.is_ascii_digit());
+    /// let machine = builder
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s1".into(),
+    ///         enable: |_, i| !i.is_ascii_digit(),
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("s1")
+    ///     .with_dispatch(classes, [0])
+    ///     .with_metrics()
+    ///     .build();
+    ///
+    /// assert!(machine.exec("s0", 0, vec![b'x']));
+    ///
+    /// // Only the transition enabled for the class "other" was tried.
+    /// let metrics = machine.metrics().unwrap();
+    /// assert_eq!(metrics[&TransitionId::new("s0", 10)].evaluated, 1);
+    /// assert_eq!(metrics[&TransitionId::new("s0", 0)].evaluated, 0);
+    ///
+    /// // The table does not cover this data, so every transition was tried.
+    /// assert!(machine.exec("s0", 5, vec![b'x']));
+    /// let metrics = machine.metrics().unwrap();
+    /// assert_eq!(metrics[&TransitionId::new("s0", 0)].evaluated, 1);
+    /// ```
+    pub fn with_dispatch<C, T>(mut self, classifier: C, data: T) -> Self
+    where
+        D: PartialEq + Send + Sync + 'static,
+        C: InputClassifier<I> + Send + Sync + 'static,
+        C::Class: Send + Sync + 'static,
+        T: IntoIterator<Item = D>,
+    {
+        trace!("dispatch inputs by class");
+        self.dispatch = Some(Dispatch::new(classifier, data.into_iter().collect()));
+        self
+    }
+
+    /// Memoize the result of each guard by transition, data, and input, keeping at most
+    /// `capacity` results.
+    ///
+    /// This pays off when guards are expensive and the same data and inputs come up again and
+    /// again, such as when monitoring over a small data domain. Every guard must then be
+    /// deterministic, since it is only evaluated the first time. Once `capacity` results are
+    /// kept, the cache is emptied and filled again. [Metrics](Machine::metrics) count cached
+    /// results as evaluations.
+    ///
+    /// The cache is shared by the clones of the machine, and emptied when the machine is
+    /// modified.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// let evaluations = Arc::new(AtomicUsize::new(0));
+    /// let counter = evaluations.clone();
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s0".into(),
+    ///         guard: Some(Arc::new(move |_, i| {
+    ///             counter.fetch_add(1, Ordering::Relaxed);
+    ///             *i != b'!'
+    ///         })),
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("s0")
+    ///     .with_guard_cache(1024)
+    ///     .build();
+    ///
+    /// assert!(machine.exec("s0", 0, b"abababab".to_vec()));
+    /// assert_eq!(evaluations.load(Ordering::Relaxed), 2);
+    /// ```
+    pub fn with_guard_cache(mut self, capacity: usize) -> Self
+    where
+        D: Hash + Eq + Send + 'static,
+        I: Hash + Eq + Clone + Send + 'static,
+    {
+        trace!("cache up to {} guard results", capacity);
+        self.guard_cache = Some(Arc::new(GuardCache::<D, I>::new(capacity)));
+        self
+    }
+
+    /// Declare every input the machine may read.
+    ///
+    /// The analyses of the [complement](Machine::complement) of a machine with
+    /// [NoTransitionPolicy::Reject] or [NoTransitionPolicy::Error] then only take the move to
+    /// its sink from the states where some input of `alphabet` enables no transition. A
+    /// transition counts as enabled for an interval of data when its bound contains the interval
+    /// and its guards hold at either end of it. Without an alphabet, the move is taken from
+    /// every state, which keeps a [Monitor](crate::monitor::Monitor) from ever reporting
+    /// [Verdict::Satisfied](crate::monitor::Verdict::Satisfied) for such a machine.
+    ///
+    /// An [InputClassifier] faithful to the machine gives a small alphabet with
+    /// [InputClassifier::alphabet].
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    /// use rust_efsm::monitor::{Monitor, Verdict};
+    ///
+    /// // Accepts the words starting with 'a'.
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "done".into(),
+    ///         enable: |_, i| *i == b'a',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("done", Transition { to_location: "done".into(), ..Default::default() })
+    ///     .with_accepting("done")
+    ///     .with_alphabet([b'a', b'b'])
+    ///     .build();
+    ///
+    /// let mut monitor = Monitor::new("s0", 0, machine).unwrap();
+    /// assert_eq!(monitor.next(&b'a').unwrap(), Verdict::Satisfied);
+    /// ```
+    pub fn with_alphabet<A>(mut self, alphabet: A) -> Self
+    where
+        A: IntoIterator<Item = I>,
+        I: Clone + Send + Sync + 'static,
+    {
+        let alphabet: Vec<I> = alphabet.into_iter().collect();
+        trace!("declare {} inputs", alphabet.len());
+        self.alphabet = Some(Arc::new(move || alphabet.clone()));
         self
     }
 
     /// Create and return a new machine from the current specification.
     pub fn build(self) -> Machine<D, I, U> {
-        info!(
+        trace!(
             "build machine with {} locations",
             self.locations.keys().len()
         );
-        Machine::new(self.locations, self.accepting)
+        let mut machine = Machine::new(self.locations, self.accepting, self.no_transition_policy);
+        machine.rejecting = self.rejecting;
+        machine.accepting_conditions = self.accepting_conditions;
+        machine.assertions = self.assertions;
+        machine.tags = self.tags;
+        if self.metrics {
+            machine.metrics = Some(MetricsTable::default());
+        }
+        if let Some(dispatch) = self.dispatch {
+            dispatch
+                .table
+                .get_or_init(|| dispatch.index(&machine.locations));
+            machine.dispatch = Some(dispatch);
+        }
+        machine.guard_cache = self.guard_cache;
+        machine.alphabet = self.alphabet;
+        machine
+    }
+}
+
+// The 64 bit FNV-1a hash, used where hashes must not change between builds.
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
     }
 }
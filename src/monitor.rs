@@ -1,9 +1,28 @@
-use crate::bound::Bound;
-use crate::machine::{Machine, State, Update};
-use num::Bounded;
-use std::collections::HashMap;
+//! # Monitor Module
+//!
+//! This module provides the [Monitor] type for runtime verification of a [Machine] against a
+//! stream of inputs.
+//!
+//! Building a monitor analyzes its machine once. Many sessions can then be monitored at once by
+//! [MonitorHandles](MonitorHandle) sharing that work, which a [MonitorPool] hands out and reuses.
+
+use crate::analysis::{Approximation, CancellationToken};
+use crate::analyzed::AnalyzedMachine;
+use crate::bound::{Bound, BoundSet};
+use crate::json::quote;
+use crate::log::{info, span, trace, warning, LARGE_STATE_SET};
+use crate::machine::{
+    AssertionViolation, Classification, Machine, MachineError, State, Successor, TransitionId,
+    Update,
+};
+use num::{Bounded, CheckedSub, Integer};
+#[cfg(feature = "parallel")]
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
 use std::fmt;
 use std::hash::Hash;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
 
 /// A monitor for observing and verifying properties of a machine.
 ///
@@ -30,6 +49,7 @@ use std::hash::Hash;
 ///         enable: |_, _| true,
 ///         bound: Bound::unbounded(),
 ///         update: AddUpdate { amount: 1 },
+///         ..Default::default()
 ///     })
 ///     .with_accepting("running")
 ///     .build();
@@ -46,13 +66,449 @@ where
 {
     prover: PartialMonitor<D, I, U>,
     falsifier: PartialMonitor<D, I, U>,
+    start: State<D>,
+    // The states the system may be in, when it was started from several hypotheses.
+    hypotheses: Option<Vec<State<D>>>,
+    initial_hypotheses: Option<Vec<State<D>>>,
+    // The locations the system may be in after skipped inputs, with an interval of its data.
+    uncertain: Uncertain<D>,
+    steps: usize,
+    mode: MonitorMode,
+    initial_verdict: Verdict,
+    verdict: Verdict,
+    metrics: MonitorMetrics,
+    measure_latency: bool,
+    // The timestamp of the first input passed to next_at, in nanoseconds.
+    first_at: Option<i128>,
+    // Whether swapping in a machine whose analysis may miss intervals is refused.
+    require_sound: bool,
+    // Whether a verdict reached by both the prover and the falsifier is an error.
+    require_consistent: bool,
+    event_log: Option<EventLog<D, I>>,
+}
+
+/// Measurements of the work done by a [Monitor].
+///
+/// See [Monitor::metrics].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MonitorMetrics {
+    /// The number of inputs processed.
+    pub events: usize,
+    /// The offset of the input that produced the first verdict.
+    pub verdict_offset: Option<usize>,
+    /// The time from the first to the verdict-producing input passed to [Monitor::next_at],
+    /// measured by their timestamps.
+    pub time_to_verdict: Option<Duration>,
+    /// The total time spent processing inputs, when [enabled](Monitor::with_latency_metrics).
+    pub total_latency: Option<Duration>,
+    /// The longest time spent processing one input, when
+    /// [enabled](Monitor::with_latency_metrics).
+    pub max_latency: Option<Duration>,
+}
+
+impl MonitorMetrics {
+    /// Returns the average time spent processing one input, if latencies were measured.
+    pub fn mean_latency(&self) -> Option<Duration> {
+        let events = u32::try_from(self.events)
+            .ok()
+            .filter(|events| *events > 0)?;
+        self.total_latency.map(|total| total / events)
+    }
+}
+
+/// An input processed by a [Monitor], as kept in its [event log](Monitor::with_event_log).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EventLogEntry<D, I> {
+    /// The offset of the input.
+    pub offset: usize,
+    /// The input.
+    pub input: I,
+    /// The states the system may have been in when the input was read.
+    pub states: Vec<State<D>>,
+    /// The transition the input enabled, when the monitor tracked a single state and the
+    /// falsifier took an explicit transition.
+    pub transition: Option<TransitionId>,
+    /// The verdict returned for the input.
+    pub verdict: Verdict,
+}
+
+impl<D: fmt::Display, I: fmt::Debug> EventLogEntry<D, I> {
+    // Formats the entry as a JSON object.
+    fn to_json(&self) -> String {
+        let states: Vec<String> = self
+            .states
+            .iter()
+            .map(|state| {
+                format!(
+                    r#"{{"location":{},"data":{}}}"#,
+                    quote(&state.location),
+                    quote(&state.data.to_string())
+                )
+            })
+            .collect();
+        let transition = match &self.transition {
+            Some(id) => quote(&id.to_string()),
+            None => "null".into(),
+        };
+
+        format!(
+            r#"{{"offset":{},"input":{},"states":[{}],"transition":{},"verdict":{}}}"#,
+            self.offset,
+            quote(&format!("{:?}", self.input)),
+            states.join(","),
+            transition,
+            quote(&self.verdict.to_string())
+        )
+    }
+}
+
+// The last inputs processed by a monitor, in a ring buffer.
+struct EventLog<D, I> {
+    capacity: usize,
+    entries: VecDeque<EventLogEntry<D, I>>,
+    dump: Option<Dump<D, I>>,
+}
+
+// Receives an event log as JSON on each conclusive verdict, with the function formatting it.
+type Dump<D, I> = (fn(&str), fn(&VecDeque<EventLogEntry<D, I>>) -> String);
+
+impl<D, I> EventLog<D, I> {
+    fn push(&mut self, entry: EventLogEntry<D, I>) {
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+
+        if let Some((dump, to_json)) = self.dump {
+            if self
+                .entries
+                .back()
+                .is_some_and(|e| e.verdict.is_conclusive())
+            {
+                dump(&to_json(&self.entries));
+            }
+        }
+    }
+}
+
+// Formats the entries of an event log as a JSON array.
+fn entries_to_json<D: fmt::Display, I: fmt::Debug>(
+    entries: &VecDeque<EventLogEntry<D, I>>,
+) -> String {
+    let entries: Vec<String> = entries.iter().map(EventLogEntry::to_json).collect();
+    format!("[{}]", entries.join(","))
+}
+
+/// A point in time at which an input was observed, used by [Monitor::next_at].
+pub trait Timestamp {
+    /// Returns the number of nanoseconds since an arbitrary epoch shared by every timestamp of
+    /// this type.
+    fn as_nanos(&self) -> i128;
+}
+
+impl Timestamp for Instant {
+    fn as_nanos(&self) -> i128 {
+        // Instants have no epoch, so the first one converted in this process serves as one.
+        static EPOCH: OnceLock<Instant> = OnceLock::new();
+        let epoch = *EPOCH.get_or_init(|| *self);
+
+        match self.checked_duration_since(epoch) {
+            Some(after) => after.as_nanos() as i128,
+            None => -(epoch.duration_since(*self).as_nanos() as i128),
+        }
+    }
+}
+
+impl Timestamp for SystemTime {
+    fn as_nanos(&self) -> i128 {
+        match self.duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(after) => after.as_nanos() as i128,
+            Err(before) => -(before.duration().as_nanos() as i128),
+        }
+    }
+}
+
+/// A duration since the start of the trace, for inputs timestamped by a custom clock.
+impl Timestamp for Duration {
+    fn as_nanos(&self) -> i128 {
+        Duration::as_nanos(self) as i128
+    }
+}
+
+/// Decides what a [Monitor] does after reaching a verdict.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum MonitorMode {
+    /// Keep processing inputs from the state that reached the verdict.
+    #[default]
+    Continue,
+    /// Restart from the initial state after every verdict, so that recurring properties report
+    /// every violation instead of only the first.
+    ResetOnVerdict,
+}
+
+/// The verdict of a [Monitor] on the inputs seen so far.
+///
+/// Verdicts follow the three-valued semantics of runtime verification: [Verdict::Satisfied] and
+/// [Verdict::Violated] are final, because every extension of the inputs is accepted or every
+/// extension is rejected, while [Verdict::Inconclusive] means the inputs do not decide the
+/// property yet.
+///
+/// ```
+/// use rust_efsm::monitor::Verdict;
+///
+/// assert!(Verdict::Violated.is_conclusive());
+/// assert_eq!(Verdict::Satisfied.to_string(), "satisfied");
+/// assert_eq!(!Verdict::Satisfied, Verdict::Violated);
+/// assert_eq!(Verdict::from(None), Verdict::Inconclusive);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Verdict {
+    /// Every extension of the inputs is accepted.
+    Satisfied,
+    /// No extension of the inputs is accepted.
+    Violated,
+    /// Some extensions of the inputs are accepted and some are not.
+    #[default]
+    Inconclusive,
+}
+
+impl Verdict {
+    /// Checks if the verdict is final, which is when it is not [Verdict::Inconclusive].
+    pub fn is_conclusive(&self) -> bool {
+        *self != Verdict::Inconclusive
+    }
+
+    /// Returns the verdict of the conjunction of two properties with these verdicts.
+    pub fn and(self, other: Verdict) -> Verdict {
+        match (self, other) {
+            (Verdict::Violated, _) | (_, Verdict::Violated) => Verdict::Violated,
+            (Verdict::Satisfied, Verdict::Satisfied) => Verdict::Satisfied,
+            _ => Verdict::Inconclusive,
+        }
+    }
+
+    /// Returns the verdict of the disjunction of two properties with these verdicts.
+    pub fn or(self, other: Verdict) -> Verdict {
+        match (self, other) {
+            (Verdict::Satisfied, _) | (_, Verdict::Satisfied) => Verdict::Satisfied,
+            (Verdict::Violated, Verdict::Violated) => Verdict::Violated,
+            _ => Verdict::Inconclusive,
+        }
+    }
+}
+
+/// Swaps [Verdict::Satisfied] and [Verdict::Violated], which is the verdict of the negated
+/// property.
+impl std::ops::Not for Verdict {
+    type Output = Verdict;
+
+    fn not(self) -> Verdict {
+        match self {
+            Verdict::Satisfied => Verdict::Violated,
+            Verdict::Violated => Verdict::Satisfied,
+            Verdict::Inconclusive => Verdict::Inconclusive,
+        }
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Verdict::Satisfied => write!(f, "satisfied"),
+            Verdict::Violated => write!(f, "violated"),
+            Verdict::Inconclusive => write!(f, "inconclusive"),
+        }
+    }
+}
+
+/// Converts the encoding used before [Verdict], where `None` is inconclusive.
+impl From<Option<bool>> for Verdict {
+    fn from(verdict: Option<bool>) -> Self {
+        match verdict {
+            Some(true) => Verdict::Satisfied,
+            Some(false) => Verdict::Violated,
+            None => Verdict::Inconclusive,
+        }
+    }
+}
+
+impl From<Verdict> for Option<bool> {
+    fn from(verdict: Verdict) -> Self {
+        match verdict {
+            Verdict::Satisfied => Some(true),
+            Verdict::Violated => Some(false),
+            Verdict::Inconclusive => None,
+        }
+    }
+}
+
+/// The outcome of processing a chunk of inputs with [Monitor::next_chunk].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ChunkOutcome {
+    /// The input at `index` in the chunk reached `verdict`, and the inputs after it were not
+    /// processed.
+    Concluded {
+        /// The index of the input in the chunk.
+        index: usize,
+        /// The verdict reached.
+        verdict: Verdict,
+    },
+    /// Every input of the chunk was processed without reaching a verdict.
+    Inconclusive {
+        /// The offset of the next input, as returned by [Monitor::offset].
+        offset: usize,
+    },
 }
 
 #[derive(Debug)]
 /// Errors that can occur during monitor operation.
+///
+/// ```
+/// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+/// use rust_efsm::monitor::{Monitor, MonitorError};
+///
+/// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+///     .with_transition("s0", Transition {
+///         to_location: "s0".into(),
+///         name: Some("stay".into()),
+///         ..Default::default()
+///     })
+///     .with_transition("s0", Transition { to_location: "s1".into(), ..Default::default() })
+///     .with_accepting("s1")
+///     .build();
+///
+/// let mut monitor = Monitor::new("s0", 0, machine).unwrap();
+/// let Err(MonitorError::TransitionFailed(reason)) = monitor.next(&0) else {
+///     panic!("both transitions are enabled");
+/// };
+/// assert!(reason.contains("stay to") && reason.contains("s0#1 to"));
+/// ```
 pub enum MonitorError {
+    /// The monitored machine did not reach exactly one state. The message names the
+    /// transitions involved by their [labels](Machine::transition_label).
     TransitionFailed(String),
+    /// The monitor could not be constructed from the machine.
     ConstructionFailed(String),
+    /// The analysis of the machine may have missed states that lead to acceptance, so the
+    /// verdicts of the monitor could be wrong.
+    Undecidable(Approximation),
+    /// The data entering a location violated one of its assertions.
+    AssertionViolated(AssertionViolation),
+    /// The prover and the falsifier both reached a verdict at once, which only an unsound
+    /// analysis or a malformed machine allows. See [Monitor::require_consistent].
+    Inconsistent(Inconsistency),
+}
+
+impl fmt::Display for MonitorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MonitorError::TransitionFailed(reason) => write!(f, "transition failed: {}", reason),
+            MonitorError::ConstructionFailed(reason) => {
+                write!(f, "construction failed: {}", reason)
+            }
+            MonitorError::AssertionViolated(violation) => write!(f, "{}", violation),
+            MonitorError::Undecidable(approximation) => {
+                write!(f, "undecidable: the analysis is {}", approximation)
+            }
+            MonitorError::Inconsistent(inconsistency) => write!(f, "{}", inconsistency),
+        }
+    }
+}
+
+impl std::error::Error for MonitorError {}
+
+/// Describes the states in which the prover and the falsifier of a [Monitor] both reached a
+/// verdict.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Inconsistency {
+    /// The position of the input that led to the verdicts, or `None` for the initial state.
+    pub input_index: Option<usize>,
+    /// The states of the prover, formatted with [Debug](fmt::Debug).
+    pub prover: String,
+    /// The states of the falsifier, formatted with [Debug](fmt::Debug).
+    pub falsifier: String,
+    /// How the analysis of the machine approximates the states that lead to acceptance.
+    pub approximation: Approximation,
+}
+
+impl fmt::Display for Inconsistency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "both satisfied and violated")?;
+        if let Some(idx) = self.input_index {
+            write!(f, " after input {}", idx)?;
+        }
+        write!(
+            f,
+            ": prover in {}, falsifier in {}, the analysis is {}",
+            self.prover, self.falsifier, self.approximation
+        )
+    }
+}
+
+// The locations reached with data known only up to an interval.
+type Uncertain<D> = BTreeMap<String, Bound<D>>;
+
+// Widens the interval of `location` in `uncertain` to contain `interval`.
+fn merge_interval<D>(uncertain: &mut Uncertain<D>, location: String, interval: Bound<D>)
+where
+    D: Ord + Copy + Bounded,
+{
+    match uncertain.get_mut(&location) {
+        Some(hull) => hull.make_contain(&interval),
+        None => {
+            uncertain.insert(location, interval);
+        }
+    }
+}
+
+// Moves the intervals of `uncertain` that hold a single value into `states` as exact states.
+fn settle<D>(states: &mut Vec<State<D>>, uncertain: &mut Uncertain<D>)
+where
+    D: Ord + Copy + Bounded,
+{
+    uncertain.retain(|location, interval| {
+        let (lower, upper) = interval.as_explicit();
+        if lower == upper {
+            states.push(State {
+                location: location.clone(),
+                data: lower,
+            });
+        }
+        lower != upper
+    });
+
+    states.sort();
+    states.dedup();
+}
+
+// Returns the distance from `data` to the nearest endpoint of `bound` that is not the minimum or
+// maximum value of the data type.
+fn margin<D>(bound: &Bound<D>, data: D) -> D
+where
+    D: Ord + Copy + Bounded + CheckedSub,
+{
+    let (lower, upper) = bound.as_explicit();
+    let below = (lower != D::min_value()).then(|| data.checked_sub(&lower));
+    let above = (upper != D::max_value()).then(|| upper.checked_sub(&data));
+    below
+        .into_iter()
+        .chain(above)
+        .map(|distance| distance.unwrap_or_else(D::max_value))
+        .min()
+        .unwrap_or_else(D::max_value)
+}
+
+// Records the position of the input that led to an assertion violation.
+fn at_input(error: MonitorError, input_index: usize) -> MonitorError {
+    match error {
+        MonitorError::AssertionViolated(violation) => {
+            MonitorError::AssertionViolated(AssertionViolation {
+                input_index: Some(input_index),
+                ..violation
+            })
+        }
+        error => error,
+    }
 }
 
 impl<D, I, U> Monitor<D, I, U>
@@ -64,6 +520,9 @@ where
     /// The monitor consists of a prover (looking for property satisfaction) and a falsifier
     /// (looking for property violation).
     ///
+    /// A warning is logged when [Machine::classify] reports that the property is not
+    /// monitorable, since such a monitor may never reach a verdict.
+    ///
     /// # Arguments
     ///
     /// * `location` - The initial location in the machine
@@ -92,192 +551,2560 @@ where
         I: Clone,
         U: Clone + Update<D = D>,
     {
+        if machine.classify(location) == Classification::NonMonitorable {
+            warning!(location, "the property is not monitorable");
+        }
+
         let prover = PartialMonitor::prove_from(location, data, machine.clone())?;
         let falsifier = PartialMonitor::falsify_from(location, data, machine)?;
 
-        Ok(Monitor { prover, falsifier })
+        // The initial state alone may already decide the property.
+        let initial_verdict = if prover.is_empty() {
+            Verdict::Satisfied
+        } else if falsifier.is_empty() {
+            Verdict::Violated
+        } else {
+            Verdict::Inconclusive
+        };
+
+        Ok(Monitor {
+            prover,
+            falsifier,
+            start: State {
+                location: location.into(),
+                data,
+            },
+            hypotheses: None,
+            initial_hypotheses: None,
+            uncertain: Uncertain::new(),
+            steps: 0,
+            mode: MonitorMode::default(),
+            metrics: MonitorMetrics::default(),
+            measure_latency: false,
+            first_at: None,
+            initial_verdict,
+            verdict: initial_verdict,
+            require_sound: false,
+            require_consistent: false,
+            event_log: None,
+        })
     }
 
-    /// Processes the next input and determines if a verdict can be reached.
+    /// Creates a new monitor for an analyzed machine, starting at its initial location with
+    /// `data`.
     ///
-    /// The monitor uses both the prover and falsifier to determine if the property is
-    /// satisfied (true), violated (false), or still inconclusive (None).
+    /// The falsifier reuses the non-empty intervals of the [analysis](crate::analyzed::MachineAnalysis) instead of
+    /// computing them again.
     ///
-    /// # Arguments
+    /// ```
+    /// use rust_efsm::analyzed::AnalysisConfig;
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    /// use rust_efsm::monitor::{Monitor, Verdict};
     ///
-    /// * `input` - The input to process
+    /// // Once an 'a' is read, every input must be an 'a'.
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s0".into(),
+    ///         enable: |_, i| *i != b'a',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s1".into(),
+    ///         enable: |_, i| *i == b'a',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("s1", Transition {
+    ///         to_location: "s1".into(),
+    ///         enable: |_, i| *i == b'a',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("s1", Transition {
+    ///         to_location: "error".into(),
+    ///         enable: |_, i| *i != b'a',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("error", Transition { to_location: "error".into(), ..Default::default() })
+    ///     .with_accepting_all(["s0", "s1"])
+    ///     .build();
     ///
-    /// # Returns
+    /// let analyzed = machine.analyze(&AnalysisConfig::new("s0")).unwrap();
+    /// let mut monitor = Monitor::from_analyzed(analyzed, 0).unwrap();
+    /// assert_eq!(monitor.next(&b'b').unwrap(), Verdict::Inconclusive);
+    /// assert_eq!(monitor.next(&b'a').unwrap(), Verdict::Inconclusive);
+    /// assert_eq!(monitor.next(&b'b').unwrap(), Verdict::Violated);
+    /// ```
+    pub fn from_analyzed(analyzed: AnalyzedMachine<D, I, U>, data: D) -> Result<Self, MonitorError>
+    where
+        D: Eq + Hash + Clone + fmt::Debug + Bounded + Ord + Copy + fmt::Display,
+        I: Clone,
+        U: Clone + Update<D = D>,
+    {
+        let (machine, analysis) = analyzed.into_parts();
+        Monitor::new(&analysis.initial, data, machine)
+    }
+
+    /// Creates a new monitor for a system that may be in any of the given states.
     ///
-    /// * `Ok(Some(true))` - Property is satisfied (proven)
-    /// * `Ok(Some(false))` - Property is violated (falsified)
-    /// * `Ok(None)` - No verdict yet (inconclusive)
-    /// * `Err(MonitorError)` - An error occurred during processing
+    /// This is useful when monitoring starts in the middle of a stream, and the current state of
+    /// the system is unknown. Each input keeps only the successors of the hypotheses that have a
+    /// transition for it, and a verdict is only reached once every remaining hypothesis agrees.
+    /// Processing an input that no hypothesis can explain fails with
+    /// [MonitorError::TransitionFailed].
     ///
-    /// # Examples
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, State, Transition};
+    /// use rust_efsm::monitor::{Monitor, Verdict};
     ///
-    /// ```ignore
-    /// use rust_efsm::machine::{Machine, MachineBuilder, AddUpdate};
-    /// use rust_efsm::monitor::Monitor;
+    /// // After a 'b', another 'b' must never follow; only "idle" can read an 'a'.
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("idle", Transition {
+    ///         to_location: "idle".into(),
+    ///         enable: |_, i| *i == b'a',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("idle", Transition {
+    ///         to_location: "busy".into(),
+    ///         enable: |_, i| *i == b'b',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("busy", Transition {
+    ///         to_location: "idle".into(),
+    ///         enable: |_, i| *i == b'c',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("busy", Transition {
+    ///         to_location: "error".into(),
+    ///         enable: |_, i| *i == b'b',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("error", Transition { to_location: "error".into(), ..Default::default() })
+    ///     .with_accepting_all(["idle", "busy"])
+    ///     .build();
     ///
-    /// // Assume we have a machine and monitor already set up
-    /// let mut monitor = Monitor::new("start", 0, machine).unwrap();
+    /// let mut monitor = Monitor::new_with_states(vec![("idle", 0), ("busy", 0)], machine).unwrap();
     ///
-    /// // Process an input and check for a verdict
-    /// match monitor.next(&42) {
-    ///     Ok(Some(true)) => println!("Property satisfied!"),
-    ///     Ok(Some(false)) => println!("Property violated!"),
-    ///     Ok(None) => println!("Still inconclusive..."),
-    ///     Err(e) => println!("Error: {:?}", e),
-    /// }
+    /// // Only "idle" can read an 'a', so the hypotheses agree from here on.
+    /// assert_eq!(monitor.next(&b'a').unwrap(), Verdict::Inconclusive);
+    /// assert_eq!(monitor.hypotheses(), &[State { location: "idle".into(), data: 0 }]);
+    ///
+    /// assert_eq!(monitor.next(&b'b').unwrap(), Verdict::Inconclusive);
+    /// assert_eq!(monitor.next(&b'b').unwrap(), Verdict::Violated);
     /// ```
-    pub fn next(&mut self, input: &I) -> Result<Option<bool>, MonitorError>
+    pub fn new_with_states<S>(
+        states: Vec<(S, D)>,
+        machine: Machine<D, I, U>,
+    ) -> Result<Self, MonitorError>
     where
+        S: AsRef<str>,
         D: Eq + Hash + Clone + fmt::Debug + Bounded + Ord + Copy + fmt::Display,
         I: Clone,
         U: Clone + Update<D = D>,
     {
-        let mut verdict = None;
-        if self.prover.next(input)? {
-            // Prover found satisfaction.
-            verdict = Some(true);
-        } else if self.falsifier.next(input)? {
-            // Falsifier found violation.
-            verdict = Some(false);
+        let mut hypotheses: Vec<State<D>> = states
+            .into_iter()
+            .map(|(location, data)| State {
+                location: location.as_ref().into(),
+                data,
+            })
+            .collect();
+        hypotheses.sort();
+        hypotheses.dedup();
+
+        let Some(first) = hypotheses.first().cloned() else {
+            return Err(MonitorError::ConstructionFailed(
+                "at least one initial state is required".into(),
+            ));
+        };
+
+        let mut monitor = Monitor::new(&first.location, first.data, machine)?;
+        for state in &hypotheses[1..] {
+            monitor.prover.include_start(&state.location)?;
+            monitor.falsifier.include_start(&state.location)?;
         }
 
-        Ok(verdict)
+        monitor.initial_verdict = monitor.agreed_verdict(&hypotheses, &Uncertain::new());
+        monitor.verdict = monitor.initial_verdict;
+        monitor.initial_hypotheses = Some(hypotheses.clone());
+        monitor.hypotheses = Some(hypotheses);
+        Ok(monitor)
     }
-}
-
-/// A partial monitor that tracks one aspect of property verification.
-///
-/// A partial monitor is used internally by the main Monitor to track either
-/// property satisfaction (prove) or property violation (falsify).
-///
-/// # Type Parameters
-///
-/// * `D` - The data type for machine states
-/// * `I` - The input type for the machine
-/// * `U` - The update type with update function
-struct PartialMonitor<D, I, U> {
-    state: State<D>,
-    machine: Machine<D, I, U>,
-    non_empty_states: HashMap<String, Bound<D>>,
-}
 
-impl<D, I, U> PartialMonitor<D, I, U> {
-    /// Creates a prover monitor from the given location, data, and machine.
+    /// Sets what the monitor does after reaching a verdict.
     ///
-    /// A prover monitor is used to detect when a property is satisfied.
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    /// use rust_efsm::monitor::{Monitor, MonitorMode, Verdict};
     ///
-    /// # Arguments
+    /// // Every request 'q' must be acknowledged by an 'a' before the next request.
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("idle", Transition {
+    ///         to_location: "idle".into(),
+    ///         enable: |_, i| *i != b'q',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("idle", Transition {
+    ///         to_location: "waiting".into(),
+    ///         enable: |_, i| *i == b'q',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("waiting", Transition {
+    ///         to_location: "idle".into(),
+    ///         enable: |_, i| *i == b'a',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("waiting", Transition {
+    ///         to_location: "waiting".into(),
+    ///         enable: |_, i| *i != b'a' && *i != b'q',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("waiting", Transition {
+    ///         to_location: "error".into(),
+    ///         enable: |_, i| *i == b'q',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("error", Transition { to_location: "error".into(), ..Default::default() })
+    ///     .with_accepting_all(["idle", "waiting"])
+    ///     .build();
     ///
-    /// * `location` - The initial location in the machine
-    /// * `data` - The initial data value
-    /// * `machine` - The machine to monitor
+    /// let mut monitor = Monitor::new("idle", 0, machine)
+    ///     .unwrap()
+    ///     .with_mode(MonitorMode::ResetOnVerdict);
     ///
-    /// # Returns
+    /// let verdicts = monitor.run(b"qaqqaqxq").unwrap();
+    /// assert_eq!(verdicts, vec![(3, Verdict::Violated), (7, Verdict::Violated)]);
+    /// ```
+    pub fn with_mode(mut self, mode: MonitorMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Decides when a verdict is reached with the
+    /// [refined analysis](Machine::find_non_empty_refined) of the machine, splitting undecided
+    /// intervals for at most `rounds` rounds. This tells apart data that the single interval per
+    /// location of the default analysis merges, such as data between two guards.
     ///
-    /// A new `PartialMonitor` instance or an error if construction fails
+    /// It is meant to be called right after the monitor is created, and restarts its verdict.
     ///
-    /// # Examples
+    /// ```
+    /// use rust_efsm::bound::Bound;
+    /// use rust_efsm::machine::{AddUpdate, MachineBuilder, Transition};
+    /// use rust_efsm::monitor::{Monitor, Verdict};
     ///
-    /// ```ignore
-    /// let prover = PartialMonitor::prove_from("start", 0, machine)?;
+    /// // Count up to a value outside of [4, 9].
+    /// let machine = MachineBuilder::<u8, u8, AddUpdate<u8>>::new()
+    ///     .with_transition("count", Transition {
+    ///         to_location: "check".into(),
+    ///         bound: Bound { lower: None, upper: Some(100) },
+    ///         update: AddUpdate { amount: 1 },
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("check", Transition {
+    ///         to_location: "done".into(),
+    ///         bound: Bound { lower: None, upper: Some(3) },
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("check", Transition {
+    ///         to_location: "done".into(),
+    ///         bound: Bound { lower: Some(10), upper: None },
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("done")
+    ///     .build();
+    ///
+    /// let monitor = Monitor::new("count", 5, machine).unwrap();
+    /// assert_eq!(monitor.verdict(), Verdict::Inconclusive);
+    ///
+    /// let monitor = monitor.with_refinement(8);
+    /// assert_eq!(monitor.verdict(), Verdict::Violated);
     /// ```
-    fn prove_from(location: &str, data: D, machine: Machine<D, I, U>) -> Result<Self, MonitorError>
+    pub fn with_refinement(mut self, rounds: usize) -> Self
     where
-        D: Eq + Hash + Clone + fmt::Debug + Bounded + Ord + Copy + fmt::Display,
-        U: Clone + Update<D = D>,
+        D: Integer + Copy + Bounded,
+        U: Update<D = D>,
     {
-        let complement = machine
-            .complement()
-            .map_err(|e| MonitorError::ConstructionFailed(format!("complement failed: {}", e)))?;
+        let starts = match &self.initial_hypotheses {
+            Some(hypotheses) => hypotheses.clone(),
+            None => vec![self.start.clone()],
+        };
 
-        PartialMonitor::falsify_from(location, data, complement)
+        self.prover.refine(&starts, rounds);
+        self.falsifier.refine(&starts, rounds);
+
+        self.initial_verdict = self.agreed_verdict(&starts, &Uncertain::new());
+        self.verdict = self.initial_verdict;
+        self
     }
 
-    /// Creates a falsifier monitor from the given location, data, and machine.
-    ///
-    /// A falsifier monitor is used to detect when a property is violated.
+    /// Returns what the monitor does after reaching a verdict.
+    pub fn mode(&self) -> MonitorMode {
+        self.mode
+    }
+
+    /// Tolerates up to `k` violations before reporting one, as for a system expected to recover
+    /// from occasional faults.
     ///
-    /// # Arguments
+    /// The monitor is switched to [MonitorMode::ResetOnVerdict], so that it restarts after each
+    /// violation. The first `k` violations are only [observed](Tolerant::violations), with an
+    /// inconclusive verdict, and the next one is reported as final.
     ///
-    /// * `location` - The initial location in the machine
-    /// * `data` - The initial data value
-    /// * `machine` - The machine to monitor
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    /// use rust_efsm::monitor::{Monitor, Verdict, Verify};
     ///
-    /// # Returns
+    /// // No two requests 'q' without an acknowledgement 'a' in between.
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("idle", Transition {
+    ///         to_location: "idle".into(),
+    ///         enable: |_, i| *i != b'q',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("idle", Transition {
+    ///         to_location: "waiting".into(),
+    ///         enable: |_, i| *i == b'q',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("waiting", Transition {
+    ///         to_location: "idle".into(),
+    ///         enable: |_, i| *i == b'a',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("waiting", Transition {
+    ///         to_location: "waiting".into(),
+    ///         enable: |_, i| *i != b'a' && *i != b'q',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("waiting", Transition {
+    ///         to_location: "error".into(),
+    ///         enable: |_, i| *i == b'q',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting_all(["idle", "waiting"])
+    ///     .with_rejecting("error")
+    ///     .build();
     ///
-    /// A new `PartialMonitor` instance or an error if construction fails
+    /// let mut monitor = Monitor::new("idle", 0, machine).unwrap().with_tolerance(1);
     ///
-    /// # Examples
+    /// let verdicts = b"qaqqaqxq"
+    ///     .iter()
+    ///     .map(|i| monitor.next(i).unwrap())
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(verdicts[3], Verdict::Inconclusive);
+    /// assert_eq!(verdicts[7], Verdict::Violated);
     ///
-    /// ```ignore
-    /// let falsifier = PartialMonitor::falsify_from("start", 0, machine)?;
+    /// let offsets = monitor.violations().iter().map(|v| v.offset).collect::<Vec<_>>();
+    /// assert_eq!(offsets, vec![3, 7]);
     /// ```
-    fn falsify_from(
-        location: &str,
-        data: D,
-        machine: Machine<D, I, U>,
-    ) -> Result<Self, MonitorError>
-    where
-        D: Eq + Hash + Clone + fmt::Debug + Bounded + Ord + Copy + fmt::Display,
-        U: Clone + Update<D = D>,
-    {
-        let location = String::from(location);
+    pub fn with_tolerance(self, k: usize) -> Tolerant<Self> {
+        Tolerant {
+            monitor: self.with_mode(MonitorMode::ResetOnVerdict),
+            tolerance: k,
+            offset: 0,
+            violations: Vec::new(),
+            observer: None,
+        }
+    }
 
-        // Find all states
-        let non_empty_states = machine
-            .find_non_empty(&location)
-            .map_err(|e| MonitorError::ConstructionFailed(format!("partial monitor: {}", e)))?;
+    /// Returns how the analysis of the machine, which decides when a verdict is reached,
+    /// approximates the states that lead to acceptance.
+    ///
+    /// An [over-approximate](Approximation::Over) analysis may only delay verdicts. An analysis
+    /// that may miss states can also report a verdict that a later input contradicts.
+    pub fn approximation(&self) -> Approximation {
+        self.prover.approximation.and(self.falsifier.approximation)
+    }
 
-        // Construct the initial state of the monitor.
-        let state = State { location, data };
+    /// Checks that every verdict of this monitor is sound, because the analysis of the machine
+    /// cannot [miss](Approximation::includes_all) states that lead to acceptance.
+    ///
+    /// Returns [MonitorError::Undecidable] otherwise. The check is repeated by
+    /// [swap_machine](Monitor::swap_machine), which then refuses the new machine.
+    ///
+    /// ```
+    /// use rust_efsm::analysis::Approximation;
+    /// use rust_efsm::bound::Bound;
+    /// use rust_efsm::machine::{AddUpdate, IdentityUpdate, MachineBuilder, Transition};
+    /// use rust_efsm::monitor::{Monitor, MonitorError};
+    ///
+    /// // Never read a zero.
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("ok", Transition {
+    ///         to_location: "ok".into(),
+    ///         enable: |_, i| *i != 0,
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("ok", Transition {
+    ///         to_location: "bad".into(),
+    ///         enable: |_, i| *i == 0,
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("bad", Transition { to_location: "bad".into(), ..Default::default() })
+    ///     .with_accepting("ok")
+    ///     .build();
+    ///
+    /// let monitor = Monitor::new("ok", 0, machine).unwrap().require_sound().unwrap();
+    /// assert!(monitor.approximation().includes_all());
+    ///
+    /// // Count up to 200 before accepting, too many steps for the analysis to follow.
+    /// let counter = MachineBuilder::<u8, u8, AddUpdate<u8>>::new()
+    ///     .with_transition("count", Transition {
+    ///         to_location: "count".into(),
+    ///         bound: Bound { lower: None, upper: Some(199) },
+    ///         update: AddUpdate { amount: 1 },
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("count", Transition {
+    ///         to_location: "done".into(),
+    ///         bound: Bound { lower: Some(200), upper: Some(200) },
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("done")
+    ///     .build();
+    ///
+    /// let monitor = Monitor::new("count", 0, counter).unwrap();
+    /// assert!(!monitor.approximation().includes_all());
+    /// assert!(matches!(monitor.require_sound(), Err(MonitorError::Undecidable(_))));
+    /// ```
+    pub fn require_sound(mut self) -> Result<Self, MonitorError> {
+        let approximation = self.approximation();
+        if !approximation.includes_all() {
+            return Err(MonitorError::Undecidable(approximation));
+        }
 
-        Ok(PartialMonitor {
-            state,
-            machine,
-            non_empty_states,
-        })
+        self.require_sound = true;
+        Ok(self)
     }
 
-    fn next(&mut self, input: &I) -> Result<bool, MonitorError>
-    where
-        D: Eq + Hash + Clone + fmt::Debug + Bounded + Ord + Copy + fmt::Display,
+    /// Checks on every input that the prover and the falsifier do not both reach a verdict, as a
+    /// debugging aid for machines whose verdicts look wrong.
+    ///
+    /// Both can only be conclusive at once when the analysis of the machine is unsound or the
+    /// machine is malformed, such as by an [update](Update) whose
+    /// [interval](Update::update_interval) does not match its values. The monitor would
+    /// otherwise report the verdict of the prover. Instead, [next](Monitor::next) fails with
+    /// [MonitorError::Inconsistent], describing the states of both. The current states are
+    /// checked right away. The falsifier is stepped even when the prover concluded, so the check
+    /// costs a little time on each input.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    /// use rust_efsm::monitor::{Monitor, Verdict};
+    ///
+    /// // Never read a zero.
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("ok", Transition {
+    ///         to_location: "ok".into(),
+    ///         enable: |_, i| *i != 0,
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("ok", Transition {
+    ///         to_location: "bad".into(),
+    ///         enable: |_, i| *i == 0,
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("bad", Transition { to_location: "bad".into(), ..Default::default() })
+    ///     .with_accepting("ok")
+    ///     .build();
+    ///
+    /// let mut monitor = Monitor::new("ok", 0, machine).unwrap().require_consistent().unwrap();
+    /// assert_eq!(monitor.next(&1).unwrap(), Verdict::Inconclusive);
+    /// assert_eq!(monitor.next(&0).unwrap(), Verdict::Violated);
+    /// ```
+    pub fn require_consistent(mut self) -> Result<Self, MonitorError>
+    where
+        D: Ord + Copy + Bounded + fmt::Debug,
+    {
+        if self.conclusions(self.hypotheses(), &self.uncertain) == (true, true) {
+            return Err(self.inconsistency(None));
+        }
+
+        self.require_consistent = true;
+        Ok(self)
+    }
+
+    /// Measures the time spent processing each input, reported by [Monitor::metrics].
+    pub fn with_latency_metrics(mut self) -> Self {
+        self.measure_latency = true;
+        self.metrics.total_latency = Some(Duration::ZERO);
+        self.metrics.max_latency = Some(Duration::ZERO);
+        self
+    }
+
+    /// Keeps the last `capacity` inputs in an event log, with the states they were read in,
+    /// the transitions they enabled, and their verdicts, for post-mortem analysis without
+    /// storing the whole trace.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    /// use rust_efsm::monitor::{Monitor, Verdict};
+    ///
+    /// // No `x` may ever be read.
+    /// let machine = MachineBuilder::<u8, char, IdentityUpdate<u8>>::new()
+    ///     .with_transition("ok", Transition {
+    ///         to_location: "ok".into(),
+    ///         enable: |_, i| *i != 'x',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("ok", Transition {
+    ///         to_location: "error".into(),
+    ///         enable: |_, i| *i == 'x',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("ok")
+    ///     .with_rejecting("error")
+    ///     .build();
+    ///
+    /// let mut monitor = Monitor::new("ok", 0, machine).unwrap().with_event_log(2);
+    /// monitor.run(&['a', 'b', 'x']).unwrap();
+    ///
+    /// let offsets: Vec<usize> = monitor.event_log().map(|entry| entry.offset).collect();
+    /// assert_eq!(offsets, [1, 2]);
+    /// assert_eq!(
+    ///     monitor.event_log_json(),
+    ///     concat!(
+    ///         r#"[{"offset":1,"input":"'b'","states":[{"location":"ok","data":"0"}],"#,
+    ///         r#""transition":"ok#0","verdict":"inconclusive"},"#,
+    ///         r#"{"offset":2,"input":"'x'","states":[{"location":"ok","data":"0"}],"#,
+    ///         r#""transition":"ok#1","verdict":"violated"}]"#,
+    ///     ),
+    /// );
+    /// ```
+    pub fn with_event_log(mut self, capacity: usize) -> Self {
+        self.event_log = Some(EventLog {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+            dump: self.event_log.and_then(|log| log.dump),
+        });
+        self
+    }
+
+    /// Calls `dump` with the [event log](Monitor::with_event_log) as JSON whenever an input
+    /// reaches a conclusive verdict, such as to write it to a file.
+    ///
+    /// Does nothing unless the event log is enabled.
+    pub fn with_event_log_dump(mut self, dump: fn(&str)) -> Self
+    where
+        D: fmt::Display,
+        I: fmt::Debug,
+    {
+        if let Some(log) = &mut self.event_log {
+            log.dump = Some((dump, entries_to_json));
+        }
+        self
+    }
+
+    /// Returns the entries of the [event log](Monitor::with_event_log), oldest first.
+    pub fn event_log(&self) -> impl Iterator<Item = &EventLogEntry<D, I>> {
+        self.event_log.iter().flat_map(|log| &log.entries)
+    }
+
+    /// Returns the entries of the [event log](Monitor::with_event_log) as a JSON array, oldest
+    /// first.
+    ///
+    /// Each entry has the `offset` of its input, the `input` formatted with [Debug](fmt::Debug),
+    /// the `states` it was read in, the `transition` it enabled or `null`, and its `verdict`.
+    pub fn event_log_json(&self) -> String
+    where
+        D: fmt::Display,
+        I: fmt::Debug,
+    {
+        match &self.event_log {
+            Some(log) => entries_to_json(&log.entries),
+            None => "[]".into(),
+        }
+    }
+
+    /// Returns measurements of the work done by this monitor so far.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    /// use rust_efsm::monitor::Monitor;
+    /// use std::time::Duration;
+    ///
+    /// // Never read a zero.
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("ok", Transition {
+    ///         to_location: "ok".into(),
+    ///         enable: |_, i| *i != 0,
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("ok", Transition {
+    ///         to_location: "bad".into(),
+    ///         enable: |_, i| *i == 0,
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("bad", Transition { to_location: "bad".into(), ..Default::default() })
+    ///     .with_accepting("ok")
+    ///     .build();
+    ///
+    /// let mut monitor = Monitor::new("ok", 0, machine).unwrap().with_latency_metrics();
+    /// monitor.next_at(&1, Duration::from_millis(100)).unwrap();
+    /// monitor.next_at(&2, Duration::from_millis(250)).unwrap();
+    /// monitor.next_at(&0, Duration::from_millis(400)).unwrap();
+    ///
+    /// let metrics = monitor.metrics();
+    /// assert_eq!(metrics.events, 3);
+    /// assert_eq!(metrics.verdict_offset, Some(2));
+    /// assert_eq!(metrics.time_to_verdict, Some(Duration::from_millis(300)));
+    /// assert!(metrics.mean_latency().is_some());
+    /// ```
+    pub fn metrics(&self) -> MonitorMetrics {
+        MonitorMetrics {
+            events: self.steps,
+            ..self.metrics.clone()
+        }
+    }
+
+    /// Processes the next input, observed at time `at`, like [Monitor::next].
+    ///
+    /// The timestamps are used to report the [time to the verdict](MonitorMetrics::time_to_verdict),
+    /// measured from the first timestamped input.
+    pub fn next_at<T>(&mut self, input: &I, at: T) -> Result<Verdict, MonitorError>
+    where
+        D: Eq + Hash + Clone + fmt::Debug + Bounded + Ord + Copy + fmt::Display,
+        I: Clone,
+        U: Clone + Update<D = D>,
+        T: Timestamp,
+    {
+        let at = at.as_nanos();
+        let first_at = *self.first_at.get_or_insert(at);
+
+        let had_verdict = self.verdict.is_conclusive();
+        let verdict = self.next(input)?;
+
+        if !had_verdict && self.verdict.is_conclusive() {
+            let elapsed = u64::try_from(at - first_at).unwrap_or(0);
+            self.metrics.time_to_verdict = Some(Duration::from_nanos(elapsed));
+        }
+
+        Ok(verdict)
+    }
+
+    /// Returns the number of inputs processed so far, which is the offset of the next input.
+    pub fn offset(&self) -> usize {
+        self.steps
+    }
+
+    /// Restarts the monitor from its initial states.
+    ///
+    /// The [offset](Monitor::offset) and the first [verdict](Monitor::verdict) are kept.
+    pub fn reset(&mut self)
+    where
+        D: Clone,
+    {
+        self.prover.state = self.start.clone();
+        self.falsifier.state = self.start.clone();
+        self.hypotheses = self.initial_hypotheses.clone();
+        self.uncertain.clear();
+    }
+
+    /// Replaces the monitored machine with `machine`, keeping the current state of the monitor.
+    ///
+    /// The current states are moved to the locations with the same names in `machine`, and the
+    /// intervals that lead to a verdict are computed again. The initial state, which the monitor
+    /// returns to when it is [reset](Monitor::reset), must also exist in `machine`. Returns the
+    /// verdict of the current states under `machine`, which replaces the
+    /// [verdict](Monitor::verdict) reached under the previous machine.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    /// use rust_efsm::monitor::{Monitor, Verdict};
+    ///
+    /// // Never read a zero.
+    /// let no_zero = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("ok", Transition {
+    ///         to_location: "ok".into(),
+    ///         enable: |_, i| *i != 0,
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("ok", Transition {
+    ///         to_location: "bad".into(),
+    ///         enable: |_, i| *i == 0,
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("bad", Transition { to_location: "bad".into(), ..Default::default() })
+    ///     .with_accepting("ok")
+    ///     .build();
+    ///
+    /// // Never read a zero or a one.
+    /// let no_zero_or_one = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("ok", Transition {
+    ///         to_location: "ok".into(),
+    ///         enable: |_, i| *i > 1,
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("ok", Transition {
+    ///         to_location: "bad".into(),
+    ///         enable: |_, i| *i <= 1,
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("bad", Transition { to_location: "bad".into(), ..Default::default() })
+    ///     .with_accepting("ok")
+    ///     .build();
+    ///
+    /// let mut monitor = Monitor::new("ok", 0, no_zero).unwrap();
+    /// assert_eq!(monitor.next(&1).unwrap(), Verdict::Inconclusive);
+    ///
+    /// assert_eq!(monitor.swap_machine(no_zero_or_one).unwrap(), Verdict::Inconclusive);
+    /// assert_eq!(monitor.next(&1).unwrap(), Verdict::Violated);
+    /// ```
+    pub fn swap_machine(&mut self, machine: Machine<D, I, U>) -> Result<Verdict, MonitorError>
+    where
+        D: Eq + Hash + Clone + fmt::Debug + Bounded + Ord + Copy + fmt::Display,
+        I: Clone,
+        U: Clone + Update<D = D>,
+    {
+        let known = machine.all_locations();
+
+        let current: Vec<State<D>> = match &self.hypotheses {
+            Some(hypotheses) => hypotheses.clone(),
+            None => vec![self.prover.state.clone(), self.falsifier.state.clone()],
+        };
+        let initial = self
+            .initial_hypotheses
+            .clone()
+            .unwrap_or_else(|| vec![self.start.clone()]);
+
+        let locations: BTreeSet<&String> = current
+            .iter()
+            .chain(&initial)
+            .map(|state| &state.location)
+            .chain(self.uncertain.keys())
+            .collect();
+
+        if let Some(missing) = locations
+            .iter()
+            .find(|location| !known.contains(location.as_str()))
+        {
+            return Err(MonitorError::ConstructionFailed(format!(
+                "location {} does not exist in the new machine",
+                missing
+            )));
+        }
+
+        let mut prover =
+            PartialMonitor::prove_from(&self.start.location, self.start.data, machine.clone())?;
+        let mut falsifier =
+            PartialMonitor::falsify_from(&self.start.location, self.start.data, machine)?;
+        for location in locations {
+            prover.include_start(location)?;
+            falsifier.include_start(location)?;
+        }
+
+        let approximation = prover.approximation.and(falsifier.approximation);
+        if self.require_sound && !approximation.includes_all() {
+            return Err(MonitorError::Undecidable(approximation));
+        }
+
+        prover.state = self.prover.state.clone();
+        falsifier.state = self.falsifier.state.clone();
+        self.prover = prover;
+        self.falsifier = falsifier;
+
+        self.initial_verdict = self.agreed_verdict(&initial, &Uncertain::new());
+        self.verdict = self.agreed_verdict(&current, &self.uncertain);
+        Ok(self.verdict)
+    }
+
+    /// Processes every input of `trace` and returns each verdict with the offset of the input
+    /// that produced it.
+    ///
+    /// In [MonitorMode::Continue], a verdict is repeated for every input after the one that
+    /// reached it.
+    pub fn run(&mut self, trace: &[I]) -> Result<Vec<(usize, Verdict)>, MonitorError>
+    where
+        D: Eq + Hash + Clone + fmt::Debug + Bounded + Ord + Copy + fmt::Display,
+        I: Clone,
+        U: Clone + Update<D = D>,
+    {
+        let mut verdicts = Vec::new();
+        for input in trace {
+            let offset = self.steps;
+            let verdict = self.next(input)?;
+            if verdict.is_conclusive() {
+                verdicts.push((offset, verdict));
+            }
+        }
+
+        Ok(verdicts)
+    }
+
+    /// Processes the inputs of `chunk` until one reaches a verdict, for consumers that read
+    /// inputs in batches, such as from a file or a message queue.
+    ///
+    /// The inputs after the one that reached a verdict are left for the caller, who may pass
+    /// them to the next call once the monitor was [reset](Monitor::reset), or in
+    /// [MonitorMode::ResetOnVerdict].
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    /// use rust_efsm::monitor::{ChunkOutcome, Monitor, MonitorMode, Verdict};
+    ///
+    /// // No `x` may ever be read.
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("ok", Transition {
+    ///         to_location: "ok".into(),
+    ///         enable: |_, i| *i != b'x',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("ok", Transition {
+    ///         to_location: "error".into(),
+    ///         enable: |_, i| *i == b'x',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("ok")
+    ///     .with_rejecting("error")
+    ///     .build();
+    ///
+    /// let mut monitor = Monitor::new("ok", 0, machine)
+    ///     .unwrap()
+    ///     .with_mode(MonitorMode::ResetOnVerdict);
+    ///
+    /// let chunk = b"abxcd";
+    /// assert_eq!(
+    ///     monitor.next_chunk(chunk).unwrap(),
+    ///     ChunkOutcome::Concluded { index: 2, verdict: Verdict::Violated },
+    /// );
+    /// assert_eq!(
+    ///     monitor.next_chunk(&chunk[3..]).unwrap(),
+    ///     ChunkOutcome::Inconclusive { offset: 5 },
+    /// );
+    /// ```
+    pub fn next_chunk(&mut self, chunk: &[I]) -> Result<ChunkOutcome, MonitorError>
+    where
+        D: Eq + Hash + Clone + fmt::Debug + Bounded + Ord + Copy + fmt::Display,
+        I: Clone,
+        U: Clone + Update<D = D>,
+    {
+        for (index, input) in chunk.iter().enumerate() {
+            let verdict = self.next(input)?;
+            if verdict.is_conclusive() {
+                return Ok(ChunkOutcome::Concluded { index, verdict });
+            }
+        }
+
+        Ok(ChunkOutcome::Inconclusive { offset: self.steps })
+    }
+
+    // Records `verdict` and restarts the monitor if its mode asks for it.
+    fn conclude(&mut self, verdict: Verdict) -> Verdict
+    where
+        D: Clone,
+    {
+        if !self.verdict.is_conclusive() {
+            self.verdict = verdict;
+        }
+
+        if verdict.is_conclusive() && self.mode == MonitorMode::ResetOnVerdict {
+            self.reset();
+        }
+
+        verdict
+    }
+
+    /// Returns the states the monitored system may currently be in.
+    ///
+    /// A monitor created by [Monitor::new] has exactly one until it [skips](Monitor::skip)
+    /// inputs. States whose data is only known to lie within an interval after skipping are not
+    /// included.
+    pub fn hypotheses(&self) -> &[State<D>] {
+        match &self.hypotheses {
+            Some(hypotheses) => hypotheses,
+            None => std::slice::from_ref(&self.falsifier.state),
+        }
+    }
+
+    /// Returns the data of `location` from which the property may still be satisfied, or `None`
+    /// when there is none.
+    ///
+    /// This is a single interval, unless the monitor was created from several states or
+    /// [refined](Monitor::with_refinement).
+    pub fn safe_interval(&self, location: &str) -> Option<&BoundSet<D>> {
+        self.falsifier
+            .non_empty_states
+            .get(location)
+            .filter(|bound| !bound.is_empty())
+    }
+
+    /// Returns how far the data of the current state is from leaving its
+    /// [safe interval](Monitor::safe_interval), or `None` when it already lies outside of it.
+    ///
+    /// The margin is the distance to the nearest endpoint of the interval holding the data, so an
+    /// application can warn before a violation happens. Endpoints at the minimum or maximum value
+    /// of the data type are never reached, and are not counted. When the system may be in
+    /// several [states](Monitor::hypotheses), the smallest margin among them is returned.
+    ///
+    /// ```
+    /// use rust_efsm::bound::Bound;
+    /// use rust_efsm::machine::{AcceptingCondition, AddUpdate, MachineBuilder, Transition};
+    /// use rust_efsm::monitor::Monitor;
+    ///
+    /// // At most five retries.
+    /// let machine = MachineBuilder::<u8, u8, AddUpdate<u8>>::new()
+    ///     .with_transition("ok", Transition {
+    ///         to_location: "ok".into(),
+    ///         enable: |_, i| *i == b'r',
+    ///         bound: Bound { lower: None, upper: Some(5) },
+    ///         update: AddUpdate { amount: 1 },
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("ok", Transition {
+    ///         to_location: "ok".into(),
+    ///         enable: |_, i| *i != b'r',
+    ///         update: AddUpdate { amount: 0 },
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("ok")
+    ///     .with_accepting_condition("ok", AcceptingCondition::new(Bound { lower: None, upper: Some(5) }))
+    ///     .build();
+    ///
+    /// // Refined, so that the interval stops where the condition does.
+    /// let mut monitor = Monitor::new("ok", 0, machine).unwrap().with_refinement(8);
+    /// assert!(monitor.safe_interval("ok").unwrap().contains(&5));
+    /// assert!(!monitor.safe_interval("ok").unwrap().contains(&6));
+    /// assert_eq!(monitor.current_margin(), Some(5));
+    ///
+    /// for _ in 0..4 {
+    ///     monitor.next(&b'r').unwrap();
+    /// }
+    /// // One retry left: time to warn.
+    /// assert_eq!(monitor.current_margin(), Some(1));
+    /// ```
+    pub fn current_margin(&self) -> Option<D>
+    where
+        D: Ord + Copy + Bounded + CheckedSub,
+    {
+        self.hypotheses()
+            .iter()
+            .filter_map(|state| {
+                let bound = self
+                    .safe_interval(&state.location)?
+                    .iter()
+                    .find(|bound| bound.contains(&state.data))?;
+                Some(margin(&bound, state.data))
+            })
+            .min()
+    }
+
+    // Returns the verdict shared by every state in `states` and `uncertain`, if they agree on
+    // one.
+    fn agreed_verdict(&self, states: &[State<D>], uncertain: &Uncertain<D>) -> Verdict
+    where
+        D: Ord + Copy + Bounded,
+    {
+        match self.conclusions(states, uncertain) {
+            (true, _) => Verdict::Satisfied,
+            (false, true) => Verdict::Violated,
+            (false, false) => Verdict::Inconclusive,
+        }
+    }
+
+    // Returns whether the prover, and whether the falsifier, reached a verdict for every state in
+    // `states` and `uncertain`.
+    fn conclusions(&self, states: &[State<D>], uncertain: &Uncertain<D>) -> (bool, bool)
+    where
+        D: Ord + Copy + Bounded,
+    {
+        let all_empty = |partial: &PartialMonitor<D, I, U>| {
+            states.iter().all(|state| partial.is_empty_at(state))
+                && uncertain
+                    .iter()
+                    .all(|(location, interval)| partial.is_empty_over(location, interval))
+        };
+
+        (all_empty(&self.prover), all_empty(&self.falsifier))
+    }
+
+    // Describes the current states, after both the prover and the falsifier reached a verdict
+    // on the input at `input_index`.
+    fn inconsistency(&self, input_index: Option<usize>) -> MonitorError
+    where
+        D: fmt::Debug,
+    {
+        let (prover, falsifier) = match &self.hypotheses {
+            Some(hypotheses) => {
+                let states = format!("{:?}", (hypotheses, &self.uncertain));
+                (states.clone(), states)
+            }
+            None => (
+                format!("{:?}", self.prover.state),
+                format!("{:?}", self.falsifier.state),
+            ),
+        };
+
+        MonitorError::Inconsistent(Inconsistency {
+            input_index,
+            prover,
+            falsifier,
+            approximation: self.approximation(),
+        })
+    }
+
+    /// Advances the monitor past a gap of at most `n` unknown inputs, such as events dropped
+    /// from a log, and returns the verdict if one is reached.
+    ///
+    /// The monitor afterwards tracks every state that the machine may have reached during the
+    /// gap, as in [Monitor::new_with_states]. The states are computed from the transition bounds
+    /// and [update intervals](Update::update_interval), so a location may be reached with an
+    /// interval of data instead of a single value. Guards cannot be evaluated over such an
+    /// interval, so later inputs move it along every transition whose bound it meets. The
+    /// verdict is therefore sound but may be reached later than without the gap.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, State, Transition};
+    /// use rust_efsm::monitor::{Monitor, Verdict};
+    ///
+    /// // A 'b' must never follow a 'b'.
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("idle", Transition {
+    ///         to_location: "idle".into(),
+    ///         enable: |_, i| *i != b'b',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("idle", Transition {
+    ///         to_location: "busy".into(),
+    ///         enable: |_, i| *i == b'b',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("busy", Transition {
+    ///         to_location: "idle".into(),
+    ///         enable: |_, i| *i != b'b',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("busy", Transition {
+    ///         to_location: "error".into(),
+    ///         enable: |_, i| *i == b'b',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("error", Transition { to_location: "error".into(), ..Default::default() })
+    ///     .with_accepting_all(["idle", "busy"])
+    ///     .build();
+    ///
+    /// let mut monitor = Monitor::new("idle", 0, machine).unwrap();
+    /// assert_eq!(monitor.next(&b'a').unwrap(), Verdict::Inconclusive);
+    ///
+    /// // A single dropped event may have been a 'b' or not.
+    /// assert_eq!(monitor.skip(1), Verdict::Inconclusive);
+    /// assert_eq!(monitor.hypotheses().len(), 2);
+    ///
+    /// // Only the hypothesis where the dropped event was a 'b' fails on the next 'b', so there is
+    /// // no verdict yet.
+    /// assert_eq!(monitor.next(&b'b').unwrap(), Verdict::Inconclusive);
+    /// assert_eq!(monitor.hypotheses(), &[
+    ///     State { location: "busy".into(), data: 0 },
+    ///     State { location: "error".into(), data: 0 },
+    /// ]);
+    ///
+    /// // Every hypothesis fails on a second 'b'.
+    /// assert_eq!(monitor.next(&b'b').unwrap(), Verdict::Violated);
+    /// ```
+    pub fn skip(&mut self, n: usize) -> Verdict
+    where
+        D: Eq + Hash + Clone + fmt::Debug + Bounded + Ord + Copy + fmt::Display,
+        U: Clone + Update<D = D>,
+    {
+        let mut reached = self.uncertain.clone();
+        for state in self.hypotheses() {
+            let point = Bound {
+                lower: Some(state.data),
+                upper: Some(state.data),
+            };
+            merge_interval(&mut reached, state.location.clone(), point);
+        }
+
+        // Only the intervals that grew in the last step can reach anything new.
+        let mut frontier = reached.clone();
+        for _ in 0..n {
+            let mut grown = Uncertain::new();
+            for (location, interval) in &frontier {
+                for (to_location, next) in self
+                    .falsifier
+                    .machine
+                    .interval_successors(location, interval)
+                {
+                    match reached.get_mut(&to_location) {
+                        Some(hull) if hull.contains_interval(&next) => {}
+                        Some(hull) => {
+                            hull.make_contain(&next);
+                            grown.insert(to_location, hull.clone());
+                        }
+                        None => {
+                            reached.insert(to_location.clone(), next.clone());
+                            grown.insert(to_location, next);
+                        }
+                    }
+                }
+            }
+
+            if grown.is_empty() {
+                break;
+            }
+            frontier = grown;
+        }
+
+        let mut states = Vec::new();
+        settle(&mut states, &mut reached);
+
+        let verdict = self.agreed_verdict(&states, &reached);
+        self.hypotheses = Some(states);
+        self.uncertain = reached;
+
+        self.conclude(verdict)
+    }
+
+    /// Returns the verdict reached before any input is processed.
+    ///
+    /// The verdict is conclusive when the initial state can no longer reach an accepting location
+    /// ([Verdict::Violated]) or can no longer reach a rejecting location ([Verdict::Satisfied]).
+    ///
+    /// ```
+    /// use rust_efsm::machine::{MachineBuilder, IdentityUpdate, Transition};
+    /// use rust_efsm::monitor::{Monitor, Verdict};
+    ///
+    /// // The initial location is a sink that never accepts.
+    /// let machine = MachineBuilder::<u32, u32, IdentityUpdate<u32>>::new()
+    ///     .with_transition("sink", Transition {
+    ///         to_location: "sink".into(),
+    ///         ..Default::default()
+    ///     })
+    ///     .build();
+    ///
+    /// let monitor = Monitor::new("sink", 0, machine).unwrap();
+    /// assert_eq!(monitor.initial_verdict(), Verdict::Violated);
+    /// ```
+    pub fn initial_verdict(&self) -> Verdict {
+        self.initial_verdict
+    }
+
+    /// Returns the first conclusive verdict reached so far, including the initial verdict, without
+    /// processing an input.
+    ///
+    /// In [MonitorMode::Continue], this is also the verdict of the latest input.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    /// use rust_efsm::monitor::{Monitor, Verdict};
+    ///
+    /// // Never read an 'a'.
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("ok", Transition {
+    ///         to_location: "ok".into(),
+    ///         enable: |_, i| *i != b'a',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("ok", Transition {
+    ///         to_location: "bad".into(),
+    ///         enable: |_, i| *i == b'a',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("bad", Transition { to_location: "bad".into(), ..Default::default() })
+    ///     .with_accepting("ok")
+    ///     .build();
+    ///
+    /// let mut monitor = Monitor::new("ok", 0, machine).unwrap();
+    /// assert_eq!(monitor.verdict(), Verdict::Inconclusive);
+    ///
+    /// monitor.next(&b'a').unwrap();
+    /// monitor.next(&b'b').unwrap();
+    /// assert_eq!(monitor.verdict(), Verdict::Violated);
+    /// ```
+    pub fn verdict(&self) -> Verdict {
+        self.verdict
+    }
+
+    /// Returns the first conclusive verdict reached on `trace` from the initial state.
+    ///
+    /// The monitor itself is not advanced, so it can check many independent traces while
+    /// sharing the analysis computed by [Monitor::new].
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    /// use rust_efsm::monitor::{Monitor, Verdict};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("safe", Transition {
+    ///         to_location: "safe".into(),
+    ///         enable: |_, i| *i != 0,
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("safe", Transition {
+    ///         to_location: "unsafe".into(),
+    ///         enable: |_, i| *i == 0,
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("unsafe", Transition { to_location: "unsafe".into(), ..Default::default() })
+    ///     .with_accepting("safe")
+    ///     .build();
+    ///
+    /// let monitor = Monitor::new("safe", 1, machine).unwrap();
+    /// assert_eq!(monitor.check(&[1, 2, 0, 3]).unwrap(), Verdict::Violated);
+    /// assert_eq!(monitor.check(&[1, 2, 3]).unwrap(), Verdict::Inconclusive);
+    /// ```
+    pub fn check(&self, trace: &[I]) -> Result<Verdict, MonitorError>
+    where
+        D: Eq + Hash + Clone + fmt::Debug + Bounded + Ord + Copy + fmt::Display,
+        U: Clone + Update<D = D>,
+    {
+        if self.initial_verdict.is_conclusive() {
+            return Ok(self.initial_verdict);
+        }
+
+        if let Some(hypotheses) = &self.hypotheses {
+            let mut states = hypotheses.clone();
+            let mut uncertain = Uncertain::new();
+            for (idx, input) in trace.iter().enumerate() {
+                (states, uncertain) = self
+                    .falsifier
+                    .step_hypotheses(&states, &uncertain, input)
+                    .map_err(|e| at_input(e, idx))?;
+                let verdict = self.agreed_verdict(&states, &uncertain);
+                if verdict.is_conclusive() {
+                    return Ok(verdict);
+                }
+            }
+
+            return Ok(Verdict::Inconclusive);
+        }
+
+        let mut prover = self.start.clone();
+        let mut falsifier = self.start.clone();
+
+        for (idx, input) in trace.iter().enumerate() {
+            prover = self
+                .prover
+                .step(&prover, input)
+                .map_err(|e| at_input(e, idx))?;
+            if self.prover.is_empty_at(&prover) {
+                return Ok(Verdict::Satisfied);
+            }
+
+            falsifier = self
+                .falsifier
+                .step(&falsifier, input)
+                .map_err(|e| at_input(e, idx))?;
+            if self.falsifier.is_empty_at(&falsifier) {
+                return Ok(Verdict::Violated);
+            }
+        }
+
+        Ok(Verdict::Inconclusive)
+    }
+
+    /// Checks every trace in `traces` in parallel, returning the result of [Monitor::check] for
+    /// each trace in order.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    /// use rust_efsm::monitor::{Monitor, Verdict};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("safe", Transition {
+    ///         to_location: "safe".into(),
+    ///         enable: |_, i| *i != 0,
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("safe", Transition {
+    ///         to_location: "unsafe".into(),
+    ///         enable: |_, i| *i == 0,
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("unsafe", Transition { to_location: "unsafe".into(), ..Default::default() })
+    ///     .with_accepting("safe")
+    ///     .build();
+    ///
+    /// let monitor = Monitor::new("safe", 1, machine).unwrap();
+    /// let verdicts = monitor.check_all(vec![vec![1, 0], vec![1, 2]]);
+    /// assert_eq!(verdicts[0].as_ref().unwrap(), &Verdict::Violated);
+    /// assert_eq!(verdicts[1].as_ref().unwrap(), &Verdict::Inconclusive);
+    /// ```
+    #[cfg(feature = "parallel")]
+    pub fn check_all<T, W>(&self, traces: T) -> Vec<Result<Verdict, MonitorError>>
+    where
+        D: Eq + Hash + Clone + fmt::Debug + Bounded + Ord + Copy + fmt::Display + Send + Sync,
+        I: Sync,
+        U: Clone + Update<D = D> + Sync,
+        T: IntoParallelIterator<Item = W>,
+        W: AsRef<[I]>,
+    {
+        traces
+            .into_par_iter()
+            .map(|trace| self.check(trace.as_ref()))
+            .collect()
+    }
+
+    /// Processes the next input and determines if a verdict can be reached.
+    ///
+    /// The monitor uses both the prover and falsifier to determine if the property is
+    /// satisfied, violated, or still inconclusive.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The input to process
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Verdict::Satisfied)` - Property is satisfied (proven)
+    /// * `Ok(Verdict::Violated)` - Property is violated (falsified)
+    /// * `Ok(Verdict::Inconclusive)` - No verdict yet
+    /// * `Err(MonitorError)` - An error occurred during processing
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use rust_efsm::machine::{Machine, MachineBuilder, AddUpdate};
+    /// use rust_efsm::monitor::{Monitor, Verdict};
+    ///
+    /// // Assume we have a machine and monitor already set up
+    /// let mut monitor = Monitor::new("start", 0, machine).unwrap();
+    ///
+    /// // Process an input and check for a verdict
+    /// match monitor.next(&42) {
+    ///     Ok(Verdict::Satisfied) => println!("Property satisfied!"),
+    ///     Ok(Verdict::Violated) => println!("Property violated!"),
+    ///     Ok(Verdict::Inconclusive) => println!("Still inconclusive..."),
+    ///     Err(e) => println!("Error: {:?}", e),
+    /// }
+    /// ```
+    pub fn next(&mut self, input: &I) -> Result<Verdict, MonitorError>
+    where
+        D: Eq + Hash + Clone + fmt::Debug + Bounded + Ord + Copy + fmt::Display,
+        I: Clone,
+        U: Clone + Update<D = D>,
+    {
+        span!(TRACE, "monitor_next", offset = self.steps);
+
+        let started = self.measure_latency.then(Instant::now);
+        let had_verdict = self.verdict.is_conclusive();
+
+        // The states are only copied when they are logged.
+        let states = self.event_log.is_some().then(|| self.hypotheses().to_vec());
+        self.falsifier.taken = None;
+
+        let verdict = self.advance(input);
+
+        if let (Some(states), Ok(verdict)) = (states, &verdict) {
+            let transition = match (&states[..], self.falsifier.taken) {
+                ([state], Some(idx)) => Some(TransitionId::new(&state.location, idx)),
+                _ => None,
+            };
+            let entry = EventLogEntry {
+                offset: self.steps - 1,
+                input: input.clone(),
+                states,
+                transition,
+                verdict: *verdict,
+            };
+            if let Some(log) = &mut self.event_log {
+                log.push(entry);
+            }
+        }
+
+        if !had_verdict && self.verdict.is_conclusive() {
+            self.metrics.verdict_offset = Some(self.steps - 1);
+            info!(offset = self.steps - 1, verdict = %self.verdict, "verdict reached");
+        }
+
+        if let Some(started) = started {
+            let latency = started.elapsed();
+            self.metrics.total_latency = self.metrics.total_latency.map(|total| total + latency);
+            self.metrics.max_latency = self.metrics.max_latency.map(|max| max.max(latency));
+        }
+
+        verdict
+    }
+
+    // Feeds `input` to the prover and the falsifier, or to every hypothesis.
+    fn advance(&mut self, input: &I) -> Result<Verdict, MonitorError>
+    where
+        D: Eq + Hash + Clone + fmt::Debug + Bounded + Ord + Copy + fmt::Display,
+        U: Clone + Update<D = D>,
+    {
+        let idx = self.steps;
+        self.steps += 1;
+
+        if let Some(hypotheses) = &self.hypotheses {
+            let (states, uncertain) = self
+                .falsifier
+                .step_hypotheses(hypotheses, &self.uncertain, input)
+                .map_err(|e| at_input(e, idx))?;
+            let verdict = self.agreed_verdict(&states, &uncertain);
+            trace!(hypotheses = states.len(), uncertain = uncertain.len(), %verdict, "stepped");
+            if hypotheses.len() <= LARGE_STATE_SET && states.len() > LARGE_STATE_SET {
+                warning!(
+                    hypotheses = states.len(),
+                    offset = idx,
+                    "state set explosion"
+                );
+            }
+            let both =
+                self.require_consistent && self.conclusions(&states, &uncertain) == (true, true);
+            self.hypotheses = Some(states);
+            self.uncertain = uncertain;
+            if both {
+                return Err(self.inconsistency(Some(idx)));
+            }
+
+            return Ok(self.conclude(verdict));
+        }
+
+        // Both are stepped, so that a verdict reached by both is noticed.
+        if self.require_consistent {
+            let proven = self.prover.next(input).map_err(|e| at_input(e, idx))?;
+            let falsified = self.falsifier.next(input).map_err(|e| at_input(e, idx))?;
+            if proven && falsified {
+                return Err(self.inconsistency(Some(idx)));
+            }
+            let verdict = match (proven, falsified) {
+                (true, _) => Verdict::Satisfied,
+                (false, true) => Verdict::Violated,
+                (false, false) => Verdict::Inconclusive,
+            };
+            return Ok(self.conclude(verdict));
+        }
+
+        let mut verdict = Verdict::Inconclusive;
+        if self.prover.next(input).map_err(|e| at_input(e, idx))? {
+            // Prover found satisfaction.
+            verdict = Verdict::Satisfied;
+        } else if self.falsifier.next(input).map_err(|e| at_input(e, idx))? {
+            // Falsifier found violation.
+            verdict = Verdict::Violated;
+        }
+
+        Ok(self.conclude(verdict))
+    }
+}
+
+impl<D, I, U> Monitor<D, I, U>
+where
+    D: Eq + Hash + Clone,
+{
+    /// Moves this monitor behind an [Arc] and returns a [MonitorHandle] to it.
+    ///
+    /// The handle starts from the initial state of the monitor, whatever inputs it processed.
+    pub fn into_handle(self) -> MonitorHandle<D, I, U> {
+        MonitorHandle::new(Arc::new(self))
+    }
+}
+
+/// A lightweight monitor sharing the machines and analyses of a [Monitor] with other handles.
+///
+/// Building a monitor analyzes its machine, while a handle only holds the states of its own run,
+/// so handles are cheap to clone, and can be sent to other threads to monitor a session each.
+/// A handle processes inputs as [check](Monitor::check) does: it keeps the first conclusive
+/// verdict it reaches, whatever the [mode](Monitor::with_mode) of the monitor.
+///
+/// A handle is `Send` and `Sync` whenever the data and update types of its monitor are.
+///
+/// ```
+/// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+/// use rust_efsm::monitor::{Monitor, MonitorHandle, Verdict};
+/// use std::thread;
+///
+/// // Every request must be answered before the next one.
+/// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+///     .with_transition("idle", Transition {
+///         to_location: "busy".into(),
+///         enable: |_, i| *i == b'q',
+///         ..Default::default()
+///     })
+///     .with_transition("busy", Transition {
+///         to_location: "idle".into(),
+///         enable: |_, i| *i == b'a',
+///         ..Default::default()
+///     })
+///     .with_transition("busy", Transition {
+///         to_location: "error".into(),
+///         enable: |_, i| *i == b'q',
+///         ..Default::default()
+///     })
+///     .with_transition("error", Transition { to_location: "error".into(), ..Default::default() })
+///     .with_accepting_all(["idle", "busy"])
+///     .build();
+///
+/// let handle = Monitor::new("idle", 0, machine).unwrap().into_handle();
+///
+/// let sessions: Vec<_> = [b"qaqa".to_vec(), b"qaqq".to_vec()]
+///     .into_iter()
+///     .map(|session| {
+///         let mut handle: MonitorHandle<_, _, _> = handle.clone();
+///         thread::spawn(move || {
+///             for input in &session {
+///                 handle.next(input).unwrap();
+///             }
+///             handle.verdict()
+///         })
+///     })
+///     .collect();
+///
+/// let verdicts: Vec<Verdict> = sessions.into_iter().map(|s| s.join().unwrap()).collect();
+/// assert_eq!(verdicts, [Verdict::Inconclusive, Verdict::Violated]);
+/// ```
+pub struct MonitorHandle<D, I, U>
+where
+    D: Eq + Hash,
+{
+    monitor: Arc<Monitor<D, I, U>>,
+    prover: State<D>,
+    falsifier: State<D>,
+    // The states the system may be in, when the monitor was started from several hypotheses.
+    hypotheses: Option<(Vec<State<D>>, Uncertain<D>)>,
+    steps: usize,
+    verdict: Verdict,
+}
+
+// Implemented by hand, because neither the input nor the update type need to be cloned.
+impl<D, I, U> Clone for MonitorHandle<D, I, U>
+where
+    D: Eq + Hash + Clone,
+{
+    fn clone(&self) -> Self {
+        MonitorHandle {
+            monitor: self.monitor.clone(),
+            prover: self.prover.clone(),
+            falsifier: self.falsifier.clone(),
+            hypotheses: self.hypotheses.clone(),
+            steps: self.steps,
+            verdict: self.verdict,
+        }
+    }
+}
+
+impl<D, I, U> MonitorHandle<D, I, U>
+where
+    D: Eq + Hash + Clone,
+{
+    /// Creates a handle starting from the initial state of `monitor`.
+    pub fn new(monitor: Arc<Monitor<D, I, U>>) -> Self {
+        MonitorHandle {
+            prover: monitor.start.clone(),
+            falsifier: monitor.start.clone(),
+            hypotheses: monitor
+                .initial_hypotheses
+                .clone()
+                .map(|states| (states, Uncertain::new())),
+            steps: 0,
+            verdict: monitor.initial_verdict,
+            monitor,
+        }
+    }
+
+    /// Returns the monitor shared by this handle.
+    pub fn monitor(&self) -> &Arc<Monitor<D, I, U>> {
+        &self.monitor
+    }
+
+    /// Returns the verdict reached so far.
+    pub fn verdict(&self) -> Verdict {
+        self.verdict
+    }
+
+    /// Returns the number of inputs processed by this handle.
+    pub fn offset(&self) -> usize {
+        self.steps
+    }
+
+    /// Returns this handle to the initial state of its monitor, to monitor a new session.
+    pub fn reset(&mut self) {
+        let monitor = &self.monitor;
+        self.prover.clone_from(&monitor.start);
+        self.falsifier.clone_from(&monitor.start);
+        self.steps = 0;
+        self.verdict = monitor.initial_verdict;
+
+        // Keep the buffers of the hypotheses for the next session.
+        match (&mut self.hypotheses, &monitor.initial_hypotheses) {
+            (Some((states, uncertain)), Some(initial)) => {
+                states.clone_from(initial);
+                uncertain.clear();
+            }
+            (hypotheses, initial) => {
+                *hypotheses = initial.clone().map(|states| (states, Uncertain::new()));
+            }
+        }
+    }
+
+    /// Processes `input` and returns the verdict reached so far.
+    pub fn next(&mut self, input: &I) -> Result<Verdict, MonitorError>
+    where
+        D: fmt::Debug + Bounded + Ord + Copy + fmt::Display,
+        U: Clone + Update<D = D>,
+    {
+        let idx = self.steps;
+        self.steps += 1;
+        if self.verdict.is_conclusive() {
+            return Ok(self.verdict);
+        }
+
+        let monitor = &self.monitor;
+        self.verdict = match &mut self.hypotheses {
+            Some((states, uncertain)) => {
+                let (next, next_uncertain) = monitor
+                    .falsifier
+                    .step_hypotheses(states, uncertain, input)
+                    .map_err(|e| at_input(e, idx))?;
+                let verdict = monitor.agreed_verdict(&next, &next_uncertain);
+                *states = next;
+                *uncertain = next_uncertain;
+                verdict
+            }
+            None => {
+                self.prover = monitor
+                    .prover
+                    .step(&self.prover, input)
+                    .map_err(|e| at_input(e, idx))?;
+                if monitor.prover.is_empty_at(&self.prover) {
+                    Verdict::Satisfied
+                } else {
+                    self.falsifier = monitor
+                        .falsifier
+                        .step(&self.falsifier, input)
+                        .map_err(|e| at_input(e, idx))?;
+                    match monitor.falsifier.is_empty_at(&self.falsifier) {
+                        true => Verdict::Violated,
+                        false => Verdict::Inconclusive,
+                    }
+                }
+            }
+        };
+
+        Ok(self.verdict)
+    }
+}
+
+/// A pool of [MonitorHandles](MonitorHandle) to one monitor, for monitoring many sessions,
+/// such as network connections, without analyzing the machine for each of them.
+///
+/// A session [checks out](MonitorPool::checkout) a handle, and [checks it
+/// in](MonitorPool::checkin) when it ends, so that the handle is reset and reused by a later
+/// session. The pool can be shared by many threads.
+///
+/// ```
+/// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+/// use rust_efsm::monitor::{Monitor, MonitorPool, Verdict};
+///
+/// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+///     .with_transition("safe", Transition {
+///         to_location: "safe".into(),
+///         enable: |_, i| *i != 0,
+///         ..Default::default()
+///     })
+///     .with_transition("safe", Transition {
+///         to_location: "unsafe".into(),
+///         enable: |_, i| *i == 0,
+///         ..Default::default()
+///     })
+///     .with_transition("unsafe", Transition { to_location: "unsafe".into(), ..Default::default() })
+///     .with_accepting("safe")
+///     .build();
+///
+/// let pool = MonitorPool::new(Monitor::new("safe", 0, machine).unwrap());
+///
+/// let mut session = pool.checkout();
+/// assert_eq!(session.next(&0).unwrap(), Verdict::Violated);
+/// pool.checkin(session);
+/// assert_eq!(pool.idle(), 1);
+///
+/// // The handle is reused, from the initial state.
+/// let session = pool.checkout();
+/// assert_eq!(session.verdict(), Verdict::Inconclusive);
+/// assert_eq!(pool.idle(), 0);
+/// ```
+pub struct MonitorPool<D, I, U>
+where
+    D: Eq + Hash,
+{
+    monitor: Arc<Monitor<D, I, U>>,
+    idle: Mutex<Vec<MonitorHandle<D, I, U>>>,
+}
+
+impl<D, I, U> MonitorPool<D, I, U>
+where
+    D: Eq + Hash + Clone,
+{
+    /// Creates an empty pool of handles to `monitor`.
+    pub fn new(monitor: Monitor<D, I, U>) -> Self {
+        MonitorPool {
+            monitor: Arc::new(monitor),
+            idle: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns the monitor shared by the handles of this pool.
+    pub fn monitor(&self) -> &Arc<Monitor<D, I, U>> {
+        &self.monitor
+    }
+
+    /// Returns an idle handle, or a new one when none is idle, starting from the initial state
+    /// of the monitor.
+    pub fn checkout(&self) -> MonitorHandle<D, I, U> {
+        let handle = self.idle.lock().ok().and_then(|mut idle| idle.pop());
+        handle.unwrap_or_else(|| MonitorHandle::new(self.monitor.clone()))
+    }
+
+    /// Resets `handle` and keeps it for a later [checkout](MonitorPool::checkout).
+    ///
+    /// Handles to another monitor are dropped.
+    pub fn checkin(&self, mut handle: MonitorHandle<D, I, U>) {
+        if !Arc::ptr_eq(&handle.monitor, &self.monitor) {
+            return;
+        }
+
+        handle.reset();
+        if let Ok(mut idle) = self.idle.lock() {
+            idle.push(handle);
+        }
+    }
+
+    /// Returns the number of handles waiting to be checked out.
+    pub fn idle(&self) -> usize {
+        self.idle.lock().map_or(0, |idle| idle.len())
+    }
+}
+
+impl<D, U> Monitor<D, u8, U>
+where
+    D: Eq + Hash + Clone + fmt::Debug + Bounded + Ord + Copy + fmt::Display,
+    U: Clone + Update<D = D>,
+{
+    /// Processes every byte of `buf` until a conclusive verdict is reached.
+    ///
+    /// Returns the offset in `buf` of the byte that produced the verdict together with the
+    /// verdict, or `None` if every byte was processed without a conclusive verdict.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    /// use rust_efsm::monitor::{Monitor, Verdict};
+    ///
+    /// // Never read a newline.
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("ok", Transition {
+    ///         to_location: "ok".into(),
+    ///         enable: |_, i| *i != b'\n',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("ok", Transition {
+    ///         to_location: "bad".into(),
+    ///         enable: |_, i| *i == b'\n',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("bad", Transition { to_location: "bad".into(), ..Default::default() })
+    ///     .with_accepting("ok")
+    ///     .build();
+    ///
+    /// let mut monitor = Monitor::new("ok", 0, machine).unwrap();
+    /// assert_eq!(monitor.write_verdicts(b"abc").unwrap(), None);
+    /// assert_eq!(monitor.write_verdicts(b"de\nf").unwrap(), Some((2, Verdict::Violated)));
+    /// ```
+    pub fn write_verdicts(&mut self, buf: &[u8]) -> Result<Option<(usize, Verdict)>, MonitorError> {
+        for (offset, byte) in buf.iter().enumerate() {
+            let verdict = self.next(byte)?;
+            if verdict.is_conclusive() {
+                return Ok(Some((offset, verdict)));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Feeds written bytes to the monitor, so it can be the target of [io::copy](std::io::copy).
+///
+/// Once a verdict is reached, writes stop accepting bytes. [io::copy](std::io::copy) then fails
+/// with [WriteZero](std::io::ErrorKind::WriteZero) and the verdict is available from
+/// [Monitor::verdict].
+///
+/// ```
+/// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+/// use rust_efsm::monitor::{Monitor, Verdict};
+/// use std::io;
+///
+/// // Never read a zero byte.
+/// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+///     .with_transition("ok", Transition {
+///         to_location: "ok".into(),
+///         enable: |_, i| *i != 0,
+///         ..Default::default()
+///     })
+///     .with_transition("ok", Transition {
+///         to_location: "bad".into(),
+///         enable: |_, i| *i == 0,
+///         ..Default::default()
+///     })
+///     .with_transition("bad", Transition { to_location: "bad".into(), ..Default::default() })
+///     .with_accepting("ok")
+///     .build();
+///
+/// let mut monitor = Monitor::new("ok", 0, machine).unwrap();
+/// let result = io::copy(&mut &[1, 2, 0, 3][..], &mut monitor);
+///
+/// assert_eq!(result.unwrap_err().kind(), io::ErrorKind::WriteZero);
+/// assert_eq!(monitor.verdict(), Verdict::Violated);
+/// ```
+impl<D, U> std::io::Write for Monitor<D, u8, U>
+where
+    D: Eq + Hash + Clone + fmt::Debug + Bounded + Ord + Copy + fmt::Display,
+    U: Clone + Update<D = D>,
+{
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.verdict.is_conclusive() {
+            return Ok(0);
+        }
+
+        match self.write_verdicts(buf).map_err(std::io::Error::other)? {
+            Some((offset, _)) => Ok(offset + 1),
+            None => Ok(buf.len()),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Common interface of [monitors](Monitor) and their combinations.
+///
+/// Verdicts follow the three-valued semantics of runtime verification described by [Verdict].
+///
+/// # Examples
+///
+/// ```
+/// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+/// use rust_efsm::monitor::{Monitor, Verdict, Verify};
+///
+/// // Never read a zero.
+/// let no_zero = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+///     .with_transition("ok", Transition {
+///         to_location: "ok".into(),
+///         enable: |_, i| *i != 0,
+///         ..Default::default()
+///     })
+///     .with_transition("ok", Transition {
+///         to_location: "bad".into(),
+///         enable: |_, i| *i == 0,
+///         ..Default::default()
+///     })
+///     .with_transition("bad", Transition { to_location: "bad".into(), ..Default::default() })
+///     .with_accepting("ok")
+///     .build();
+///
+/// // Never read a one.
+/// let no_one = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+///     .with_transition("ok", Transition {
+///         to_location: "ok".into(),
+///         enable: |_, i| *i != 1,
+///         ..Default::default()
+///     })
+///     .with_transition("ok", Transition {
+///         to_location: "bad".into(),
+///         enable: |_, i| *i == 1,
+///         ..Default::default()
+///     })
+///     .with_transition("bad", Transition { to_location: "bad".into(), ..Default::default() })
+///     .with_accepting("ok")
+///     .build();
+///
+/// let mut both = Monitor::new("ok", 0, no_zero.clone())
+///     .unwrap()
+///     .and(Monitor::new("ok", 0, no_one.clone()).unwrap());
+/// assert_eq!(both.next(&2).unwrap(), Verdict::Inconclusive);
+/// assert_eq!(both.next(&1).unwrap(), Verdict::Violated);
+///
+/// let mut either = Monitor::new("ok", 0, no_zero)
+///     .unwrap()
+///     .or(Monitor::new("ok", 0, no_one).unwrap());
+/// assert_eq!(either.next(&1).unwrap(), Verdict::Inconclusive);
+/// assert_eq!(either.next(&0).unwrap(), Verdict::Violated);
+/// ```
+pub trait Verify<I> {
+    /// Processes the next input and returns the verdict, if one has been reached.
+    fn next(&mut self, input: &I) -> Result<Verdict, MonitorError>;
+
+    /// Returns the verdict reached before any input is processed.
+    fn initial_verdict(&self) -> Verdict;
+
+    /// Combines two monitors into one that is satisfied when both are satisfied.
+    fn and<M>(self, other: M) -> And<Self, M>
+    where
+        Self: Sized,
+        M: Verify<I>,
+    {
+        And {
+            left: Tracked::new(self),
+            right: Tracked::new(other),
+        }
+    }
+
+    /// Combines two monitors into one that is satisfied when either is satisfied.
+    fn or<M>(self, other: M) -> Or<Self, M>
+    where
+        Self: Sized,
+        M: Verify<I>,
+    {
+        Or {
+            left: Tracked::new(self),
+            right: Tracked::new(other),
+        }
+    }
+
+    /// Turns a monitor into one that is satisfied when it is violated, and vice versa.
+    fn not(self) -> Not<Self>
+    where
+        Self: Sized,
+    {
+        Not {
+            inner: Tracked::new(self),
+        }
+    }
+
+    /// Turns a monitor into one reading raw events, which `preprocessor` maps to inputs or
+    /// drops, so that events can come straight from an event bus.
+    ///
+    /// Further stages are added after it with [filter](Preprocessed::filter) and
+    /// [debounce](Preprocessed::debounce). Since the result is a monitor too, a preprocessor
+    /// for rawer events can be put in front of it.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    /// use rust_efsm::monitor::{Monitor, Verdict, Verify};
+    ///
+    /// // A locked door must not be opened.
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("unlocked", Transition {
+    ///         to_location: "locked".into(),
+    ///         enable: |_, i| *i == b'l',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("unlocked", Transition {
+    ///         to_location: "unlocked".into(),
+    ///         enable: |_, i| *i != b'l',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("locked", Transition {
+    ///         to_location: "error".into(),
+    ///         enable: |_, i| *i == b'o',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("locked", Transition {
+    ///         to_location: "locked".into(),
+    ///         enable: |_, i| *i != b'o',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting_all(["unlocked", "locked"])
+    ///     .with_rejecting("error")
+    ///     .build();
+    ///
+    /// // Events name the sensor they come from, and sensors repeat themselves.
+    /// let mut monitor = Monitor::new("unlocked", 0, machine)
+    ///     .unwrap()
+    ///     .with_preprocessor(|(sensor, input): &(String, u8)| (sensor == "door").then_some(*input))
+    ///     .debounce()
+    ///     .with_preprocessor(|line: &&str| {
+    ///         let (sensor, state) = line.trim().split_once(':')?;
+    ///         let input = match state {
+    ///             "opened" => b'o',
+    ///             "locked" => b'l',
+    ///             _ => return None,
+    ///         };
+    ///         Some((sensor.to_string(), input))
+    ///     });
+    ///
+    /// for event in ["door:opened", "door:opened ", "door:locked", "window:opened", "noise"] {
+    ///     assert_eq!(monitor.next(&event).unwrap(), Verdict::Inconclusive);
+    /// }
+    /// assert_eq!(monitor.next(&"door:opened").unwrap(), Verdict::Violated);
+    /// ```
+    fn with_preprocessor<R>(self, preprocessor: fn(&R) -> Option<I>) -> Preprocessed<Self, R, I>
+    where
+        Self: Sized,
+        R: 'static,
+        I: 'static,
+    {
+        let verdict = self.initial_verdict();
+        Preprocessed {
+            monitor: self,
+            stage: Box::new(preprocessor),
+            verdict,
+            dropped: 0,
+        }
+    }
+}
+
+impl<D, I, U> Verify<I> for Monitor<D, I, U>
+where
+    D: Eq + Hash + Clone + fmt::Debug + Bounded + Ord + Copy + fmt::Display,
+    I: Clone,
+    U: Clone + Update<D = D>,
+{
+    fn next(&mut self, input: &I) -> Result<Verdict, MonitorError> {
+        Monitor::next(self, input)
+    }
+
+    fn initial_verdict(&self) -> Verdict {
+        Monitor::initial_verdict(self)
+    }
+}
+
+// A monitor together with the final verdict it has reached, if any.
+struct Tracked<M> {
+    monitor: M,
+    verdict: Verdict,
+}
+
+impl<M> Tracked<M> {
+    fn new<I>(monitor: M) -> Self
+    where
+        M: Verify<I>,
+    {
+        let verdict = monitor.initial_verdict();
+        Tracked { monitor, verdict }
+    }
+
+    // Feeds the input to the monitor until it reaches a verdict, which is then kept.
+    fn next<I>(&mut self, input: &I) -> Result<Verdict, MonitorError>
+    where
+        M: Verify<I>,
+    {
+        if !self.verdict.is_conclusive() {
+            self.verdict = self.monitor.next(input)?;
+        }
+        Ok(self.verdict)
+    }
+}
+
+/// The conjunction of two monitors, created by [Verify::and].
+pub struct And<A, B> {
+    left: Tracked<A>,
+    right: Tracked<B>,
+}
+
+impl<I, A, B> Verify<I> for And<A, B>
+where
+    A: Verify<I>,
+    B: Verify<I>,
+{
+    fn next(&mut self, input: &I) -> Result<Verdict, MonitorError> {
+        let left = self.left.next(input)?;
+        let right = self.right.next(input)?;
+        Ok(left.and(right))
+    }
+
+    fn initial_verdict(&self) -> Verdict {
+        self.left.verdict.and(self.right.verdict)
+    }
+}
+
+/// The disjunction of two monitors, created by [Verify::or].
+pub struct Or<A, B> {
+    left: Tracked<A>,
+    right: Tracked<B>,
+}
+
+impl<I, A, B> Verify<I> for Or<A, B>
+where
+    A: Verify<I>,
+    B: Verify<I>,
+{
+    fn next(&mut self, input: &I) -> Result<Verdict, MonitorError> {
+        let left = self.left.next(input)?;
+        let right = self.right.next(input)?;
+        Ok(left.or(right))
+    }
+
+    fn initial_verdict(&self) -> Verdict {
+        self.left.verdict.or(self.right.verdict)
+    }
+}
+
+/// The negation of a monitor, created by [Verify::not].
+pub struct Not<A> {
+    inner: Tracked<A>,
+}
+
+impl<I, A> Verify<I> for Not<A>
+where
+    A: Verify<I>,
+{
+    fn next(&mut self, input: &I) -> Result<Verdict, MonitorError> {
+        Ok(!self.inner.next(input)?)
+    }
+
+    fn initial_verdict(&self) -> Verdict {
+        !self.inner.verdict
+    }
+}
+
+/// A monitor reading raw events through a preprocessing stage, created by
+/// [Verify::with_preprocessor].
+///
+/// A dropped event leaves the monitor unchanged, and its verdict is the last one reached.
+pub struct Preprocessed<M, R, I> {
+    monitor: M,
+    stage: Stage<R, I>,
+    verdict: Verdict,
+    dropped: usize,
+}
+
+// Maps a raw event to an input, or drops it.
+type Stage<R, I> = Box<dyn FnMut(&R) -> Option<I> + Send>;
+
+impl<M, R, I> Preprocessed<M, R, I> {
+    /// Drops the inputs for which `keep` does not hold.
+    pub fn filter(mut self, keep: fn(&I) -> bool) -> Self
+    where
+        R: 'static,
+        I: 'static,
+    {
+        let mut stage = self.stage;
+        self.stage = Box::new(move |raw| stage(raw).filter(keep));
+        self
+    }
+
+    /// Drops every input equal to the last input passed on to the monitor, such as a sensor
+    /// repeating its state.
+    pub fn debounce(mut self) -> Self
+    where
+        R: 'static,
+        I: PartialEq + Clone + Send + 'static,
+    {
+        let mut stage = self.stage;
+        let mut last: Option<I> = None;
+        self.stage = Box::new(move |raw| {
+            let input = stage(raw)?;
+            if last.as_ref() == Some(&input) {
+                return None;
+            }
+            last = Some(input.clone());
+            Some(input)
+        });
+        self
+    }
+
+    /// Returns the number of events dropped so far.
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Returns the wrapped monitor.
+    pub fn monitor(&self) -> &M {
+        &self.monitor
+    }
+
+    /// Returns the wrapped monitor, discarding the preprocessing stages.
+    pub fn into_inner(self) -> M {
+        self.monitor
+    }
+}
+
+impl<M, R, I> Verify<R> for Preprocessed<M, R, I>
+where
+    M: Verify<I>,
+{
+    fn next(&mut self, raw: &R) -> Result<Verdict, MonitorError> {
+        match (self.stage)(raw) {
+            Some(input) => self.verdict = self.monitor.next(&input)?,
+            None => self.dropped += 1,
+        }
+        Ok(self.verdict)
+    }
+
+    fn initial_verdict(&self) -> Verdict {
+        self.monitor.initial_verdict()
+    }
+}
+
+/// A violation seen by a [Tolerant] monitor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ViolationObserved {
+    /// The offset of the input that produced the violation.
+    pub offset: usize,
+    /// The number of violations seen so far, including this one.
+    pub count: usize,
+}
+
+/// A monitor tolerating a number of violations before reporting one, created by
+/// [Monitor::with_tolerance].
+///
+/// Once the tolerance is exceeded, its verdict stays [Verdict::Violated] and inputs are no longer
+/// passed on to the wrapped monitor.
+pub struct Tolerant<M> {
+    monitor: M,
+    tolerance: usize,
+    offset: usize,
+    violations: Vec<ViolationObserved>,
+    observer: Option<fn(&ViolationObserved)>,
+}
+
+impl<M> Tolerant<M> {
+    /// Calls `observer` on every violation, including the one reported as final.
+    pub fn with_observer(mut self, observer: fn(&ViolationObserved)) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Returns the violations seen so far, in order.
+    pub fn violations(&self) -> &[ViolationObserved] {
+        &self.violations
+    }
+
+    /// Returns the number of violations that are still tolerated.
+    pub fn remaining(&self) -> usize {
+        self.tolerance.saturating_sub(self.violations.len())
+    }
+
+    /// Checks if more violations were seen than tolerated.
+    pub fn is_exceeded(&self) -> bool {
+        self.violations.len() > self.tolerance
+    }
+
+    /// Returns the wrapped monitor.
+    pub fn monitor(&self) -> &M {
+        &self.monitor
+    }
+
+    /// Returns the wrapped monitor, discarding the violations seen.
+    pub fn into_inner(self) -> M {
+        self.monitor
+    }
+}
+
+impl<M, I> Verify<I> for Tolerant<M>
+where
+    M: Verify<I>,
+{
+    fn next(&mut self, input: &I) -> Result<Verdict, MonitorError> {
+        if self.is_exceeded() {
+            return Ok(Verdict::Violated);
+        }
+
+        let offset = self.offset;
+        self.offset += 1;
+        match self.monitor.next(input)? {
+            Verdict::Violated => {
+                let violation = ViolationObserved {
+                    offset,
+                    count: self.violations.len() + 1,
+                };
+                if let Some(observer) = self.observer {
+                    observer(&violation);
+                }
+                self.violations.push(violation);
+                Ok(match self.is_exceeded() {
+                    true => Verdict::Violated,
+                    false => Verdict::Inconclusive,
+                })
+            }
+            verdict => Ok(verdict),
+        }
+    }
+
+    fn initial_verdict(&self) -> Verdict {
+        match self.monitor.initial_verdict() {
+            Verdict::Violated if self.tolerance > 0 => Verdict::Inconclusive,
+            verdict => verdict,
+        }
+    }
+}
+
+/// A partial monitor that tracks one aspect of property verification.
+///
+/// A partial monitor is used internally by the main Monitor to track either
+/// property satisfaction (prove) or property violation (falsify).
+///
+/// # Type Parameters
+///
+/// * `D` - The data type for machine states
+/// * `I` - The input type for the machine
+/// * `U` - The update type with update function
+struct PartialMonitor<D, I, U> {
+    state: State<D>,
+    machine: Machine<D, I, U>,
+    // Indexes the non-empty intervals of each location for lookups in logarithmic time.
+    non_empty_states: HashMap<String, BoundSet<D>>,
+    // How the non-empty intervals approximate the states that lead to acceptance.
+    approximation: Approximation,
+    // The index of the transition taken by the last input, if any.
+    taken: Option<usize>,
+}
+
+impl<D, I, U> PartialMonitor<D, I, U> {
+    /// Creates a prover monitor from the given location, data, and machine.
+    ///
+    /// A prover monitor is used to detect when a property is satisfied.
+    ///
+    /// # Arguments
+    ///
+    /// * `location` - The initial location in the machine
+    /// * `data` - The initial data value
+    /// * `machine` - The machine to monitor
+    ///
+    /// # Returns
+    ///
+    /// A new `PartialMonitor` instance or an error if construction fails
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let prover = PartialMonitor::prove_from("start", 0, machine)?;
+    /// ```
+    fn prove_from(location: &str, data: D, machine: Machine<D, I, U>) -> Result<Self, MonitorError>
+    where
+        D: Eq + Hash + Clone + fmt::Debug + Bounded + Ord + Copy + fmt::Display,
+        U: Clone + Update<D = D>,
+    {
+        let complement = machine
+            .complement()
+            .map_err(|e| MonitorError::ConstructionFailed(format!("complement failed: {}", e)))?;
+
+        PartialMonitor::falsify_from(location, data, complement)
+    }
+
+    /// Creates a falsifier monitor from the given location, data, and machine.
+    ///
+    /// A falsifier monitor is used to detect when a property is violated.
+    ///
+    /// # Arguments
+    ///
+    /// * `location` - The initial location in the machine
+    /// * `data` - The initial data value
+    /// * `machine` - The machine to monitor
+    ///
+    /// # Returns
+    ///
+    /// A new `PartialMonitor` instance or an error if construction fails
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let falsifier = PartialMonitor::falsify_from("start", 0, machine)?;
+    /// ```
+    fn falsify_from(
+        location: &str,
+        data: D,
+        machine: Machine<D, I, U>,
+    ) -> Result<Self, MonitorError>
+    where
+        D: Eq + Hash + Clone + fmt::Debug + Bounded + Ord + Copy + fmt::Display,
+        U: Clone + Update<D = D>,
+    {
+        let location = String::from(location);
+
+        // Find all states
+        let outcome = machine
+            .find_non_empty_with(&location, |_| {}, &CancellationToken::new())
+            .map_err(|e| MonitorError::ConstructionFailed(format!("partial monitor: {}", e)))?;
+        let non_empty_states = outcome
+            .result
+            .into_iter()
+            .map(|(location, bound)| (location, BoundSet::from(bound)))
+            .collect();
+
+        // Construct the initial state of the monitor.
+        let state = State { location, data };
+
+        Ok(PartialMonitor {
+            state,
+            machine,
+            non_empty_states,
+            approximation: outcome.approximation,
+            taken: None,
+        })
+    }
+
+    // Adds the non-empty intervals found from `location` to those used by this monitor.
+    fn include_start(&mut self, location: &str) -> Result<(), MonitorError>
+    where
+        D: Eq + Hash + Clone + fmt::Debug + Bounded + Ord + Copy + fmt::Display,
+        U: Update<D = D>,
+    {
+        let outcome = self
+            .machine
+            .find_non_empty_with(location, |_| {}, &CancellationToken::new())
+            .map_err(|e| MonitorError::ConstructionFailed(format!("partial monitor: {}", e)))?;
+
+        self.approximation = self.approximation.and(outcome.approximation);
+        for (location, bound) in outcome.result {
+            self.non_empty_states
+                .entry(location)
+                .or_insert_with(BoundSet::new)
+                .insert(bound);
+        }
+
+        Ok(())
+    }
+
+    // Replaces the non-empty intervals used by this monitor with those found by the refined
+    // analysis from the locations of `starts`.
+    fn refine(&mut self, starts: &[State<D>], rounds: usize)
+    where
+        D: Integer + Copy + Bounded + Hash,
+        U: Update<D = D>,
+    {
+        self.non_empty_states.clear();
+        self.approximation = Approximation::Exact;
+        for start in starts {
+            let outcome = self.machine.find_non_empty_refined(&start.location, rounds);
+
+            self.approximation = self.approximation.and(outcome.approximation);
+            for (location, set) in outcome.result {
+                let states = self
+                    .non_empty_states
+                    .entry(location)
+                    .or_insert_with(BoundSet::new);
+                for bound in set.iter() {
+                    states.insert(bound);
+                }
+            }
+        }
+    }
+
+    fn next(&mut self, input: &I) -> Result<bool, MonitorError>
+    where
+        D: Eq + Hash + Clone + fmt::Debug + Bounded + Ord + Copy + fmt::Display,
         U: Clone + Update<D = D>,
     {
         // Feed the input to the partial monitor using the current state.
-        // Record the output state as next.
-        let mut next = self.machine.transition(input, vec![self.state.clone()]);
+        (self.taken, self.state) = self.step_taking(&self.state, input)?;
+
+        // A verdict can only be returned when the next state cannot reach an accepting
+        // condition.
+        Ok(self.is_empty())
+    }
+
+    // Returns the state reached from `state` on `input`, without changing the current state.
+    fn step(&self, state: &State<D>, input: &I) -> Result<State<D>, MonitorError>
+    where
+        D: Eq + Hash + Clone + fmt::Debug + Bounded + Ord + Copy + fmt::Display,
+        U: Clone + Update<D = D>,
+    {
+        self.step_taking(state, input).map(|(_, state)| state)
+    }
+
+    // Returns the state reached from `state` on `input`, with the index of the transition taken.
+    fn step_taking(&self, state: &State<D>, input: &I) -> Result<Successor<D>, MonitorError>
+    where
+        D: Eq + Hash + Clone + fmt::Debug + Bounded + Ord + Copy + fmt::Display,
+        U: Clone + Update<D = D>,
+    {
+        let mut next = self
+            .machine
+            .successors(state.clone(), input)
+            .map_err(|e| match e {
+                MachineError::AssertionViolated(violation) => {
+                    MonitorError::AssertionViolated(violation)
+                }
+                e => MonitorError::TransitionFailed(format!("{}", e)),
+            })?;
 
         // If there is more than one next state, return an error.
         if next.len() == 1 {
-            let (location, data) = next.pop().expect("the length was just checked").into();
+            return Ok(next.pop().expect("the length was just checked"));
+        }
 
-            // If the next state is in the sink state list, then check the non_empty interval.
-            if let Some(bound) = self.non_empty_states.get(&location) {
-                // If the next state is in the interval, we are still inconclusive.
-                if bound.contains(&data) {
-                    // This is because a verdict can only be returned when the next state cannot reach an
-                    // accepting condition.
+        // Name the transitions involved, so the error can be traced back to the specification.
+        let label = |index: usize| {
+            self.machine
+                .transition_label(&TransitionId::new(&state.location, index))
+        };
 
-                    self.state = (location, data).into();
-                    return Ok(false);
+        // The machine is malformed.
+        if next.is_empty() {
+            let count = self
+                .machine
+                .get_transitions_from(&state.location)
+                .map_or(0, Vec::len);
+            return Err(MonitorError::TransitionFailed(format!(
+                "no transition is enabled from {:?}, tried [{}]",
+                state,
+                (0..count).map(label).collect::<Vec<_>>().join(", ")
+            )));
+        }
+
+        // The machine is non-deterministic.
+        Err(MonitorError::TransitionFailed(format!(
+            "{} transitions are enabled from {:?}: [{}]",
+            next.len(),
+            state,
+            next.iter()
+                .map(|(index, next)| match index {
+                    Some(index) => format!("{} to {:?}", label(*index), next),
+                    None => format!("no transition policy to {:?}", next),
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        )))
+    }
+
+    // Returns every successor of `states` and `uncertain` on `input`, dropping the states that
+    // have none.
+    fn step_hypotheses(
+        &self,
+        states: &[State<D>],
+        uncertain: &Uncertain<D>,
+        input: &I,
+    ) -> Result<(Vec<State<D>>, Uncertain<D>), MonitorError>
+    where
+        D: Eq + Hash + Clone + fmt::Debug + Bounded + Ord + Copy + fmt::Display,
+        U: Clone + Update<D = D>,
+    {
+        let mut next = Vec::new();
+        for state in states {
+            match self.machine.successors(state.clone(), input) {
+                Ok(successors) => next.extend(successors.into_iter().map(|(_, state)| state)),
+                Err(MachineError::AssertionViolated(violation)) => {
+                    return Err(MonitorError::AssertionViolated(violation));
                 }
+                // The input rules this hypothesis out.
+                Err(_) => {}
             }
+        }
 
-            // In this case we are in an empty state with no possible path to an accepting
-            // condition.
-            // Return a conclusive verdict.
-            return Ok(true);
+        let mut next_uncertain = Uncertain::new();
+        for (location, interval) in uncertain {
+            for (to_location, next) in self.machine.interval_successors(location, interval) {
+                merge_interval(&mut next_uncertain, to_location, next);
+            }
         }
 
-        // The machine is non-deterministic or malformed.
-        Err(MonitorError::TransitionFailed(format!(
-            "length of states is not 1: {:?}",
-            next
-        )))
+        settle(&mut next, &mut next_uncertain);
+
+        if next.is_empty() && next_uncertain.is_empty() {
+            return Err(MonitorError::TransitionFailed(
+                "no hypothesis has a transition for the input".into(),
+            ));
+        }
+
+        Ok((next, next_uncertain))
+    }
+
+    /// Checks if the current state has no possible path to an accepting condition.
+    fn is_empty(&self) -> bool
+    where
+        D: Ord + Copy + Bounded,
+    {
+        self.is_empty_at(&self.state)
+    }
+
+    // Checks if no data in `interval` has a possible path from `location` to an accepting
+    // condition.
+    fn is_empty_over(&self, location: &str, interval: &Bound<D>) -> bool
+    where
+        D: Ord + Copy + Bounded,
+    {
+        match self.non_empty_states.get(location) {
+            Some(bound) => !bound.intersects(interval),
+            None => true,
+        }
+    }
+
+    // Checks if `state` has no possible path to an accepting condition.
+    fn is_empty_at(&self, state: &State<D>) -> bool
+    where
+        D: Ord + Copy + Bounded,
+    {
+        // The state is non-empty only if its data lies inside of the non-empty interval.
+        match self.non_empty_states.get(&state.location) {
+            Some(bound) => !bound.contains(&state.data),
+            None => true,
+        }
     }
 }
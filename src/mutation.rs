@@ -0,0 +1,320 @@
+//! # Mutation Module
+//!
+//! This module measures how good a suite of input sequences is at testing a [Machine]. It
+//! derives mutants of the machine, each with a single small fault, and checks which of them the
+//! suite distinguishes from the original. A mutant that survives points at behaviour that no
+//! input sequence in the suite observes.
+//!
+//! ## Examples
+//!
+//! ```
+//! use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+//! use rust_efsm::mutation;
+//!
+//! let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+//!     .with_transition("s0", Transition {
+//!         to_location: "s1".into(),
+//!         enable: |_, i| *i == b'a',
+//!         ..Default::default()
+//!     })
+//!     .with_accepting("s1")
+//!     .build();
+//!
+//! // The empty sequence only distinguishes the mutant that makes s0 accepting.
+//! let report = mutation::evaluate(&machine, "s0", 0, &[vec![]]);
+//! assert_eq!(report.killed.len(), 1);
+//!
+//! let report = mutation::evaluate(&machine, "s0", 0, &[vec![], vec![b'a']]);
+//! assert!(report.survived.is_empty());
+//! ```
+
+use crate::bound::Bound;
+use crate::machine::{Machine, TransitionId, Update};
+use num::{CheckedAdd, CheckedSub, One};
+use std::fmt::Debug;
+
+/// A single fault introduced into a machine.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Mutation<D> {
+    /// Makes an accepting location rejecting, or a rejecting location accepting.
+    FlipAccepting(String),
+    /// Replaces the bound of a transition with one that is off by one at an endpoint.
+    ///
+    /// Bounds are only used by the analyses, so such a mutant can only be distinguished by an
+    /// observation that relies on them, such as the verdicts of a
+    /// [monitor](crate::monitor::Monitor).
+    Bound {
+        /// The mutated transition.
+        transition: TransitionId,
+        /// The new bound.
+        bound: Bound<D>,
+    },
+    /// Changes the target location of a transition.
+    Retarget {
+        /// The mutated transition.
+        transition: TransitionId,
+        /// The new target location.
+        to_location: String,
+    },
+    /// Exchanges the updates of two transitions.
+    SwapUpdates(TransitionId, TransitionId),
+}
+
+impl<D: Clone> Mutation<D> {
+    /// Returns a copy of `machine` with this mutation applied.
+    pub fn apply<I, U: Clone>(&self, machine: &Machine<D, I, U>) -> Machine<D, I, U> {
+        let mut mutant = machine.clone();
+
+        match self {
+            Mutation::FlipAccepting(location) => {
+                let accepting = mutant.get_accepting().contains(location);
+                mutant.set_accepting(location, !accepting);
+            }
+            Mutation::Bound { transition, bound } => {
+                if let Some(transition) = mutant.get_transition_mut(transition) {
+                    transition.bound = bound.clone();
+                }
+            }
+            Mutation::Retarget {
+                transition,
+                to_location,
+            } => {
+                if let Some(transition) = mutant.get_transition_mut(transition) {
+                    transition.to_location = to_location.clone();
+                }
+            }
+            Mutation::SwapUpdates(first, second) => {
+                let updates = (
+                    machine.get_transition(first).map(|t| t.update.clone()),
+                    machine.get_transition(second).map(|t| t.update.clone()),
+                );
+
+                if let (Some(first_update), Some(second_update)) = updates {
+                    if let Some(transition) = mutant.get_transition_mut(first) {
+                        transition.update = second_update;
+                    }
+                    if let Some(transition) = mutant.get_transition_mut(second) {
+                        transition.update = first_update;
+                    }
+                }
+            }
+        }
+
+        mutant
+    }
+}
+
+/// Derives every single fault mutation of `machine`.
+///
+/// The mutations are ordered so that the same machine always yields the same list. Shifting an
+/// endpoint of a bound past the other is skipped, since an empty bound only yields a trivially
+/// dead mutant.
+///
+/// ```
+/// use rust_efsm::bound::Bound;
+/// use rust_efsm::machine::{AddUpdate, MachineBuilder, Transition};
+/// use rust_efsm::mutation::{self, Mutation};
+///
+/// let machine = MachineBuilder::<u8, u8, AddUpdate<u8>>::new()
+///     .with_transition("s0", Transition { to_location: "s1".into(), ..Default::default() })
+///     .with_accepting("s1")
+///     .build();
+///
+/// let mutations = mutation::mutations(&machine);
+/// assert!(mutations.contains(&Mutation::FlipAccepting("s0".into())));
+/// assert!(mutations.iter().any(|mutation| matches!(mutation, Mutation::Retarget { .. })));
+///
+/// let machine = MachineBuilder::<u8, u8, AddUpdate<u8>>::new()
+///     .with_transition("s0", Transition {
+///         to_location: "s1".into(),
+///         bound: Bound { lower: Some(2), upper: Some(2) },
+///         ..Default::default()
+///     })
+///     .build();
+///
+/// let bounds: Vec<_> = mutation::mutations(&machine)
+///     .into_iter()
+///     .filter_map(|mutation| match mutation {
+///         Mutation::Bound { bound, .. } => Some((bound.lower, bound.upper)),
+///         _ => None,
+///     })
+///     .collect();
+/// assert_eq!(bounds, [(Some(1), Some(2)), (Some(2), Some(3))]);
+/// ```
+pub fn mutations<D, I, U>(machine: &Machine<D, I, U>) -> Vec<Mutation<D>>
+where
+    D: Copy + PartialOrd + CheckedAdd + CheckedSub + One,
+    U: PartialEq,
+{
+    let locations = machine.all_locations();
+    let mut ids: Vec<_> = machine.transition_ids().collect();
+    ids.sort();
+
+    let mut mutations: Vec<Mutation<D>> = locations
+        .iter()
+        .map(|location| Mutation::FlipAccepting((*location).into()))
+        .collect();
+
+    for id in &ids {
+        let Some(transition) = machine.get_transition(id) else {
+            continue;
+        };
+        let bound = &transition.bound;
+
+        // Move each explicit endpoint one step in both directions, unless that leaves the bound
+        // empty, which would only yield a trivially dead mutant.
+        let non_empty = |lower: Option<D>, upper: Option<D>| match (lower, upper) {
+            (Some(lower), Some(upper)) => lower <= upper,
+            _ => true,
+        };
+        for lower in shifted(bound.lower) {
+            if !non_empty(Some(lower), bound.upper) {
+                continue;
+            }
+
+            mutations.push(Mutation::Bound {
+                transition: id.clone(),
+                bound: Bound {
+                    lower: Some(lower),
+                    upper: bound.upper,
+                },
+            });
+        }
+
+        for upper in shifted(bound.upper) {
+            if !non_empty(bound.lower, Some(upper)) {
+                continue;
+            }
+            mutations.push(Mutation::Bound {
+                transition: id.clone(),
+                bound: Bound {
+                    lower: bound.lower,
+                    upper: Some(upper),
+                },
+            });
+        }
+
+        for location in &locations {
+            if **location != transition.to_location {
+                mutations.push(Mutation::Retarget {
+                    transition: id.clone(),
+                    to_location: (*location).into(),
+                });
+            }
+        }
+    }
+
+    for (idx, first) in ids.iter().enumerate() {
+        for second in &ids[idx + 1..] {
+            let updates = (
+                machine.get_transition(first),
+                machine.get_transition(second),
+            );
+            if let (Some(first_transition), Some(second_transition)) = updates {
+                if first_transition.update != second_transition.update {
+                    mutations.push(Mutation::SwapUpdates(first.clone(), second.clone()));
+                }
+            }
+        }
+    }
+
+    mutations
+}
+
+/// The outcome of running a suite of input sequences against the mutants of a machine.
+///
+/// See [evaluate] and [evaluate_with].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MutationReport<D> {
+    /// The mutations that were distinguished, each with the index of the first input sequence
+    /// in the suite that distinguished it.
+    pub killed: Vec<(Mutation<D>, usize)>,
+    /// The mutations that no input sequence distinguished.
+    pub survived: Vec<Mutation<D>>,
+}
+
+impl<D> MutationReport<D> {
+    /// Returns the fraction of mutations that were distinguished, or 1 if there were none.
+    pub fn score(&self) -> f64 {
+        let total = self.killed.len() + self.survived.len();
+        match total {
+            0 => 1.0,
+            _ => self.killed.len() as f64 / total as f64,
+        }
+    }
+}
+
+/// Checks which [mutations] of `machine` are distinguished by `suite`, by comparing whether each
+/// input sequence is accepted from `location` with `data`.
+pub fn evaluate<D, I, U>(
+    machine: &Machine<D, I, U>,
+    location: &str,
+    data: D,
+    suite: &[Vec<I>],
+) -> MutationReport<D>
+where
+    D: Copy + Debug + PartialOrd + CheckedAdd + CheckedSub + One,
+    I: Clone + Debug,
+    U: Update<D = D> + Clone + PartialEq,
+{
+    evaluate_with(machine, suite, |machine, input| {
+        machine.exec(location, data, input.to_vec())
+    })
+}
+
+/// Checks which [mutations] of `machine` are distinguished by `suite`, by comparing the result
+/// of `observe` on the original machine and on each mutant.
+///
+/// An observation that also uses the analyses, such as the verdicts of a
+/// [monitor](crate::monitor::Monitor), can distinguish [bound](Mutation::Bound) mutations.
+pub fn evaluate_with<D, I, U, R, F>(
+    machine: &Machine<D, I, U>,
+    suite: &[Vec<I>],
+    observe: F,
+) -> MutationReport<D>
+where
+    D: Copy + PartialOrd + CheckedAdd + CheckedSub + One,
+    U: Clone + PartialEq,
+    R: PartialEq,
+    F: Fn(&Machine<D, I, U>, &[I]) -> R,
+{
+    let expected: Vec<R> = suite.iter().map(|input| observe(machine, input)).collect();
+
+    let mut report = MutationReport {
+        killed: Vec::new(),
+        survived: Vec::new(),
+    };
+
+    for mutation in mutations(machine) {
+        let mutant = mutation.apply(machine);
+        let killed_by = suite
+            .iter()
+            .zip(&expected)
+            .position(|(input, expected)| observe(&mutant, input) != *expected);
+
+        match killed_by {
+            Some(idx) => report.killed.push((mutation, idx)),
+            None => report.survived.push(mutation),
+        }
+    }
+
+    report
+}
+
+// Returns the values one below and one above `endpoint`, when it is explicit and they exist.
+fn shifted<D>(endpoint: Option<D>) -> Vec<D>
+where
+    D: Copy + CheckedAdd + CheckedSub + One,
+{
+    let Some(endpoint) = endpoint else {
+        return Vec::new();
+    };
+
+    [
+        endpoint.checked_sub(&D::one()),
+        endpoint.checked_add(&D::one()),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
@@ -0,0 +1,135 @@
+//! # NFA Module
+//!
+//! This module provides [Nfa], a location-level projection of a [Machine] that keeps only its
+//! locations, its transitions between them, and its accepting locations. Data, guards, bounds,
+//! and updates are discarded, so the projection over-approximates the behaviour of the machine:
+//! every run of the machine is a path of the projection, but not every path is a run.
+//!
+//! This is enough for quick structural analyses and visualizations that do not need the data.
+
+use crate::machine::{Machine, NoTransitionPolicy, TransitionId};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A non-deterministic finite automaton over the transitions of a machine.
+///
+/// Each edge is labelled with the [TransitionId] it was projected from, or with `None` for an
+/// implicit move under the machine's [NoTransitionPolicy].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Nfa {
+    /// Every location of the machine.
+    pub locations: BTreeSet<String>,
+    /// The accepting locations.
+    pub accepting: BTreeSet<String>,
+    /// The labelled edges out of each location, with their target location.
+    pub edges: BTreeMap<String, Vec<(Option<TransitionId>, String)>>,
+}
+
+impl Nfa {
+    /// Iterates over the locations reachable from `location` in one step.
+    pub fn successors<'a>(&'a self, location: &str) -> impl Iterator<Item = &'a str> + 'a {
+        self.edges
+            .get(location)
+            .into_iter()
+            .flatten()
+            .map(|(_, to_location)| to_location.as_str())
+    }
+
+    /// Returns the locations reachable from `location` in any number of steps, including
+    /// `location` itself.
+    pub fn reachable(&self, location: &str) -> BTreeSet<String> {
+        let mut reachable = BTreeSet::from([String::from(location)]);
+        let mut to_visit = vec![String::from(location)];
+
+        while let Some(location) = to_visit.pop() {
+            for next in self.successors(&location) {
+                if reachable.insert(next.into()) {
+                    to_visit.push(next.into());
+                }
+            }
+        }
+
+        reachable
+    }
+
+    /// Checks if the sequence of edge labels `labels` leads from `location` to an accepting
+    /// location.
+    pub fn accepts<'a>(
+        &self,
+        location: &str,
+        labels: impl IntoIterator<Item = &'a Option<TransitionId>>,
+    ) -> bool {
+        let mut current = BTreeSet::from([String::from(location)]);
+
+        for label in labels {
+            current = current
+                .iter()
+                .flat_map(|location| self.edges.get(location).into_iter().flatten())
+                .filter(|(edge, _)| edge == label)
+                .map(|(_, to_location)| to_location.clone())
+                .collect();
+        }
+
+        current
+            .iter()
+            .any(|location| self.accepting.contains(location))
+    }
+}
+
+impl<D, I, U> Machine<D, I, U> {
+    /// Projects this machine onto its locations, discarding data, guards, bounds, and updates.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition, TransitionId};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s1".into(),
+    ///         enable: |data, _| *data > 100,
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("s1")
+    ///     .build();
+    ///
+    /// let nfa = machine.project_locations();
+    /// assert_eq!(nfa.locations.len(), 2);
+    /// assert!(nfa.reachable("s0").contains("s1"));
+    ///
+    /// // The guard is discarded, so the projection accepts the transition regardless of data.
+    /// assert!(nfa.accepts("s0", &[Some(TransitionId::new("s0", 0))]));
+    /// ```
+    pub fn project_locations(&self) -> Nfa {
+        let locations: BTreeSet<String> =
+            self.all_locations().into_iter().map(String::from).collect();
+
+        let mut edges: BTreeMap<String, Vec<(Option<TransitionId>, String)>> = BTreeMap::new();
+
+        for location in &locations {
+            let transitions = self.get_transitions_from(location).into_iter().flatten();
+            let mut out: Vec<_> = transitions
+                .enumerate()
+                .map(|(index, transition)| {
+                    (
+                        Some(TransitionId::new(location, index)),
+                        transition.to_location.clone(),
+                    )
+                })
+                .collect();
+
+            match self.get_no_transition_policy() {
+                NoTransitionPolicy::Stutter => out.push((None, location.clone())),
+                NoTransitionPolicy::SinkTo(sink) => out.push((None, sink.clone())),
+                NoTransitionPolicy::Reject | NoTransitionPolicy::Error => {}
+            }
+
+            if !out.is_empty() {
+                edges.insert(location.clone(), out);
+            }
+        }
+
+        Nfa {
+            locations,
+            accepting: self.get_accepting().iter().cloned().collect(),
+            edges,
+        }
+    }
+}
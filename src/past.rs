@@ -0,0 +1,404 @@
+//! # Past Module
+//!
+//! This module provides [updates](Update) that keep a history register in the data, so
+//! past-time properties such as "q has held since p" can be written without a custom update:
+//!
+//! - [LatchUpdate] stores a sticky boolean as zero or one.
+//! - [CountSinceUpdate] counts the transitions taken since it was last reset.
+//! - [TimeSinceUpdate] adds up the time elapsed since it was last reset.
+//!
+//! Each update has an exact [update_interval](Update::update_interval), so the analyses of a
+//! [Machine](crate::machine::Machine) remain sound. The [MachineBuilder] helpers add the
+//! self-loops that maintain a register at a location dedicated to it.
+
+use crate::bound::Bound;
+use crate::machine::{InputAbstraction, MachineBuilder, Transition, Update};
+use num::{Bounded, CheckedAdd, One, Zero};
+use std::fmt;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+enum Latch {
+    #[default]
+    Keep,
+    Set,
+    Clear,
+}
+
+/// An update that keeps, sets to one, or clears to zero a sticky boolean.
+///
+/// ```
+/// use rust_efsm::bound::Bound;
+/// use rust_efsm::machine::Update;
+/// use rust_efsm::past::LatchUpdate;
+///
+/// assert_eq!(LatchUpdate::<u8>::set().update(0, &()), 1);
+/// assert_eq!(LatchUpdate::<u8>::keep().update(1, &()), 1);
+/// assert_eq!(
+///     LatchUpdate::<u8>::clear().update_interval(Bound::unbounded()),
+///     Bound { lower: Some(0), upper: Some(0) },
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Hash)]
+pub struct LatchUpdate<D> {
+    latch: Latch,
+    data: PhantomData<D>,
+}
+
+impl<D> LatchUpdate<D> {
+    /// Creates an update that leaves the latch unchanged.
+    pub fn keep() -> Self {
+        LatchUpdate {
+            latch: Latch::Keep,
+            data: PhantomData,
+        }
+    }
+
+    /// Creates an update that sets the latch to one.
+    pub fn set() -> Self {
+        LatchUpdate {
+            latch: Latch::Set,
+            data: PhantomData,
+        }
+    }
+
+    /// Creates an update that clears the latch to zero.
+    pub fn clear() -> Self {
+        LatchUpdate {
+            latch: Latch::Clear,
+            data: PhantomData,
+        }
+    }
+}
+
+impl<D> Update for LatchUpdate<D>
+where
+    D: Zero + One,
+{
+    type D = D;
+
+    fn update<I>(&self, data: D, _input: &I) -> D {
+        match self.latch {
+            Latch::Keep => data,
+            Latch::Set => D::one(),
+            Latch::Clear => D::zero(),
+        }
+    }
+
+    fn update_interval(&self, interval: Bound<D>) -> Bound<D> {
+        match self.latch {
+            Latch::Keep => interval,
+            Latch::Set => Bound {
+                lower: Some(D::one()),
+                upper: Some(D::one()),
+            },
+            Latch::Clear => Bound {
+                lower: Some(D::zero()),
+                upper: Some(D::zero()),
+            },
+        }
+    }
+}
+
+impl<D> fmt::Display for LatchUpdate<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.latch {
+            Latch::Keep => write!(f, "data"),
+            Latch::Set => write!(f, "data = 1"),
+            Latch::Clear => write!(f, "data = 0"),
+        }
+    }
+}
+
+/// An update that counts the transitions taken since it was last reset.
+///
+/// The count saturates at the largest value of the data type instead of overflowing.
+///
+/// ```
+/// use rust_efsm::bound::Bound;
+/// use rust_efsm::machine::Update;
+/// use rust_efsm::past::CountSinceUpdate;
+///
+/// assert_eq!(CountSinceUpdate::<u8>::count().update(254, &()), 255);
+/// assert_eq!(CountSinceUpdate::<u8>::count().update(255, &()), 255);
+/// assert_eq!(CountSinceUpdate::<u8>::reset().update(7, &()), 0);
+/// assert_eq!(
+///     CountSinceUpdate::<u8>::count().update_interval(Bound { lower: Some(3), upper: None }),
+///     Bound { lower: Some(4), upper: Some(255) },
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Hash)]
+pub struct CountSinceUpdate<D> {
+    reset: bool,
+    data: PhantomData<D>,
+}
+
+impl<D> CountSinceUpdate<D> {
+    /// Creates an update that adds one to the count.
+    pub fn count() -> Self {
+        CountSinceUpdate {
+            reset: false,
+            data: PhantomData,
+        }
+    }
+
+    /// Creates an update that resets the count to zero.
+    pub fn reset() -> Self {
+        CountSinceUpdate {
+            reset: true,
+            data: PhantomData,
+        }
+    }
+}
+
+impl<D> Update for CountSinceUpdate<D>
+where
+    D: Zero + One + Bounded + CheckedAdd + Copy,
+{
+    type D = D;
+
+    fn update<I>(&self, data: D, _input: &I) -> D {
+        if self.reset {
+            D::zero()
+        } else {
+            saturating_add(data, D::one())
+        }
+    }
+
+    fn update_interval(&self, interval: Bound<D>) -> Bound<D> {
+        if self.reset {
+            return Bound {
+                lower: Some(D::zero()),
+                upper: Some(D::zero()),
+            };
+        }
+
+        let (lower, upper) = interval.as_explicit();
+        Bound {
+            lower: Some(saturating_add(lower, D::one())),
+            upper: Some(saturating_add(upper, D::one())),
+        }
+    }
+}
+
+impl<D> fmt::Display for CountSinceUpdate<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.reset {
+            write!(f, "data = 0")
+        } else {
+            write!(f, "data += 1")
+        }
+    }
+}
+
+/// An update that adds up the time elapsed since it was last reset.
+///
+/// Every transition that does not reset the register adds a fixed `elapsed` time, such as the
+/// period at which inputs arrive. The sum saturates at the largest value of the data type.
+///
+/// ```
+/// use rust_efsm::machine::Update;
+/// use rust_efsm::past::TimeSinceUpdate;
+///
+/// assert_eq!(TimeSinceUpdate::<u32>::elapse(20).update(100, &()), 120);
+/// assert_eq!(TimeSinceUpdate::<u32>::reset().update(100, &()), 0);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Hash)]
+pub struct TimeSinceUpdate<D> {
+    /// The time added by this update, or `None` if it resets the register.
+    pub elapsed: Option<D>,
+}
+
+impl<D> TimeSinceUpdate<D> {
+    /// Creates an update that adds `elapsed` to the time.
+    pub fn elapse(elapsed: D) -> Self {
+        TimeSinceUpdate {
+            elapsed: Some(elapsed),
+        }
+    }
+
+    /// Creates an update that resets the time to zero.
+    pub fn reset() -> Self {
+        TimeSinceUpdate { elapsed: None }
+    }
+}
+
+impl<D> Update for TimeSinceUpdate<D>
+where
+    D: Zero + Bounded + CheckedAdd + Copy,
+{
+    type D = D;
+
+    fn update<I>(&self, data: D, _input: &I) -> D {
+        match self.elapsed {
+            Some(elapsed) => saturating_add(data, elapsed),
+            None => D::zero(),
+        }
+    }
+
+    fn update_interval(&self, interval: Bound<D>) -> Bound<D> {
+        match self.elapsed {
+            Some(elapsed) => {
+                let (lower, upper) = interval.as_explicit();
+                Bound {
+                    lower: Some(saturating_add(lower, elapsed)),
+                    upper: Some(saturating_add(upper, elapsed)),
+                }
+            }
+            None => Bound {
+                lower: Some(D::zero()),
+                upper: Some(D::zero()),
+            },
+        }
+    }
+}
+
+impl<D: fmt::Display> fmt::Display for TimeSinceUpdate<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.elapsed {
+            Some(elapsed) => write!(f, "data += {}", elapsed),
+            None => write!(f, "data = 0"),
+        }
+    }
+}
+
+// Adds `amount` to `data`, stopping at the largest value of the data type.
+fn saturating_add<D: Bounded + CheckedAdd>(data: D, amount: D) -> D {
+    data.checked_add(&amount).unwrap_or_else(D::max_value)
+}
+
+// Creates a self-loop on `location` that is enabled when `when` holds for the input.
+fn self_loop<D, I, U>(
+    location: &str,
+    when: impl Fn(&I) -> bool + Send + Sync + 'static,
+    update: U,
+) -> Transition<D, I, U> {
+    Transition {
+        to_location: location.into(),
+        enable: |_, _| true,
+        bound: Bound::unbounded(),
+        update,
+        guard: Some(Arc::new(move |_: &D, input: &I| when(input))),
+        name: None,
+        input: InputAbstraction::Unknown,
+        binding: None,
+    }
+}
+
+impl<D, I> MachineBuilder<D, I, LatchUpdate<D>>
+where
+    D: Default + Clone + Debug + Zero + One + 'static,
+    I: Debug + 'static,
+{
+    /// Add self-loops on `location` that latch whether `q` has held since `p` last held.
+    ///
+    /// The data is set to one on an input satisfying `p`, kept on an input satisfying only `q`,
+    /// and cleared otherwise. The location should have no other transitions.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{MachineBuilder, State};
+    /// use rust_efsm::past::LatchUpdate;
+    ///
+    /// // Every 'b' must come after an 'a', with only 'b's in between.
+    /// let machine = MachineBuilder::<u8, u8, LatchUpdate<u8>>::new()
+    ///     .with_since("m", |i| *i == b'a', |i| *i == b'b')
+    ///     .with_accepting("m")
+    ///     .build();
+    ///
+    /// let states = vec![State { location: "m".into(), data: 0 }];
+    /// let states = machine.transition(&b'a', states).unwrap();
+    /// let states = machine.transition(&b'b', states).unwrap();
+    /// assert_eq!(states[0].data, 1);
+    ///
+    /// let states = machine.transition(&b'c', states).unwrap();
+    /// assert_eq!(states[0].data, 0);
+    /// ```
+    pub fn with_since(self, location: &str, p: fn(&I) -> bool, q: fn(&I) -> bool) -> Self {
+        self.with_transition(location, self_loop(location, p, LatchUpdate::set()))
+            .with_transition(
+                location,
+                self_loop(location, move |i| !p(i) && q(i), LatchUpdate::keep()),
+            )
+            .with_transition(
+                location,
+                self_loop(location, move |i| !p(i) && !q(i), LatchUpdate::clear()),
+            )
+    }
+}
+
+impl<D, I> MachineBuilder<D, I, CountSinceUpdate<D>>
+where
+    D: Default + Clone + Debug + Zero + One + Bounded + CheckedAdd + Copy + 'static,
+    I: Debug + 'static,
+{
+    /// Add self-loops on `location` that count the inputs since one last satisfied `reset`.
+    ///
+    /// The location should have no other transitions.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{MachineBuilder, State};
+    /// use rust_efsm::past::CountSinceUpdate;
+    ///
+    /// let machine = MachineBuilder::<u8, u8, CountSinceUpdate<u8>>::new()
+    ///     .with_count_since("m", |i| *i == b'r')
+    ///     .build();
+    ///
+    /// let mut states = vec![State { location: "m".into(), data: 0 }];
+    /// for i in b"xrxx" {
+    ///     states = machine.transition(i, states).unwrap();
+    /// }
+    /// assert_eq!(states[0].data, 2);
+    /// ```
+    pub fn with_count_since(self, location: &str, reset: fn(&I) -> bool) -> Self {
+        self.with_transition(
+            location,
+            self_loop(location, reset, CountSinceUpdate::reset()),
+        )
+        .with_transition(
+            location,
+            self_loop(location, move |i| !reset(i), CountSinceUpdate::count()),
+        )
+    }
+}
+
+impl<D, I> MachineBuilder<D, I, TimeSinceUpdate<D>>
+where
+    D: Default + Clone + Debug + Zero + Bounded + CheckedAdd + Copy + 'static,
+    I: Debug + 'static,
+{
+    /// Add self-loops on `location` that add up the time since an input last satisfied `reset`,
+    /// assuming that `period` elapses between consecutive inputs.
+    ///
+    /// The location should have no other transitions.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{MachineBuilder, State};
+    /// use rust_efsm::past::TimeSinceUpdate;
+    ///
+    /// let machine = MachineBuilder::<u32, u8, TimeSinceUpdate<u32>>::new()
+    ///     .with_time_since("m", |i| *i == b'r', 50)
+    ///     .build();
+    ///
+    /// let mut states = vec![State { location: "m".into(), data: 0 }];
+    /// for i in b"rxxx" {
+    ///     states = machine.transition(i, states).unwrap();
+    /// }
+    /// assert_eq!(states[0].data, 150);
+    /// ```
+    pub fn with_time_since(self, location: &str, reset: fn(&I) -> bool, period: D) -> Self {
+        self.with_transition(
+            location,
+            self_loop(location, reset, TimeSinceUpdate::reset()),
+        )
+        .with_transition(
+            location,
+            self_loop(
+                location,
+                move |i| !reset(i),
+                TimeSinceUpdate::elapse(period),
+            ),
+        )
+    }
+}
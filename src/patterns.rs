@@ -0,0 +1,574 @@
+//! # Patterns Module
+//!
+//! This module builds machines for common properties, so that they need not be wired up by
+//! hand. Each function returns a [MachineBuilder] that can be extended further, such as with
+//! more transitions or another [NoTransitionPolicy](crate::machine::NoTransitionPolicy).
+//!
+//! Every pattern starts in [START] with its data at zero. A run that violates the property
+//! enters [VIOLATION], which is rejecting, and a run that satisfies it for good enters [DONE],
+//! which is accepting whatever follows.
+//!
+//! The counting patterns keep the count in the data with an [AddUpdate]. Their transitions
+//! check the count at runtime, and give the analyses the matching [bounds](Bound).
+//!
+//! ```
+//! use rust_efsm::monitor::{Monitor, Verdict};
+//! use rust_efsm::patterns::{self, START};
+//!
+//! // At most two errors.
+//! let machine = patterns::at_most_n::<u8, u8>(|i| *i == b'e', 2).build();
+//!
+//! let monitor = Monitor::new(START, 0, machine).unwrap();
+//! assert_eq!(monitor.check(b"xexe").unwrap(), Verdict::Inconclusive);
+//! assert_eq!(monitor.check(b"xexee").unwrap(), Verdict::Violated);
+//! ```
+//!
+//! The specification patterns of Dwyer et al. \[1\], [absence], [universality], [precedence],
+//! and [response], leave the data unchanged, and hold within a [Scope] of the trace.
+//!
+//! ```
+//! use rust_efsm::patterns::{self, Scope, START};
+//!
+//! // Every request is acknowledged, once the connection is open.
+//! let scope = Scope::After(|i| *i == b'o');
+//! let machine = patterns::response::<u8, u8>(|i| *i == b'q', |i| *i == b'a', scope).build();
+//! assert!(machine.exec(START, 0, b"qxoqxa".to_vec()));
+//! assert!(!machine.exec(START, 0, b"oqaqx".to_vec()));
+//! ```
+//!
+//! \[1\] Dwyer, M. B., Avrunin, G. S. & Corbett, J. C. Patterns in property specifications for
+//! finite-state verification. In Proceedings of the 21st International Conference on Software
+//! Engineering, 411–420 (1999).
+
+use crate::bound::Bound;
+use crate::deadline::guarded;
+use crate::machine::{AddUpdate, IdentityUpdate, InputAbstraction, MachineBuilder, Transition};
+use num::{Bounded, CheckedAdd, CheckedSub, One, Zero};
+use std::collections::BTreeSet;
+use std::fmt::Debug;
+use std::ops::{Add, Neg};
+use std::sync::Arc;
+
+/// The location every pattern starts in.
+pub const START: &str = "start";
+
+/// The rejecting location entered by runs that violate the property.
+pub const VIOLATION: &str = "violation";
+
+/// The accepting location entered by runs that satisfy the property whatever follows.
+pub const DONE: &str = "done";
+
+/// Builds a machine accepting the traces with at most `n` inputs satisfying `symbol`.
+pub fn at_most_n<D, I>(symbol: fn(&I) -> bool, n: D) -> MachineBuilder<D, I, AddUpdate<D>>
+where
+    D: Counter,
+    I: Debug + 'static,
+{
+    let builder = MachineBuilder::new()
+        .with_transition(
+            START,
+            count(START, Bound::unbounded(), D::zero(), move |i| !symbol(i)),
+        )
+        .with_transition(
+            VIOLATION,
+            Transition {
+                to_location: VIOLATION.into(),
+                ..Default::default()
+            },
+        )
+        .with_transition(START, count(VIOLATION, from(n), D::zero(), symbol))
+        .with_accepting(START)
+        .with_rejecting(VIOLATION);
+
+    match n.checked_sub(&D::one()) {
+        Some(last) => builder.with_transition(START, count(START, upto(last), D::one(), symbol)),
+        None => builder,
+    }
+}
+
+/// Builds a machine accepting the traces with at least `n` inputs satisfying `symbol` before
+/// the first input satisfying `other`.
+///
+/// An input satisfying both counts as `symbol`. The run enters [DONE] on the first input
+/// satisfying `other` once `n` were counted.
+///
+/// ```
+/// use rust_efsm::monitor::{Monitor, Verdict};
+/// use rust_efsm::patterns::{self, DONE, START};
+///
+/// // At least three pings before the first send.
+/// let machine = patterns::at_least_n_before::<u8, u8>(|i| *i == b'p', |i| *i == b's', 3)
+///     .with_alphabet(*b"psx")
+///     .build();
+///
+/// let report = machine.exec_report(START, 0, b"ppxps".to_vec());
+/// assert_eq!(report.states[0].location, DONE);
+///
+/// let monitor = Monitor::new(START, 0, machine).unwrap();
+/// assert_eq!(monitor.check(b"pps").unwrap(), Verdict::Violated);
+/// assert_eq!(monitor.check(b"ppps").unwrap(), Verdict::Satisfied);
+/// ```
+pub fn at_least_n_before<D, I>(
+    symbol: fn(&I) -> bool,
+    other: fn(&I) -> bool,
+    n: D,
+) -> MachineBuilder<D, I, AddUpdate<D>>
+where
+    D: Counter,
+    I: Debug + 'static,
+{
+    let neither = move |i: &I| !symbol(i) && !other(i);
+    let only_other = move |i: &I| !symbol(i) && other(i);
+
+    let builder = MachineBuilder::new()
+        .with_transition(START, count(START, Bound::unbounded(), D::zero(), neither))
+        .with_transition(START, count(START, from(n), D::zero(), symbol))
+        .with_transition(START, count(DONE, from(n), D::zero(), only_other))
+        .with_transition(
+            DONE,
+            Transition {
+                to_location: DONE.into(),
+                ..Default::default()
+            },
+        )
+        .with_transition(
+            VIOLATION,
+            Transition {
+                to_location: VIOLATION.into(),
+                ..Default::default()
+            },
+        )
+        .with_accepting_all([START, DONE])
+        .with_rejecting(VIOLATION);
+
+    match n.checked_sub(&D::one()) {
+        Some(last) => builder
+            .with_transition(START, count(START, upto(last), D::one(), symbol))
+            .with_transition(START, count(VIOLATION, upto(last), D::zero(), only_other)),
+        None => builder,
+    }
+}
+
+/// Builds a machine accepting the traces in which every block of consecutive inputs satisfying
+/// `symbol` is exactly `n` long.
+///
+/// The count is set back to zero by adding `-n`, so the data type must be signed. A block cut
+/// short by the end of the trace is not a violation, since later inputs may complete it.
+///
+/// ```
+/// use rust_efsm::monitor::{Monitor, Verdict};
+/// use rust_efsm::patterns::{self, START};
+///
+/// // Blinks come in pairs.
+/// let machine = patterns::exactly_n_in_a_row::<i8, u8>(|i| *i == b'b', 2).build();
+///
+/// let monitor = Monitor::new(START, 0, machine).unwrap();
+/// assert_eq!(monitor.check(b"xbbxxbbx").unwrap(), Verdict::Inconclusive);
+/// assert_eq!(monitor.check(b"xbbbx").unwrap(), Verdict::Violated);
+/// assert_eq!(monitor.check(b"xbxbb").unwrap(), Verdict::Violated);
+/// ```
+pub fn exactly_n_in_a_row<D, I>(symbol: fn(&I) -> bool, n: D) -> MachineBuilder<D, I, AddUpdate<D>>
+where
+    D: Counter + Neg<Output = D>,
+    I: Debug + 'static,
+{
+    let other = move |i: &I| !symbol(i);
+
+    let builder = MachineBuilder::new()
+        .with_transition(START, count(VIOLATION, from(n), D::zero(), symbol))
+        .with_transition(
+            VIOLATION,
+            Transition {
+                to_location: VIOLATION.into(),
+                ..Default::default()
+            },
+        )
+        .with_accepting(START)
+        .with_rejecting(VIOLATION);
+
+    // Outside of a block, other inputs leave the count at zero. At the end of a block, they set
+    // it back to zero, and within a block, they cut it short.
+    let builder = match n.is_zero() {
+        true => builder.with_transition(START, count(START, upto(n), D::zero(), other)),
+        false => builder
+            .with_transition(START, count(START, upto(D::zero()), D::zero(), other))
+            .with_transition(START, count(START, between(n, n), -n, other)),
+    };
+
+    match n.checked_sub(&D::one()) {
+        Some(last) if !n.is_zero() => builder
+            .with_transition(START, count(START, upto(last), D::one(), symbol))
+            .with_transition(
+                START,
+                count(VIOLATION, between(D::one(), last), D::zero(), other),
+            ),
+        _ => builder,
+    }
+}
+
+/// The data types that can count the inputs of a pattern.
+pub trait Counter:
+    Default
+    + Debug
+    + Copy
+    + Ord
+    + Bounded
+    + Zero
+    + One
+    + Add<Output = Self>
+    + CheckedAdd
+    + CheckedSub
+    + Send
+    + Sync
+    + 'static
+{
+}
+
+impl<D> Counter for D where
+    D: Default
+        + Debug
+        + Copy
+        + Ord
+        + Bounded
+        + Zero
+        + One
+        + Add<Output = D>
+        + CheckedAdd
+        + CheckedSub
+        + Send
+        + Sync
+        + 'static
+{
+}
+
+// Creates a transition to `to_location` within `bound` adding `amount` to the count, enabled
+// when `when` holds for the input.
+fn count<D, I>(
+    to_location: &str,
+    bound: Bound<D>,
+    amount: D,
+    when: impl Fn(&I) -> bool + Send + Sync + 'static,
+) -> Transition<D, I, AddUpdate<D>>
+where
+    D: Counter,
+{
+    guarded(to_location, bound, AddUpdate { amount }, when)
+}
+
+// Returns the bound holding the counts up to `last`.
+fn upto<D>(last: D) -> Bound<D> {
+    Bound {
+        lower: None,
+        upper: Some(last),
+    }
+}
+
+// Returns the bound holding the counts from `first` on.
+fn from<D>(first: D) -> Bound<D> {
+    Bound {
+        lower: Some(first),
+        upper: None,
+    }
+}
+
+// Returns the bound holding the counts from `first` up to `last`.
+fn between<D>(first: D, last: D) -> Bound<D> {
+    Bound {
+        lower: Some(first),
+        upper: Some(last),
+    }
+}
+
+/// The part of a trace in which a specification pattern must hold.
+///
+/// A scope ends just before the input that closes it. A scope that is never closed lasts until
+/// the end of the trace, so that [AfterUntil](Scope::AfterUntil) also stands for the scope
+/// between two inputs.
+pub enum Scope<I> {
+    /// The whole trace.
+    Globally,
+    /// From the start of the trace up to the first input satisfying the predicate. The pattern
+    /// holds for good once that input is read.
+    Before(fn(&I) -> bool),
+    /// From the first input satisfying the predicate on.
+    After(fn(&I) -> bool),
+    /// From each input satisfying the first predicate up to the next input satisfying the
+    /// second one.
+    AfterUntil(fn(&I) -> bool, fn(&I) -> bool),
+}
+
+// Implemented by hand, because the input type does not need to be cloned.
+impl<I> Clone for Scope<I> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<I> Copy for Scope<I> {}
+
+// The predicate opening or closing a scope, if it has one.
+type Boundary<I> = Option<fn(&I) -> bool>;
+
+impl<I> Scope<I> {
+    // Returns the predicates opening and closing the scope, if any.
+    fn ends(self) -> (Boundary<I>, Boundary<I>) {
+        match self {
+            Scope::Globally => (None, None),
+            Scope::Before(close) => (None, Some(close)),
+            Scope::After(open) => (Some(open), None),
+            Scope::AfterUntil(open, close) => (Some(open), Some(close)),
+        }
+    }
+}
+
+/// Builds a machine accepting the traces in which no input within `scope` satisfies `p`.
+///
+/// ```
+/// use rust_efsm::patterns::{self, Scope, START};
+///
+/// // No write before the file is opened.
+/// let scope = Scope::Before(|i| *i == b'o');
+/// let machine = patterns::absence::<u8, u8>(|i| *i == b'w', scope).build();
+/// assert!(machine.exec(START, 0, b"xowr".to_vec()));
+/// assert!(!machine.exec(START, 0, b"xwor".to_vec()));
+/// ```
+pub fn absence<D, I>(p: fn(&I) -> bool, scope: Scope<I>) -> MachineBuilder<D, I, IdentityUpdate<D>>
+where
+    D: Default + Clone + Debug,
+    I: Debug + 'static,
+{
+    let pattern = Pattern {
+        initial: "holds",
+        edges: vec![
+            ("holds", "holds", condition(move |i| !p(i))),
+            ("holds", VIOLATION, condition(p)),
+        ],
+        open: Vec::new(),
+    };
+    pattern.within(scope)
+}
+
+/// Builds a machine accepting the traces in which every input within `scope` satisfies `p`.
+///
+/// ```
+/// use rust_efsm::patterns::{self, Scope, START};
+///
+/// // Between a start and a stop, every input is a tick.
+/// let scope = Scope::AfterUntil(|i| *i == b's', |i| *i == b'e');
+/// let machine = patterns::universality::<u8, u8>(|i| *i == b't' || *i == b's', scope).build();
+/// assert!(machine.exec(START, 0, b"xsttexsttt".to_vec()));
+/// assert!(!machine.exec(START, 0, b"sttxe".to_vec()));
+/// ```
+pub fn universality<D, I>(
+    p: fn(&I) -> bool,
+    scope: Scope<I>,
+) -> MachineBuilder<D, I, IdentityUpdate<D>>
+where
+    D: Default + Clone + Debug,
+    I: Debug + 'static,
+{
+    let pattern = Pattern {
+        initial: "holds",
+        edges: vec![
+            ("holds", "holds", condition(p)),
+            ("holds", VIOLATION, condition(move |i| !p(i))),
+        ],
+        open: Vec::new(),
+    };
+    pattern.within(scope)
+}
+
+/// Builds a machine accepting the traces in which, within `scope`, no input satisfies `p`
+/// before one satisfies `s`.
+///
+/// An input satisfying both `s` and `p` is preceded by itself.
+///
+/// ```
+/// use rust_efsm::patterns::{self, Scope, START};
+///
+/// // No access is granted before a login.
+/// let machine = patterns::precedence::<u8, u8>(|i| *i == b'l', |i| *i == b'g', Scope::Globally)
+///     .build();
+/// assert!(machine.exec(START, 0, b"xlgg".to_vec()));
+/// assert!(!machine.exec(START, 0, b"xgl".to_vec()));
+/// ```
+pub fn precedence<D, I>(
+    s: fn(&I) -> bool,
+    p: fn(&I) -> bool,
+    scope: Scope<I>,
+) -> MachineBuilder<D, I, IdentityUpdate<D>>
+where
+    D: Default + Clone + Debug,
+    I: Debug + 'static,
+{
+    let pattern = Pattern {
+        initial: "waiting",
+        edges: vec![
+            ("waiting", "released", condition(s)),
+            ("waiting", "waiting", condition(move |i| !s(i) && !p(i))),
+            ("waiting", VIOLATION, condition(move |i| !s(i) && p(i))),
+            ("released", "released", condition(|_| true)),
+        ],
+        open: Vec::new(),
+    };
+    pattern.within(scope)
+}
+
+/// Builds a machine accepting the traces in which, within `scope`, every input satisfying
+/// `request` is answered by a later input satisfying `response`.
+///
+/// An input satisfying both answers itself. A request still pending when the scope closes is a
+/// violation, while one pending at the end of the trace only keeps the trace from being
+/// accepted.
+///
+/// ```
+/// use rust_efsm::monitor::{Monitor, Verdict};
+/// use rust_efsm::patterns::{self, Scope, START};
+///
+/// // Every request is acknowledged before the session ends.
+/// let scope = Scope::Before(|i| *i == b'e');
+/// let machine = patterns::response::<u8, u8>(|i| *i == b'q', |i| *i == b'a', scope).build();
+/// assert!(!machine.exec(START, 0, b"qxaq".to_vec()));
+/// assert!(machine.exec(START, 0, b"qxaqae".to_vec()));
+///
+/// let monitor = Monitor::new(START, 0, machine).unwrap();
+/// assert_eq!(monitor.check(b"qxe").unwrap(), Verdict::Violated);
+/// ```
+pub fn response<D, I>(
+    request: fn(&I) -> bool,
+    response: fn(&I) -> bool,
+    scope: Scope<I>,
+) -> MachineBuilder<D, I, IdentityUpdate<D>>
+where
+    D: Default + Clone + Debug,
+    I: Debug + 'static,
+{
+    let pattern = Pattern {
+        initial: "idle",
+        edges: vec![
+            (
+                "idle",
+                "pending",
+                condition(move |i| request(i) && !response(i)),
+            ),
+            (
+                "idle",
+                "idle",
+                condition(move |i| !request(i) || response(i)),
+            ),
+            ("pending", "idle", condition(response)),
+            ("pending", "pending", condition(move |i| !response(i))),
+        ],
+        open: vec!["pending"],
+    };
+    pattern.within(scope)
+}
+
+// A predicate over the inputs, shared by the transitions built from it.
+type Condition<I> = Arc<dyn Fn(&I) -> bool + Send + Sync>;
+
+// Returns `when` as a shared predicate.
+fn condition<I>(when: impl Fn(&I) -> bool + Send + Sync + 'static) -> Condition<I> {
+    Arc::new(when)
+}
+
+// A specification pattern, as the moves between its locations while its scope is open. The
+// moves out of each location are exclusive and cover every input.
+struct Pattern<I> {
+    initial: &'static str,
+    edges: Vec<(&'static str, &'static str, Condition<I>)>,
+    // The locations with an obligation still pending, which are not accepting.
+    open: Vec<&'static str>,
+}
+
+impl<I: Debug + 'static> Pattern<I> {
+    // Builds the machine in which the pattern holds within `scope`.
+    //
+    // Without an opening predicate, the initial location of the pattern is START. Otherwise
+    // START waits for the scope to open, and the input opening it is also read by the pattern.
+    fn within<D>(self, scope: Scope<I>) -> MachineBuilder<D, I, IdentityUpdate<D>>
+    where
+        D: Default + Clone + Debug,
+    {
+        let (open, close) = scope.ends();
+        let closes = move |i: &I| close.is_some_and(|close| close(i));
+        let initial = self.initial;
+        let name = move |location: &'static str| match open {
+            None if location == initial => START,
+            _ => location,
+        };
+
+        let mut builder = MachineBuilder::new()
+            .with_transition(VIOLATION, always(VIOLATION))
+            .with_rejecting(VIOLATION);
+
+        let locations: BTreeSet<&'static str> = self
+            .edges
+            .iter()
+            .map(|(from, _, _)| *from)
+            .filter(|location| *location != VIOLATION)
+            .collect();
+        for location in &locations {
+            for (from, to, when) in self.edges.iter().filter(|(from, _, _)| from == location) {
+                let when = when.clone();
+                builder = builder.with_transition(
+                    name(from),
+                    guarded_by(name(to), move |i| !closes(i) && when(i)),
+                );
+            }
+
+            if close.is_some() {
+                // The input closing the scope is outside of it.
+                let to = match (self.open.contains(location), open) {
+                    (true, _) => VIOLATION,
+                    (false, Some(_)) => START,
+                    (false, None) => DONE,
+                };
+                builder = builder.with_transition(name(location), guarded_by(to, closes));
+            }
+
+            if !self.open.contains(location) {
+                builder = builder.with_accepting(name(location));
+            }
+        }
+
+        if let Some(open) = open {
+            let opens = move |i: &I| open(i) && !closes(i);
+            for (_, to, when) in self.edges.iter().filter(|(from, _, _)| *from == initial) {
+                let when = when.clone();
+                builder = builder
+                    .with_transition(START, guarded_by(name(to), move |i| opens(i) && when(i)));
+            }
+            builder = builder
+                .with_transition(START, guarded_by(START, move |i| !opens(i)))
+                .with_accepting(START);
+        } else if close.is_some() {
+            builder = builder
+                .with_transition(DONE, always(DONE))
+                .with_accepting(DONE);
+        }
+
+        builder
+    }
+}
+
+// Creates a transition to `to_location`, enabled when `when` holds for the input.
+fn guarded_by<D, I, U: Default>(
+    to_location: &str,
+    when: impl Fn(&I) -> bool + Send + Sync + 'static,
+) -> Transition<D, I, U> {
+    Transition {
+        to_location: to_location.into(),
+        guard: Some(Arc::new(move |_: &D, input: &I| when(input))),
+        input: InputAbstraction::Unknown,
+        ..Default::default()
+    }
+}
+
+// Creates a transition to `to_location`, enabled for every input.
+fn always<D, I, U: Default>(to_location: &str) -> Transition<D, I, U> {
+    Transition {
+        to_location: to_location.into(),
+        ..Default::default()
+    }
+}
@@ -0,0 +1,381 @@
+//! # Persist Module
+//!
+//! This module saves a [Machine] to a compact, versioned binary format and loads it back, so a
+//! large generated machine can be loaded quickly without running its builder again.
+//!
+//! Guards and updates are code, so they are not stored. Instead, each transition stores the
+//! name of its guard and of its update, which a [Registry] resolves when the machine is
+//! loaded. The guard of a transition is named by [Transition::name], and its update is named by
+//! the registry entry equal to it. Metrics, assertions, and tags are not stored, and neither are
+//! [input abstractions](Transition::input), which are loaded as unknown.
+//!
+//! Capturing [guards](Transition::guard) and [bindings](Transition::binding) are not stored
+//! either, and the [bound](Transition::bound) of a transition is the one registered with its
+//! guard. Saving fails rather than dropping them, so a transition with a capturing guard, a
+//! binding, or a bound other than the registered one cannot be saved.
+//!
+//! ## Format
+//!
+//! The format starts with the magic bytes `EFSM` and a version number, followed by the
+//! [NoTransitionPolicy], the accepting locations, the rejecting locations, and the transitions
+//! out of each location in order. Integers are little-endian, and strings are prefixed by their
+//! length. Version 1 did not store rejecting locations, and can still be read.
+
+use crate::bound::Bound;
+use crate::machine::{
+    InputAbstraction, Machine, MachineBuilder, NoTransitionPolicy, Transition, TransitionId, Update,
+};
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Debug;
+use std::io::{Read, Write};
+
+const MAGIC: &[u8; 4] = b"EFSM";
+
+/// The version of the format written by [Machine::save].
+pub const FORMAT_VERSION: u16 = 2;
+
+/// Errors that can occur while saving or loading a machine.
+#[derive(Debug)]
+pub enum PersistError {
+    /// The machine could not be read or written.
+    Io(std::io::Error),
+    /// The data does not start with the magic bytes of the format.
+    BadMagic,
+    /// The data was written in a version of the format that cannot be read.
+    UnsupportedVersion(u16),
+    /// The data is truncated or otherwise malformed.
+    Malformed(String),
+    /// A transition has no name to reference its guard by.
+    Unnamed(TransitionId),
+    /// No guard is registered under the name.
+    UnknownGuard(String),
+    /// No update is registered under the name.
+    UnknownUpdate(String),
+    /// No registered update is equal to the update of the transition.
+    UnregisteredUpdate(TransitionId),
+    /// The location has an accepting condition, which the format cannot reference.
+    AcceptingCondition(String),
+    /// The transition has a capturing guard, a binding, or a bound other than the registered
+    /// one, which the format cannot store.
+    Unstorable(TransitionId, String),
+}
+
+impl fmt::Display for PersistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PersistError::Io(error) => write!(f, "i/o error: {}", error),
+            PersistError::BadMagic => write!(f, "not a saved machine"),
+            PersistError::UnsupportedVersion(version) => {
+                write!(f, "unsupported format version {}", version)
+            }
+            PersistError::Malformed(reason) => write!(f, "malformed machine: {}", reason),
+            PersistError::Unnamed(id) => write!(f, "transition {} has no name", id),
+            PersistError::UnknownGuard(name) => write!(f, "no guard is registered as {}", name),
+            PersistError::UnknownUpdate(name) => {
+                write!(f, "no update is registered as {}", name)
+            }
+            PersistError::UnregisteredUpdate(id) => {
+                write!(f, "the update of transition {} is not registered", id)
+            }
+            PersistError::AcceptingCondition(location) => {
+                write!(f, "location {} has an accepting condition", location)
+            }
+            PersistError::Unstorable(id, part) => {
+                write!(f, "transition {} has {}, which cannot be saved", id, part)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PersistError {}
+
+impl From<std::io::Error> for PersistError {
+    fn from(error: std::io::Error) -> Self {
+        PersistError::Io(error)
+    }
+}
+
+// The enable function and data bound of a named guard.
+type GuardEntry<D, I> = (fn(&D, &I) -> bool, Bound<D>);
+
+/// The named guards and updates used to save and load machines.
+pub struct Registry<D, I, U> {
+    guards: HashMap<String, GuardEntry<D, I>>,
+    updates: Vec<(String, U)>,
+}
+
+impl<D, I, U> Default for Registry<D, I, U> {
+    fn default() -> Self {
+        Registry {
+            guards: HashMap::new(),
+            updates: Vec::new(),
+        }
+    }
+}
+
+impl<D, I, U> Registry<D, I, U> {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Registry::default()
+    }
+
+    /// Registers the `enable` function and data `bound` of the transitions named `name`.
+    pub fn with_guard(mut self, name: &str, enable: fn(&D, &I) -> bool, bound: Bound<D>) -> Self {
+        self.guards.insert(name.into(), (enable, bound));
+        self
+    }
+
+    /// Registers `update` under `name`.
+    pub fn with_update(mut self, name: &str, update: U) -> Self {
+        self.updates.push((name.into(), update));
+        self
+    }
+}
+
+impl<D, I, U> Machine<D, I, U> {
+    /// Writes this machine to `writer`, referencing guards and updates by their names in
+    /// `registry`.
+    ///
+    /// Every transition must be named after a guard in `registry`, its bound must be the one
+    /// registered with that guard, and its update must be equal to one in `registry`. Accepting
+    /// conditions, capturing guards, and bindings cannot be saved.
+    ///
+    /// ```
+    /// use rust_efsm::bound::Bound;
+    /// use rust_efsm::machine::{AddUpdate, Machine, MachineBuilder, Transition};
+    /// use rust_efsm::persist::{PersistError, Registry};
+    ///
+    /// let registry = Registry::<u8, u8, AddUpdate<u8>>::new()
+    ///     .with_guard("is_b", |_, i| *i == b'b', Bound::unbounded())
+    ///     .with_update("count", AddUpdate { amount: 1 });
+    ///
+    /// let machine = MachineBuilder::<u8, u8, AddUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s1".into(),
+    ///         enable: |_, i| *i == b'b',
+    ///         update: AddUpdate { amount: 1 },
+    ///         name: Some("is_b".into()),
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("s1")
+    ///     .build();
+    ///
+    /// let mut bytes = Vec::new();
+    /// machine.save(&mut bytes, &registry).unwrap();
+    ///
+    /// let loaded = Machine::load(&mut &bytes[..], &registry).unwrap();
+    /// assert!(loaded.exec("s0", 0, vec![b'b']));
+    /// assert!(!loaded.exec("s0", 0, vec![b'a']));
+    ///
+    /// let bounded = MachineBuilder::<u8, u8, AddUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s1".into(),
+    ///         update: AddUpdate { amount: 1 },
+    ///         name: Some("is_b".into()),
+    ///         ..Default::default()
+    ///     }.with_input_bound(|i| Bound { lower: None, upper: Some(*i) }))
+    ///     .build();
+    /// assert!(matches!(
+    ///     bounded.save(&mut Vec::new(), &registry),
+    ///     Err(PersistError::Unstorable(_, _))
+    /// ));
+    /// ```
+    pub fn save<W>(&self, mut writer: W, registry: &Registry<D, I, U>) -> Result<(), PersistError>
+    where
+        W: Write,
+        D: PartialEq,
+        U: PartialEq,
+    {
+        if let Some(location) = self.get_accepting_conditions().keys().min() {
+            return Err(PersistError::AcceptingCondition(location.clone()));
+        }
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+
+        match self.get_no_transition_policy() {
+            NoTransitionPolicy::Reject => writer.write_all(&[0])?,
+            NoTransitionPolicy::Stutter => writer.write_all(&[1])?,
+            NoTransitionPolicy::SinkTo(sink) => {
+                writer.write_all(&[2])?;
+                write_str(&mut writer, sink)?;
+            }
+            NoTransitionPolicy::Error => writer.write_all(&[3])?,
+        }
+
+        let mut accepting: Vec<&String> = self.get_accepting().iter().collect();
+        accepting.sort();
+        write_len(&mut writer, accepting.len())?;
+        for location in accepting {
+            write_str(&mut writer, location)?;
+        }
+
+        let mut rejecting: Vec<&String> = self.get_rejecting().iter().collect();
+        rejecting.sort();
+        write_len(&mut writer, rejecting.len())?;
+        for location in rejecting {
+            write_str(&mut writer, location)?;
+        }
+
+        let mut locations: Vec<_> = self.get_locations().iter().collect();
+        locations.sort_by_key(|(location, _)| *location);
+        write_len(&mut writer, locations.len())?;
+
+        for (location, transitions) in locations {
+            write_str(&mut writer, location)?;
+            write_len(&mut writer, transitions.len())?;
+
+            for (index, transition) in transitions.iter().enumerate() {
+                let id = || TransitionId::new(location, index);
+                let guard = transition
+                    .name
+                    .as_ref()
+                    .ok_or_else(|| PersistError::Unnamed(id()))?;
+                let (_, bound) = registry
+                    .guards
+                    .get(guard)
+                    .ok_or_else(|| PersistError::UnknownGuard(guard.clone()))?;
+
+                let unstorable = |part: &str| Err(PersistError::Unstorable(id(), part.into()));
+                if transition.guard.is_some() {
+                    return unstorable("a capturing guard");
+                }
+                if transition.binding.is_some() {
+                    return unstorable("a binding");
+                }
+                if transition.bound != *bound {
+                    return unstorable("a bound other than the registered one");
+                }
+
+                let (update, _) = registry
+                    .updates
+                    .iter()
+                    .find(|(_, update)| *update == transition.update)
+                    .ok_or_else(|| PersistError::UnregisteredUpdate(id()))?;
+
+                write_str(&mut writer, &transition.to_location)?;
+                write_str(&mut writer, guard)?;
+                write_str(&mut writer, update)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads a machine written by [Machine::save] from `reader`, resolving its guards and
+    /// updates in `registry`.
+    pub fn load<R>(mut reader: R, registry: &Registry<D, I, U>) -> Result<Self, PersistError>
+    where
+        R: Read,
+        D: Default + Clone + Debug,
+        I: Debug,
+        U: Clone + Update<D = D>,
+    {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(PersistError::BadMagic);
+        }
+
+        let mut version = [0; 2];
+        reader.read_exact(&mut version)?;
+        let version = u16::from_le_bytes(version);
+        if !(1..=FORMAT_VERSION).contains(&version) {
+            return Err(PersistError::UnsupportedVersion(version));
+        }
+
+        let mut tag = [0];
+        reader.read_exact(&mut tag)?;
+        let policy = match tag[0] {
+            0 => NoTransitionPolicy::Reject,
+            1 => NoTransitionPolicy::Stutter,
+            2 => NoTransitionPolicy::SinkTo(read_str(&mut reader)?),
+            3 => NoTransitionPolicy::Error,
+            tag => {
+                return Err(PersistError::Malformed(format!(
+                    "unknown no transition policy {}",
+                    tag
+                )))
+            }
+        };
+
+        let mut builder = MachineBuilder::new().with_no_transition_policy(policy);
+
+        for _ in 0..read_len(&mut reader)? {
+            builder = builder.with_accepting(&read_str(&mut reader)?);
+        }
+
+        if version >= 2 {
+            for _ in 0..read_len(&mut reader)? {
+                builder = builder.with_rejecting(&read_str(&mut reader)?);
+            }
+        }
+
+        for _ in 0..read_len(&mut reader)? {
+            let location = read_str(&mut reader)?;
+
+            for _ in 0..read_len(&mut reader)? {
+                let to_location = read_str(&mut reader)?;
+                let guard = read_str(&mut reader)?;
+                let update = read_str(&mut reader)?;
+
+                let (enable, bound) = registry
+                    .guards
+                    .get(&guard)
+                    .ok_or_else(|| PersistError::UnknownGuard(guard.clone()))?;
+                let (_, update) = registry
+                    .updates
+                    .iter()
+                    .find(|(name, _)| *name == update)
+                    .ok_or_else(|| PersistError::UnknownUpdate(update.clone()))?;
+
+                builder = builder.with_transition(
+                    &location,
+                    Transition {
+                        to_location,
+                        enable: *enable,
+                        bound: bound.clone(),
+                        update: update.clone(),
+                        guard: None,
+                        name: Some(guard),
+                        input: InputAbstraction::Unknown,
+                        binding: None,
+                    },
+                );
+            }
+        }
+
+        Ok(builder.build())
+    }
+}
+
+fn write_len<W: Write>(writer: &mut W, len: usize) -> Result<(), PersistError> {
+    let len = u32::try_from(len)
+        .map_err(|_| PersistError::Malformed("too many elements to save".into()))?;
+    writer.write_all(&len.to_le_bytes())?;
+    Ok(())
+}
+
+fn write_str<W: Write>(writer: &mut W, s: &str) -> Result<(), PersistError> {
+    write_len(writer, s.len())?;
+    writer.write_all(s.as_bytes())?;
+    Ok(())
+}
+
+fn read_len<R: Read>(reader: &mut R) -> Result<u32, PersistError> {
+    let mut len = [0; 4];
+    reader.read_exact(&mut len)?;
+    Ok(u32::from_le_bytes(len))
+}
+
+fn read_str<R: Read>(reader: &mut R) -> Result<String, PersistError> {
+    let len = read_len(reader)? as u64;
+    let mut bytes = Vec::new();
+    reader.take(len).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != len {
+        return Err(PersistError::Malformed("unexpected end of data".into()));
+    }
+
+    String::from_utf8(bytes).map_err(|_| PersistError::Malformed("invalid string".into()))
+}
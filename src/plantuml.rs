@@ -0,0 +1,209 @@
+//! # PlantUML Module
+//!
+//! This module converts a [Machine] into a PlantUML state diagram, for design documents written
+//! with PlantUML rather than Graphviz. As in the [DOT output](crate::gviz), each transition is
+//! labelled with its name, update, and bound, unless it is enabled for any data.
+//!
+//! Accepting locations carry the `<<accepting>>` stereotype, which is styled with a thicker
+//! border and a fill, and list their accepting condition, if any, in their description. A
+//! machine has no start location of its own, so the initial transition is only drawn when one is
+//! [given](PumlDiagram::with_initial).
+//!
+//! ```
+//! use rust_efsm::machine::{AddUpdate, MachineBuilder, Transition};
+//! use rust_efsm::plantuml::PumlDiagram;
+//!
+//! let machine = MachineBuilder::<u8, u8, AddUpdate<u8>>::new()
+//!     .with_transition("idle", Transition {
+//!         to_location: "busy".into(),
+//!         update: AddUpdate { amount: 1 },
+//!         ..Default::default()
+//!     })
+//!     .with_accepting("idle")
+//!     .build();
+//!
+//! let uml = String::from(PumlDiagram::from(machine).with_initial("idle"));
+//! assert!(uml.starts_with("@startuml\n"));
+//! assert!(uml.contains("state idle <<accepting>>\n"));
+//! assert!(uml.contains("[*] --> idle\n"));
+//! assert!(uml.contains("idle --> busy : data += 1\n"));
+//! assert!(uml.ends_with("@enduml\n"));
+//! ```
+
+use crate::machine::Machine;
+use num::Bounded;
+use std::fmt;
+
+/// A state diagram that can be written out in the PlantUML language.
+pub struct PumlDiagram {
+    states: Vec<PumlState>,
+    transitions: Vec<PumlTransition>,
+    initial: Option<String>,
+}
+
+struct PumlState {
+    name: String,
+    id: String,
+    accepting: bool,
+    description: Option<String>,
+}
+
+struct PumlTransition {
+    from: String,
+    to: String,
+    label: String,
+}
+
+impl PumlDiagram {
+    /// Draws the initial transition of the diagram into `location`.
+    ///
+    /// Locations missing from the machine are ignored.
+    pub fn with_initial(mut self, location: &str) -> Self {
+        if self.states.iter().any(|state| state.name == location) {
+            self.initial = Some(location.into());
+        }
+        self
+    }
+
+    // Returns the identifier of the state for `location`.
+    fn id<'a>(&'a self, location: &'a str) -> &'a str {
+        self.states
+            .iter()
+            .find(|state| state.name == location)
+            .map_or(location, |state| &state.id)
+    }
+}
+
+impl<D, I, U> From<Machine<D, I, U>> for PumlDiagram
+where
+    D: fmt::Display + Bounded + Copy,
+    U: fmt::Display,
+{
+    /// Converts `machine` into a diagram with a state for each location.
+    ///
+    /// Locations that are not plain identifiers are declared under an alias.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    /// use rust_efsm::plantuml::PumlDiagram;
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("wait for ack", Transition {
+    ///         name: Some("ack".into()),
+    ///         to_location: "done".into(),
+    ///         ..Default::default()
+    ///     })
+    ///     .build();
+    ///
+    /// let uml = String::from(PumlDiagram::from(machine));
+    /// assert!(uml.contains("state \"wait for ack\" as l1\n"));
+    /// assert!(uml.contains("l1 --> done : ack\\ndata\n"));
+    /// ```
+    fn from(machine: Machine<D, I, U>) -> Self {
+        let mut diagram = PumlDiagram {
+            states: Vec::new(),
+            transitions: Vec::new(),
+            initial: None,
+        };
+
+        for (idx, location) in machine.all_locations().into_iter().enumerate() {
+            // PlantUML only accepts plain identifiers as state names.
+            let plain = location
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+                && !location.starts_with(|c: char| c.is_ascii_digit());
+
+            diagram.states.push(PumlState {
+                name: location.into(),
+                id: match plain {
+                    true => location.into(),
+                    false => format!("l{}", idx),
+                },
+                accepting: machine.get_accepting().contains(location),
+                description: machine
+                    .get_accepting_conditions()
+                    .get(location)
+                    .map(|condition| format!("accepts {}", condition)),
+            });
+
+            for t in machine.get_transitions_from(location).into_iter().flatten() {
+                let action = match t.bound.is_unbounded() {
+                    true => t.update.to_string(),
+                    false => format!("{}\\nin {}", t.update, t.bound),
+                };
+                diagram.transitions.push(PumlTransition {
+                    from: location.into(),
+                    to: t.to_location.clone(),
+                    label: match &t.name {
+                        Some(name) => format!("{}\\n{}", name, action),
+                        None => action,
+                    },
+                });
+            }
+        }
+
+        diagram
+    }
+}
+
+impl From<PumlDiagram> for String {
+    /// Writes out `diagram` in the PlantUML language.
+    ///
+    /// ```
+    /// use rust_efsm::bound::Bound;
+    /// use rust_efsm::machine::{AcceptingCondition, IdentityUpdate, MachineBuilder};
+    /// use rust_efsm::plantuml::PumlDiagram;
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_accepting_condition("s0", AcceptingCondition::new(Bound {
+    ///         lower: Some(1),
+    ///         upper: Some(3),
+    ///     }))
+    ///     .build();
+    ///
+    /// let uml = String::from(PumlDiagram::from(machine));
+    /// assert!(uml.contains("state s0 <<accepting>>\ns0 : accepts [1, 3]\n"));
+    /// ```
+    fn from(diagram: PumlDiagram) -> Self {
+        let mut spec = String::new();
+
+        spec.push_str("@startuml\n");
+        spec.push_str("hide empty description\n");
+        spec.push_str("skinparam state {\n");
+        spec.push_str("BorderThickness<<accepting>> 3\n");
+        spec.push_str("BackgroundColor<<accepting>> PaleGreen\n");
+        spec.push_str("}\n");
+
+        for state in &diagram.states {
+            match state.name == state.id {
+                true => spec.push_str(&format!("state {}", state.id)),
+                false => spec.push_str(&format!("state \"{}\" as {}", state.name, state.id)),
+            }
+            if state.accepting {
+                spec.push_str(" <<accepting>>");
+            }
+            spec.push('\n');
+
+            if let Some(description) = &state.description {
+                spec.push_str(&format!("{} : {}\n", state.id, description));
+            }
+        }
+
+        if let Some(initial) = &diagram.initial {
+            spec.push_str(&format!("[*] --> {}\n", diagram.id(initial)));
+        }
+
+        for transition in &diagram.transitions {
+            spec.push_str(&format!(
+                "{} --> {} : {}\n",
+                diagram.id(&transition.from),
+                diagram.id(&transition.to),
+                transition.label
+            ));
+        }
+
+        spec.push_str("@enduml\n");
+
+        spec
+    }
+}
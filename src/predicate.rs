@@ -0,0 +1,330 @@
+//! # Predicate Module
+//!
+//! This module brings the analysis of [Machine::find_non_empty] to data types that intervals do
+//! not fit, such as enums and structs. A [PredicateDomain] lists predicates over the data, and
+//! describes the data only by which of them hold, as a [Valuation]. For each transition, the
+//! user tells which valuations may enable it, and which valuations its update may lead to.
+//! [Machine::find_non_empty_abstract] then finds the valuations of each location that lead to
+//! acceptance.
+//!
+//! ```
+//! use rust_efsm::bound::Bound;
+//! use rust_efsm::machine::{MachineBuilder, Transition, TransitionId, Update};
+//! use rust_efsm::predicate::{PredicateDomain, Valuation};
+//!
+//! #[derive(Clone, Copy, Debug, Default, PartialEq)]
+//! enum Door {
+//!     Open,
+//!     #[default]
+//!     Closed,
+//!     Locked,
+//! }
+//!
+//! // Sets the state of the door, if any.
+//! #[derive(Clone, Default)]
+//! struct Set(Option<Door>);
+//!
+//! impl Update for Set {
+//!     type D = Door;
+//!     fn update<I>(&self, data: Door, _: &I) -> Door {
+//!         self.0.unwrap_or(data)
+//!     }
+//!     fn update_interval(&self, _: Bound<Door>) -> Bound<Door> {
+//!         Bound::unbounded()
+//!     }
+//! }
+//!
+//! // A locked door can never be opened again.
+//! let machine = MachineBuilder::<Door, u8, Set>::new()
+//!     .with_transition("door", Transition {
+//!         to_location: "door".into(),
+//!         enable: |d, i| *i == b'l' && *d != Door::Open,
+//!         update: Set(Some(Door::Locked)),
+//!         ..Default::default()
+//!     })
+//!     .with_transition("door", Transition {
+//!         to_location: "door".into(),
+//!         enable: |d, i| *i == b'o' && *d == Door::Closed,
+//!         update: Set(Some(Door::Open)),
+//!         ..Default::default()
+//!     })
+//!     .with_transition("door", Transition {
+//!         to_location: "inside".into(),
+//!         enable: |d, i| *i == b'e' && *d == Door::Open,
+//!         ..Default::default()
+//!     })
+//!     .with_accepting("inside")
+//!     .build();
+//!
+//! // The predicate "open" is at index 0, and "locked" at index 1.
+//! let domain = PredicateDomain::new()
+//!     .with_predicate("open", |d| *d == Door::Open)
+//!     .with_predicate("locked", |d| *d == Door::Locked)
+//!     .with_transition(
+//!         TransitionId::new("door", 0),
+//!         |v| !v.holds(0),
+//!         |_| vec![Valuation::default().with(1, true)],
+//!     )
+//!     .with_transition(
+//!         TransitionId::new("door", 1),
+//!         |v| !v.holds(0) && !v.holds(1),
+//!         |_| vec![Valuation::default().with(0, true)],
+//!     )
+//!     .with_transition(TransitionId::new("door", 2), |v| v.holds(0), |v| vec![v]);
+//!
+//! let outcome = machine.find_non_empty_abstract("door", &domain);
+//! assert!(outcome.result["door"].contains(&domain.valuation(&Door::Closed)));
+//! assert!(outcome.result["door"].contains(&domain.valuation(&Door::Open)));
+//! assert!(!outcome.result["door"].contains(&domain.valuation(&Door::Locked)));
+//! ```
+
+use crate::analysis::{AnalysisOutcome, Approximation};
+use crate::log::{debug, span};
+use crate::machine::{Machine, NoTransitionPolicy, TransitionId};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+/// The most predicates a [PredicateDomain] can hold.
+pub const MAX_PREDICATES: usize = 64;
+
+/// Which predicates of a [PredicateDomain] hold, as a set of bits in the order the predicates
+/// were added.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Valuation(u64);
+
+impl Valuation {
+    /// Checks if the predicate at `index` holds.
+    pub fn holds(self, index: usize) -> bool {
+        index < MAX_PREDICATES && self.0 & (1 << index) != 0
+    }
+
+    /// Returns this valuation, with the predicate at `index` set to `holds`.
+    ///
+    /// ```
+    /// use rust_efsm::predicate::Valuation;
+    ///
+    /// let valuation = Valuation::default().with(2, true);
+    /// assert!(valuation.holds(2));
+    /// assert!(!valuation.with(2, false).holds(2));
+    /// ```
+    pub fn with(self, index: usize, holds: bool) -> Self {
+        match index < MAX_PREDICATES {
+            true if holds => Valuation(self.0 | (1 << index)),
+            true => Valuation(self.0 & !(1 << index)),
+            false => self,
+        }
+    }
+}
+
+impl fmt::Display for Valuation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:b}", self.0)
+    }
+}
+
+/// Decides whether a property of the data holds.
+pub type Predicate<D> = fn(&D) -> bool;
+
+/// Decides which valuations may enable a transition.
+pub type Enabled = fn(Valuation) -> bool;
+
+/// Returns the valuations the update of a transition may lead to from a valuation.
+pub type Transfer = fn(Valuation) -> Vec<Valuation>;
+
+/// Abstracts the data of a machine by a list of predicates over it.
+///
+/// Transitions without an abstraction may be enabled by any valuation, and lead to any
+/// valuation, which keeps the analysis sound at the cost of precision.
+pub struct PredicateDomain<D> {
+    predicates: Vec<(String, Predicate<D>)>,
+    transitions: HashMap<TransitionId, (Enabled, Transfer)>,
+}
+
+impl<D> Default for PredicateDomain<D> {
+    fn default() -> Self {
+        PredicateDomain::new()
+    }
+}
+
+impl<D> PredicateDomain<D> {
+    /// Creates a domain without predicates.
+    pub fn new() -> Self {
+        PredicateDomain {
+            predicates: Vec::new(),
+            transitions: HashMap::new(),
+        }
+    }
+
+    /// Adds a predicate named `name`, at the next index of the valuations.
+    ///
+    /// The analysis starts from every valuation, so its work doubles with each predicate.
+    /// Predicates past [MAX_PREDICATES] are ignored.
+    pub fn with_predicate(mut self, name: &str, predicate: Predicate<D>) -> Self {
+        if self.predicates.len() < MAX_PREDICATES {
+            self.predicates.push((name.into(), predicate));
+        }
+        self
+    }
+
+    /// Abstracts the transition `id`: it may only be enabled by the valuations for which
+    /// `enabled` holds, and its update leads from a valuation to those returned by `transfer`.
+    pub fn with_transition(
+        mut self,
+        id: TransitionId,
+        enabled: Enabled,
+        transfer: Transfer,
+    ) -> Self {
+        self.transitions.insert(id, (enabled, transfer));
+        self
+    }
+
+    /// Returns the names of the predicates, in the order of their index.
+    pub fn predicates(&self) -> Vec<&str> {
+        self.predicates
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// Returns which predicates hold for `data`.
+    pub fn valuation(&self, data: &D) -> Valuation {
+        self.predicates.iter().enumerate().fold(
+            Valuation::default(),
+            |valuation, (index, (_, predicate))| valuation.with(index, predicate(data)),
+        )
+    }
+
+    // Returns every valuation of the predicates.
+    fn valuations(&self) -> impl Iterator<Item = Valuation> {
+        let unused = (MAX_PREDICATES - self.predicates.len()) as u32;
+        (0..=u64::MAX.checked_shr(unused).unwrap_or(0)).map(Valuation)
+    }
+}
+
+impl<D, I, U> Machine<D, I, U> {
+    /// Find the valuations of `domain` that lead to acceptance, for each location reachable from
+    /// `location`.
+    ///
+    /// Like [find_non_empty](Machine::find_non_empty), the result is tagged with its
+    /// [Approximation]. The abstractions given to the domain are assumed to be exact. The result
+    /// is [over-approximate](Approximation::Over) when a transition has no abstraction or a
+    /// runtime guard, when a location has an accepting condition, or when implicit moves to a
+    /// sink are taken.
+    pub fn find_non_empty_abstract(
+        &self,
+        location: &str,
+        domain: &PredicateDomain<D>,
+    ) -> AnalysisOutcome<HashMap<String, BTreeSet<Valuation>>> {
+        span!(DEBUG, "find_non_empty_abstract", location);
+
+        let mut over = false;
+
+        // Explore every pair of a location and a valuation reachable from `location`. Runs end on
+        // entering a rejecting location, so no node is created for one.
+        let mut nodes: Vec<(String, Valuation)> = Vec::new();
+        let mut index: HashMap<(String, Valuation), usize> = HashMap::new();
+        let mut successors: Vec<Vec<usize>> = Vec::new();
+
+        let mut to_visit = Vec::new();
+        if !self.get_rejecting().contains(location) {
+            for valuation in domain.valuations() {
+                index.insert((location.into(), valuation), nodes.len());
+                to_visit.push(nodes.len());
+                nodes.push((location.into(), valuation));
+                successors.push(Vec::new());
+            }
+        }
+
+        while let Some(idx) = to_visit.pop() {
+            let (from, valuation) = nodes[idx].clone();
+            over |= self.get_accepting_conditions().contains_key(&from);
+
+            let mut targets: Vec<(String, Valuation)> = Vec::new();
+            for (position, transition) in self
+                .get_transitions_from(&from)
+                .into_iter()
+                .flatten()
+                .enumerate()
+            {
+                // Runtime guards are ignored, as if they always held.
+                over |= transition.guard.is_some();
+
+                match domain.transitions.get(&TransitionId::new(&from, position)) {
+                    Some((enabled, transfer)) => {
+                        if enabled(valuation) {
+                            targets.extend(
+                                transfer(valuation)
+                                    .into_iter()
+                                    .map(|next| (transition.to_location.clone(), next)),
+                            );
+                        }
+                    }
+                    None => {
+                        over = true;
+                        targets.extend(
+                            domain
+                                .valuations()
+                                .map(|next| (transition.to_location.clone(), next)),
+                        );
+                    }
+                }
+            }
+
+            // The implicit move to a sink leaves the data unchanged, and is taken as if no
+            // transition were ever enabled.
+            if let NoTransitionPolicy::SinkTo(sink) = self.get_no_transition_policy() {
+                if *sink != from {
+                    over = true;
+                    targets.push((sink.clone(), valuation));
+                }
+            }
+
+            for target in targets {
+                if self.get_rejecting().contains(&target.0) {
+                    continue;
+                }
+
+                let next = *index.entry(target.clone()).or_insert_with(|| {
+                    to_visit.push(nodes.len());
+                    nodes.push(target);
+                    successors.push(Vec::new());
+                    nodes.len() - 1
+                });
+                successors[idx].push(next);
+            }
+        }
+
+        // A node leads to acceptance when it is accepting, or when one of its successors does.
+        let mut safe: Vec<bool> = nodes
+            .iter()
+            .map(|(location, _)| self.get_accepting().contains(location))
+            .collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for idx in 0..nodes.len() {
+                if !safe[idx] && successors[idx].iter().any(|next| safe[*next]) {
+                    safe[idx] = true;
+                    changed = true;
+                }
+            }
+        }
+
+        let mut result: HashMap<String, BTreeSet<Valuation>> = HashMap::new();
+        for ((location, valuation), _) in nodes.into_iter().zip(safe).filter(|(_, safe)| *safe) {
+            result.entry(location).or_default().insert(valuation);
+        }
+
+        let approximation = match over {
+            true => Approximation::Over,
+            false => Approximation::Exact,
+        };
+        debug!(%approximation, "found non-empty valuations");
+
+        AnalysisOutcome {
+            result,
+            approximation,
+        }
+    }
+}
@@ -0,0 +1,360 @@
+//! # Refine Module
+//!
+//! This module refines the interval analysis of [Machine::find_non_empty], which keeps a single
+//! interval per location. When the data leading to acceptance from a location is made of
+//! disjoint intervals, such as behind the guards `d <= 3` and `d >= 10`, that interval is their
+//! hull, and a [monitor](crate::monitor::Monitor) cannot tell that the data in the gap is lost.
+//!
+//! [Machine::find_non_empty_refined] instead splits the data of each location into cells at the
+//! endpoints of the bounds out of it, and settles which cells lead to acceptance. A cell whose
+//! successors only partly lead to acceptance is split in half, and the cells are settled again,
+//! until every cell is decided or the number of rounds runs out. The cells still undecided then
+//! count as leading to acceptance, so the result never misses data that does.
+//!
+//! ```
+//! use rust_efsm::bound::Bound;
+//! use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+//!
+//! let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+//!     .with_transition("s0", Transition {
+//!         to_location: "s1".into(),
+//!         bound: Bound { lower: None, upper: Some(3) },
+//!         ..Default::default()
+//!     })
+//!     .with_transition("s0", Transition {
+//!         to_location: "s1".into(),
+//!         bound: Bound { lower: Some(10), upper: None },
+//!         ..Default::default()
+//!     })
+//!     .with_accepting("s1")
+//!     .build();
+//!
+//! // The hull of both guards.
+//! assert!(machine.find_non_empty("s0").unwrap()["s0"].contains(&5));
+//!
+//! let refined = machine.find_non_empty_refined("s0", 0).result;
+//! assert!(refined["s0"].contains(&3));
+//! assert!(!refined["s0"].contains(&5));
+//! assert!(refined["s0"].contains(&10));
+//! ```
+
+use crate::analysis::{AnalysisOutcome, Approximation};
+use crate::bound::{Bound, BoundSet};
+use crate::log::{debug, span};
+use crate::machine::{Machine, NoTransitionPolicy, Transition, Update};
+use num::{Bounded, Integer};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+// The sorted, disjoint cells covering all of the data of each location, as explicit intervals.
+type Cells<D> = HashMap<String, Vec<(D, D)>>;
+
+// Whether each cell of each location leads to acceptance.
+type Marks = HashMap<String, Vec<bool>>;
+
+impl<D, I, U> Machine<D, I, U> {
+    /// Find the data of each location reachable from `location` that leads to acceptance, as
+    /// sets of intervals, splitting undecided intervals for at most `rounds` rounds.
+    ///
+    /// Like [find_non_empty](Machine::find_non_empty), the result is tagged with its
+    /// [Approximation]. It is [over-approximate](Approximation::Over) when undecided intervals
+    /// remain after the last round, when a transition has a runtime guard, or when implicit moves
+    /// to a sink are taken, but it never misses data.
+    ///
+    /// ```
+    /// use rust_efsm::analysis::Approximation;
+    /// use rust_efsm::bound::Bound;
+    /// use rust_efsm::machine::{AddUpdate, MachineBuilder, Transition};
+    ///
+    /// // Count up to a value outside of [4, 9].
+    /// let machine = MachineBuilder::<u8, u8, AddUpdate<u8>>::new()
+    ///     .with_transition("count", Transition {
+    ///         to_location: "check".into(),
+    ///         bound: Bound { lower: None, upper: Some(100) },
+    ///         update: AddUpdate { amount: 1 },
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("check", Transition {
+    ///         to_location: "done".into(),
+    ///         bound: Bound { lower: None, upper: Some(3) },
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("check", Transition {
+    ///         to_location: "done".into(),
+    ///         bound: Bound { lower: Some(10), upper: None },
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("done")
+    ///     .build();
+    ///
+    /// // Splitting [0, 100] in half once is not enough to find the gap.
+    /// let coarse = machine.find_non_empty_refined("count", 1);
+    /// assert_eq!(coarse.approximation, Approximation::Over);
+    /// assert!(coarse.result["count"].contains(&5));
+    ///
+    /// let fine = machine.find_non_empty_refined("count", 8);
+    /// assert_eq!(fine.approximation, Approximation::Exact);
+    /// let count: Vec<Bound<u8>> = fine.result["count"].iter().collect();
+    /// assert!(count.iter().all(|bound| !bound.contains(&3) && !bound.contains(&8)));
+    /// assert!(count.iter().any(|bound| bound.contains(&2)));
+    /// assert!(count.iter().any(|bound| bound.contains(&9)));
+    /// ```
+    pub fn find_non_empty_refined(
+        &self,
+        location: &str,
+        rounds: usize,
+    ) -> AnalysisOutcome<HashMap<String, BoundSet<D>>>
+    where
+        D: Integer + Copy + Bounded + Hash,
+        U: Update<D = D>,
+    {
+        span!(DEBUG, "find_non_empty_refined", location);
+
+        // Runs end on entering a rejecting location, so it has no cells.
+        let reachable = self.reachable(location);
+        let mut cells: Cells<D> = reachable
+            .iter()
+            .filter(|location| !self.get_rejecting().contains(*location))
+            .map(|location| (location.clone(), self.initial_cells(location)))
+            .collect();
+
+        let mut over = self
+            .get_locations()
+            .iter()
+            .filter(|(location, _)| cells.contains_key(*location))
+            .flat_map(|(_, transitions)| transitions)
+            .any(|transition| transition.guard.is_some())
+            || matches!(
+                self.get_no_transition_policy(),
+                NoTransitionPolicy::SinkTo(_)
+            );
+
+        let mut round = 0;
+        let marks = loop {
+            let (marks, undecided) = self.settle(&cells, false);
+            if undecided.is_empty() {
+                break marks;
+            }
+
+            // Cells holding a single value cannot be split any further.
+            let splittable: Vec<(String, usize)> = undecided
+                .into_iter()
+                .filter(|(location, idx)| {
+                    let (lower, upper) = cells[location][*idx];
+                    lower < upper
+                })
+                .collect();
+            if round == rounds || splittable.is_empty() {
+                over = true;
+                break self.settle(&cells, true).0;
+            }
+
+            for (location, idx) in splittable.into_iter().rev() {
+                let location_cells = cells.get_mut(&location).expect("the location has cells");
+                let (lower, upper) = location_cells[idx];
+                let middle = midpoint(lower, upper);
+                location_cells.splice(idx..=idx, [(lower, middle), (middle + D::one(), upper)]);
+            }
+            round += 1;
+        };
+
+        let mut result: HashMap<String, BoundSet<D>> = HashMap::new();
+        for (location, location_cells) in &cells {
+            for (cell, safe) in location_cells.iter().zip(&marks[location]) {
+                if *safe {
+                    result
+                        .entry(location.clone())
+                        .or_insert_with(BoundSet::new)
+                        .insert(Bound::from_explicit(*cell));
+                }
+            }
+        }
+
+        let approximation = match over {
+            true => Approximation::Over,
+            false => Approximation::Exact,
+        };
+        debug!(rounds = round, %approximation, "found refined non-empty intervals");
+
+        AnalysisOutcome {
+            result,
+            approximation,
+        }
+    }
+
+    // Splits the data of `location` at the endpoints of the bounds out of it, and of its
+    // accepting condition.
+    fn initial_cells(&self, location: &str) -> Vec<(D, D)>
+    where
+        D: Integer + Copy + Bounded,
+    {
+        let mut bounds: Vec<&Bound<D>> = self
+            .get_transitions_from(location)
+            .into_iter()
+            .flatten()
+            .map(|transition| &transition.bound)
+            .collect();
+        if let Some(bound) = self
+            .get_accepting_conditions()
+            .get(location)
+            .and_then(|condition| condition.get_bound())
+        {
+            bounds.push(bound);
+        }
+
+        // Every cell starts at the lowest value or right after a cut.
+        let mut starts = vec![D::min_value()];
+        for bound in bounds {
+            starts.extend(bound.lower);
+            starts.extend(
+                bound
+                    .upper
+                    .filter(|upper| *upper < D::max_value())
+                    .map(|upper| upper + D::one()),
+            );
+        }
+        starts.sort();
+        starts.dedup();
+
+        let mut cells: Vec<(D, D)> = starts
+            .windows(2)
+            .map(|pair| (pair[0], pair[1] - D::one()))
+            .collect();
+        cells.push((
+            *starts.last().expect("the lowest value starts a cell"),
+            D::max_value(),
+        ));
+        cells
+    }
+
+    // Marks the cells that lead to acceptance, and returns the other cells that some of their
+    // data may lead to acceptance from. A lenient pass marks those cells too.
+    fn settle(&self, cells: &Cells<D>, lenient: bool) -> (Marks, Vec<(String, usize)>)
+    where
+        D: Integer + Copy + Bounded,
+        U: Update<D = D>,
+    {
+        let accepts = |location: &str, cell: &Bound<D>, lenient: bool| {
+            self.get_accepting().contains(location)
+                && match self.get_accepting_conditions().get(location) {
+                    None => true,
+                    Some(condition) if lenient => condition.may_hold(cell),
+                    Some(condition) => condition.holds_within(cell),
+                }
+        };
+
+        let mut marks: Marks = cells
+            .iter()
+            .map(|(location, location_cells)| {
+                let location_marks = location_cells
+                    .iter()
+                    .map(|cell| accepts(location, &Bound::from_explicit(*cell), lenient))
+                    .collect();
+                (location.clone(), location_marks)
+            })
+            .collect();
+
+        // Marks only ever get set, so this reaches a fixed point.
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (location, location_cells) in cells {
+                for (idx, cell) in location_cells.iter().enumerate() {
+                    if marks[location][idx] {
+                        continue;
+                    }
+
+                    let safe = self.moves(location, *cell).any(|(to, image)| {
+                        let (any, all) = covered(cells, &marks, &to, image);
+                        match lenient {
+                            true => any,
+                            false => all,
+                        }
+                    });
+                    if safe {
+                        marks.get_mut(location).expect("the location has marks")[idx] = true;
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        let mut undecided = Vec::new();
+        for (location, location_cells) in cells {
+            for (idx, cell) in location_cells.iter().enumerate() {
+                let undecided_cell = !marks[location][idx]
+                    && (accepts(location, &Bound::from_explicit(*cell), true)
+                        || self
+                            .moves(location, *cell)
+                            .any(|(to, image)| covered(cells, &marks, &to, image).0));
+                if undecided_cell {
+                    undecided.push((location.clone(), idx));
+                }
+            }
+        }
+
+        (marks, undecided)
+    }
+
+    // Returns the location and data reached by every move out of `cell` of `location` into a
+    // location that is not rejecting. Runtime guards are ignored, as if they always held, and
+    // the implicit move to a sink is taken as if no transition were ever enabled.
+    fn moves<'a>(
+        &'a self,
+        location: &'a str,
+        cell: (D, D),
+    ) -> impl Iterator<Item = (String, (D, D))> + 'a
+    where
+        D: Integer + Copy + Bounded,
+        U: Update<D = D>,
+    {
+        let cell = Bound::from_explicit(cell);
+        let sink = match self.get_no_transition_policy() {
+            NoTransitionPolicy::SinkTo(sink) if sink != location => {
+                Some((sink.clone(), cell.clone()))
+            }
+            _ => None,
+        };
+
+        self.get_transitions_from(location)
+            .into_iter()
+            .flatten()
+            .filter_map(move |transition: &Transition<D, I, U>| {
+                let precondition = cell.intersect(&transition.bound)?;
+                Some((
+                    transition.to_location.clone(),
+                    transition.update_interval(precondition),
+                ))
+            })
+            .chain(sink)
+            .filter(|(to, _)| !self.get_rejecting().contains(to))
+            .map(|(to, image)| (to, image.as_explicit()))
+    }
+}
+
+// Returns whether some, and whether all, of the cells of `location` sharing data with `image`
+// are marked.
+fn covered<D: Ord>(cells: &Cells<D>, marks: &Marks, location: &str, image: (D, D)) -> (bool, bool) {
+    let Some(location_cells) = cells.get(location) else {
+        return (false, false);
+    };
+
+    let (lower, upper) = image;
+    if lower > upper {
+        return (false, false);
+    }
+
+    let start = location_cells.partition_point(|(_, u)| *u < lower);
+    let end = location_cells.partition_point(|(l, _)| *l <= upper);
+    let overlapping = &marks[location][start..end];
+    let any = overlapping.iter().any(|safe| *safe);
+    (any, any && overlapping.iter().all(|safe| *safe))
+}
+
+// Returns the floor of the mean of `lower` and `upper`, without overflowing.
+fn midpoint<D: Integer + Copy>(lower: D, upper: D) -> D {
+    let two = D::one() + D::one();
+    lower.div_floor(&two)
+        + upper.div_floor(&two)
+        + (lower.mod_floor(&two) + upper.mod_floor(&two)).div_floor(&two)
+}
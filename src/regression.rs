@@ -0,0 +1,305 @@
+//! # Regression Module
+//!
+//! This module keeps golden traces: named traces with the verdict a monitor is expected to
+//! reach on each. A [RegressionSuite] is saved to a file next to a specification, and run
+//! against every new version of it, so that a refactoring which changes a verdict is caught by
+//! the test suite. [RegressionSuite::bless] records the current verdicts when a change is
+//! intended.
+//!
+//! ```
+//! use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+//! use rust_efsm::monitor::{Monitor, Verdict};
+//! use rust_efsm::regression::RegressionSuite;
+//!
+//! // No `x` may ever be read.
+//! let machine = MachineBuilder::<u8, char, IdentityUpdate<u8>>::new()
+//!     .with_transition("ok", Transition {
+//!         to_location: "ok".into(),
+//!         enable: |_, i| *i != 'x',
+//!         ..Default::default()
+//!     })
+//!     .with_transition("ok", Transition {
+//!         to_location: "error".into(),
+//!         enable: |_, i| *i == 'x',
+//!         ..Default::default()
+//!     })
+//!     .with_accepting("ok")
+//!     .with_rejecting("error")
+//!     .build();
+//!
+//! let suite = RegressionSuite::new()
+//!     .with_trace("clean", vec!['a', 'b'], Verdict::Inconclusive)
+//!     .with_trace("bad", vec!['a', 'x'], Verdict::Violated);
+//!
+//! // Saved alongside the specification, and loaded back by its tests.
+//! let mut file = Vec::new();
+//! suite.save(&mut file, |i| i.to_string()).unwrap();
+//! let suite = RegressionSuite::load(file.as_slice(), |s| s.parse().ok()).unwrap();
+//!
+//! let report = suite
+//!     .run(|| Monitor::new("ok", 0, machine.clone()).unwrap())
+//!     .unwrap();
+//! assert!(report.is_ok(), "{}", report);
+//! ```
+
+use crate::monitor::{MonitorError, Verdict, Verify};
+use std::fmt;
+use std::io::{BufRead, BufReader, Read, Write};
+
+/// Errors that can occur while loading a regression suite.
+#[derive(Debug)]
+pub enum RegressionError {
+    /// The suite could not be read.
+    Io(std::io::Error),
+    /// A line of the suite is malformed.
+    Malformed {
+        /// The one-based line number.
+        line: usize,
+        /// A description of the problem.
+        message: String,
+    },
+}
+
+impl fmt::Display for RegressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegressionError::Io(error) => write!(f, "i/o error: {}", error),
+            RegressionError::Malformed { line, message } => {
+                write!(f, "line {}: {}", line, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RegressionError {}
+
+impl From<std::io::Error> for RegressionError {
+    fn from(error: std::io::Error) -> Self {
+        RegressionError::Io(error)
+    }
+}
+
+/// A named trace with the verdict a monitor is expected to reach on it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GoldenTrace<I> {
+    /// The name of the trace.
+    pub name: String,
+    /// The inputs of the trace.
+    pub inputs: Vec<I>,
+    /// The verdict expected after the last input.
+    pub expected: Verdict,
+}
+
+/// A trace whose verdict differs from the expected one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerdictChange {
+    /// The name of the trace.
+    pub name: String,
+    /// The verdict expected after the last input.
+    pub expected: Verdict,
+    /// The verdict reached after the last input.
+    pub actual: Verdict,
+}
+
+/// The outcome of running a [RegressionSuite].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RegressionReport {
+    /// The number of traces that reached their expected verdict.
+    pub passed: usize,
+    /// The traces that did not, in the order of the suite.
+    pub changes: Vec<VerdictChange>,
+}
+
+impl RegressionReport {
+    /// Checks if every trace reached its expected verdict.
+    pub fn is_ok(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl fmt::Display for RegressionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} passed, {} changed", self.passed, self.changes.len())?;
+        for change in &self.changes {
+            write!(
+                f,
+                "\n{}: expected {}, got {}",
+                change.name, change.expected, change.actual
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A set of [golden traces](GoldenTrace).
+///
+/// ## Format
+///
+/// A saved suite has one trace per line: its name, its expected verdict, and each of its
+/// inputs, separated by tabs. Tabs, newlines, backslashes, and `#` within a field are escaped
+/// with a backslash. Empty lines and lines starting with `#` are skipped.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegressionSuite<I> {
+    traces: Vec<GoldenTrace<I>>,
+}
+
+impl<I> Default for RegressionSuite<I> {
+    fn default() -> Self {
+        RegressionSuite::new()
+    }
+}
+
+impl<I> RegressionSuite<I> {
+    /// Creates a suite without traces.
+    pub fn new() -> Self {
+        RegressionSuite { traces: Vec::new() }
+    }
+
+    /// Adds the trace `inputs`, named `name`, on which `expected` is the verdict.
+    pub fn with_trace(mut self, name: &str, inputs: Vec<I>, expected: Verdict) -> Self {
+        self.traces.push(GoldenTrace {
+            name: name.into(),
+            inputs,
+            expected,
+        });
+        self
+    }
+
+    /// Returns the traces of the suite, in order.
+    pub fn traces(&self) -> &[GoldenTrace<I>] {
+        &self.traces
+    }
+
+    /// Runs every trace on a fresh monitor from `monitor`, and reports the traces that reached
+    /// another verdict than expected.
+    pub fn run<M, F>(&self, mut monitor: F) -> Result<RegressionReport, MonitorError>
+    where
+        M: Verify<I>,
+        F: FnMut() -> M,
+    {
+        let mut report = RegressionReport::default();
+        for trace in &self.traces {
+            let actual = verdict_of(monitor(), &trace.inputs)?;
+            match actual == trace.expected {
+                true => report.passed += 1,
+                false => report.changes.push(VerdictChange {
+                    name: trace.name.clone(),
+                    expected: trace.expected,
+                    actual,
+                }),
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Replaces the expected verdict of every trace with the one reached on a fresh monitor from
+    /// `monitor`, once a change of verdicts was reviewed.
+    pub fn bless<M, F>(&mut self, mut monitor: F) -> Result<(), MonitorError>
+    where
+        M: Verify<I>,
+        F: FnMut() -> M,
+    {
+        for trace in &mut self.traces {
+            trace.expected = verdict_of(monitor(), &trace.inputs)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the suite to `writer`, formatting each input with `encode`.
+    pub fn save<W: Write>(&self, mut writer: W, encode: fn(&I) -> String) -> std::io::Result<()> {
+        for trace in &self.traces {
+            let mut fields = vec![escape(&trace.name), trace.expected.to_string()];
+            fields.extend(trace.inputs.iter().map(|input| escape(&encode(input))));
+            writeln!(writer, "{}", fields.join("\t"))?;
+        }
+        writer.flush()
+    }
+
+    /// Reads a suite written by [save](RegressionSuite::save), parsing each input with
+    /// `decode`.
+    pub fn load<R: Read>(
+        reader: R,
+        decode: fn(&str) -> Option<I>,
+    ) -> Result<Self, RegressionError> {
+        let mut suite = RegressionSuite::new();
+        for (idx, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line?;
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let malformed = |message: String| RegressionError::Malformed {
+                line: idx + 1,
+                message,
+            };
+
+            let mut fields = line.split('\t').map(unescape);
+            let name = fields.next().unwrap_or_default();
+            let expected = match fields.next().as_deref() {
+                Some("satisfied") => Verdict::Satisfied,
+                Some("violated") => Verdict::Violated,
+                Some("inconclusive") => Verdict::Inconclusive,
+                Some(verdict) => return Err(malformed(format!("unknown verdict {}", verdict))),
+                None => return Err(malformed("missing verdict".into())),
+            };
+            let inputs = fields
+                .map(|field| {
+                    decode(&field).ok_or_else(|| malformed(format!("bad input {}", field)))
+                })
+                .collect::<Result<_, _>>()?;
+
+            suite.traces.push(GoldenTrace {
+                name,
+                inputs,
+                expected,
+            });
+        }
+
+        Ok(suite)
+    }
+}
+
+// Returns the verdict `monitor` reaches after the last input of `inputs`.
+fn verdict_of<I, M: Verify<I>>(mut monitor: M, inputs: &[I]) -> Result<Verdict, MonitorError> {
+    let mut verdict = monitor.initial_verdict();
+    for input in inputs {
+        verdict = monitor.next(input)?;
+    }
+    Ok(verdict)
+}
+
+// Escapes the characters that separate the fields and lines of a saved suite.
+fn escape(field: &str) -> String {
+    let mut escaped = String::with_capacity(field.len());
+    for c in field.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '#' => escaped.push_str("\\#"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// Reverses escape.
+fn unescape(field: &str) -> String {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('t') => unescaped.push('\t'),
+                Some('n') => unescaped.push('\n'),
+                Some('r') => unescaped.push('\r'),
+                Some(c) => unescaped.push(c),
+                None => unescaped.push('\\'),
+            },
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
@@ -0,0 +1,393 @@
+//! # Simulation Module
+//!
+//! This module checks whether one [Machine] simulates another, or whether two machines are
+//! bisimilar. A machine simulates another when it can match every move of the other on the same
+//! input, accepting whenever the other accepts. Simulation implies language inclusion, but unlike
+//! inclusion it can be checked without making either machine deterministic.
+//!
+//! Guards are evaluated on concrete data, so the check explores the pairs of states reachable
+//! over a finite alphabet, as [Machine::to_dfa] does. When the machines are not related, a
+//! [Distinction] shows how they can be told apart.
+
+use crate::machine::{Machine, MachineError, State, Update};
+use std::collections::BTreeMap;
+
+/// The result of comparing two machines.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Comparison<D, I> {
+    /// The machines are related.
+    Related,
+    /// The machines are not related, as shown by the distinction.
+    Distinguished(Distinction<D, I>),
+}
+
+impl<D, I> Comparison<D, I> {
+    /// Checks if the machines are related.
+    pub fn is_related(&self) -> bool {
+        matches!(self, Comparison::Related)
+    }
+}
+
+/// A context in which two machines can be told apart.
+///
+/// After reading `inputs`, the first machine reaches `left` and the second reaches `right`,
+/// where only one of them is accepting, or only one of them has a state at all. The second
+/// machine is assumed to answer each move with the state that resists the longest, so any other
+/// answer is told apart at least as quickly.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Distinction<D, I> {
+    /// The inputs leading to the distinguishing states.
+    pub inputs: Vec<I>,
+    /// The state of the first machine, if it has one.
+    pub left: Option<State<D>>,
+    /// The state of the second machine, if it has one.
+    pub right: Option<State<D>>,
+}
+
+// Why a pair of states was found not to be related.
+#[derive(Clone, Copy)]
+enum Reason {
+    // The states do not agree on acceptance.
+    Accepting,
+    // No successor of the right state matches this successor of the left state on an input.
+    Left(usize, usize),
+    // No successor of the left state matches this successor of the right state on an input.
+    Right(usize, usize),
+}
+
+// The successors of both states of a pair on one input, and the pairs they form.
+struct Move<D> {
+    left: Vec<State<D>>,
+    right: Vec<State<D>>,
+    pairs: Vec<Vec<usize>>,
+}
+
+impl<D, I, U> Machine<D, I, U> {
+    /// Checks if this machine, starting from `start`, simulates `other` starting from
+    /// `other_start`, over the inputs in `alphabet`.
+    ///
+    /// Returns [MachineError::Undecidable] when more than `max_pairs` pairs of states are found,
+    /// which happens when the reachable data is not finite.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, State, Transition};
+    /// use rust_efsm::simulation::Comparison;
+    ///
+    /// // Accepts "ab" and "ac", deciding on the first input which one to expect.
+    /// let guessing = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "b".into(),
+    ///         enable: |_, i| *i == b'a',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "c".into(),
+    ///         enable: |_, i| *i == b'a',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("b", Transition {
+    ///         to_location: "done".into(),
+    ///         enable: |_, i| *i == b'b',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("c", Transition {
+    ///         to_location: "done".into(),
+    ///         enable: |_, i| *i == b'c',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("done")
+    ///     .build();
+    ///
+    /// // Accepts the same inputs, deciding on the second input.
+    /// let waiting = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s1".into(),
+    ///         enable: |_, i| *i == b'a',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("s1", Transition {
+    ///         to_location: "done".into(),
+    ///         enable: |_, i| *i == b'b' || *i == b'c',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("done")
+    ///     .build();
+    ///
+    /// let start = State { location: "s0".into(), data: 0 };
+    /// let alphabet = [b'a', b'b', b'c'];
+    ///
+    /// assert!(waiting
+    ///     .simulates(start.clone(), &guessing, start.clone(), &alphabet, 64)
+    ///     .unwrap()
+    ///     .is_related());
+    ///
+    /// // After an 'a', the guessing machine has to commit to a 'b' or a 'c', and the other one
+    /// // may come instead.
+    /// let Comparison::Distinguished(distinction) = guessing
+    ///     .simulates(start.clone(), &waiting, start, &alphabet, 64)
+    ///     .unwrap()
+    /// else {
+    ///     panic!("the machines should be told apart");
+    /// };
+    /// assert_eq!(distinction.inputs, vec![b'a', b'b']);
+    /// assert_eq!(distinction.left, None);
+    /// ```
+    pub fn simulates<V>(
+        &self,
+        start: State<D>,
+        other: &Machine<D, I, V>,
+        other_start: State<D>,
+        alphabet: &[I],
+        max_pairs: usize,
+    ) -> Result<Comparison<D, I>, MachineError>
+    where
+        D: Clone + Ord,
+        I: Clone,
+        U: Update<D = D>,
+        V: Update<D = D>,
+    {
+        // This machine plays the second role, matching the moves of the other.
+        let comparison = other.compare(other_start, self, start, alphabet, max_pairs, false)?;
+
+        Ok(match comparison {
+            Comparison::Related => Comparison::Related,
+            Comparison::Distinguished(distinction) => Comparison::Distinguished(Distinction {
+                inputs: distinction.inputs,
+                left: distinction.right,
+                right: distinction.left,
+            }),
+        })
+    }
+
+    /// Checks if this machine, starting from `start`, and `other`, starting from `other_start`,
+    /// are bisimilar over the inputs in `alphabet`, so that each can match every move of the
+    /// other.
+    ///
+    /// Returns [MachineError::Undecidable] when more than `max_pairs` pairs of states are found.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{AddUpdate, MachineBuilder, State, Transition};
+    /// use rust_efsm::simulation::Comparison;
+    ///
+    /// // Alternates between two locations.
+    /// let locations = MachineBuilder::<i8, u8, AddUpdate<i8>>::new()
+    ///     .with_transition("even", Transition { to_location: "odd".into(), ..Default::default() })
+    ///     .with_transition("odd", Transition { to_location: "even".into(), ..Default::default() })
+    ///     .with_accepting("even")
+    ///     .build();
+    ///
+    /// // Alternates between two locations, while counting up and down.
+    /// let counting = MachineBuilder::<i8, u8, AddUpdate<i8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "done".into(),
+    ///         update: AddUpdate { amount: 1 },
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("done", Transition {
+    ///         to_location: "s0".into(),
+    ///         update: AddUpdate { amount: -1 },
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("done")
+    ///     .build();
+    ///
+    /// let even = State { location: "even".into(), data: 0 };
+    /// let odd = State { location: "odd".into(), data: 0 };
+    /// let s0 = State { location: "s0".into(), data: 0 };
+    ///
+    /// assert!(locations.bisimilar(odd, &counting, s0.clone(), &[0], 16).unwrap().is_related());
+    ///
+    /// let Comparison::Distinguished(distinction) =
+    ///     locations.bisimilar(even.clone(), &counting, s0.clone(), &[0], 16).unwrap()
+    /// else {
+    ///     panic!("the machines should be told apart");
+    /// };
+    /// assert_eq!(distinction.inputs, vec![]);
+    /// assert_eq!((distinction.left, distinction.right), (Some(even), Some(s0)));
+    /// ```
+    pub fn bisimilar<V>(
+        &self,
+        start: State<D>,
+        other: &Machine<D, I, V>,
+        other_start: State<D>,
+        alphabet: &[I],
+        max_pairs: usize,
+    ) -> Result<Comparison<D, I>, MachineError>
+    where
+        D: Clone + Ord,
+        I: Clone,
+        U: Update<D = D>,
+        V: Update<D = D>,
+    {
+        self.compare(start, other, other_start, alphabet, max_pairs, true)
+    }
+
+    // Checks if `other` simulates this machine, or if both are bisimilar when `symmetric` is
+    // set, by removing pairs of states that are not related until none are left to remove.
+    fn compare<V>(
+        &self,
+        start: State<D>,
+        other: &Machine<D, I, V>,
+        other_start: State<D>,
+        alphabet: &[I],
+        max_pairs: usize,
+        symmetric: bool,
+    ) -> Result<Comparison<D, I>, MachineError>
+    where
+        D: Clone + Ord,
+        I: Clone,
+        U: Update<D = D>,
+        V: Update<D = D>,
+    {
+        let initial = (start, other_start);
+        let mut ids: BTreeMap<(State<D>, State<D>), usize> = BTreeMap::from([(initial.clone(), 0)]);
+        let mut pairs = vec![initial];
+        let mut moves: Vec<Vec<Move<D>>> = Vec::new();
+
+        // Pairs are numbered in the order they are found, so each is explored exactly once.
+        while let Some((left, right)) = pairs.get(moves.len()).cloned() {
+            let mut next = Vec::with_capacity(alphabet.len());
+
+            for i in alphabet {
+                let mut left_next = self.transition(i, vec![left.clone()])?;
+                left_next.sort();
+                left_next.dedup();
+                let mut right_next = other.transition(i, vec![right.clone()])?;
+                right_next.sort();
+                right_next.dedup();
+
+                let mut pair_ids = Vec::with_capacity(left_next.len());
+                for l in &left_next {
+                    let mut row = Vec::with_capacity(right_next.len());
+                    for r in &right_next {
+                        let pair = (l.clone(), r.clone());
+                        let id = match ids.get(&pair) {
+                            Some(id) => *id,
+                            None => {
+                                if pairs.len() >= max_pairs {
+                                    return Err(MachineError::Undecidable);
+                                }
+
+                                ids.insert(pair.clone(), pairs.len());
+                                pairs.push(pair);
+                                pairs.len() - 1
+                            }
+                        };
+                        row.push(id);
+                    }
+                    pair_ids.push(row);
+                }
+
+                next.push(Move {
+                    left: left_next,
+                    right: right_next,
+                    pairs: pair_ids,
+                });
+            }
+
+            moves.push(next);
+        }
+
+        // The round in which each pair was removed, and why.
+        let mut removed: Vec<Option<(usize, Reason)>> = vec![None; pairs.len()];
+        for round in 0.. {
+            let related = |id: usize| removed[id].is_none();
+            let mut found = Vec::new();
+
+            for (id, (left, right)) in pairs.iter().enumerate() {
+                if !related(id) {
+                    continue;
+                }
+
+                let left_accepting = self.is_accepting(left);
+                let right_accepting = other.is_accepting(right);
+                if (left_accepting && !right_accepting)
+                    || (symmetric && !left_accepting && right_accepting)
+                {
+                    found.push((id, Reason::Accepting));
+                    continue;
+                }
+
+                let reason = moves[id].iter().enumerate().find_map(|(input, m)| {
+                    let unmatched_left = (0..m.left.len())
+                        .find(|&l| !m.pairs[l].iter().any(|&pair| related(pair)))
+                        .map(|l| Reason::Left(input, l));
+                    let unmatched_right = || {
+                        (0..m.right.len())
+                            .find(|&r| !m.pairs.iter().any(|row| related(row[r])))
+                            .map(|r| Reason::Right(input, r))
+                    };
+
+                    unmatched_left.or_else(|| symmetric.then(unmatched_right).flatten())
+                });
+                if let Some(reason) = reason {
+                    found.push((id, reason));
+                }
+            }
+
+            if found.is_empty() {
+                break;
+            }
+            for (id, reason) in found {
+                removed[id] = Some((round, reason));
+            }
+        }
+
+        if removed[0].is_none() {
+            return Ok(Comparison::Related);
+        }
+
+        // Follow the reasons from the initial pair, answering each move with the pair that was
+        // removed last. Each step reaches a pair removed in an earlier round, so this ends.
+        let round = |id: usize| removed[id].map_or(usize::MAX, |(round, _)| round);
+        let mut inputs = Vec::new();
+        let mut id = 0;
+        loop {
+            let Some((_, reason)) = removed[id] else {
+                unreachable!("only removed pairs are followed");
+            };
+
+            let (left, right) = &pairs[id];
+            let next = match reason {
+                Reason::Accepting => {
+                    return Ok(Comparison::Distinguished(Distinction {
+                        inputs,
+                        left: Some(left.clone()),
+                        right: Some(right.clone()),
+                    }));
+                }
+                Reason::Left(input, l) => {
+                    inputs.push(alphabet[input].clone());
+                    let m = &moves[id][input];
+                    let Some(&next) = m.pairs[l].iter().max_by_key(|&&pair| round(pair)) else {
+                        return Ok(Comparison::Distinguished(Distinction {
+                            inputs,
+                            left: Some(m.left[l].clone()),
+                            right: None,
+                        }));
+                    };
+                    next
+                }
+                Reason::Right(input, r) => {
+                    inputs.push(alphabet[input].clone());
+                    let m = &moves[id][input];
+                    let Some(next) = m
+                        .pairs
+                        .iter()
+                        .map(|row| row[r])
+                        .max_by_key(|&pair| round(pair))
+                    else {
+                        return Ok(Comparison::Distinguished(Distinction {
+                            inputs,
+                            left: None,
+                            right: Some(m.right[r].clone()),
+                        }));
+                    };
+                    next
+                }
+            };
+
+            id = next;
+        }
+    }
+}
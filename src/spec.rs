@@ -0,0 +1,338 @@
+//! # Spec Module
+//!
+//! This module loads [machines](Machine) from a plain text specification, so a machine can be
+//! changed without recompiling the program that monitors it. Guards are written in the
+//! [expression language](crate::expr) and updates add a constant to the data.
+//!
+//! ## Format
+//!
+//! Each line is either blank, a comment starting with `#`, a list of accepting or
+//! [rejecting](crate::machine::MachineBuilder::with_rejecting) locations, or a transition with an
+//! optional guard and an optional update.
+//!
+//! ```text
+//! # Count consecutive 'b's, accepting while there are at most three.
+//! accepting s1
+//! rejecting error
+//! s0 -> s0 when data <= 10 && input != 'b'
+//! s0 -> s1 when data <= 3 && input == 'b' do data += 1
+//! s0 -> error when input == '!'
+//! s1 -> s1 when input == 'b' do data += 1
+//! ```
+//!
+//! The data [bound](crate::bound::Bound) of every transition is derived from its guard.
+//!
+//! ## Examples
+//!
+//! ```
+//! use rust_efsm::spec;
+//!
+//! let machine = spec::parse::<u32, u8>(
+//!     "accepting s1
+//!      s0 -> s1 when input == 'b' do data += 1",
+//! )
+//! .unwrap();
+//!
+//! assert!(machine.exec("s0", 0, vec![b'b']));
+//! assert!(!machine.exec("s0", 0, vec![b'a']));
+//! ```
+
+use crate::bound::Bound;
+use crate::expr::{Expr, ExprError};
+use crate::machine::{AddUpdate, InputAbstraction, Machine, MachineBuilder, Transition};
+use num::{Bounded, CheckedAdd, FromPrimitive, ToPrimitive, Zero};
+use std::fmt;
+use std::fmt::Debug;
+use std::ops::Add;
+use std::path::Path;
+
+/// Errors that can occur while loading a specification.
+#[derive(Debug)]
+pub enum SpecError {
+    /// The specification file could not be read, or the code generated from it could not be
+    /// written.
+    Io(std::io::Error),
+    /// A line of the specification is malformed.
+    Syntax {
+        /// The one-based line number.
+        line: usize,
+        /// A description of the problem.
+        message: String,
+    },
+    /// A guard on a line of the specification failed to parse.
+    Guard {
+        /// The one-based line number.
+        line: usize,
+        /// The underlying expression error.
+        error: ExprError,
+    },
+}
+
+impl fmt::Display for SpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpecError::Io(error) => write!(f, "i/o error: {}", error),
+            SpecError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+            SpecError::Guard { line, error } => {
+                write!(f, "line {}: invalid guard: {}", line, error)
+            }
+        }
+    }
+}
+
+/// Parses a machine from the text of a specification.
+///
+/// A guard that no data value can satisfy is rejected, since its transition could never fire.
+///
+/// ```
+/// use rust_efsm::spec;
+///
+/// let result = spec::parse::<u32, u8>("s0 => s1");
+/// assert!(result.is_err());
+///
+/// let result = spec::parse::<u32, u8>("s0 -> s1 when data < 3 && data > 5");
+/// assert_eq!(result.unwrap_err().to_string(), "line 1: guard is never satisfied");
+/// ```
+pub fn parse<D, I>(source: &str) -> Result<Machine<D, I, AddUpdate<D>>, SpecError>
+where
+    D: Add<Output = D>
+        + Bounded
+        + CheckedAdd
+        + Copy
+        + Debug
+        + Default
+        + Eq
+        + FromPrimitive
+        + ToPrimitive
+        + Zero,
+    I: Debug + ToPrimitive,
+{
+    let mut builder = MachineBuilder::new();
+
+    for item in items(source, Expr::parse, amount)? {
+        let (line, from_location, to_location, guard, amount) = match item {
+            Item::Accepting(locations) => {
+                builder = builder.with_accepting_all(locations);
+                continue;
+            }
+            Item::Rejecting(locations) => {
+                for location in locations {
+                    builder = builder.with_rejecting(&location);
+                }
+                continue;
+            }
+            Item::Transition {
+                line,
+                from_location,
+                to_location,
+                guard,
+                update,
+            } => (line, from_location, to_location, guard, update),
+        };
+
+        let guard = guard.unwrap_or(Expr::Bool(true));
+        let amount = match amount {
+            Some(amount) => D::from_i128(amount).ok_or_else(|| out_of_range(line))?,
+            None => D::zero(),
+        };
+
+        builder = builder.with_transition(
+            &from_location,
+            Transition {
+                to_location,
+                enable: |_, _| true,
+                bound: bound(line, &guard)?,
+                update: AddUpdate { amount },
+                guard: Some(guard.guard()),
+                name: None,
+                input: InputAbstraction::Unknown,
+                binding: None,
+            },
+        );
+    }
+
+    Ok(builder.build())
+}
+
+/// Reads and parses a machine from the specification file at `path`.
+pub fn load<D, I>(path: impl AsRef<Path>) -> Result<Machine<D, I, AddUpdate<D>>, SpecError>
+where
+    D: Add<Output = D>
+        + Bounded
+        + CheckedAdd
+        + Copy
+        + Debug
+        + Default
+        + Eq
+        + FromPrimitive
+        + ToPrimitive
+        + Zero,
+    I: Debug + ToPrimitive,
+{
+    let source = std::fs::read_to_string(path).map_err(SpecError::Io)?;
+    parse(&source)
+}
+
+// A line of a specification, with its guard and update parsed by the caller.
+pub(crate) enum Item<G, U> {
+    Accepting(Vec<String>),
+    Rejecting(Vec<String>),
+    Transition {
+        line: usize,
+        from_location: String,
+        to_location: String,
+        guard: Option<G>,
+        update: Option<U>,
+    },
+}
+
+// Parses the lines of a specification, skipping blank lines and comments. The text after `when`
+// is parsed by `guard`, and the text after `do` by `update`, which describes its errors.
+pub(crate) fn items<G, U>(
+    source: &str,
+    guard: impl Fn(&str) -> Result<G, ExprError>,
+    update: impl Fn(&str) -> Result<U, String>,
+) -> Result<Vec<Item<G, U>>, SpecError> {
+    let mut items = Vec::new();
+
+    for (idx, line) in source.lines().enumerate() {
+        let line_number = idx + 1;
+        let line = line.trim();
+        let syntax = |message: &str| SpecError::Syntax {
+            line: line_number,
+            message: message.into(),
+        };
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(locations) = line.strip_prefix("accepting ") {
+            items.push(Item::Accepting(
+                locations.split_whitespace().map(String::from).collect(),
+            ));
+            continue;
+        }
+
+        if let Some(locations) = line.strip_prefix("rejecting ") {
+            items.push(Item::Rejecting(
+                locations.split_whitespace().map(String::from).collect(),
+            ));
+            continue;
+        }
+
+        let (from_location, rest) = line
+            .split_once("->")
+            .ok_or_else(|| syntax("expected 'accepting' or a transition 'from -> to'"))?;
+        let from_location = from_location.trim();
+        if from_location.is_empty() || from_location.contains(char::is_whitespace) {
+            return Err(syntax("expected a single source location"));
+        }
+
+        let (rest, update_source) = match split_keyword(rest, "do") {
+            Some((rest, update)) => (rest, Some(update)),
+            None => (rest, None),
+        };
+
+        let (to_location, guard_source) = match split_keyword(rest, "when") {
+            Some((to_location, guard)) => (to_location, Some(guard)),
+            None => (rest, None),
+        };
+
+        let to_location = to_location.trim();
+        if to_location.is_empty() || to_location.contains(char::is_whitespace) {
+            return Err(syntax("expected a single target location"));
+        }
+
+        let guard = guard_source
+            .map(&guard)
+            .transpose()
+            .map_err(|error| SpecError::Guard {
+                line: line_number,
+                error,
+            })?;
+        let update = update_source
+            .map(&update)
+            .transpose()
+            .map_err(|message| syntax(&message))?;
+
+        items.push(Item::Transition {
+            line: line_number,
+            from_location: from_location.into(),
+            to_location: to_location.into(),
+            guard,
+            update,
+        });
+    }
+
+    Ok(items)
+}
+
+// Parses an update of the form `data += N`, returning `N`.
+pub(crate) fn amount(update: &str) -> Result<i128, String> {
+    let amount = update
+        .trim()
+        .strip_prefix("data")
+        .and_then(|update| update.trim_start().strip_prefix("+="))
+        .ok_or("expected an update of the form 'data += N'")?;
+
+    amount.trim().parse().map_err(|_| OUT_OF_RANGE.into())
+}
+
+// Derives the data bound of the transition guarded by `guard` on `line`, rejecting a guard that no
+// data value can satisfy. Data types beyond the range of `i128` are left unbounded.
+pub(crate) fn bound<D>(line: usize, guard: &Expr) -> Result<Bound<D>, SpecError>
+where
+    D: Bounded + Copy + Eq + ToPrimitive + FromPrimitive,
+{
+    if let Some(bound) = guard.bound() {
+        return Ok(bound);
+    }
+
+    match (D::min_value().to_i128(), D::max_value().to_i128()) {
+        (Some(_), Some(_)) => Err(SpecError::Syntax {
+            line,
+            message: "guard is never satisfied".into(),
+        }),
+        _ => Ok(Bound::unbounded()),
+    }
+}
+
+// Returns the error for an update amount that the data type cannot hold.
+pub(crate) fn out_of_range(line: usize) -> SpecError {
+    SpecError::Syntax {
+        line,
+        message: OUT_OF_RANGE.into(),
+    }
+}
+
+const OUT_OF_RANGE: &str = "update amount is out of range for the data type";
+
+// Splits `line` around the first occurrence of `keyword` as a whole word outside of a character
+// or string literal.
+fn split_keyword<'a>(line: &'a str, keyword: &str) -> Option<(&'a str, &'a str)> {
+    // The quote of the literal the scan is in, if any, and whether the next character is escaped.
+    let mut literal = None;
+    let mut escaped = false;
+    let mut prev = ' ';
+
+    for (idx, c) in line.char_indices() {
+        match literal {
+            Some(_) if escaped => escaped = false,
+            Some(_) if c == '\\' => escaped = true,
+            Some(quote) if c == quote => literal = None,
+            Some(_) => {}
+            None if c == '\'' || c == '"' => literal = Some(c),
+            None if prev.is_whitespace() && line[idx..].starts_with(keyword) => {
+                let after = &line[idx + keyword.len()..];
+                if after.is_empty() || after.starts_with(char::is_whitespace) {
+                    return Some((&line[..idx], after));
+                }
+            }
+            None => {}
+        }
+        prev = c;
+    }
+
+    None
+}
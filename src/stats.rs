@@ -0,0 +1,635 @@
+//! # Stats Module
+//!
+//! This module provides [Stats], a composite data type holding the running count, sum, minimum,
+//! and maximum of observed samples, and [StatsUpdate], which folds a sample into it. Guards can
+//! then constrain aggregates such as [Stats::mean], so a property like "the average latency
+//! stays below 100" can be written directly.
+//!
+//! An [Update] cannot read the input, so each transition carries the sample it observes.
+//! [MachineBuilder::with_stats] adds one self-loop per sample value for inputs that are
+//! classified into a few values, such as latency buckets.
+//!
+//! It also measures how real traffic exercises a machine: [Machine::fit_probabilities] replays a
+//! corpus of traces and counts how often each transition fires, as [FiringFrequencies] that
+//! can be [drawn](crate::gviz::GvGraph::with_firing_frequencies) as the thickness of the edges.
+//! An [AnomalyScorer] then scores each new input by how surprising it is under those
+//! frequencies, next to the verdicts of a [monitor](crate::monitor::Monitor).
+//!
+//! ## Limitations
+//!
+//! - Inputs are quantised: the statistics only ever see the `samples` given to
+//!   [MachineBuilder::with_stats], never the raw inputs, so a mean is only as precise as the
+//!   buckets. An input classified outside of `samples` has no transition at all.
+//! - The standard analyses see nothing of the statistics. A [Bound] over composite data is not
+//!   meaningful, so [StatsUpdate] maps every interval to [Bound::unbounded], and interval
+//!   analyses such as [Machine::find_non_empty] would treat any statistics as reachable.
+//!   [StatsInterval] tracks an interval for each field instead, through
+//!   [StatsUpdate::update_stats_interval], but nothing in the crate consumes it yet.
+
+use crate::bound::Bound;
+use crate::machine::{
+    InputAbstraction, Machine, MachineBuilder, MachineError, State, Transition, TransitionId,
+    Update,
+};
+use num::{Bounded, CheckedAdd, One, Zero};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::Debug;
+use std::ops::Div;
+use std::sync::Arc;
+
+/// The running statistics of the samples observed so far.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Stats<V> {
+    /// The number of samples.
+    pub count: V,
+    /// The sum of the samples.
+    pub sum: V,
+    /// The smallest sample, or the largest value of `V` before the first sample.
+    pub min: V,
+    /// The largest sample, or the smallest value of `V` before the first sample.
+    pub max: V,
+}
+
+impl<V> Stats<V>
+where
+    V: Zero + Bounded,
+{
+    /// Creates statistics without any samples.
+    pub fn new() -> Self {
+        Stats {
+            count: V::zero(),
+            sum: V::zero(),
+            min: V::max_value(),
+            max: V::min_value(),
+        }
+    }
+}
+
+impl<V> Stats<V> {
+    /// Returns the mean of the samples, or `None` before the first sample.
+    ///
+    /// The mean is exact only while the sum has not saturated.
+    ///
+    /// ```
+    /// use rust_efsm::machine::Update;
+    /// use rust_efsm::stats::{Stats, StatsUpdate};
+    ///
+    /// let stats = [10_u32, 20, 60]
+    ///     .into_iter()
+    ///     .fold(Stats::new(), |stats, sample| StatsUpdate::observe(sample).update(stats, &()));
+    ///
+    /// assert_eq!(stats.mean(), Some(30));
+    /// assert_eq!((stats.min, stats.max), (10, 60));
+    /// assert_eq!(Stats::<u32>::new().mean(), None);
+    /// ```
+    pub fn mean(&self) -> Option<V>
+    where
+        V: Zero + Copy + Div<Output = V>,
+    {
+        if self.count.is_zero() {
+            None
+        } else {
+            Some(self.sum / self.count)
+        }
+    }
+}
+
+impl<V> Default for Stats<V>
+where
+    V: Zero + Bounded,
+{
+    fn default() -> Self {
+        Stats::new()
+    }
+}
+
+/// An update that folds a sample into [Stats], or leaves them unchanged.
+///
+/// The count and the sum saturate at the largest value of `V` instead of overflowing.
+///
+/// Its [update_interval](Update::update_interval) always returns [Bound::unbounded], so the
+/// interval analyses learn nothing about the statistics. Use
+/// [update_stats_interval](StatsUpdate::update_stats_interval) to bound each field.
+#[derive(Clone, Debug, Default, PartialEq, Hash)]
+pub struct StatsUpdate<V> {
+    /// The sample observed by this update, if any.
+    pub sample: Option<V>,
+}
+
+impl<V> StatsUpdate<V> {
+    /// Creates an update that observes `sample`.
+    pub fn observe(sample: V) -> Self {
+        StatsUpdate {
+            sample: Some(sample),
+        }
+    }
+
+    /// Creates an update that leaves the statistics unchanged.
+    pub fn keep() -> Self {
+        StatsUpdate { sample: None }
+    }
+
+    /// Returns intervals containing every field of the result of [update](Update::update) for
+    /// all statistics within `interval`.
+    ///
+    /// ```
+    /// use rust_efsm::bound::Bound;
+    /// use rust_efsm::stats::{StatsInterval, StatsUpdate};
+    ///
+    /// let interval = StatsUpdate::observe(50_u32).update_stats_interval(StatsInterval::new());
+    /// let interval = StatsUpdate::observe(10_u32).update_stats_interval(interval);
+    ///
+    /// assert_eq!(interval.count, Bound { lower: Some(2), upper: Some(2) });
+    /// assert_eq!(interval.mean(), Bound { lower: Some(10), upper: Some(50) });
+    /// ```
+    pub fn update_stats_interval(&self, interval: StatsInterval<V>) -> StatsInterval<V>
+    where
+        V: Ord + Copy + Bounded + CheckedAdd + One,
+    {
+        let Some(sample) = self.sample else {
+            return interval;
+        };
+
+        let shift = |bound: Bound<V>, amount: V| {
+            let (lower, upper) = bound.as_explicit();
+            Bound {
+                lower: Some(saturating_add(lower, amount)),
+                upper: Some(saturating_add(upper, amount)),
+            }
+        };
+        let (min_lower, min_upper) = interval.min.as_explicit();
+        let (max_lower, max_upper) = interval.max.as_explicit();
+
+        StatsInterval {
+            count: shift(interval.count, V::one()),
+            sum: shift(interval.sum, sample),
+            min: Bound {
+                lower: Some(min_lower.min(sample)),
+                upper: Some(min_upper.min(sample)),
+            },
+            max: Bound {
+                lower: Some(max_lower.max(sample)),
+                upper: Some(max_upper.max(sample)),
+            },
+        }
+    }
+}
+
+impl<V> Update for StatsUpdate<V>
+where
+    V: Ord + Copy + Bounded + CheckedAdd + One,
+{
+    type D = Stats<V>;
+
+    fn update<I>(&self, data: Stats<V>, _input: &I) -> Stats<V> {
+        let Some(sample) = self.sample else {
+            return data;
+        };
+
+        Stats {
+            count: saturating_add(data.count, V::one()),
+            sum: saturating_add(data.sum, sample),
+            min: data.min.min(sample),
+            max: data.max.max(sample),
+        }
+    }
+
+    // Intervals over composite data are not meaningful, see update_stats_interval instead.
+    fn update_interval(&self, _interval: Bound<Stats<V>>) -> Bound<Stats<V>> {
+        Bound::unbounded()
+    }
+}
+
+impl<V: fmt::Display> fmt::Display for StatsUpdate<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.sample {
+            Some(sample) => write!(f, "observe {}", sample),
+            None => write!(f, "data"),
+        }
+    }
+}
+
+/// An interval for each field of [Stats].
+#[derive(Clone, Debug, PartialEq)]
+pub struct StatsInterval<V> {
+    /// The interval of the count.
+    pub count: Bound<V>,
+    /// The interval of the sum.
+    pub sum: Bound<V>,
+    /// The interval of the minimum.
+    pub min: Bound<V>,
+    /// The interval of the maximum.
+    pub max: Bound<V>,
+}
+
+impl<V> StatsInterval<V>
+where
+    V: Zero + Bounded + Copy,
+{
+    /// Creates the interval containing only statistics without any samples.
+    pub fn new() -> Self {
+        let point = |value: V| Bound {
+            lower: Some(value),
+            upper: Some(value),
+        };
+
+        StatsInterval {
+            count: point(V::zero()),
+            sum: point(V::zero()),
+            min: point(V::max_value()),
+            max: point(V::min_value()),
+        }
+    }
+}
+
+impl<V> StatsInterval<V> {
+    /// Returns an interval containing the mean of any statistics within this interval that have
+    /// at least one sample.
+    ///
+    /// The mean always lies between the minimum and the maximum.
+    pub fn mean(&self) -> Bound<V>
+    where
+        V: Copy,
+    {
+        Bound {
+            lower: self.min.lower,
+            upper: self.max.upper,
+        }
+    }
+}
+
+impl<V> Default for StatsInterval<V>
+where
+    V: Zero + Bounded + Copy,
+{
+    fn default() -> Self {
+        StatsInterval::new()
+    }
+}
+
+// Adds `amount` to `value`, stopping at the largest value of the type.
+fn saturating_add<V: Bounded + CheckedAdd>(value: V, amount: V) -> V {
+    value.checked_add(&amount).unwrap_or_else(V::max_value)
+}
+
+impl<V, I> MachineBuilder<Stats<V>, I, StatsUpdate<V>>
+where
+    V: Ord + Copy + Bounded + CheckedAdd + Zero + One + Debug + Send + Sync + 'static,
+    I: Debug + 'static,
+{
+    /// Add a self-loop on `location` for each of `samples`, observing the sample that `classify`
+    /// returns for the input.
+    ///
+    /// Inputs are quantised to `samples`: the statistics observe the classified value, not the
+    /// input. Inputs classified as a value outside of `samples` leave the location without a
+    /// transition. The location should have no other transitions.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{MachineBuilder, State};
+    /// use rust_efsm::stats::{Stats, StatsUpdate};
+    ///
+    /// // Round latencies up to the next multiple of 50.
+    /// let machine = MachineBuilder::<Stats<u32>, u32, StatsUpdate<u32>>::new()
+    ///     .with_stats("m", &[50, 100, 150, 200], |latency| latency.div_ceil(50) * 50)
+    ///     .build();
+    ///
+    /// let mut states = vec![State { location: "m".into(), data: Stats::new() }];
+    /// for latency in [20, 120, 70] {
+    ///     states = machine.transition(&latency, states).unwrap();
+    /// }
+    /// assert_eq!(states[0].data.mean(), Some(100));
+    /// ```
+    pub fn with_stats(self, location: &str, samples: &[V], classify: fn(&I) -> V) -> Self {
+        samples.iter().fold(self, |builder, &sample| {
+            builder.with_transition(
+                location,
+                Transition {
+                    to_location: location.into(),
+                    enable: |_, _| true,
+                    bound: Bound::unbounded(),
+                    update: StatsUpdate::observe(sample),
+                    guard: Some(Arc::new(move |_: &Stats<V>, input: &I| {
+                        classify(input) == sample
+                    })),
+                    name: None,
+                    input: InputAbstraction::Unknown,
+                    binding: None,
+                },
+            )
+        })
+    }
+}
+
+/// How often a transition fired while a corpus of traces was replayed.
+///
+/// See [Machine::fit_probabilities].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FiringFrequency {
+    /// The number of times a state in the location of the transition read an input.
+    pub visits: u64,
+    /// The number of times the transition was taken.
+    pub fired: u64,
+}
+
+impl FiringFrequency {
+    /// Returns the empirical probability that the transition fires when its location reads an
+    /// input, or `None` when the location was never visited.
+    pub fn probability(&self) -> Option<f64> {
+        match self.visits {
+            0 => None,
+            visits => Some(self.fired as f64 / visits as f64),
+        }
+    }
+}
+
+/// The [FiringFrequency] of every transition of a machine.
+pub type FiringFrequencies = BTreeMap<TransitionId, FiringFrequency>;
+
+impl<D, I, U> Machine<D, I, U> {
+    /// Replays each of `traces` from `location` with `data`, and counts how often every
+    /// transition fires.
+    ///
+    /// Every transition is listed, including those that never fired. A nondeterministic machine
+    /// visits a location once per state in it, so the probabilities of the transitions out of a
+    /// location may add up to more than one.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition, TransitionId};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("idle", Transition {
+    ///         to_location: "busy".into(),
+    ///         enable: |_, i| *i == b'q',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("idle", Transition {
+    ///         to_location: "idle".into(),
+    ///         enable: |_, i| *i != b'q',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("busy", Transition {
+    ///         to_location: "idle".into(),
+    ///         enable: |_, i| *i == b'a',
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("busy", Transition {
+    ///         to_location: "failed".into(),
+    ///         enable: |_, i| *i == b'e',
+    ///         ..Default::default()
+    ///     })
+    ///     .build();
+    ///
+    /// let corpus = vec![b"qa".to_vec(), b"xxqa".to_vec(), b"x".to_vec()];
+    /// let frequencies = machine.fit_probabilities("idle", 0, corpus).unwrap();
+    ///
+    /// let request = frequencies[&TransitionId::new("idle", 0)];
+    /// assert_eq!((request.fired, request.visits), (2, 5));
+    /// assert_eq!(request.probability(), Some(0.4));
+    /// // No trace ever failed.
+    /// assert_eq!(frequencies[&TransitionId::new("busy", 1)].fired, 0);
+    /// ```
+    pub fn fit_probabilities<T>(
+        &self,
+        location: &str,
+        data: D,
+        traces: T,
+    ) -> Result<FiringFrequencies, MachineError>
+    where
+        D: Clone,
+        U: Update<D = D>,
+        T: IntoIterator<Item = Vec<I>>,
+    {
+        let mut visits: BTreeMap<String, u64> = BTreeMap::new();
+        let mut fired: BTreeMap<TransitionId, u64> = BTreeMap::new();
+        for trace in traces {
+            let mut states = vec![State {
+                location: location.to_string(),
+                data: data.clone(),
+            }];
+            for input in &trace {
+                let mut next_states = Vec::with_capacity(states.len());
+                for state in states {
+                    *visits.entry(state.location.clone()).or_default() += 1;
+                    let from_location = state.location.clone();
+                    for (index, next) in self.successors(state, input)? {
+                        if let Some(index) = index {
+                            *fired
+                                .entry(TransitionId::new(&from_location, index))
+                                .or_default() += 1;
+                        }
+                        if !self.get_rejecting().contains(&next.location) {
+                            next_states.push(next);
+                        }
+                    }
+                }
+                states = next_states;
+            }
+        }
+
+        let frequencies = self
+            .transition_ids()
+            .map(|id| {
+                let frequency = FiringFrequency {
+                    visits: visits.get(&id.from_location).copied().unwrap_or_default(),
+                    fired: fired.get(&id).copied().unwrap_or_default(),
+                };
+                (id, frequency)
+            })
+            .collect();
+        Ok(frequencies)
+    }
+}
+
+/// Scores each input by its surprise under the [firing frequencies](FiringFrequencies) of a
+/// machine, created by [Machine::anomaly_scorer].
+///
+/// The surprise of an input is its negative log-likelihood, in nats: `-ln p`, where `p` is the
+/// probability that the current states take the transitions it enables, averaged over the states.
+/// An input taking no transition is scored by how often the location took none in the corpus.
+/// Inputs that never occurred in the corpus score infinity, unless
+/// [smoothed](AnomalyScorer::with_smoothing).
+///
+/// ```
+/// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+///
+/// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+///     .with_transition("idle", Transition {
+///         to_location: "busy".into(),
+///         enable: |_, i| *i == b'q',
+///         ..Default::default()
+///     })
+///     .with_transition("idle", Transition {
+///         to_location: "idle".into(),
+///         enable: |_, i| *i != b'q',
+///         ..Default::default()
+///     })
+///     .with_transition("busy", Transition {
+///         to_location: "idle".into(),
+///         enable: |_, i| *i == b'a',
+///         ..Default::default()
+///     })
+///     .with_transition("busy", Transition {
+///         to_location: "failed".into(),
+///         enable: |_, i| *i == b'e',
+///         ..Default::default()
+///     })
+///     .build();
+///
+/// let corpus = vec![b"qaxqa".to_vec(), b"xxqa".to_vec()];
+/// let frequencies = machine.fit_probabilities("idle", 0, corpus).unwrap();
+///
+/// let mut scorer = machine.anomaly_scorer("idle", 0, &frequencies);
+/// let request = scorer.score(&b'q').unwrap();
+/// // Half of the inputs read while idle were requests.
+/// assert!((request - 2.0_f64.ln()).abs() < 1e-9);
+/// // Every request was answered in the corpus.
+/// assert_eq!(scorer.score(&b'a').unwrap(), 0.0);
+///
+/// let mut scorer = machine
+///     .anomaly_scorer("idle", 0, &frequencies)
+///     .with_smoothing(1.0);
+/// scorer.score(&b'q').unwrap();
+/// let error = scorer.score(&b'e').unwrap();
+/// assert!(error.is_finite() && error > 1.0);
+/// assert!(scorer.total() > error);
+/// ```
+pub struct AnomalyScorer<'a, D, I, U> {
+    machine: &'a Machine<D, I, U>,
+    frequencies: &'a FiringFrequencies,
+    states: Vec<State<D>>,
+    smoothing: f64,
+    total: f64,
+}
+
+impl<D, I, U> Machine<D, I, U> {
+    /// Creates an [AnomalyScorer] starting in `location` with `data`, scoring inputs under
+    /// `frequencies`, as returned by [fit_probabilities](Machine::fit_probabilities).
+    pub fn anomaly_scorer<'a>(
+        &'a self,
+        location: &str,
+        data: D,
+        frequencies: &'a FiringFrequencies,
+    ) -> AnomalyScorer<'a, D, I, U> {
+        AnomalyScorer {
+            machine: self,
+            frequencies,
+            states: vec![State {
+                location: location.into(),
+                data,
+            }],
+            smoothing: 0.0,
+            total: 0.0,
+        }
+    }
+}
+
+impl<'a, D, I, U> AnomalyScorer<'a, D, I, U> {
+    /// Adds `alpha` to the firing count of every transition, and to the number of inputs that
+    /// took no transition, so that an outcome never seen in the corpus is unlikely instead of
+    /// impossible.
+    pub fn with_smoothing(mut self, alpha: f64) -> Self {
+        self.smoothing = alpha;
+        self
+    }
+
+    /// Returns the states the scored system may currently be in.
+    pub fn states(&self) -> &[State<D>] {
+        &self.states
+    }
+
+    /// Returns the sum of the scores so far, which is the surprise of the whole trace.
+    pub fn total(&self) -> f64 {
+        self.total
+    }
+
+    /// Returns the surprise of `input`, and moves to the states it leads to.
+    ///
+    /// A state that no transition accepts `input` from is scored by how often its location read
+    /// an input without taking any transition in the corpus, whatever the [no transition
+    /// policy](crate::machine::NoTransitionPolicy) then does with it. Once no state is left,
+    /// every input scores infinity.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s0".into(),
+    ///         enable: |_, i| *i == b'a',
+    ///         ..Default::default()
+    ///     })
+    ///     .build();
+    ///
+    /// let frequencies = machine.fit_probabilities("s0", 0, vec![b"aa".to_vec()]).unwrap();
+    /// let mut scorer = machine
+    ///     .anomaly_scorer("s0", 0, &frequencies)
+    ///     .with_smoothing(1.0);
+    ///
+    /// // The corpus never read an input without a transition, but smoothing makes it possible.
+    /// let blocked = scorer.score(&b'b').unwrap();
+    /// assert!((blocked - 4.0_f64.ln()).abs() < 1e-9);
+    /// // The state was rejected, so nothing is left to explain the next input.
+    /// assert_eq!(scorer.score(&b'a').unwrap(), f64::INFINITY);
+    /// ```
+    pub fn score(&mut self, input: &I) -> Result<f64, MachineError>
+    where
+        D: Clone,
+        U: Update<D = D>,
+    {
+        let mut likelihood = 0.0;
+        let mut next_states = Vec::with_capacity(self.states.len());
+        for state in &self.states {
+            let location = state.location.clone();
+            let successors = self.machine.successors(state.clone(), input)?;
+            // A state dropped for lack of a transition took no transition either.
+            if successors.is_empty() {
+                likelihood += self.probability(&location, None);
+            }
+            for (index, next) in successors {
+                likelihood += self.probability(&location, index);
+                if !self.machine.get_rejecting().contains(&next.location) {
+                    next_states.push(next);
+                }
+            }
+        }
+
+        let score = match self.states.len() {
+            0 => f64::INFINITY,
+            states => -(likelihood / states as f64).ln(),
+        };
+        self.states = next_states;
+        self.total += score;
+        Ok(score)
+    }
+
+    // Returns the probability that a state in `location` takes the transition at `index`, or no
+    // transition at all for `None`.
+    fn probability(&self, location: &str, index: Option<usize>) -> f64 {
+        let count = self
+            .machine
+            .get_transitions_from(location)
+            .map_or(0, Vec::len);
+        let frequency = |index: usize| {
+            self.frequencies
+                .get(&TransitionId::new(location, index))
+                .copied()
+                .unwrap_or_default()
+        };
+        // Taking no transition is smoothed as one more outcome.
+        let reads = frequency(0).visits as f64;
+        let visits = reads + self.smoothing * (count + 1) as f64;
+        if visits == 0.0 {
+            return 0.0;
+        }
+
+        let fired = |index: usize| frequency(index).fired as f64;
+        match index {
+            Some(index) => (fired(index) + self.smoothing) / visits,
+            None => {
+                let idle = (reads - (0..count).map(fired).sum::<f64>()).max(0.0);
+                (idle + self.smoothing) / visits
+            }
+        }
+    }
+}
@@ -0,0 +1,339 @@
+//! # Template Module
+//!
+//! This module provides [MachineTemplate], for properties that share a shape but differ in their
+//! constants, such as "no more than N errors in a row". A template names its parameters, and its
+//! bounds, accepting conditions, and updates refer to them by name. Each call to
+//! [instantiate](MachineTemplate::instantiate) builds a concrete [Machine] from values for the
+//! parameters.
+//!
+//! ```
+//! use rust_efsm::past::CountSinceUpdate;
+//! use rust_efsm::template::{BoundTemplate, MachineTemplate, TransitionTemplate, UpdateTemplate};
+//!
+//! // No more than `n` errors in a row.
+//! let template = MachineTemplate::<u8, u8, CountSinceUpdate<u8>>::new()
+//!     .with_parameter("n")
+//!     .with_transition("ok", TransitionTemplate {
+//!         to_location: "ok".into(),
+//!         enable: |_, i| *i == b'e',
+//!         update: UpdateTemplate::Const(CountSinceUpdate::count()),
+//!         ..Default::default()
+//!     })
+//!     .with_transition("ok", TransitionTemplate {
+//!         to_location: "ok".into(),
+//!         enable: |_, i| *i != b'e',
+//!         update: UpdateTemplate::Const(CountSinceUpdate::reset()),
+//!         ..Default::default()
+//!     })
+//!     .with_accepting_condition("ok", BoundTemplate::at_most("n"));
+//!
+//! let two = template.instantiate([("n", 2)]).unwrap();
+//! assert!(two.exec("ok", 0, b"eexee".to_vec()));
+//! assert!(!two.exec("ok", 0, b"eee".to_vec()));
+//!
+//! let three = template.instantiate([("n", 3)]).unwrap();
+//! assert!(three.exec("ok", 0, b"eee".to_vec()));
+//! ```
+
+use crate::bound::Bound;
+use crate::machine::{
+    AcceptingCondition, Machine, MachineBuilder, NoTransitionPolicy, Transition, Update,
+};
+use num::Bounded;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::fmt::Debug;
+
+/// Errors that can occur while instantiating a template.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TemplateError {
+    /// The template refers to a parameter that was given no value.
+    MissingParameter(String),
+    /// A value was given for a parameter that the template does not declare.
+    UnknownParameter(String),
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::MissingParameter(name) => write!(f, "no value for parameter {}", name),
+            TemplateError::UnknownParameter(name) => write!(f, "unknown parameter {}", name),
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+/// A data value, or a reference to a parameter of a template.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value<D> {
+    /// A fixed value.
+    Const(D),
+    /// The value of the named parameter.
+    Param(String),
+}
+
+impl<D: Clone> Value<D> {
+    // Returns the value, looking parameters up in `params`.
+    fn resolve(&self, params: &HashMap<String, D>) -> Result<D, TemplateError> {
+        match self {
+            Value::Const(value) => Ok(value.clone()),
+            Value::Param(name) => params
+                .get(name)
+                .cloned()
+                .ok_or_else(|| TemplateError::MissingParameter(name.clone())),
+        }
+    }
+}
+
+/// A [Bound] whose endpoints may refer to parameters.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BoundTemplate<D> {
+    /// The lower endpoint, if any.
+    pub lower: Option<Value<D>>,
+    /// The upper endpoint, if any.
+    pub upper: Option<Value<D>>,
+}
+
+// Implemented by hand, because the data type does not need a default.
+impl<D> Default for BoundTemplate<D> {
+    fn default() -> Self {
+        BoundTemplate {
+            lower: None,
+            upper: None,
+        }
+    }
+}
+
+impl<D> BoundTemplate<D> {
+    /// Creates a bound holding the data up to the parameter `name`.
+    pub fn at_most(name: &str) -> Self {
+        BoundTemplate {
+            lower: None,
+            upper: Some(Value::Param(name.into())),
+        }
+    }
+
+    /// Creates a bound holding the data from the parameter `name` on.
+    pub fn at_least(name: &str) -> Self {
+        BoundTemplate {
+            lower: Some(Value::Param(name.into())),
+            upper: None,
+        }
+    }
+
+    // Returns the bound, looking parameters up in `params`.
+    fn resolve(&self, params: &HashMap<String, D>) -> Result<Bound<D>, TemplateError>
+    where
+        D: Clone,
+    {
+        let resolve = |value: &Option<Value<D>>| value.as_ref().map(|v| v.resolve(params));
+        Ok(Bound {
+            lower: resolve(&self.lower).transpose()?,
+            upper: resolve(&self.upper).transpose()?,
+        })
+    }
+}
+
+/// An update, or a function building one from the value of a parameter.
+pub enum UpdateTemplate<D, U> {
+    /// A fixed update.
+    Const(U),
+    /// The update built from the value of the named parameter.
+    Param(String, fn(D) -> U),
+}
+
+// Implemented by hand, because the data type does not need to be cloned.
+impl<D, U: Clone> Clone for UpdateTemplate<D, U> {
+    fn clone(&self) -> Self {
+        match self {
+            UpdateTemplate::Const(update) => UpdateTemplate::Const(update.clone()),
+            UpdateTemplate::Param(name, build) => UpdateTemplate::Param(name.clone(), *build),
+        }
+    }
+}
+
+impl<D: Clone, U: Clone> UpdateTemplate<D, U> {
+    // Returns the update, looking parameters up in `params`.
+    fn resolve(&self, params: &HashMap<String, D>) -> Result<U, TemplateError> {
+        match self {
+            UpdateTemplate::Const(update) => Ok(update.clone()),
+            UpdateTemplate::Param(name, build) => {
+                Value::Param(name.clone()).resolve(params).map(build)
+            }
+        }
+    }
+}
+
+/// A [Transition] whose bound and update may refer to parameters.
+pub struct TransitionTemplate<D, I, U> {
+    /// The location entered when this transition is taken.
+    pub to_location: String,
+    /// Guard deciding whether this transition is enabled for some data and input.
+    pub enable: fn(&D, &I) -> bool,
+    /// Interval of data values over which this transition may be enabled.
+    pub bound: BoundTemplate<D>,
+    /// Update applied to the data when this transition is taken.
+    pub update: UpdateTemplate<D, U>,
+    /// Optional name used in place of the transition identifier in reports and graphs.
+    pub name: Option<String>,
+}
+
+impl<D, I, U: Default> Default for TransitionTemplate<D, I, U> {
+    fn default() -> Self {
+        TransitionTemplate {
+            to_location: "default".into(),
+            enable: |_, _| true,
+            bound: BoundTemplate::default(),
+            update: UpdateTemplate::Const(U::default()),
+            name: None,
+        }
+    }
+}
+
+/// A machine whose constants are named parameters.
+///
+/// Its methods mirror those of [MachineBuilder].
+pub struct MachineTemplate<D, I, U> {
+    parameters: BTreeSet<String>,
+    transitions: Vec<(String, TransitionTemplate<D, I, U>)>,
+    accepting: Vec<String>,
+    accepting_conditions: Vec<(String, BoundTemplate<D>)>,
+    rejecting: Vec<String>,
+    no_transition_policy: NoTransitionPolicy,
+}
+
+impl<D, I, U> Default for MachineTemplate<D, I, U> {
+    fn default() -> Self {
+        MachineTemplate::new()
+    }
+}
+
+impl<D, I, U> MachineTemplate<D, I, U> {
+    /// Creates a template without parameters.
+    pub fn new() -> Self {
+        MachineTemplate {
+            parameters: BTreeSet::new(),
+            transitions: Vec::new(),
+            accepting: Vec::new(),
+            accepting_conditions: Vec::new(),
+            rejecting: Vec::new(),
+            no_transition_policy: NoTransitionPolicy::default(),
+        }
+    }
+
+    /// Declares a parameter named `name`.
+    pub fn with_parameter(mut self, name: &str) -> Self {
+        self.parameters.insert(name.into());
+        self
+    }
+
+    /// Adds a transition out of `from_location`.
+    pub fn with_transition(
+        mut self,
+        from_location: &str,
+        transition: TransitionTemplate<D, I, U>,
+    ) -> Self {
+        self.transitions.push((from_location.into(), transition));
+        self
+    }
+
+    /// Marks `location` as accepting.
+    pub fn with_accepting(mut self, location: &str) -> Self {
+        self.accepting.push(location.into());
+        self
+    }
+
+    /// Marks `location` as accepting only for the data in `bound`.
+    pub fn with_accepting_condition(mut self, location: &str, bound: BoundTemplate<D>) -> Self {
+        self.accepting_conditions.push((location.into(), bound));
+        self
+    }
+
+    /// Marks `location` as rejecting.
+    pub fn with_rejecting(mut self, location: &str) -> Self {
+        self.rejecting.push(location.into());
+        self
+    }
+
+    /// Sets what happens to a state that has no enabled transition for an input.
+    pub fn with_no_transition_policy(mut self, policy: NoTransitionPolicy) -> Self {
+        self.no_transition_policy = policy;
+        self
+    }
+
+    /// Returns the names of the declared parameters, in order.
+    pub fn parameters(&self) -> Vec<&str> {
+        self.parameters.iter().map(String::as_str).collect()
+    }
+
+    /// Builds the machine with the given value of each parameter.
+    ///
+    /// Fails when a value is given for an undeclared parameter, or when the template refers to a
+    /// parameter without a value.
+    ///
+    /// ```
+    /// use rust_efsm::machine::IdentityUpdate;
+    /// use rust_efsm::template::{BoundTemplate, MachineTemplate, TemplateError};
+    ///
+    /// let template = MachineTemplate::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_parameter("low")
+    ///     .with_accepting_condition("s0", BoundTemplate::at_least("low"));
+    ///
+    /// assert_eq!(
+    ///     template.instantiate([("high", 3)]).err(),
+    ///     Some(TemplateError::UnknownParameter("high".into())),
+    /// );
+    /// assert_eq!(
+    ///     template.instantiate([]).err(),
+    ///     Some(TemplateError::MissingParameter("low".into())),
+    /// );
+    /// ```
+    pub fn instantiate<'a, P>(&self, params: P) -> Result<Machine<D, I, U>, TemplateError>
+    where
+        P: IntoIterator<Item = (&'a str, D)>,
+        D: Default + Debug + Ord + Copy + Bounded + Send + Sync + 'static,
+        I: Debug,
+        U: Update<D = D> + Clone,
+    {
+        let mut values = HashMap::new();
+        for (name, value) in params {
+            if !self.parameters.contains(name) {
+                return Err(TemplateError::UnknownParameter(name.into()));
+            }
+            values.insert(name.to_string(), value);
+        }
+
+        let mut builder =
+            MachineBuilder::new().with_no_transition_policy(self.no_transition_policy.clone());
+        for (from, template) in &self.transitions {
+            builder = builder.with_transition(
+                from,
+                Transition {
+                    to_location: template.to_location.clone(),
+                    enable: template.enable,
+                    bound: template.bound.resolve(&values)?,
+                    update: template.update.resolve(&values)?,
+                    guard: None,
+                    name: template.name.clone(),
+                    input: Default::default(),
+                    binding: None,
+                },
+            );
+        }
+        for location in &self.accepting {
+            builder = builder.with_accepting(location);
+        }
+        for (location, bound) in &self.accepting_conditions {
+            builder = builder.with_accepting_condition(
+                location,
+                AcceptingCondition::new(bound.resolve(&values)?),
+            );
+        }
+        for location in &self.rejecting {
+            builder = builder.with_rejecting(location);
+        }
+
+        Ok(builder.build())
+    }
+}
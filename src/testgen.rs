@@ -0,0 +1,425 @@
+//! # Test Generation Module
+//!
+//! This module generates suites of input sequences that exercise a [Machine] according to a
+//! coverage criterion. A generated suite can be replayed against an implementation of the
+//! specification, or against a [monitor](crate::monitor::Monitor) of it.
+//!
+//! The generators explore the machine concretely from an initial state over a finite alphabet
+//! of inputs. The transition [bounds](crate::bound::Bound) decide which coverage goals cannot be
+//! met, and which data values lie on the boundary of a transition.
+
+use crate::machine::{Machine, MachineError, State, TransitionId, Update};
+use num::{Bounded, CheckedAdd, CheckedSub, One};
+use std::collections::{BTreeSet, HashSet};
+use std::hash::Hash;
+
+/// Two transitions taken one after the other.
+pub type TransitionPair = (TransitionId, TransitionId);
+
+/// A suite of input sequences generated for transition pair coverage.
+///
+/// See [pair_coverage].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PairCoverage<I> {
+    /// The generated input sequences.
+    pub suite: Vec<Vec<I>>,
+    /// The pairs exercised by the suite.
+    pub covered: BTreeSet<TransitionPair>,
+    /// The pairs that may be feasible but were not exercised within the length limit.
+    pub uncovered: BTreeSet<TransitionPair>,
+    /// The pairs proved infeasible by interval analysis.
+    pub infeasible: BTreeSet<TransitionPair>,
+}
+
+/// Generates input sequences over `alphabet` that exercise every feasible pair of consecutive
+/// transitions, starting from `location` with `data`.
+///
+/// A pair is infeasible when the data leaving the first transition, according to its bound and
+/// [Update::update_interval], never lies within the bound of the second. Every other pair is
+/// searched for among the input sequences of at most `max_len` inputs. Sequences that are a
+/// prefix of another sequence in the suite are left out, since the longer sequence exercises
+/// the same pairs. Pairs leaving a [rejecting](crate::machine::MachineBuilder::with_rejecting)
+/// location are infeasible, since a run ends on entering it. Under [NoTransitionPolicy::Error](crate::machine::NoTransitionPolicy::Error),
+/// an input without an enabled transition does not extend a sequence, as in
+/// [Machine::words_up_to].
+///
+/// ```
+/// use rust_efsm::bound::Bound;
+/// use rust_efsm::machine::{AddUpdate, MachineBuilder, TransitionId, Transition};
+/// use rust_efsm::testgen;
+///
+/// // Count two 'a's, then require a 'b'. The data is never reset, so 'c' is never enabled.
+/// let machine = MachineBuilder::<u8, u8, AddUpdate<u8>>::new()
+///     .with_transition("s0", Transition {
+///         to_location: "s0".into(),
+///         enable: |data, i| *data < 2 && *i == b'a',
+///         bound: Bound { lower: None, upper: Some(1) },
+///         update: AddUpdate { amount: 1 },
+///         ..Default::default()
+///     })
+///     .with_transition("s0", Transition {
+///         to_location: "s1".into(),
+///         enable: |data, i| *data == 2 && *i == b'b',
+///         bound: Bound { lower: Some(2), upper: Some(2) },
+///         ..Default::default()
+///     })
+///     .with_transition("s1", Transition {
+///         to_location: "s2".into(),
+///         enable: |data, i| *data == 0 && *i == b'c',
+///         bound: Bound { lower: Some(0), upper: Some(0) },
+///         ..Default::default()
+///     })
+///     .with_accepting("s1")
+///     .build();
+///
+/// let coverage = testgen::pair_coverage(&machine, "s0", 0, &[b'a', b'b', b'c'], 4).unwrap();
+///
+/// assert_eq!(coverage.suite, vec![vec![b'a', b'a', b'b']]);
+/// assert_eq!(coverage.covered.len(), 2);
+/// assert!(coverage.uncovered.is_empty());
+/// assert_eq!(
+///     coverage.infeasible.into_iter().collect::<Vec<_>>(),
+///     vec![(TransitionId::new("s0", 1), TransitionId::new("s1", 0))],
+/// );
+/// ```
+///
+/// ```
+/// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, NoTransitionPolicy, Transition};
+/// use rust_efsm::testgen;
+///
+/// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+///     .with_transition("s0", Transition {
+///         to_location: "s0".into(),
+///         enable: |_, i| *i == b'a',
+///         ..Default::default()
+///     })
+///     .with_no_transition_policy(NoTransitionPolicy::Error)
+///     .build();
+///
+/// let coverage = testgen::pair_coverage(&machine, "s0", 0, &[b'b', b'a'], 2).unwrap();
+/// assert_eq!(coverage.suite, vec![vec![b'a', b'a']]);
+///
+/// // A run ends on entering a rejecting location, so the pairs leaving it are infeasible.
+/// let machine = machine
+///     .to_builder()
+///     .with_transition("s0", Transition {
+///         to_location: "error".into(),
+///         enable: |_, i| *i == b'b',
+///         ..Default::default()
+///     })
+///     .with_transition("error", Transition { to_location: "error".into(), ..Default::default() })
+///     .with_rejecting("error")
+///     .build();
+///
+/// let coverage = testgen::pair_coverage(&machine, "s0", 0, &[b'b', b'a'], 3).unwrap();
+/// assert!(coverage.covered.iter().all(|(first, _)| first.from_location != "error"));
+/// assert!(coverage.uncovered.is_empty());
+/// assert_eq!(coverage.infeasible.len(), 2);
+/// ```
+pub fn pair_coverage<D, I, U>(
+    machine: &Machine<D, I, U>,
+    location: &str,
+    data: D,
+    alphabet: &[I],
+    max_len: usize,
+) -> Result<PairCoverage<I>, MachineError>
+where
+    D: Ord + Copy + Bounded + Hash,
+    I: Clone + PartialEq,
+    U: Update<D = D>,
+{
+    let (mut uncovered, infeasible) = candidate_pairs(machine);
+    let mut covered = BTreeSet::new();
+    let mut suite: Vec<Vec<I>> = Vec::new();
+
+    // Each configuration is a set of states, each with the transition that led to it.
+    type Config<D> = BTreeSet<(State<D>, Option<TransitionId>)>;
+
+    let initial: Config<D> = BTreeSet::from([(
+        State {
+            location: location.into(),
+            data,
+        },
+        None,
+    )]);
+    let mut seen: HashSet<Config<D>> = HashSet::from([initial.clone()]);
+    let mut frontier: Vec<(Vec<I>, Config<D>)> = vec![(Vec::new(), initial)];
+
+    for _ in 0..max_len {
+        if uncovered.is_empty() || frontier.is_empty() {
+            break;
+        }
+
+        let mut next_frontier = Vec::new();
+        for (word, config) in frontier {
+            for i in alphabet {
+                let mut next_config = BTreeSet::new();
+                let mut exercised = false;
+
+                // An input that no transition is enabled for is infeasible here.
+                let successors = config
+                    .iter()
+                    .map(|(state, last)| Ok((state, last, machine.successors(state.clone(), i)?)))
+                    .collect::<Result<Vec<_>, MachineError>>();
+                let successors = match successors {
+                    Err(MachineError::NoTransition(_)) => continue,
+                    successors => successors?,
+                };
+
+                for (state, last, successors) in successors {
+                    for (idx, next) in successors {
+                        let taken = idx.map(|idx| TransitionId::new(&state.location, idx));
+
+                        if let (Some(last), Some(taken)) = (last, &taken) {
+                            let pair = (last.clone(), taken.clone());
+                            if uncovered.remove(&pair) {
+                                covered.insert(pair);
+                                exercised = true;
+                            }
+                        }
+
+                        // A run ends on entering a rejecting location, as in Machine::transition.
+                        if !machine.get_rejecting().contains(&next.location) {
+                            next_config.insert((next, taken));
+                        }
+                    }
+                }
+
+                let mut next_word = word.clone();
+                next_word.push(i.clone());
+
+                if exercised {
+                    suite.push(next_word.clone());
+                }
+
+                if !next_config.is_empty() && seen.insert(next_config.clone()) {
+                    next_frontier.push((next_word, next_config));
+                }
+            }
+        }
+
+        frontier = next_frontier;
+    }
+
+    // Leave out sequences that a longer sequence in the suite extends.
+    let suite = suite
+        .iter()
+        .enumerate()
+        .filter(|(idx, word)| {
+            !suite[idx + 1..]
+                .iter()
+                .any(|other| other.len() > word.len() && other.starts_with(word))
+        })
+        .map(|(_, word)| word.clone())
+        .collect();
+
+    Ok(PairCoverage {
+        suite,
+        covered,
+        uncovered,
+        infeasible,
+    })
+}
+
+// Splits the consecutive transition pairs of `machine` into those that may be feasible and those
+// that are infeasible according to the transition bounds. Pairs leaving a rejecting location are
+// infeasible, since a run ends on entering it.
+fn candidate_pairs<D, I, U>(
+    machine: &Machine<D, I, U>,
+) -> (BTreeSet<TransitionPair>, BTreeSet<TransitionPair>)
+where
+    D: Ord + Copy + Bounded,
+    U: Update<D = D>,
+{
+    let mut feasible = BTreeSet::new();
+    let mut infeasible = BTreeSet::new();
+
+    for (from_location, transitions) in machine.get_locations() {
+        for (first_idx, first) in transitions.iter().enumerate() {
+            let leaving = first.update_interval(first.bound.clone());
+            let Some(next_transitions) = machine.get_transitions_from(&first.to_location) else {
+                continue;
+            };
+
+            for (second_idx, second) in next_transitions.iter().enumerate() {
+                let pair = (
+                    TransitionId::new(from_location, first_idx),
+                    TransitionId::new(&first.to_location, second_idx),
+                );
+
+                let rejected = machine.get_rejecting().contains(&first.to_location);
+                match leaving.intersect(&second.bound) {
+                    Some(_) if !rejected => feasible.insert(pair),
+                    _ => infeasible.insert(pair),
+                };
+            }
+        }
+    }
+
+    (feasible, infeasible)
+}
+
+/// An input sequence that drives the data to an endpoint of a transition bound, or just past it.
+///
+/// See [boundary_values].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BoundaryCase<D, I> {
+    /// The transition whose bound is tested.
+    pub transition: TransitionId,
+    /// The data value reached in the source location of the transition.
+    pub data: D,
+    /// Whether `data` lies within the bound.
+    pub inside: bool,
+    /// The input sequence reaching `data` in the source location.
+    pub input: Vec<I>,
+}
+
+/// A suite of input sequences generated for the endpoints of the transition bounds.
+///
+/// See [boundary_values].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BoundaryValues<D, I> {
+    /// The boundary values that were reached.
+    pub cases: Vec<BoundaryCase<D, I>>,
+    /// The boundary values that were not reached within the length limit, with their transition.
+    pub unreached: Vec<(TransitionId, D)>,
+}
+
+/// Generates input sequences over `alphabet` that drive the data exactly to the endpoints of
+/// each transition bound, and just outside them, starting from `location` with `data`.
+///
+/// Only explicit endpoints are tested, since an unbounded side cannot be left. For every
+/// endpoint, the shortest sequence of at most `max_len` inputs that reaches the source location
+/// of the transition with the boundary value is reported. As in [pair_coverage], an input
+/// without an enabled transition does not extend a sequence.
+///
+/// ```
+/// use rust_efsm::bound::Bound;
+/// use rust_efsm::machine::{AddUpdate, MachineBuilder, NoTransitionPolicy, Transition};
+/// use rust_efsm::testgen;
+///
+/// // Count up to three 'a's.
+/// let machine = MachineBuilder::<u8, u8, AddUpdate<u8>>::new()
+///     .with_transition("s0", Transition {
+///         to_location: "s0".into(),
+///         enable: |data, i| *data < 3 && *i == b'a',
+///         bound: Bound { lower: None, upper: Some(2) },
+///         update: AddUpdate { amount: 1 },
+///         ..Default::default()
+///     })
+///     .with_accepting("s0")
+///     .build();
+///
+/// let values = testgen::boundary_values(&machine, "s0", 0, &[b'a'], 5).unwrap();
+///
+/// let cases: Vec<_> = values
+///     .cases
+///     .iter()
+///     .map(|case| (case.data, case.inside, case.input.len()))
+///     .collect();
+/// assert_eq!(cases, vec![(2, true, 2), (3, false, 3)]);
+/// assert!(values.unreached.is_empty());
+///
+/// // The same bound, reached with 'b's that no transition is enabled for.
+/// let machine = machine
+///     .to_builder()
+///     .with_no_transition_policy(NoTransitionPolicy::Error)
+///     .build();
+/// let values = testgen::boundary_values(&machine, "s0", 0, &[b'b', b'a'], 5).unwrap();
+/// assert_eq!(values.cases.len(), 2);
+/// ```
+pub fn boundary_values<D, I, U>(
+    machine: &Machine<D, I, U>,
+    location: &str,
+    data: D,
+    alphabet: &[I],
+    max_len: usize,
+) -> Result<BoundaryValues<D, I>, MachineError>
+where
+    D: Ord + Copy + Bounded + Hash + CheckedAdd + CheckedSub + One,
+    I: Clone,
+    U: Update<D = D>,
+{
+    // The source location, data value, and whether it lies within the bound, of every target.
+    let mut pending: BTreeSet<(TransitionId, D, bool)> = BTreeSet::new();
+    for id in machine.transition_ids() {
+        let Some(transition) = machine.get_transition(&id) else {
+            continue;
+        };
+
+        if let Some(lower) = transition.bound.lower {
+            pending.insert((id.clone(), lower, true));
+            if let Some(below) = lower.checked_sub(&D::one()) {
+                pending.insert((id.clone(), below, false));
+            }
+        }
+
+        if let Some(upper) = transition.bound.upper {
+            pending.insert((id.clone(), upper, true));
+            if let Some(above) = upper.checked_add(&D::one()) {
+                pending.insert((id, above, false));
+            }
+        }
+    }
+
+    let mut cases = Vec::new();
+    let initial = BTreeSet::from([State {
+        location: location.into(),
+        data,
+    }]);
+    let mut seen = HashSet::from([initial.clone()]);
+    let mut frontier: Vec<(Vec<I>, BTreeSet<State<D>>)> = vec![(Vec::new(), initial)];
+
+    for len in 0..=max_len {
+        let mut next_frontier = Vec::new();
+
+        for (word, states) in frontier {
+            // Record the targets reached by this sequence.
+            pending.retain(|(id, data, inside)| {
+                let reached = states
+                    .iter()
+                    .any(|state| state.location == id.from_location && state.data == *data);
+
+                if reached {
+                    cases.push(BoundaryCase {
+                        transition: id.clone(),
+                        data: *data,
+                        inside: *inside,
+                        input: word.clone(),
+                    });
+                }
+
+                !reached
+            });
+
+            if len == max_len || pending.is_empty() {
+                continue;
+            }
+
+            for i in alphabet {
+                // An input that no transition is enabled for is infeasible here.
+                let next_states: BTreeSet<_> =
+                    match machine.transition(i, states.iter().cloned().collect()) {
+                        Err(MachineError::NoTransition(_)) => continue,
+                        next_states => next_states?.into_iter().collect(),
+                    };
+
+                if !next_states.is_empty() && seen.insert(next_states.clone()) {
+                    let mut next_word = word.clone();
+                    next_word.push(i.clone());
+                    next_frontier.push((next_word, next_states));
+                }
+            }
+        }
+
+        frontier = next_frontier;
+    }
+
+    Ok(BoundaryValues {
+        cases,
+        unreached: pending
+            .into_iter()
+            .map(|(id, data, _)| (id, data))
+            .collect(),
+    })
+}
@@ -0,0 +1,308 @@
+//! # Traverse Module
+//!
+//! This module walks the location graph of a [Machine] in depth first or breadth first order,
+//! so custom analyses do not have to keep their own stack and visited set. Guards and bounds are
+//! ignored, and each location is visited once, along the first edge that reaches it.
+//!
+//! Under [NoTransitionPolicy::SinkTo], every location has an implicit edge to the sink, which is
+//! visited without a [TransitionId].
+//!
+//! [Machine::simple_paths] lists every path between two locations that does not repeat a
+//! location, and [Machine::feasible_paths] keeps only those whose bounds can be met.
+
+use crate::bound::Bound;
+use crate::machine::{Machine, NoTransitionPolicy, TransitionId, Update};
+use crate::nfa::Nfa;
+use num::Bounded;
+use std::collections::{BTreeSet, VecDeque};
+
+/// A path of a machine, as the edges it follows, labelled as in [Nfa].
+pub type Path = Vec<Option<TransitionId>>;
+
+/// A location visited by a [Traversal], with the edge that first reached it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Visit {
+    /// The location visited.
+    pub location: String,
+    /// The location the edge leaves from, or `None` for the start location.
+    pub parent: Option<String>,
+    /// The transition taken, or `None` for the start location and implicit moves.
+    pub transition: Option<TransitionId>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Order {
+    DepthFirst,
+    BreadthFirst,
+}
+
+/// An iterator over the locations reachable from a start location.
+///
+/// Created by [Machine::dfs] and [Machine::bfs].
+pub struct Traversal<'a, D, I, U> {
+    machine: &'a Machine<D, I, U>,
+    order: Order,
+    pending: VecDeque<Visit>,
+    visited: BTreeSet<String>,
+}
+
+impl<D, I, U> Machine<D, I, U> {
+    /// Visits the locations reachable from `start` in depth first order, following the
+    /// transitions out of each location in the order they were added.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition, TransitionId};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition { to_location: "s1".into(), ..Default::default() })
+    ///     .with_transition("s0", Transition { to_location: "s2".into(), ..Default::default() })
+    ///     .with_transition("s1", Transition { to_location: "s3".into(), ..Default::default() })
+    ///     .build();
+    ///
+    /// let order: Vec<_> = machine.dfs("s0").map(|visit| visit.location).collect();
+    /// assert_eq!(order, vec!["s0", "s1", "s3", "s2"]);
+    ///
+    /// let s3 = machine.dfs("s0").find(|visit| visit.location == "s3").unwrap();
+    /// assert_eq!(s3.transition, Some(TransitionId::new("s1", 0)));
+    /// ```
+    pub fn dfs(&self, start: &str) -> Traversal<'_, D, I, U> {
+        Traversal::new(self, start, Order::DepthFirst)
+    }
+
+    /// Visits the locations reachable from `start` in breadth first order, so each location is
+    /// reached along a path with the fewest transitions.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition { to_location: "s1".into(), ..Default::default() })
+    ///     .with_transition("s0", Transition { to_location: "s2".into(), ..Default::default() })
+    ///     .with_transition("s1", Transition { to_location: "s3".into(), ..Default::default() })
+    ///     .build();
+    ///
+    /// let order: Vec<_> = machine.bfs("s0").map(|visit| visit.location).collect();
+    /// assert_eq!(order, vec!["s0", "s1", "s2", "s3"]);
+    /// ```
+    pub fn bfs(&self, start: &str) -> Traversal<'_, D, I, U> {
+        Traversal::new(self, start, Order::BreadthFirst)
+    }
+
+    /// Returns every path from `from` to `to` with at most `max_len` edges that visits each
+    /// location at most once, ignoring guards and bounds.
+    ///
+    /// The number of paths can grow exponentially with the size of the machine, so `max_len`
+    /// should be kept small for large machines.
+    ///
+    /// ```
+    /// use rust_efsm::machine::{IdentityUpdate, MachineBuilder, Transition, TransitionId};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, IdentityUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition { to_location: "s1".into(), ..Default::default() })
+    ///     .with_transition("s0", Transition { to_location: "s2".into(), ..Default::default() })
+    ///     .with_transition("s1", Transition { to_location: "s2".into(), ..Default::default() })
+    ///     .with_transition("s2", Transition { to_location: "s0".into(), ..Default::default() })
+    ///     .build();
+    ///
+    /// let paths = machine.simple_paths("s0", "s2", 4);
+    /// assert_eq!(paths, vec![
+    ///     vec![Some(TransitionId::new("s0", 0)), Some(TransitionId::new("s1", 0))],
+    ///     vec![Some(TransitionId::new("s0", 1))],
+    /// ]);
+    /// assert_eq!(machine.simple_paths("s0", "s2", 1).len(), 1);
+    /// ```
+    pub fn simple_paths(&self, from: &str, to: &str, max_len: usize) -> Vec<Path> {
+        let mut search = PathSearch {
+            nfa: self.project_locations(),
+            to,
+            max_len,
+            locations: Vec::new(),
+            edges: Vec::new(),
+            found: Vec::new(),
+        };
+
+        search.visit(from, (), &|_, _| Some(()));
+        search.found
+    }
+
+    /// Returns the paths of [simple_paths](Machine::simple_paths) whose bounds can be met when
+    /// starting with data in `interval`.
+    ///
+    /// The bounds are checked over intervals, so a returned path may still be infeasible for
+    /// every concrete data value, but a discarded path is always infeasible.
+    ///
+    /// ```
+    /// use rust_efsm::bound::Bound;
+    /// use rust_efsm::machine::{AddUpdate, MachineBuilder, Transition, TransitionId};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, AddUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s1".into(),
+    ///         update: AddUpdate { amount: 10 },
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("s0", Transition { to_location: "s2".into(), ..Default::default() })
+    ///     // The data entering "s1" is at least 10.
+    ///     .with_transition("s1", Transition {
+    ///         to_location: "sink".into(),
+    ///         bound: Bound { lower: None, upper: Some(5) },
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("s2", Transition {
+    ///         to_location: "sink".into(),
+    ///         bound: Bound { lower: None, upper: Some(5) },
+    ///         ..Default::default()
+    ///     })
+    ///     .build();
+    ///
+    /// let start = Bound { lower: Some(0), upper: Some(0) };
+    /// assert_eq!(machine.simple_paths("s0", "sink", 2).len(), 2);
+    /// assert_eq!(
+    ///     machine.feasible_paths("s0", "sink", 2, start),
+    ///     vec![vec![Some(TransitionId::new("s0", 1)), Some(TransitionId::new("s2", 0))]],
+    /// );
+    /// ```
+    pub fn feasible_paths(
+        &self,
+        from: &str,
+        to: &str,
+        max_len: usize,
+        interval: Bound<D>,
+    ) -> Vec<Path>
+    where
+        D: Ord + Copy + Bounded,
+        U: Update<D = D>,
+    {
+        let mut search = PathSearch {
+            nfa: self.project_locations(),
+            to,
+            max_len,
+            locations: Vec::new(),
+            edges: Vec::new(),
+            found: Vec::new(),
+        };
+
+        search.visit(from, interval, &|interval, label| match label {
+            Some(id) => self.get_transition(id).and_then(|transition| {
+                interval
+                    .intersect(&transition.bound)
+                    .map(|bound| transition.update_interval(bound))
+            }),
+            // Implicit moves leave the data unchanged.
+            None => Some(interval.clone()),
+        });
+        search.found
+    }
+}
+
+// The search state shared by every step of the depth first search for simple paths.
+struct PathSearch<'a> {
+    nfa: Nfa,
+    to: &'a str,
+    max_len: usize,
+    locations: Vec<String>,
+    edges: Path,
+    found: Vec<Path>,
+}
+
+impl PathSearch<'_> {
+    // Extends the current path from `location`, where `state` is what `step` tracks along the
+    // path. An edge is only followed when `step` returns the state after it.
+    fn visit<S, F>(&mut self, location: &str, state: S, step: &F)
+    where
+        F: Fn(&S, &Option<TransitionId>) -> Option<S>,
+    {
+        if location == self.to {
+            self.found.push(self.edges.clone());
+            return;
+        }
+        if self.edges.len() == self.max_len {
+            return;
+        }
+
+        self.locations.push(location.into());
+
+        let out = self.nfa.edges.get(location).cloned().unwrap_or_default();
+        for (label, to_location) in out {
+            if self.locations.contains(&to_location) {
+                continue;
+            }
+            let Some(next) = step(&state, &label) else {
+                continue;
+            };
+
+            self.edges.push(label);
+            self.visit(&to_location, next, step);
+            self.edges.pop();
+        }
+
+        self.locations.pop();
+    }
+}
+
+impl<'a, D, I, U> Traversal<'a, D, I, U> {
+    fn new(machine: &'a Machine<D, I, U>, start: &str, order: Order) -> Self {
+        Traversal {
+            machine,
+            order,
+            pending: VecDeque::from([Visit {
+                location: start.into(),
+                parent: None,
+                transition: None,
+            }]),
+            visited: BTreeSet::new(),
+        }
+    }
+
+    // Returns the edges out of `location`, in the order they should be visited.
+    fn edges(&self, location: &str) -> Vec<Visit> {
+        let mut edges: Vec<Visit> = self
+            .machine
+            .get_transitions_from(location)
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .map(|(index, transition)| Visit {
+                location: transition.to_location.clone(),
+                parent: Some(location.into()),
+                transition: Some(TransitionId::new(location, index)),
+            })
+            .collect();
+
+        if let NoTransitionPolicy::SinkTo(sink) = self.machine.get_no_transition_policy() {
+            edges.push(Visit {
+                location: sink.clone(),
+                parent: Some(location.into()),
+                transition: None,
+            });
+        }
+
+        edges
+    }
+}
+
+impl<D, I, U> Iterator for Traversal<'_, D, I, U> {
+    type Item = Visit;
+
+    fn next(&mut self) -> Option<Visit> {
+        loop {
+            let visit = match self.order {
+                Order::DepthFirst => self.pending.pop_back()?,
+                Order::BreadthFirst => self.pending.pop_front()?,
+            };
+
+            if !self.visited.insert(visit.location.clone()) {
+                continue;
+            }
+
+            let edges = self.edges(&visit.location);
+            match self.order {
+                // The stack is popped from the back, so the first edge is pushed last.
+                Order::DepthFirst => self.pending.extend(edges.into_iter().rev()),
+                Order::BreadthFirst => self.pending.extend(edges),
+            }
+
+            return Some(visit);
+        }
+    }
+}
@@ -0,0 +1,185 @@
+//! # UPPAAL Module
+//!
+//! This module exports a [Machine] to the textual XTA format of the UPPAAL model checker, so a
+//! specification can be model checked in UPPAAL and monitored with this crate from a single
+//! source.
+//!
+//! The data becomes an integer variable `data`, and each transition becomes an edge guarded by
+//! its [bound](crate::machine::Transition::bound) and assigning its update. UPPAAL cannot run
+//! Rust code, so the `enable` functions and runtime guards are not exported, and every input is
+//! treated as possible. The exported model therefore over-approximates the machine, as long as
+//! the bounds contain every data value for which a transition is enabled. The
+//! [NoTransitionPolicy](crate::machine::NoTransitionPolicy) is not exported either.
+
+use crate::bound::Bound;
+use crate::machine::{Machine, TransitionId};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fmt::Write;
+
+impl<D, I, U> Machine<D, I, U>
+where
+    D: fmt::Display + Copy,
+    U: fmt::Display,
+{
+    /// Returns the machine as an UPPAAL XTA model of a single process named `efsm`, starting
+    /// from `location` with `data`.
+    ///
+    /// Location names are turned into UPPAAL identifiers by replacing every other character with
+    /// an underscore. Updates are written in their [Display](fmt::Display) form, which must be
+    /// a valid UPPAAL assignment, except that an update displayed as `data` leaves the data
+    /// unchanged. Use [Machine::to_uppaal_query] to check acceptance.
+    ///
+    /// ```
+    /// use rust_efsm::bound::Bound;
+    /// use rust_efsm::machine::{AddUpdate, MachineBuilder, Transition};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, AddUpdate<u8>>::new()
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "s0".into(),
+    ///         bound: Bound { lower: None, upper: Some(3) },
+    ///         update: AddUpdate { amount: 1 },
+    ///         ..Default::default()
+    ///     })
+    ///     .with_transition("s0", Transition {
+    ///         to_location: "done".into(),
+    ///         bound: Bound { lower: Some(4), upper: None },
+    ///         ..Default::default()
+    ///     })
+    ///     .with_accepting("done")
+    ///     .build();
+    ///
+    /// let xta = machine.to_uppaal_xta("s0", 0);
+    /// assert!(xta.contains("int data = 0;"));
+    /// assert!(xta.contains("state done, s0;"));
+    /// assert!(xta.contains("s0 -> s0 { guard data <= 3; assign data += 1; }"));
+    /// assert!(xta.contains("s0 -> done { guard data >= 4; assign data += 0; }"));
+    /// assert_eq!(machine.to_uppaal_query(), "E<> (efsm.done)");
+    /// ```
+    pub fn to_uppaal_xta(&self, location: &str, data: D) -> String {
+        let mut locations = self.all_locations();
+        locations.insert(location);
+
+        let mut xta = String::new();
+        let _ = writeln!(xta, "int data = {};", data);
+        let _ = writeln!(xta);
+        let _ = writeln!(xta, "process efsm() {{");
+
+        let names: Vec<String> = locations.iter().map(|name| identifier(name)).collect();
+        let _ = writeln!(xta, "state {};", names.join(", "));
+        let _ = writeln!(xta, "init {};", identifier(location));
+
+        let mut edges = Vec::new();
+        for from in &locations {
+            let transitions = self.get_transitions_from(from).into_iter().flatten();
+            for (index, transition) in transitions.enumerate() {
+                let mut labels = Vec::new();
+
+                let guards = constraints(&transition.bound);
+                if !guards.is_empty() {
+                    labels.push(format!("guard {};", guards.join(" && ")));
+                }
+
+                let update = transition.update.to_string();
+                if update != "data" {
+                    labels.push(format!("assign {};", update));
+                }
+
+                edges.push(format!(
+                    "  {} -> {} {{ {} }} /* {} */",
+                    identifier(from),
+                    identifier(&transition.to_location),
+                    labels.join(" "),
+                    self.transition_label(&TransitionId::new(from, index))
+                ));
+            }
+        }
+
+        if !edges.is_empty() {
+            let _ = writeln!(xta, "trans");
+            let _ = writeln!(xta, "{};", edges.join(",\n"));
+        }
+
+        let _ = writeln!(xta, "}}");
+        let _ = writeln!(xta);
+        let _ = writeln!(xta, "system efsm;");
+        xta
+    }
+
+    /// Returns an UPPAAL query that holds when an accepting location of the model exported by
+    /// [Machine::to_uppaal_xta] is reachable.
+    ///
+    /// The bound of an [AcceptingCondition](crate::machine::AcceptingCondition) is added to the
+    /// query, but a condition built from a predicate is not exported, like the guards.
+    ///
+    /// ```
+    /// use rust_efsm::bound::Bound;
+    /// use rust_efsm::machine::{AddUpdate, MachineBuilder, Transition};
+    ///
+    /// let machine = MachineBuilder::<u8, u8, AddUpdate<u8>>::new()
+    ///     .with_accepting_when("s0", Bound { lower: Some(2), upper: Some(3) })
+    ///     .build();
+    ///
+    /// assert_eq!(
+    ///     machine.to_uppaal_query(),
+    ///     "E<> (efsm.s0 && data >= 2 && data <= 3)",
+    /// );
+    /// ```
+    pub fn to_uppaal_query(&self) -> String {
+        let conditions = self.get_accepting_conditions();
+        let accepting: BTreeSet<String> = self
+            .get_accepting()
+            .iter()
+            .map(|location| {
+                let location_name = format!("efsm.{}", identifier(location));
+                let Some(condition) = conditions.get(location) else {
+                    return location_name;
+                };
+                let Some(bound) = condition.get_bound() else {
+                    return location_name;
+                };
+                let data = constraints(bound);
+                match (data.is_empty(), condition.is_negated()) {
+                    (true, false) => location_name,
+                    (true, true) => String::from("false"),
+                    (false, false) => format!("{} && {}", location_name, data.join(" && ")),
+                    (false, true) => format!("{} && !({})", location_name, data.join(" && ")),
+                }
+            })
+            .collect();
+
+        if accepting.is_empty() {
+            return String::from("E<> false");
+        }
+
+        format!(
+            "E<> ({})",
+            accepting.into_iter().collect::<Vec<_>>().join(" || ")
+        )
+    }
+}
+
+// Returns the UPPAAL constraints on the data equivalent to `bound`.
+fn constraints<D: fmt::Display + Copy>(bound: &Bound<D>) -> Vec<String> {
+    [
+        bound.lower.map(|lower| format!("data >= {}", lower)),
+        bound.upper.map(|upper| format!("data <= {}", upper)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+// Turns `name` into an UPPAAL identifier.
+fn identifier(name: &str) -> String {
+    let mut identifier: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+
+    if !identifier.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        identifier.insert(0, '_');
+    }
+
+    identifier
+}